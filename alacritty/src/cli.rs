@@ -1,6 +1,7 @@
 use std::cmp::max;
 use std::os::raw::c_ulong;
 use std::path::PathBuf;
+use std::sync::Arc;
 
 use clap::{ArgAction, Args, Parser, Subcommand, ValueHint};
 use log::{self, error, LevelFilter};
@@ -8,9 +9,11 @@ use serde::{Deserialize, Serialize};
 use toml::{Table, Value};
 
 use alacritty_terminal::config::{Program, PtyConfig};
+use alacritty_terminal::replay::Session;
 
 use crate::config::window::{Class, Identity};
 use crate::config::{serde_utils, UiConfig};
+use crate::session::WindowSession;
 
 /// CLI options for the main Alacritty executable.
 #[derive(Parser, Default, Debug)]
@@ -24,6 +27,10 @@ pub struct Options {
     #[clap(long)]
     pub ref_test: bool,
 
+    /// Reopen the windows saved when Alacritty was last closed.
+    #[clap(long)]
+    pub restore_session: bool,
+
     /// X11 window ID to embed Alacritty within (decimal or hexadecimal with "0x" prefix).
     #[clap(long)]
     pub embed: Option<String>,
@@ -167,6 +174,14 @@ pub struct TerminalOptions {
     #[clap(long)]
     pub hold: bool,
 
+    /// Record the session to an asciicast v2 file.
+    #[clap(long, value_hint = ValueHint::FilePath)]
+    pub record: Option<PathBuf>,
+
+    /// Include keyboard input in session recordings.
+    #[clap(long)]
+    pub record_input: bool,
+
     /// Command and args to execute (must be last argument).
     #[clap(short = 'e', long, allow_hyphen_values = true, num_args = 1..)]
     command: Vec<String>,
@@ -237,6 +252,7 @@ pub enum Subcommands {
     #[cfg(unix)]
     Msg(MessageOptions),
     Migrate(MigrateOptions),
+    Replay(ReplayOptions),
 }
 
 /// Send a message to the Alacritty socket.
@@ -261,6 +277,32 @@ pub enum SocketMessage {
 
     /// Update the Alacritty configuration.
     Config(IpcConfig),
+
+    /// List all open windows.
+    ListWindows,
+
+    /// Get the text content of a window.
+    GetText(IpcGetText),
+
+    /// Get the current configuration.
+    GetConfig(IpcGetConfig),
+
+    /// Print events of all windows as newline-delimited JSON.
+    Subscribe,
+
+    /// Execute a binding action in a window.
+    Action(IpcAction),
+
+    /// Write text to the terminal of a window.
+    SendText(IpcSendText),
+}
+
+/// Replay a recorded terminal session.
+#[derive(Args, Clone, Debug)]
+pub struct ReplayOptions {
+    /// Path to an asciicast v2 or `--ref-test` recording.
+    #[clap(value_hint = ValueHint::FilePath)]
+    pub file: PathBuf,
 }
 
 /// Migrate the configuration file.
@@ -302,6 +344,16 @@ pub struct WindowOptions {
     #[cfg(target_os = "macos")]
     /// The window tabbing identifier to use when building a window.
     pub window_tabbing_id: Option<String>,
+
+    /// Recorded session which is played instead of spawning a shell.
+    #[clap(skip)]
+    #[serde(skip)]
+    pub replay: Option<Arc<Session>>,
+
+    /// Saved window whose content is restored above the new shell.
+    #[clap(skip)]
+    #[serde(skip)]
+    pub session: Option<Arc<WindowSession>>,
 }
 
 /// Parameters to the `config` IPC subcommand.
@@ -323,6 +375,75 @@ pub struct IpcConfig {
     pub reset: bool,
 }
 
+/// Parameters to the `get-text` IPC subcommand.
+#[cfg(unix)]
+#[derive(Args, Serialize, Deserialize, Default, Debug, Clone, PartialEq, Eq)]
+pub struct IpcGetText {
+    /// Window ID of the requested text, defaults to the focused window.
+    #[clap(short, long, env = "ALACRITTY_WINDOW_ID")]
+    pub window_id: Option<u64>,
+
+    /// Include the scrollback history instead of only the visible text.
+    #[clap(long)]
+    pub scrollback: bool,
+}
+
+/// Parameters to the `get-config` IPC subcommand.
+#[cfg(unix)]
+#[derive(Args, Serialize, Deserialize, Default, Debug, Clone, PartialEq, Eq)]
+pub struct IpcGetConfig {
+    /// Configuration option to retrieve, defaults to the entire configuration [example:
+    /// cursor.style].
+    #[clap(value_name = "CONFIG_OPTION")]
+    pub option: Option<String>,
+
+    /// Window ID of the requested configuration, defaults to the global configuration.
+    #[clap(short, long, env = "ALACRITTY_WINDOW_ID")]
+    pub window_id: Option<u64>,
+}
+
+/// Parameters to the `action` IPC subcommand.
+#[cfg(unix)]
+#[derive(Args, Serialize, Deserialize, Default, Debug, Clone, PartialEq, Eq)]
+pub struct IpcAction {
+    /// Binding action to execute [example: ScrollToTop].
+    #[clap(value_name = "ACTION", required_unless_present_any = ["chars", "command"])]
+    pub action: Option<String>,
+
+    /// Characters to write to the terminal, like the `chars` binding field.
+    #[clap(long, conflicts_with_all = ["action", "command"])]
+    pub chars: Option<String>,
+
+    /// Command to spawn, like the `command` binding field.
+    #[clap(long, num_args = 1.., allow_hyphen_values = true, conflicts_with = "action")]
+    pub command: Vec<String>,
+
+    /// Window ID the action is executed in, defaults to the focused window.
+    #[clap(short, long, env = "ALACRITTY_WINDOW_ID")]
+    pub window_id: Option<u64>,
+}
+
+/// Parameters to the `send-text` IPC subcommand.
+#[cfg(unix)]
+#[derive(Args, Serialize, Deserialize, Default, Debug, Clone, PartialEq, Eq)]
+pub struct IpcSendText {
+    /// Text to write to the terminal.
+    #[clap(value_name = "TEXT")]
+    pub text: String,
+
+    /// Send the text like a paste, using bracketed paste if the application requested it.
+    #[clap(short, long)]
+    pub paste: bool,
+
+    /// Interpret the backslash escapes `\n`, `\r`, `\t`, `\e`, `\\` and `\xHH` in the text.
+    #[clap(short, long)]
+    pub escape: bool,
+
+    /// Window ID the text is sent to, defaults to the focused window.
+    #[clap(short, long, env = "ALACRITTY_WINDOW_ID")]
+    pub window_id: Option<u64>,
+}
+
 /// Toml value with default implementation.
 #[derive(Debug)]
 pub struct TomlValue(pub Value);
@@ -1,10 +1,11 @@
 #![allow(clippy::enum_glob_use)]
 
 use std::fmt::{self, Debug, Display};
+use std::path::Path;
 
 use bitflags::bitflags;
 use serde::de::{self, Error as SerdeError, MapAccess, Unexpected, Visitor};
-use serde::{Deserialize, Deserializer};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use toml::Value as SerdeValue;
 use winit::event::MouseButton;
 use winit::keyboard::Key::*;
@@ -18,6 +19,7 @@ use alacritty_terminal::term::TermMode;
 use alacritty_terminal::vi_mode::ViMotion;
 
 use crate::config::ui_config::Hint;
+use crate::daemon::ForegroundProcess;
 
 /// Describes a state and action to take in that state.
 ///
@@ -36,10 +38,25 @@ pub struct Binding<T> {
     /// Excluded binding modes where the binding won't be activated.
     pub notmode: BindingMode,
 
+    /// User-defined mode required to activate binding.
+    pub custom_mode: Option<String>,
+
+    /// Excluded user-defined modes where the binding won't be activated.
+    pub custom_notmodes: Vec<String>,
+
+    /// Foreground processes of which one must be running to activate binding.
+    pub processes: Vec<String>,
+
+    /// Foreground processes for which the binding won't be activated.
+    pub notprocesses: Vec<String>,
+
     /// This property is used as part of the trigger detection code.
     ///
     /// For example, this might be a key like "G", or a mouse button.
     pub trigger: T,
+
+    /// Keys which must be pressed in order before the trigger.
+    pub prefix: Vec<KeyStroke>,
 }
 
 /// Bindings that are triggered by a keyboard key.
@@ -60,10 +77,59 @@ impl<T: Eq> Binding<T> {
             && !mode.intersects(self.notmode)
     }
 
+    /// Check if the binding is active in the current user-defined mode.
+    #[inline]
+    pub fn custom_mode_matches(&self, custom_mode: Option<&str>) -> bool {
+        let excluded = custom_mode
+            .map_or(false, |active| self.custom_notmodes.iter().any(|mode| mode == active));
+        !excluded
+            && self.custom_mode.as_ref().map_or(true, |mode| Some(mode.as_str()) == custom_mode)
+    }
+
+    /// Check if the binding is active for the terminal's foreground process.
+    ///
+    /// The foreground process is only requested for bindings which depend on it. Names containing
+    /// a `/` are compared to the entire path of the executable or `argv[0]`, otherwise the file
+    /// name of either or the process's command name has to match.
+    pub fn process_matches<'a>(
+        &self,
+        process: impl FnOnce() -> Option<&'a ForegroundProcess>,
+    ) -> bool {
+        if self.processes.is_empty() && self.notprocesses.is_empty() {
+            return true;
+        }
+
+        // Only bindings excluding processes are active when the process is unknown.
+        let process = match process() {
+            Some(process) => process,
+            None => return self.processes.is_empty(),
+        };
+
+        let argv0 = process.argv0.as_deref();
+        let matches = |name: &String| {
+            if name.contains('/') {
+                let path = Path::new(name);
+                process.executable == path || argv0 == Some(path)
+            } else {
+                process.command.as_ref() == Some(name)
+                    || [Some(process.executable.as_path()), argv0]
+                        .into_iter()
+                        .flatten()
+                        .any(|path| path.file_name().map_or(false, |file| file == name.as_str()))
+            }
+        };
+
+        (self.processes.is_empty() || self.processes.iter().any(matches))
+            && !self.notprocesses.iter().any(matches)
+    }
+
     #[inline]
     pub fn triggers_match(&self, binding: &Binding<T>) -> bool {
         // Check the binding's key and modifiers.
-        if self.trigger != binding.trigger || self.mods != binding.mods {
+        if self.trigger != binding.trigger
+            || self.mods != binding.mods
+            || self.prefix != binding.prefix
+        {
             return false;
         }
 
@@ -80,6 +146,16 @@ impl<T: Eq> Binding<T> {
             return false;
         }
 
+        // Bindings for user-defined modes only replace bindings of the same mode.
+        if self.custom_mode != binding.custom_mode {
+            return false;
+        }
+
+        // Bindings depending on the foreground process only replace identical conditions.
+        if self.processes != binding.processes || self.notprocesses != binding.notprocesses {
+            return false;
+        }
+
         true
     }
 }
@@ -114,6 +190,17 @@ pub enum Action {
     #[config(skip)]
     Mouse(MouseAction),
 
+    /// Enter a user-defined binding mode.
+    #[config(skip)]
+    EnterMode(String),
+
+    /// Leave the active user-defined binding mode.
+    LeaveMode,
+
+    /// Perform multiple actions in order.
+    #[config(skip)]
+    Sequence(Vec<Action>),
+
     /// Paste contents of system clipboard.
     Paste,
 
@@ -127,6 +214,12 @@ pub enum Action {
     /// Paste contents of selection buffer.
     PasteSelection,
 
+    /// Store the output of the last shell command into clipboard.
+    CopyLastCommandOutput,
+
+    /// Store the HTML source of the current selection into clipboard as plain text.
+    CopyHtmlSource,
+
     /// Increase font size.
     IncreaseFontSize,
 
@@ -160,6 +253,12 @@ pub enum Action {
     /// Scroll all the way to the bottom.
     ScrollToBottom,
 
+    /// Scroll to the previous shell prompt.
+    ScrollToPreviousPrompt,
+
+    /// Scroll to the next shell prompt.
+    ScrollToNextPrompt,
+
     /// Clear the display buffer(s) to remove history.
     ClearHistory,
 
@@ -253,6 +352,27 @@ pub enum Action {
     /// Toggle vi mode.
     ToggleViMode,
 
+    /// Toggle recording of the session to an asciicast file.
+    ToggleRecording,
+
+    /// Save the scrollback history to an HTML file.
+    SaveScrollbackHtml,
+
+    /// Save the scrollback history to a text file with ANSI escape sequences.
+    SaveScrollbackAnsi,
+
+    /// Pause or resume a replay.
+    ToggleReplayPause,
+
+    /// Pause a replay and advance it by one frame.
+    ReplayStep,
+
+    /// Double the replay speed.
+    IncreaseReplaySpeed,
+
+    /// Halve the replay speed.
+    DecreaseReplaySpeed,
+
     /// Allow receiving char input.
     ReceiveChar,
 
@@ -266,6 +386,83 @@ pub enum Action {
     None,
 }
 
+impl Action {
+    /// Deserialize any keyboard or mouse action from its configuration value.
+    pub fn from_value(value: SerdeValue) -> Result<Self, toml::de::Error> {
+        if let SerdeValue::Array(values) = value {
+            if values.is_empty() {
+                return Err(toml::de::Error::custom("action sequence must not be empty"));
+            }
+
+            let actions = values.into_iter().map(Self::from_sequence_value);
+            return Ok(Action::Sequence(actions.collect::<Result<_, _>>()?));
+        }
+
+        if let Ok(vi_action) = ViAction::deserialize(value.clone()) {
+            Ok(vi_action.into())
+        } else if let Ok(vi_motion) = ViMotion::deserialize(value.clone()) {
+            Ok(vi_motion.into())
+        } else if let Ok(search_action) = SearchAction::deserialize(value.clone()) {
+            Ok(search_action.into())
+        } else if let Ok(mouse_action) = MouseAction::deserialize(value.clone()) {
+            Ok(mouse_action.into())
+        } else {
+            Action::deserialize(value.clone()).map_err(|err| match value {
+                SerdeValue::String(value) => {
+                    toml::de::Error::custom(format!("unknown keyboard action `{}`", value))
+                },
+                _ => err,
+            })
+        }
+    }
+
+    /// Deserialize a single element of an action sequence.
+    ///
+    /// Besides action names, elements can be tables with one of the `chars`, `command`,
+    /// `enter_mode` or `action` binding fields.
+    fn from_sequence_value(value: SerdeValue) -> Result<Self, toml::de::Error> {
+        let (field, value) = match value {
+            SerdeValue::Table(table) if table.len() == 1 => table.into_iter().next().unwrap(),
+            value @ SerdeValue::String(_) => return Self::from_value(value),
+            _ => {
+                return Err(toml::de::Error::custom(
+                    "action sequence elements must be an action or a table with one of chars, \
+                     command, enter_mode or action",
+                ))
+            },
+        };
+
+        match (field.as_str(), value) {
+            ("chars", value) => Ok(Action::Esc(String::deserialize(value)?)),
+            ("command", value) => Ok(Action::Command(Program::deserialize(value)?)),
+            ("enter_mode", value) => Ok(Action::EnterMode(mode_name(String::deserialize(value)?)?)),
+            ("action", value @ SerdeValue::String(_)) => Self::from_value(value),
+            (field, _) => Err(toml::de::Error::custom(format!(
+                "invalid action sequence element field `{}`",
+                field
+            ))),
+        }
+    }
+
+    /// Check if the action lets the pressed key's text through to the terminal.
+    pub fn receives_char(&self) -> bool {
+        match self {
+            Action::ReceiveChar => true,
+            Action::Sequence(actions) => actions.iter().any(Action::receives_char),
+            _ => false,
+        }
+    }
+
+    /// Iterate over the action or all actions of a sequence.
+    pub fn actions(&self) -> impl Iterator<Item = &Action> {
+        let actions = match self {
+            Action::Sequence(actions) => actions.as_slice(),
+            action => std::slice::from_ref(action),
+        };
+        actions.iter()
+    }
+}
+
 impl From<&'static str> for Action {
     fn from(s: &'static str) -> Action {
         Action::Esc(s.into())
@@ -319,6 +516,8 @@ pub enum ViAction {
     ToggleBlockSelection,
     /// Toggle semantic vi selection.
     ToggleSemanticSelection,
+    /// Toggle shell command output vi selection.
+    ToggleOutputSelection,
     /// Jump to the beginning of the next match.
     SearchNext,
     /// Jump to the beginning of the previous match.
@@ -331,6 +530,30 @@ pub enum ViAction {
     Open,
     /// Centers the screen around the vi mode cursor.
     CenterAroundViCursor,
+    /// Copy the selection, or yank the text covered by the next motion or text object.
+    Yank,
+    /// Jump to the next occurrence of a character to the right of the vi mode cursor.
+    InlineSearchForward,
+    /// Jump to the next occurrence of a character to the left of the vi mode cursor.
+    InlineSearchBackward,
+    /// Jump right before the next occurrence of a character to the right of the cursor.
+    InlineSearchForwardShort,
+    /// Jump right after the next occurrence of a character to the left of the cursor.
+    InlineSearchBackwardShort,
+    /// Repeat the last inline search.
+    InlineSearchNext,
+    /// Repeat the last inline search in the opposite direction.
+    InlineSearchPrevious,
+    /// Set a mark at the vi mode cursor position.
+    SetMark,
+    /// Jump to a mark.
+    GotoMark,
+    /// Jump to the first occupied cell in the line of a mark.
+    GotoMarkLine,
+    /// Jump to the previous position in the jump list.
+    JumpBack,
+    /// Jump to the next position in the jump list.
+    JumpForward,
 }
 
 /// Search mode specific actions.
@@ -390,7 +613,12 @@ macro_rules! bindings {
                 mods: _mods,
                 mode: _mode,
                 notmode: _notmode,
+                custom_mode: None,
+                custom_notmodes: Vec::new(),
+                processes: Vec::new(),
+                notprocesses: Vec::new(),
                 action: $action.into(),
+                prefix: Vec::new(),
             });
         )*
 
@@ -430,6 +658,7 @@ pub fn default_key_bindings() -> Vec<KeyBinding> {
         Paste, ~BindingMode::VI; Action::Paste;
         "l",       ModifiersState::CONTROL; Action::ClearLogNotice;
         "l",       ModifiersState::CONTROL, ~BindingMode::VI, ~BindingMode::SEARCH; Action::Esc("\x0c".into());
+        "l",       ModifiersState::CONTROL, +BindingMode::DISAMBIGUATE, ~BindingMode::VI, ~BindingMode::SEARCH; Action::ReceiveChar;
         Tab,       ModifiersState::SHIFT,   ~BindingMode::VI, ~BindingMode::SEARCH; Action::Esc("\x1b[Z".into());
         Backspace, ModifiersState::ALT,     ~BindingMode::VI, ~BindingMode::SEARCH; Action::Esc("\x1b\x7f".into());
         Backspace, ModifiersState::SHIFT,   ~BindingMode::VI, ~BindingMode::SEARCH; Action::Esc("\x7f".into());
@@ -479,6 +708,12 @@ pub fn default_key_bindings() -> Vec<KeyBinding> {
         F19,        ~BindingMode::VI, ~BindingMode::SEARCH; Action::Esc("\x1b[33~".into());
         F20,        ~BindingMode::VI, ~BindingMode::SEARCH; Action::Esc("\x1b[34~".into());
 
+        // Replay.
+        Space,      +BindingMode::REPLAY, ~BindingMode::VI, ~BindingMode::SEARCH; Action::ToggleReplayPause;
+        ArrowRight, +BindingMode::REPLAY, ~BindingMode::VI, ~BindingMode::SEARCH; Action::ReplayStep;
+        ArrowUp,    +BindingMode::REPLAY, ~BindingMode::VI, ~BindingMode::SEARCH; Action::IncreaseReplaySpeed;
+        ArrowDown,  +BindingMode::REPLAY, ~BindingMode::VI, ~BindingMode::SEARCH; Action::DecreaseReplaySpeed;
+
         // Vi mode.
         Space, ModifiersState::SHIFT | ModifiersState::CONTROL, ~BindingMode::SEARCH; Action::ToggleViMode;
         Space, ModifiersState::SHIFT | ModifiersState::CONTROL, +BindingMode::VI, ~BindingMode::SEARCH; Action::ScrollToBottom;
@@ -494,8 +729,7 @@ pub fn default_key_bindings() -> Vec<KeyBinding> {
         "f",      ModifiersState::CONTROL,  +BindingMode::VI, ~BindingMode::SEARCH; Action::ScrollPageDown;
         "u",      ModifiersState::CONTROL,  +BindingMode::VI, ~BindingMode::SEARCH; Action::ScrollHalfPageUp;
         "d",      ModifiersState::CONTROL,  +BindingMode::VI, ~BindingMode::SEARCH; Action::ScrollHalfPageDown;
-        "y",                                +BindingMode::VI, ~BindingMode::SEARCH; Action::Copy;
-        "y",                                +BindingMode::VI, ~BindingMode::SEARCH; Action::ClearSelection;
+        "y",                                +BindingMode::VI, ~BindingMode::SEARCH; ViAction::Yank;
         "/",                                +BindingMode::VI, ~BindingMode::SEARCH; Action::SearchForward;
         "/",      ModifiersState::SHIFT,    +BindingMode::VI, ~BindingMode::SEARCH; Action::SearchBackward;
         "v",                                +BindingMode::VI, ~BindingMode::SEARCH; ViAction::ToggleNormalSelection;
@@ -527,6 +761,19 @@ pub fn default_key_bindings() -> Vec<KeyBinding> {
         "w",      ModifiersState::SHIFT,    +BindingMode::VI, ~BindingMode::SEARCH; ViMotion::WordRight;
         "e",      ModifiersState::SHIFT,    +BindingMode::VI, ~BindingMode::SEARCH; ViMotion::WordRightEnd;
         "%",      ModifiersState::SHIFT,    +BindingMode::VI, ~BindingMode::SEARCH; ViMotion::Bracket;
+        "{",      ModifiersState::SHIFT,    +BindingMode::VI, ~BindingMode::SEARCH; ViMotion::ParagraphUp;
+        "}",      ModifiersState::SHIFT,    +BindingMode::VI, ~BindingMode::SEARCH; ViMotion::ParagraphDown;
+        "f",                                +BindingMode::VI, ~BindingMode::SEARCH; ViAction::InlineSearchForward;
+        "f",      ModifiersState::SHIFT,    +BindingMode::VI, ~BindingMode::SEARCH; ViAction::InlineSearchBackward;
+        "t",                                +BindingMode::VI, ~BindingMode::SEARCH; ViAction::InlineSearchForwardShort;
+        "t",      ModifiersState::SHIFT,    +BindingMode::VI, ~BindingMode::SEARCH; ViAction::InlineSearchBackwardShort;
+        ";",                                +BindingMode::VI, ~BindingMode::SEARCH; ViAction::InlineSearchNext;
+        ",",                                +BindingMode::VI, ~BindingMode::SEARCH; ViAction::InlineSearchPrevious;
+        "m",                                +BindingMode::VI, ~BindingMode::SEARCH; ViAction::SetMark;
+        "`",                                +BindingMode::VI, ~BindingMode::SEARCH; ViAction::GotoMark;
+        "'",                                +BindingMode::VI, ~BindingMode::SEARCH; ViAction::GotoMarkLine;
+        "o",      ModifiersState::CONTROL,  +BindingMode::VI, ~BindingMode::SEARCH; ViAction::JumpBack;
+        "i",      ModifiersState::CONTROL,  +BindingMode::VI, ~BindingMode::SEARCH; ViAction::JumpForward;
         Enter,                              +BindingMode::VI, +BindingMode::SEARCH; SearchAction::SearchConfirm;
         // Plain search.
         Escape,                             +BindingMode::SEARCH; SearchAction::SearchCancel;
@@ -607,6 +854,11 @@ pub fn default_key_bindings() -> Vec<KeyBinding> {
         }
     }
 
+    // Keys are encoded by the kitty keyboard protocol while it's disambiguating escape codes.
+    for binding in bindings.iter_mut().filter(|binding| matches!(binding.action, Action::Esc(_))) {
+        binding.notmode |= BindingMode::DISAMBIGUATE;
+    }
+
     bindings.extend(platform_key_bindings());
 
     bindings
@@ -702,6 +954,21 @@ pub enum BindingKey {
     Keycode { key: Key, location: KeyLocation },
 }
 
+/// Key with modifiers which is part of a key sequence.
+#[derive(Deserialize, Clone, Debug, Eq, PartialEq)]
+pub struct KeyStroke {
+    pub key: BindingKey,
+    #[serde(default, deserialize_with = "deserialize_mods")]
+    pub mods: ModifiersState,
+}
+
+fn deserialize_mods<'a, D>(deserializer: D) -> Result<ModifiersState, D::Error>
+where
+    D: Deserializer<'a>,
+{
+    Ok(ModsWrapper::deserialize(deserializer)?.into_inner())
+}
+
 impl<'a> Deserialize<'a> for BindingKey {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
@@ -778,10 +1045,12 @@ impl<'a> Deserialize<'a> for BindingKey {
     }
 }
 
-#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub struct ModeWrapper {
     pub mode: BindingMode,
     pub not_mode: BindingMode,
+    pub custom_mode: Option<String>,
+    pub custom_notmodes: Vec<String>,
 }
 
 bitflags! {
@@ -793,24 +1062,36 @@ bitflags! {
         const ALT_SCREEN          = 0b0000_0100;
         const VI                  = 0b0000_1000;
         const SEARCH              = 0b0001_0000;
+        const DISAMBIGUATE        = 0b0010_0000;
+        const REPLAY              = 0b0100_0000;
     }
 }
 
 impl BindingMode {
-    pub fn new(mode: &TermMode, search: bool) -> BindingMode {
+    pub fn new(mode: &TermMode, search: bool, replay: bool) -> BindingMode {
         let mut binding_mode = BindingMode::empty();
         binding_mode.set(BindingMode::APP_CURSOR, mode.contains(TermMode::APP_CURSOR));
         binding_mode.set(BindingMode::APP_KEYPAD, mode.contains(TermMode::APP_KEYPAD));
         binding_mode.set(BindingMode::ALT_SCREEN, mode.contains(TermMode::ALT_SCREEN));
         binding_mode.set(BindingMode::VI, mode.contains(TermMode::VI));
         binding_mode.set(BindingMode::SEARCH, search);
+        binding_mode.set(BindingMode::REPLAY, replay);
+        binding_mode.set(
+            BindingMode::DISAMBIGUATE,
+            mode.intersects(TermMode::DISAMBIGUATE_ESC_CODES | TermMode::REPORT_ALL_KEYS_AS_ESC),
+        );
         binding_mode
     }
 }
 
 impl Default for ModeWrapper {
     fn default() -> Self {
-        Self { mode: BindingMode::empty(), not_mode: BindingMode::empty() }
+        Self {
+            mode: BindingMode::empty(),
+            not_mode: BindingMode::empty(),
+            custom_mode: None,
+            custom_notmodes: Vec::new(),
+        }
     }
 }
 
@@ -826,7 +1107,8 @@ impl<'a> Deserialize<'a> for ModeWrapper {
 
             fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
                 f.write_str(
-                    "a combination of AppCursor | AppKeypad | Alt | Vi, possibly with negation (~)",
+                    "a combination of AppCursor | AppKeypad | Alt | Vi or a user-defined mode, \
+                     possibly with negation (~)",
                 )
             }
 
@@ -834,8 +1116,7 @@ impl<'a> Deserialize<'a> for ModeWrapper {
             where
                 E: de::Error,
             {
-                let mut res =
-                    ModeWrapper { mode: BindingMode::empty(), not_mode: BindingMode::empty() };
+                let mut res = ModeWrapper::default();
 
                 for modifier in value.split('|') {
                     match modifier.trim().to_lowercase().as_str() {
@@ -849,7 +1130,17 @@ impl<'a> Deserialize<'a> for ModeWrapper {
                         "~vi" => res.not_mode |= BindingMode::VI,
                         "search" => res.mode |= BindingMode::SEARCH,
                         "~search" => res.not_mode |= BindingMode::SEARCH,
-                        _ => return Err(E::invalid_value(Unexpected::Str(modifier), &self)),
+                        "replay" => res.mode |= BindingMode::REPLAY,
+                        "~replay" => res.not_mode |= BindingMode::REPLAY,
+                        custom => match custom.strip_prefix('~') {
+                            Some(name) if is_mode_name(name) => {
+                                res.custom_notmodes.push(name.into());
+                            },
+                            None if is_mode_name(custom) && res.custom_mode.is_none() => {
+                                res.custom_mode = Some(custom.into());
+                            },
+                            _ => return Err(E::invalid_value(Unexpected::Str(modifier), &self)),
+                        },
                     }
                 }
 
@@ -860,6 +1151,20 @@ impl<'a> Deserialize<'a> for ModeWrapper {
     }
 }
 
+/// Check if a string is a valid name for a user-defined mode.
+pub fn is_mode_name(name: &str) -> bool {
+    !name.is_empty() && name.chars().all(|c| c.is_alphanumeric() || c == '-' || c == '_')
+}
+
+/// Normalize the name of a user-defined mode.
+fn mode_name<E: SerdeError>(name: String) -> Result<String, E> {
+    let name = name.to_lowercase();
+    if !is_mode_name(&name) {
+        return Err(E::custom(format!("invalid mode name `{}`", name)));
+    }
+    Ok(name)
+}
+
 struct MouseButtonWrapper(MouseButton);
 
 impl MouseButtonWrapper {
@@ -918,7 +1223,12 @@ struct RawBinding {
     mods: ModifiersState,
     mode: BindingMode,
     notmode: BindingMode,
+    custom_mode: Option<String>,
+    custom_notmodes: Vec<String>,
+    processes: Vec<String>,
+    notprocesses: Vec<String>,
     action: Action,
+    prefix: Vec<KeyStroke>,
 }
 
 impl RawBinding {
@@ -930,6 +1240,11 @@ impl RawBinding {
                 action: self.action,
                 mode: self.mode,
                 notmode: self.notmode,
+                custom_mode: self.custom_mode,
+                custom_notmodes: self.custom_notmodes,
+                processes: self.processes,
+                notprocesses: self.notprocesses,
+                prefix: Vec::new(),
             })
         } else {
             Err(Box::new(self))
@@ -944,6 +1259,11 @@ impl RawBinding {
                 action: self.action,
                 mode: self.mode,
                 notmode: self.notmode,
+                custom_mode: self.custom_mode,
+                custom_notmodes: self.custom_notmodes,
+                processes: self.processes,
+                notprocesses: self.notprocesses,
+                prefix: self.prefix,
             })
         } else {
             Err(Box::new(self))
@@ -956,7 +1276,18 @@ impl<'a> Deserialize<'a> for RawBinding {
     where
         D: Deserializer<'a>,
     {
-        const FIELDS: &[&str] = &["key", "mods", "mode", "action", "chars", "mouse", "command"];
+        const FIELDS: &[&str] = &[
+            "key",
+            "mods",
+            "mode",
+            "action",
+            "chars",
+            "mouse",
+            "command",
+            "prefix",
+            "enter_mode",
+            "process",
+        ];
 
         enum Field {
             Key,
@@ -966,6 +1297,9 @@ impl<'a> Deserialize<'a> for RawBinding {
             Chars,
             Mouse,
             Command,
+            Prefix,
+            EnterMode,
+            Process,
         }
 
         impl<'a> Deserialize<'a> for Field {
@@ -994,6 +1328,9 @@ impl<'a> Deserialize<'a> for RawBinding {
                             "chars" => Ok(Field::Chars),
                             "mouse" => Ok(Field::Mouse),
                             "command" => Ok(Field::Command),
+                            "prefix" => Ok(Field::Prefix),
+                            "enter_mode" => Ok(Field::EnterMode),
+                            "process" => Ok(Field::Process),
                             _ => Err(E::unknown_field(value, FIELDS)),
                         }
                     }
@@ -1023,6 +1360,12 @@ impl<'a> Deserialize<'a> for RawBinding {
                 let mut not_mode: Option<BindingMode> = None;
                 let mut mouse: Option<MouseButton> = None;
                 let mut command: Option<Program> = None;
+                let mut prefix: Option<Vec<KeyStroke>> = None;
+                let mut enter_mode: Option<String> = None;
+                let mut custom_mode: Option<String> = None;
+                let mut custom_notmodes = Vec::new();
+                let mut processes: Option<Vec<String>> = None;
+                let mut notprocesses = Vec::new();
 
                 use de::Error;
 
@@ -1070,6 +1413,8 @@ impl<'a> Deserialize<'a> for RawBinding {
                             let mode_deserializer = map.next_value::<ModeWrapper>()?;
                             mode = Some(mode_deserializer.mode);
                             not_mode = Some(mode_deserializer.not_mode);
+                            custom_mode = mode_deserializer.custom_mode;
+                            custom_notmodes = mode_deserializer.custom_notmodes;
                         },
                         Field::Action => {
                             if action.is_some() {
@@ -1078,32 +1423,7 @@ impl<'a> Deserialize<'a> for RawBinding {
 
                             let value = map.next_value::<SerdeValue>()?;
 
-                            action = if let Ok(vi_action) = ViAction::deserialize(value.clone()) {
-                                Some(vi_action.into())
-                            } else if let Ok(vi_motion) = ViMotion::deserialize(value.clone()) {
-                                Some(vi_motion.into())
-                            } else if let Ok(search_action) =
-                                SearchAction::deserialize(value.clone())
-                            {
-                                Some(search_action.into())
-                            } else if let Ok(mouse_action) = MouseAction::deserialize(value.clone())
-                            {
-                                Some(mouse_action.into())
-                            } else {
-                                match Action::deserialize(value.clone()).map_err(V::Error::custom) {
-                                    Ok(action) => Some(action),
-                                    Err(err) => {
-                                        let value = match value {
-                                            SerdeValue::String(string) => string,
-                                            _ => return Err(err),
-                                        };
-                                        return Err(V::Error::custom(format!(
-                                            "unknown keyboard action `{}`",
-                                            value
-                                        )));
-                                    },
-                                }
-                            };
+                            action = Some(Action::from_value(value).map_err(V::Error::custom)?);
                         },
                         Field::Chars => {
                             if chars.is_some() {
@@ -1126,6 +1446,43 @@ impl<'a> Deserialize<'a> for RawBinding {
 
                             command = Some(map.next_value::<Program>()?);
                         },
+                        Field::Prefix => {
+                            if prefix.is_some() {
+                                return Err(<V::Error as Error>::duplicate_field("prefix"));
+                            }
+
+                            prefix = Some(map.next_value()?);
+                        },
+                        Field::EnterMode => {
+                            if enter_mode.is_some() {
+                                return Err(<V::Error as Error>::duplicate_field("enter_mode"));
+                            }
+
+                            enter_mode = Some(mode_name(map.next_value()?)?);
+                        },
+                        Field::Process => {
+                            if processes.is_some() {
+                                return Err(<V::Error as Error>::duplicate_field("process"));
+                            }
+
+                            let value = map.next_value::<String>()?;
+                            let mut required = Vec::new();
+                            for name in value.split('|').map(str::trim) {
+                                match name.strip_prefix('~') {
+                                    Some(name) if !name.is_empty() => {
+                                        notprocesses.push(name.into())
+                                    },
+                                    None if !name.is_empty() => required.push(name.into()),
+                                    _ => {
+                                        return Err(V::Error::custom(format!(
+                                            "invalid process `{}`",
+                                            value
+                                        )))
+                                    },
+                                }
+                            }
+                            processes = Some(required);
+                        },
                     }
                 }
 
@@ -1133,25 +1490,27 @@ impl<'a> Deserialize<'a> for RawBinding {
                 let not_mode = not_mode.unwrap_or_else(BindingMode::empty);
                 let mods = mods.unwrap_or_default();
 
-                let action = match (action, chars, command) {
-                    (Some(action @ Action::ViMotion(_)), None, None)
-                    | (Some(action @ Action::Vi(_)), None, None) => action,
-                    (Some(action @ Action::Search(_)), None, None) => action,
-                    (Some(action @ Action::Mouse(_)), None, None) => {
-                        if mouse.is_none() {
+                let action = match (action, chars, command, enter_mode) {
+                    (Some(action @ Action::ViMotion(_)), None, None, None)
+                    | (Some(action @ Action::Vi(_)), None, None, None) => action,
+                    (Some(action @ Action::Search(_)), None, None, None) => action,
+                    (Some(action), None, None, None) => {
+                        let mouse_action =
+                            action.actions().find(|action| matches!(action, Action::Mouse(_)));
+                        if let (Some(mouse_action), None) = (mouse_action, mouse) {
                             return Err(V::Error::custom(format!(
                                 "action `{}` is only available for mouse bindings",
-                                action,
+                                mouse_action,
                             )));
                         }
                         action
                     },
-                    (Some(action), None, None) => action,
-                    (None, Some(chars), None) => Action::Esc(chars),
-                    (None, None, Some(cmd)) => Action::Command(cmd),
+                    (None, Some(chars), None, None) => Action::Esc(chars),
+                    (None, None, Some(cmd), None) => Action::Command(cmd),
+                    (None, None, None, Some(mode)) => Action::EnterMode(mode),
                     _ => {
                         return Err(V::Error::custom(
-                            "must specify exactly one of chars, action or command",
+                            "must specify exactly one of chars, action, command or enter_mode",
                         ));
                     },
                 };
@@ -1160,7 +1519,24 @@ impl<'a> Deserialize<'a> for RawBinding {
                     return Err(V::Error::custom("bindings require mouse button or key"));
                 }
 
-                Ok(RawBinding { mode, notmode: not_mode, action, key, mouse, mods })
+                let prefix = prefix.unwrap_or_default();
+                if mouse.is_some() && !prefix.is_empty() {
+                    return Err(V::Error::custom("prefix is only available for key bindings"));
+                }
+
+                Ok(RawBinding {
+                    mode,
+                    notmode: not_mode,
+                    custom_mode,
+                    custom_notmodes,
+                    processes: processes.unwrap_or_default(),
+                    notprocesses,
+                    action,
+                    key,
+                    mouse,
+                    mods,
+                    prefix,
+                })
             }
         }
 
@@ -1241,6 +1617,31 @@ impl<'a> de::Deserialize<'a> for ModsWrapper {
     }
 }
 
+impl Serialize for ModsWrapper {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut mods = Vec::new();
+        for (modifier, name) in [
+            (ModifiersState::CONTROL, "Control"),
+            (ModifiersState::SHIFT, "Shift"),
+            (ModifiersState::ALT, "Alt"),
+            (ModifiersState::SUPER, "Super"),
+        ] {
+            if self.0.contains(modifier) {
+                mods.push(name);
+            }
+        }
+
+        if mods.is_empty() {
+            serializer.serialize_str("None")
+        } else {
+            serializer.serialize_str(&mods.join("|"))
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1257,6 +1658,11 @@ mod tests {
                 mode: BindingMode::empty(),
                 notmode: BindingMode::empty(),
                 trigger: Default::default(),
+                custom_mode: Default::default(),
+                custom_notmodes: Default::default(),
+                processes: Default::default(),
+                notprocesses: Default::default(),
+                prefix: Default::default(),
             }
         }
     }
@@ -1446,4 +1852,205 @@ mod tests {
         assert!(!binding.is_triggered_by(BindingMode::ALT_SCREEN, mods, &t));
         assert!(!binding.is_triggered_by(BindingMode::ALT_SCREEN | BindingMode::VI, mods, &t));
     }
+
+    #[test]
+    fn binding_mismatches_prefix() {
+        let prefix = KeyStroke {
+            key: BindingKey::Keycode {
+                key: Character("a".into()),
+                location: KeyLocation::Standard,
+            },
+            mods: ModifiersState::CONTROL,
+        };
+        let b1 = MockBinding::default();
+        let b2 = MockBinding { prefix: vec![prefix], ..MockBinding::default() };
+
+        assert!(!b1.triggers_match(&b2));
+        assert!(!b2.triggers_match(&b1));
+    }
+
+    #[test]
+    fn deserialize_prefix() {
+        let binding: KeyBinding = toml::from_str(
+            r#"
+            key = "C"
+            prefix = [{ key = "A", mods = "Control" }, { key = "B" }]
+            action = "CreateNewWindow"
+            "#,
+        )
+        .unwrap();
+
+        let stroke = |key: &str, mods| KeyStroke {
+            key: BindingKey::Keycode {
+                key: Character(key.into()),
+                location: KeyLocation::Standard,
+            },
+            mods,
+        };
+        assert_eq!(
+            binding.prefix,
+            vec![stroke("a", ModifiersState::CONTROL), stroke("b", ModifiersState::empty())]
+        );
+
+        let mouse = toml::from_str::<MouseBinding>(
+            r#"
+            mouse = "Middle"
+            prefix = [{ key = "A" }]
+            action = "Paste"
+            "#,
+        );
+        assert!(mouse.is_err());
+    }
+
+    #[test]
+    fn binding_custom_modes() {
+        let default = MockBinding::default();
+        let resize = MockBinding { custom_mode: Some("resize".into()), ..MockBinding::default() };
+        let not_resize =
+            MockBinding { custom_notmodes: vec!["resize".into()], ..MockBinding::default() };
+
+        assert!(!default.triggers_match(&resize));
+        assert!(!resize.triggers_match(&default));
+        assert!(default.triggers_match(&not_resize));
+
+        assert!(default.custom_mode_matches(None));
+        assert!(default.custom_mode_matches(Some("resize")));
+        assert!(!resize.custom_mode_matches(None));
+        assert!(resize.custom_mode_matches(Some("resize")));
+        assert!(!resize.custom_mode_matches(Some("scroll")));
+        assert!(not_resize.custom_mode_matches(None));
+        assert!(!not_resize.custom_mode_matches(Some("resize")));
+    }
+
+    #[test]
+    fn deserialize_custom_mode() {
+        let binding: KeyBinding = toml::from_str(
+            r#"
+            key = "R"
+            mode = "~Vi|Resize|~Scroll"
+            enter_mode = "Resize"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(binding.notmode, BindingMode::VI);
+        assert_eq!(binding.custom_mode, Some("resize".into()));
+        assert_eq!(binding.custom_notmodes, vec![String::from("scroll")]);
+        assert_eq!(binding.action, Action::EnterMode("resize".into()));
+
+        let two_modes = toml::from_str::<KeyBinding>(
+            r#"
+            key = "R"
+            mode = "resize|scroll"
+            action = "LeaveMode"
+            "#,
+        );
+        assert!(two_modes.is_err());
+    }
+
+    #[test]
+    fn deserialize_action_sequence() {
+        let binding: KeyBinding = toml::from_str(
+            r#"
+            key = "C"
+            action = ["Copy", { chars = "\u0003" }, { command = "true" }, "ToggleViMode"]
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            binding.action,
+            Action::Sequence(vec![
+                Action::Copy,
+                Action::Esc("\x03".into()),
+                Action::Command(Program::Just("true".into())),
+                Action::ToggleViMode,
+            ])
+        );
+
+        let nested = toml::from_str::<KeyBinding>(
+            r#"
+            key = "C"
+            action = ["Copy", ["Paste"]]
+            "#,
+        );
+        assert!(nested.is_err());
+
+        let mouse_action = toml::from_str::<KeyBinding>(
+            r#"
+            key = "C"
+            action = ["Copy", "ExpandSelection"]
+            "#,
+        );
+        assert!(mouse_action.is_err());
+    }
+
+    #[test]
+    fn binding_processes() {
+        let default = MockBinding::default();
+        let shell = MockBinding { processes: vec!["bash".into()], ..MockBinding::default() };
+        let not_vim = MockBinding {
+            notprocesses: vec!["vim".into(), "/usr/bin/nvim".into()],
+            ..MockBinding::default()
+        };
+
+        assert!(!default.triggers_match(&shell));
+        assert!(!shell.triggers_match(&not_vim));
+
+        let process = |executable: &str| ForegroundProcess {
+            executable: executable.into(),
+            ..ForegroundProcess::default()
+        };
+        let bash = process("/bin/bash");
+        let vim = process("/usr/bin/vim");
+        let nvim = process("/usr/bin/nvim");
+        let local_nvim = process("/usr/local/bin/nvim");
+
+        assert!(default.process_matches(|| unreachable!()));
+        assert!(shell.process_matches(|| Some(&bash)));
+        assert!(!shell.process_matches(|| Some(&vim)));
+        assert!(!shell.process_matches(|| None));
+        assert!(not_vim.process_matches(|| Some(&bash)));
+        assert!(!not_vim.process_matches(|| Some(&vim)));
+        assert!(!not_vim.process_matches(|| Some(&nvim)));
+        assert!(not_vim.process_matches(|| Some(&local_nvim)));
+        assert!(not_vim.process_matches(|| None));
+
+        // Executables installed under a different name.
+        let vim_basic =
+            ForegroundProcess { command: Some("vim".into()), ..process("/usr/bin/vim.basic") };
+        assert!(!not_vim.process_matches(|| Some(&vim_basic)));
+
+        // Programs started through a link to a different executable.
+        let busybox = ForegroundProcess { argv0: Some("bash".into()), ..process("/bin/busybox") };
+        assert!(shell.process_matches(|| Some(&busybox)));
+        let nvim_link =
+            ForegroundProcess { argv0: Some("/usr/bin/nvim".into()), ..process("/opt/nvim/nvim") };
+        assert!(!not_vim.process_matches(|| Some(&nvim_link)));
+    }
+
+    #[test]
+    fn deserialize_process() {
+        let binding: KeyBinding = toml::from_str(
+            r#"
+            key = "W"
+            mods = "Control"
+            process = "bash | zsh|~/usr/bin/vim"
+            action = "Quit"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(binding.processes, vec![String::from("bash"), String::from("zsh")]);
+        assert_eq!(binding.notprocesses, vec![String::from("/usr/bin/vim")]);
+
+        let empty = toml::from_str::<KeyBinding>(
+            r#"
+            key = "W"
+            process = "bash||zsh"
+            action = "Quit"
+            "#,
+        );
+        assert!(empty.is_err());
+    }
 }
@@ -1,11 +1,11 @@
 use log::LevelFilter;
 
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
 use alacritty_config_derive::ConfigDeserialize;
 
 /// Debugging options.
-#[derive(ConfigDeserialize, Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(ConfigDeserialize, Serialize, Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
 pub struct Debug {
     pub log_level: LevelFilter,
 
@@ -28,6 +28,7 @@ pub struct Debug {
 
     /// Record ref test.
     #[config(skip)]
+    #[serde(skip)]
     pub ref_test: bool,
 }
 
@@ -47,7 +48,7 @@ impl Default for Debug {
 }
 
 /// The renderer configuration options.
-#[derive(Deserialize, Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Deserialize, Serialize, Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
 pub enum RendererPreference {
     /// OpenGL 3.3 renderer.
     #[serde(rename = "glsl3")]
@@ -26,7 +26,8 @@ mod mouse;
 
 use crate::cli::Options;
 pub use crate::config::bindings::{
-    Action, Binding, BindingKey, BindingMode, MouseAction, SearchAction, ViAction,
+    Action, Binding, BindingKey, BindingMode, KeyBinding, KeyStroke, MouseAction, SearchAction,
+    ViAction,
 };
 #[cfg(test)]
 pub use crate::config::mouse::Mouse;
@@ -172,6 +173,9 @@ fn after_loading(config: &mut UiConfig, options: &Options) {
 
     // Create key bindings for regex hints.
     config.generate_hint_bindings();
+
+    // Ignore bindings for modes which do not exist.
+    config.remove_undeclared_mode_bindings();
 }
 
 /// Load configuration file and log errors.
@@ -1,11 +1,13 @@
 use std::cell::RefCell;
 use std::fmt::{self, Formatter};
+use std::mem;
 use std::path::PathBuf;
 use std::rc::Rc;
+use std::time::Duration;
 
 use log::{error, warn};
 use serde::de::{Error as SerdeError, MapAccess, Visitor};
-use serde::{self, Deserialize, Deserializer};
+use serde::{self, Deserialize, Deserializer, Serialize, Serializer};
 use unicode_width::UnicodeWidthChar;
 use winit::keyboard::{Key, KeyLocation, ModifiersState};
 
@@ -28,7 +30,7 @@ use crate::config::window::WindowConfig;
 const URL_REGEX: &str = "(ipfs:|ipns:|magnet:|mailto:|gemini://|gopher://|https://|http://|news:|file:|git://|ssh:|ftp://)\
                          [^\u{0000}-\u{001F}\u{007F}-\u{009F}<>\"\\s{-}\\^⟨⟩`]+";
 
-#[derive(ConfigDeserialize, Clone, Debug, PartialEq)]
+#[derive(ConfigDeserialize, Serialize, Clone, Debug, PartialEq)]
 pub struct UiConfig {
     /// Font configuration.
     pub font: Font,
@@ -45,6 +47,7 @@ pub struct UiConfig {
     /// Send escape sequences using the alt key.
     #[config(removed = "It's now always set to 'true'. If you're on macOS use \
                         'window.option_as_alt' to alter behavior of Option")]
+    #[serde(skip)]
     pub alt_send_esc: Option<bool>,
 
     /// Live config reload.
@@ -58,6 +61,7 @@ pub struct UiConfig {
 
     /// Path where config was loaded from.
     #[config(skip)]
+    #[serde(skip)]
     pub config_paths: Vec<PathBuf>,
 
     /// Regex hints for interacting with terminal content.
@@ -67,29 +71,38 @@ pub struct UiConfig {
     #[cfg(unix)]
     pub ipc_socket: bool,
 
+    /// Save the content of windows on exit, for restoring with `--restore-session`.
+    pub save_session: bool,
+
     /// Config for the alacritty_terminal itself.
     #[config(flatten)]
+    #[serde(flatten)]
     pub terminal_config: TerminalConfig,
 
     /// Keyboard configuration.
+    #[serde(skip)]
     keyboard: Keyboard,
 
     /// Should draw bold text with brighter colors instead of bold font.
     #[config(deprecated = "use colors.draw_bold_text_with_bright_colors instead")]
+    #[serde(skip)]
     draw_bold_text_with_bright_colors: bool,
 
     /// Keybindings.
     #[config(deprecated = "use keyboard.bindings instead")]
+    #[serde(skip)]
     key_bindings: Option<KeyBindings>,
 
     /// Bindings for the mouse.
     #[config(deprecated = "use mouse.bindings instead")]
+    #[serde(skip)]
     mouse_bindings: Option<MouseBindings>,
 
     /// Configuration file imports.
     ///
     /// This is never read since the field is directly accessed through the config's
     /// [`toml::Value`], but still present to prevent unused field warnings.
+    #[serde(skip)]
     import: Vec<String>,
 }
 
@@ -99,6 +112,7 @@ impl Default for UiConfig {
             live_config_reload: true,
             #[cfg(unix)]
             ipc_socket: true,
+            save_session: Default::default(),
             draw_bold_text_with_bright_colors: Default::default(),
             terminal_config: Default::default(),
             mouse_bindings: Default::default(),
@@ -141,7 +155,12 @@ impl UiConfig {
                 mods: binding.mods.0,
                 mode: binding.mode.mode,
                 notmode: binding.mode.not_mode,
+                custom_mode: binding.mode.custom_mode.clone(),
+                custom_notmodes: binding.mode.custom_notmodes.clone(),
+                processes: Vec::new(),
+                notprocesses: Vec::new(),
                 action: Action::Hint(hint.clone()),
+                prefix: Vec::new(),
             };
 
             key_bindings.push(binding);
@@ -162,6 +181,46 @@ impl UiConfig {
         }
     }
 
+    /// Check if a user-defined binding mode was declared.
+    pub fn has_custom_mode(&self, name: &str) -> bool {
+        self.keyboard.modes.iter().any(|mode| mode.eq_ignore_ascii_case(name))
+    }
+
+    /// Remove bindings referring to user-defined modes which were not declared.
+    pub fn remove_undeclared_mode_bindings(&mut self) {
+        let modes = &self.keyboard.modes;
+        let is_declared = |binding_name: &str, mode: &str| {
+            let declared = modes.iter().any(|declared| declared.eq_ignore_ascii_case(mode));
+            if !declared {
+                error!(
+                    target: LOG_TARGET_CONFIG,
+                    "Config error: unknown mode `{}` in {}; ignoring binding", mode, binding_name
+                );
+            }
+            declared
+        };
+
+        let key_bindings = match self.key_bindings.as_mut() {
+            Some(key_bindings) => &mut key_bindings.0,
+            None => &mut self.keyboard.bindings.0,
+        };
+        key_bindings
+            .retain(|binding| binding_modes_declared(binding, |m| is_declared("key binding", m)));
+
+        let mouse_bindings = match self.mouse_bindings.as_mut() {
+            Some(mouse_bindings) => &mut mouse_bindings.0,
+            None => &mut self.mouse.bindings.0,
+        };
+        mouse_bindings
+            .retain(|binding| binding_modes_declared(binding, |m| is_declared("mouse binding", m)));
+    }
+
+    /// Time to wait for the next key of a key sequence.
+    #[inline]
+    pub fn key_sequence_timeout(&self) -> Duration {
+        Duration::from_millis(self.keyboard.sequence_timeout as u64)
+    }
+
     #[inline]
     pub fn mouse_bindings(&self) -> &[MouseBinding] {
         if let Some(mouse_bindings) = self.mouse_bindings.as_ref() {
@@ -177,11 +236,41 @@ impl UiConfig {
     }
 }
 
+/// Check all user-defined modes referenced by a binding.
+fn binding_modes_declared<T>(
+    binding: &Binding<T>,
+    mut is_declared: impl FnMut(&str) -> bool,
+) -> bool {
+    let entered_modes = binding.action.actions().filter_map(|action| match action {
+        Action::EnterMode(mode) => Some(mode),
+        _ => None,
+    });
+
+    binding
+        .custom_mode
+        .iter()
+        .chain(&binding.custom_notmodes)
+        .chain(entered_modes)
+        .all(|mode| is_declared(mode))
+}
+
 /// Keyboard configuration.
-#[derive(ConfigDeserialize, Default, Clone, Debug, PartialEq)]
+#[derive(ConfigDeserialize, Clone, Debug, PartialEq)]
 struct Keyboard {
     /// Keybindings.
     bindings: KeyBindings,
+
+    /// Time in milliseconds to wait for the next key of a key sequence.
+    sequence_timeout: u16,
+
+    /// User-defined binding modes.
+    modes: Vec<String>,
+}
+
+impl Default for Keyboard {
+    fn default() -> Self {
+        Self { bindings: Default::default(), sequence_timeout: 1000, modes: Default::default() }
+    }
 }
 
 #[derive(SerdeReplace, Clone, Debug, PartialEq, Eq)]
@@ -235,7 +324,7 @@ where
 }
 
 /// A delta for a point in a 2 dimensional plane.
-#[derive(ConfigDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[derive(ConfigDeserialize, Serialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
 pub struct Delta<T: Default> {
     /// Horizontal change.
     pub x: T,
@@ -244,7 +333,7 @@ pub struct Delta<T: Default> {
 }
 
 /// Regex terminal hints.
-#[derive(ConfigDeserialize, Clone, Debug, PartialEq, Eq)]
+#[derive(ConfigDeserialize, Serialize, Clone, Debug, PartialEq, Eq)]
 pub struct Hints {
     /// Characters for the hint labels.
     alphabet: HintsAlphabet,
@@ -298,7 +387,7 @@ impl Hints {
     }
 }
 
-#[derive(SerdeReplace, Clone, Debug, PartialEq, Eq)]
+#[derive(SerdeReplace, Serialize, Clone, Debug, PartialEq, Eq)]
 struct HintsAlphabet(String);
 
 impl Default for HintsAlphabet {
@@ -331,7 +420,7 @@ impl<'de> Deserialize<'de> for HintsAlphabet {
 }
 
 /// Built-in actions for hint mode.
-#[derive(ConfigDeserialize, Clone, Debug, PartialEq, Eq)]
+#[derive(ConfigDeserialize, Serialize, Clone, Debug, PartialEq, Eq)]
 pub enum HintInternalAction {
     /// Copy the text to the clipboard.
     Copy,
@@ -344,7 +433,7 @@ pub enum HintInternalAction {
 }
 
 /// Actions for hint bindings.
-#[derive(Deserialize, Clone, Debug, PartialEq, Eq)]
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
 pub enum HintAction {
     /// Built-in hint action.
     #[serde(rename = "action")]
@@ -356,7 +445,7 @@ pub enum HintAction {
 }
 
 /// Hint configuration.
-#[derive(Deserialize, Clone, Debug, PartialEq, Eq)]
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
 pub struct Hint {
     /// Regex for finding matches.
     #[serde(flatten)]
@@ -378,10 +467,11 @@ pub struct Hint {
     pub mouse: Option<HintMouse>,
 
     /// Binding required to search for this hint.
+    #[serde(skip_serializing)]
     binding: Option<HintBinding>,
 }
 
-#[derive(Default, Clone, Debug, PartialEq, Eq)]
+#[derive(Serialize, Default, Clone, Debug, PartialEq, Eq)]
 pub struct HintContent {
     /// Regex for finding matches.
     pub regex: Option<LazyRegex>,
@@ -468,7 +558,7 @@ pub struct HintBinding {
 }
 
 /// Hint mouse highlighting.
-#[derive(ConfigDeserialize, Default, Copy, Clone, Debug, PartialEq, Eq)]
+#[derive(ConfigDeserialize, Serialize, Default, Copy, Clone, Debug, PartialEq, Eq)]
 pub struct HintMouse {
     /// Hint mouse highlighting availability.
     pub enabled: bool,
@@ -501,12 +591,21 @@ impl<'de> Deserialize<'de> for LazyRegex {
     }
 }
 
+impl Serialize for LazyRegex {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        self.0.borrow().pattern().serialize(serializer)
+    }
+}
+
 /// Regex which is compiled on demand, to avoid expensive computations at startup.
 #[derive(Clone, Debug)]
 pub enum LazyRegexVariant {
-    Compiled(Box<RegexSearch>),
+    Compiled(String, Box<RegexSearch>),
     Pattern(String),
-    Uncompilable,
+    Uncompilable(String),
 }
 
 impl LazyRegexVariant {
@@ -517,28 +616,35 @@ impl LazyRegexVariant {
     fn compiled(&mut self) -> Option<&RegexSearch> {
         // Check if the regex has already been compiled.
         let regex = match self {
-            Self::Compiled(regex_search) => return Some(regex_search),
-            Self::Uncompilable => return None,
-            Self::Pattern(regex) => regex,
+            Self::Compiled(_, regex_search) => return Some(regex_search),
+            Self::Uncompilable(_) => return None,
+            Self::Pattern(regex) => mem::take(regex),
         };
 
         // Compile the regex.
-        let regex_search = match RegexSearch::new(regex) {
+        let regex_search = match RegexSearch::new(&regex) {
             Ok(regex_search) => regex_search,
             Err(err) => {
                 error!("could not compile hint regex: {err}");
-                *self = Self::Uncompilable;
+                *self = Self::Uncompilable(regex);
                 return None;
             },
         };
-        *self = Self::Compiled(Box::new(regex_search));
+        *self = Self::Compiled(regex, Box::new(regex_search));
 
         // Return a reference to the compiled DFAs.
         match self {
-            Self::Compiled(dfas) => Some(dfas),
+            Self::Compiled(_, dfas) => Some(dfas),
             _ => unreachable!(),
         }
     }
+
+    /// Get the original regex pattern.
+    fn pattern(&self) -> &str {
+        match self {
+            Self::Compiled(regex, _) | Self::Pattern(regex) | Self::Uncompilable(regex) => regex,
+        }
+    }
 }
 
 impl PartialEq for LazyRegexVariant {
@@ -1,5 +1,6 @@
 use std::fmt::{self, Formatter};
 use std::os::raw::c_ulong;
+use std::time::Duration;
 
 use log::{error, warn};
 use serde::de::{self, MapAccess, Visitor};
@@ -18,7 +19,7 @@ use crate::config::ui_config::Delta;
 /// Default Alacritty name, used for window title and class.
 pub const DEFAULT_NAME: &str = "Alacritty";
 
-#[derive(ConfigDeserialize, Debug, Clone, PartialEq)]
+#[derive(ConfigDeserialize, Serialize, Debug, Clone, PartialEq)]
 pub struct WindowConfig {
     /// Initial position.
     pub position: Option<Delta<i32>>,
@@ -31,6 +32,7 @@ pub struct WindowConfig {
 
     /// XEmbed parent.
     #[config(skip)]
+    #[serde(skip)]
     pub embed: Option<c_ulong>,
 
     /// System decorations theme variant.
@@ -44,6 +46,7 @@ pub struct WindowConfig {
 
     /// Information to identify a particular window.
     #[config(flatten)]
+    #[serde(flatten)]
     pub identity: Identity,
 
     /// Background opacity from 0.0 to 1.0.
@@ -56,6 +59,9 @@ pub struct WindowConfig {
     /// Resize increments.
     pub resize_increments: bool,
 
+    /// Scrollbar showing the position within the scrollback history.
+    pub scrollbar: ScrollbarConfig,
+
     /// Pixel padding.
     padding: Delta<u16>,
 
@@ -78,6 +84,7 @@ impl Default for WindowConfig {
             padding: Default::default(),
             dimensions: Default::default(),
             resize_increments: Default::default(),
+            scrollbar: Default::default(),
             #[cfg(target_os = "macos")]
             option_as_alt: Default::default(),
         }
@@ -85,6 +92,11 @@ impl Default for WindowConfig {
 }
 
 impl WindowConfig {
+    /// Override the initial dimensions.
+    pub fn set_dimensions(&mut self, dimensions: Dimensions) {
+        self.dimensions = dimensions;
+    }
+
     #[inline]
     pub fn dimensions(&self) -> Option<Dimensions> {
         let (lines, columns) = (self.dimensions.lines, self.dimensions.columns.0);
@@ -149,7 +161,7 @@ impl WindowConfig {
     }
 }
 
-#[derive(ConfigDeserialize, Debug, Clone, PartialEq, Eq)]
+#[derive(ConfigDeserialize, Serialize, Debug, Clone, PartialEq, Eq)]
 pub struct Identity {
     /// Window title.
     pub title: String,
@@ -164,7 +176,7 @@ impl Default for Identity {
     }
 }
 
-#[derive(ConfigDeserialize, Default, Debug, Copy, Clone, PartialEq, Eq)]
+#[derive(ConfigDeserialize, Serialize, Default, Debug, Copy, Clone, PartialEq, Eq)]
 pub enum StartupMode {
     #[default]
     Windowed,
@@ -174,7 +186,7 @@ pub enum StartupMode {
     SimpleFullscreen,
 }
 
-#[derive(ConfigDeserialize, Default, Debug, Copy, Clone, PartialEq, Eq)]
+#[derive(ConfigDeserialize, Serialize, Default, Debug, Copy, Clone, PartialEq, Eq)]
 pub enum Decorations {
     #[default]
     Full,
@@ -185,10 +197,53 @@ pub enum Decorations {
     None,
 }
 
+#[derive(ConfigDeserialize, Serialize, Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ScrollbarConfig {
+    /// When the scrollbar is shown.
+    pub mode: ScrollbarMode,
+
+    /// Scrollbar width in pixels.
+    width: u8,
+
+    /// Time in milliseconds the scrollbar stays visible after scrolling in `Auto` mode.
+    hide_delay: u16,
+}
+
+impl Default for ScrollbarConfig {
+    fn default() -> Self {
+        Self { mode: Default::default(), width: 8, hide_delay: 1000 }
+    }
+}
+
+impl ScrollbarConfig {
+    #[inline]
+    pub fn width(&self, scale_factor: f32) -> f32 {
+        (f32::from(self.width) * scale_factor).floor()
+    }
+
+    #[inline]
+    pub fn hide_delay(&self) -> Duration {
+        Duration::from_millis(self.hide_delay as u64)
+    }
+}
+
+#[derive(ConfigDeserialize, Serialize, Default, Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ScrollbarMode {
+    /// Never show the scrollbar.
+    #[default]
+    Never,
+
+    /// Show the scrollbar while scrolling or while the mouse is above it.
+    Auto,
+
+    /// Always show the scrollbar while there is scrollback history.
+    Always,
+}
+
 /// Window Dimensions.
 ///
 /// Newtype to avoid passing values incorrectly.
-#[derive(ConfigDeserialize, Default, Debug, Copy, Clone, PartialEq, Eq)]
+#[derive(ConfigDeserialize, Serialize, Default, Debug, Copy, Clone, PartialEq, Eq)]
 pub struct Dimensions {
     /// Window width in character columns.
     pub columns: Column,
@@ -275,7 +330,7 @@ impl<'de> Deserialize<'de> for Class {
 }
 
 #[cfg(target_os = "macos")]
-#[derive(ConfigDeserialize, Default, Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(ConfigDeserialize, Serialize, Default, Debug, Clone, Copy, PartialEq, Eq)]
 pub enum OptionAsAlt {
     /// The left `Option` key is treated as `Alt`.
     OnlyLeft,
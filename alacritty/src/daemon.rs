@@ -2,8 +2,11 @@ use std::ffi::OsStr;
 #[cfg(not(any(target_os = "macos", windows)))]
 use std::fs;
 use std::io;
+#[cfg(not(any(target_os = "macos", windows)))]
+use std::os::unix::ffi::OsStrExt;
 #[cfg(windows)]
 use std::os::windows::process::CommandExt;
+use std::path::PathBuf;
 use std::process::{Command, Stdio};
 
 #[rustfmt::skip]
@@ -12,9 +15,12 @@ use {
     std::error::Error,
     std::os::unix::process::CommandExt,
     std::os::unix::io::RawFd,
-    std::path::PathBuf,
+    std::path::Path,
 };
 
+#[cfg(not(windows))]
+use alacritty_terminal::ansi::WorkingDirectory;
+
 #[cfg(not(windows))]
 use libc::pid_t;
 #[cfg(windows)]
@@ -23,6 +29,19 @@ use windows_sys::Win32::System::Threading::{CREATE_NEW_PROCESS_GROUP, CREATE_NO_
 #[cfg(target_os = "macos")]
 use crate::macos;
 
+/// Process running in the foreground of the terminal.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ForegroundProcess {
+    /// Path of the process executable.
+    pub executable: PathBuf,
+
+    /// Command name of the process, which is the script's name for interpreted scripts.
+    pub command: Option<String>,
+
+    /// First argument passed to the process.
+    pub argv0: Option<PathBuf>,
+}
+
 /// Start a new process in the background.
 #[cfg(windows)]
 pub fn spawn_daemon<I, S>(program: &str, args: I) -> io::Result<()>
@@ -49,8 +68,7 @@ where
 pub fn spawn_daemon<I, S>(
     program: &str,
     args: I,
-    master_fd: RawFd,
-    shell_pid: u32,
+    working_directory: Option<&Path>,
 ) -> io::Result<()>
 where
     I: IntoIterator<Item = S> + Copy,
@@ -58,7 +76,7 @@ where
 {
     let mut command = Command::new(program);
     command.args(args).stdin(Stdio::null()).stdout(Stdio::null()).stderr(Stdio::null());
-    if let Ok(cwd) = foreground_process_path(master_fd, shell_pid) {
+    if let Some(cwd) = working_directory {
         command.current_dir(cwd);
     }
     unsafe {
@@ -82,16 +100,50 @@ where
     }
 }
 
+/// Get working directory for spawning new processes.
+///
+/// The directory reported by the shell is preferred, since it is accurate even when the foreground
+/// process is a multiplexer like tmux. If it isn't available locally, the working directory of the
+/// foreground process is used instead.
+#[cfg(not(windows))]
+pub fn working_directory(
+    reported: Option<&WorkingDirectory>,
+    master_fd: RawFd,
+    shell_pid: u32,
+) -> Option<PathBuf> {
+    let reported = reported
+        .filter(|cwd| cwd.hostname.as_deref().map_or(true, is_local_host) && cwd.path.is_dir());
+
+    match reported {
+        Some(cwd) => Some(cwd.path.clone()),
+        None => foreground_process_path(master_fd, shell_pid).ok(),
+    }
+}
+
+/// Check if a hostname refers to this machine.
+#[cfg(not(windows))]
+fn is_local_host(hostname: &str) -> bool {
+    if hostname == "localhost" {
+        return true;
+    }
+
+    let mut buf = [0u8; 256];
+    let result = unsafe { libc::gethostname(buf.as_mut_ptr().cast(), buf.len()) };
+    if result != 0 {
+        return false;
+    }
+
+    let len = buf.iter().position(|&b| b == 0).unwrap_or(buf.len());
+    &buf[..len] == hostname.as_bytes()
+}
+
 /// Get working directory of controlling process.
 #[cfg(not(windows))]
 pub fn foreground_process_path(
     master_fd: RawFd,
     shell_pid: u32,
 ) -> Result<PathBuf, Box<dyn Error>> {
-    let mut pid = unsafe { libc::tcgetpgrp(master_fd) };
-    if pid < 0 {
-        pid = shell_pid as pid_t;
-    }
+    let pid = foreground_pid(master_fd, shell_pid);
 
     #[cfg(not(any(target_os = "macos", target_os = "freebsd")))]
     let link_path = format!("/proc/{}/cwd", pid);
@@ -106,3 +158,60 @@ pub fn foreground_process_path(
 
     Ok(cwd)
 }
+
+/// Get the process running in the foreground of the terminal.
+#[cfg(not(windows))]
+pub fn foreground_process(
+    master_fd: RawFd,
+    shell_pid: u32,
+) -> Result<ForegroundProcess, Box<dyn Error>> {
+    // Without a PTY, there is no shell which could have started a foreground process.
+    if master_fd < 0 {
+        return Err("no terminal available".into());
+    }
+
+    let pid = foreground_pid(master_fd, shell_pid);
+
+    #[cfg(not(any(target_os = "macos", target_os = "freebsd")))]
+    let proc_path = format!("/proc/{}", pid);
+    #[cfg(target_os = "freebsd")]
+    let proc_path = format!("/compat/linux/proc/{}", pid);
+
+    #[cfg(not(target_os = "macos"))]
+    let process = {
+        let executable = fs::read_link(format!("{}/exe", proc_path))?;
+
+        let command = fs::read_to_string(format!("{}/comm", proc_path))
+            .ok()
+            .map(|command| command.trim_end_matches('\n').to_owned());
+
+        let cmdline = fs::read(format!("{}/cmdline", proc_path)).unwrap_or_default();
+        let argv0 = cmdline
+            .split(|&byte| byte == 0)
+            .next()
+            .filter(|argv0| !argv0.is_empty())
+            .map(|argv0| PathBuf::from(OsStr::from_bytes(argv0)));
+
+        ForegroundProcess { executable, command, argv0 }
+    };
+
+    #[cfg(target_os = "macos")]
+    let process = ForegroundProcess {
+        executable: macos::proc::executable(pid)?,
+        command: macos::proc::name(pid).ok(),
+        argv0: None,
+    };
+
+    Ok(process)
+}
+
+/// Get the process group in the foreground of the terminal, falling back to the shell.
+#[cfg(not(windows))]
+fn foreground_pid(master_fd: RawFd, shell_pid: u32) -> pid_t {
+    let pid = unsafe { libc::tcgetpgrp(master_fd) };
+    if pid < 0 {
+        shell_pid as pid_t
+    } else {
+        pid
+    }
+}
@@ -3,7 +3,7 @@ use std::ops::{Index, IndexMut};
 use log::trace;
 
 use alacritty_terminal::ansi::NamedColor;
-use alacritty_terminal::term::color::{Rgb, COUNT};
+use alacritty_terminal::term::color::{Colors as TermColors, Rgb, COUNT};
 
 use crate::config::color::Colors;
 
@@ -27,6 +27,15 @@ impl<'a> From<&'a Colors> for List {
 }
 
 impl List {
+    /// Convert to a terminal color palette with every color set.
+    pub fn palette(&self) -> TermColors {
+        let mut palette = TermColors::default();
+        for (index, color) in self.0.iter().enumerate() {
+            palette[index] = Some(*color);
+        }
+        palette
+    }
+
     pub fn fill_named(&mut self, colors: &Colors) {
         // Normals.
         self[NamedColor::Black] = colors.normal.black;
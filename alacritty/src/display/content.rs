@@ -4,10 +4,11 @@ use std::{cmp, mem};
 
 use alacritty_terminal::ansi::{Color, CursorShape, NamedColor};
 use alacritty_terminal::event::EventListener;
-use alacritty_terminal::grid::Indexed;
+use alacritty_terminal::graphics::GraphicCell;
+use alacritty_terminal::grid::{Dimensions, Indexed};
 use alacritty_terminal::index::{Column, Line, Point};
 use alacritty_terminal::selection::SelectionRange;
-use alacritty_terminal::term::cell::{Cell, Flags, Hyperlink};
+use alacritty_terminal::term::cell::{Cell, Flags, Hyperlink, LineLength};
 use alacritty_terminal::term::color::{CellRgb, Rgb};
 use alacritty_terminal::term::search::{Match, RegexSearch};
 use alacritty_terminal::term::{self, RenderableContent as TerminalContent, Term, TermMode};
@@ -196,6 +197,7 @@ pub struct RenderableCell {
 pub struct RenderableCellExtra {
     pub zerowidth: Option<Vec<char>>,
     pub hyperlink: Option<Hyperlink>,
+    pub graphics: Vec<GraphicCell>,
 }
 
 impl RenderableCell {
@@ -272,13 +274,16 @@ impl RenderableCell {
 
         let zerowidth = cell.zerowidth();
         let hyperlink = cell.hyperlink();
+        let graphics = cell.graphics();
 
-        let extra = (zerowidth.is_some() || hyperlink.is_some()).then(|| {
-            Box::new(RenderableCellExtra {
-                zerowidth: zerowidth.map(|zerowidth| zerowidth.to_vec()),
-                hyperlink,
-            })
-        });
+        let extra =
+            (zerowidth.is_some() || hyperlink.is_some() || !graphics.is_empty()).then(|| {
+                Box::new(RenderableCellExtra {
+                    zerowidth: zerowidth.map(|zerowidth| zerowidth.to_vec()),
+                    hyperlink,
+                    graphics: graphics.to_vec(),
+                })
+            });
 
         RenderableCell { flags, character, bg_alpha, point, fg, bg, underline, extra }
     }
@@ -487,10 +492,41 @@ impl<'a> HintMatches<'a> {
 
     /// Create from regex matches on term visable part.
     fn visible_regex_matches<T>(term: &Term<T>, dfas: &RegexSearch) -> Self {
-        let matches = hint::visible_regex_match_iter(term, dfas).collect::<Vec<_>>();
+        let matches = hint::visible_regex_match_iter(term, dfas);
+        let matches = if dfas.is_multiline() {
+            matches.flat_map(|regex_match| Self::split_lines(term, regex_match)).collect()
+        } else {
+            matches.collect::<Vec<_>>()
+        };
         Self::new(matches)
     }
 
+    /// Split a multi-line match into one match per line.
+    ///
+    /// Empty cells after the newline of a line are excluded, so only the text and the newline
+    /// itself are highlighted.
+    fn split_lines<T>(term: &Term<T>, regex_match: Match) -> Vec<Match> {
+        let (start, end) = regex_match.into_inner();
+        let last_column = term.last_column();
+
+        (start.line.0..=end.line.0)
+            .map(Line)
+            .filter_map(|line| {
+                let line_start =
+                    if line == start.line { start } else { Point::new(line, Column(0)) };
+                let mut line_end =
+                    if line == end.line { end } else { Point::new(line, last_column) };
+
+                let row = &term.grid()[line];
+                if line != end.line && !row[last_column].flags.contains(Flags::WRAPLINE) {
+                    line_end.column = cmp::min(line_end.column, row.line_length());
+                }
+
+                (line_start <= line_end).then_some(line_start..=line_end)
+            })
+            .collect()
+    }
+
     /// Advance the regex tracker to the next point.
     ///
     /// This will return `true` if the point passed is part of a regex match.
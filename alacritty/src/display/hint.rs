@@ -298,6 +298,15 @@ pub fn visible_regex_match_iter<'a, T>(
     start.line = start.line.max(viewport_start - MAX_SEARCH_LINES);
     end.line = end.line.min(viewport_end + MAX_SEARCH_LINES);
 
+    // Multi-line matches can extend beyond the logical lines in the viewport.
+    if regex.is_multiline() {
+        let screen_lines = term.screen_lines();
+        let above = (viewport_start - screen_lines).grid_clamp(term, Boundary::Grid);
+        let below = (viewport_end + screen_lines).grid_clamp(term, Boundary::Grid);
+        start.line = start.line.min(above);
+        end.line = end.line.max(below);
+    }
+
     RegexIter::new(start, end, Direction::Right, term, regex)
         .skip_while(move |rm| rm.end().line < viewport_start)
         .take_while(move |rm| rm.start().line <= viewport_end)
@@ -699,4 +708,19 @@ mod tests {
         // The interator should match everything in the viewport.
         assert_eq!(visible_regex_match_iter(&term, &regex).count(), 4096);
     }
+
+    #[test]
+    fn visible_multiline_regex_match() {
+        let term = mock_term("foo\r\nbar\r\nfoo\r\nbar");
+        let regex = RegexSearch::new(r"foo\nbar").unwrap();
+
+        let matches = visible_regex_match_iter(&term, &regex).collect::<Vec<_>>();
+        assert_eq!(
+            matches,
+            vec![
+                Point::new(Line(0), Column(0))..=Point::new(Line(1), Column(2)),
+                Point::new(Line(2), Column(0))..=Point::new(Line(3), Column(2)),
+            ]
+        );
+    }
 }
@@ -45,9 +45,11 @@ use crate::display::cursor::IntoRects;
 use crate::display::damage::RenderDamageIterator;
 use crate::display::hint::{HintMatch, HintState};
 use crate::display::meter::Meter;
+use crate::display::scrollbar::{Scrollbar, ScrollbarGeometry};
 use crate::display::window::Window;
-use crate::event::{Event, EventType, Mouse, SearchState};
+use crate::event::{Event, EventType, KeySequence, Mouse, SearchState};
 use crate::message_bar::{MessageBuffer, MessageType};
+use crate::renderer::graphics::RenderGraphic;
 use crate::renderer::rects::{RenderLine, RenderLines, RenderRect};
 use crate::renderer::{self, GlyphCache, Renderer};
 use crate::scheduler::{Scheduler, TimerId, Topic};
@@ -56,6 +58,7 @@ use crate::string::{ShortenDirection, StrShortener};
 pub mod content;
 pub mod cursor;
 pub mod hint;
+pub mod scrollbar;
 pub mod window;
 
 mod bell;
@@ -353,6 +356,9 @@ pub struct Display {
 
     pub visual_bell: VisualBell,
 
+    /// Scrollbar interaction state.
+    pub scrollbar: Scrollbar,
+
     /// Mapped RGB values for each terminal color.
     pub colors: List,
 
@@ -399,7 +405,7 @@ impl Display {
         let rasterizer = Rasterizer::new(scale_factor)?;
 
         debug!("Loading \"{}\" font", &config.font.normal().family);
-        let mut glyph_cache = GlyphCache::new(rasterizer, &config.font)?;
+        let mut glyph_cache = GlyphCache::new(rasterizer, &config.font, scale_factor)?;
 
         let metrics = glyph_cache.font_metrics();
         let (cell_width, cell_height) = compute_cell_size(config, &metrics);
@@ -513,6 +519,7 @@ impl Display {
             cursor_hidden: false,
             frame_timer: FrameTimer::new(),
             visual_bell: VisualBell::from(&config.bell),
+            scrollbar: Default::default(),
             colors: List::from(&config.colors),
             pending_update: Default::default(),
             pending_renderer_update: Default::default(),
@@ -595,7 +602,7 @@ impl Display {
         terminal: &mut Term<T>,
         pty_resize_handle: &mut dyn OnResize,
         message_buffer: &MessageBuffer,
-        search_active: bool,
+        footer_active: bool,
         config: &UiConfig,
     ) where
         T: EventListener,
@@ -641,8 +648,8 @@ impl Display {
 
         // Update number of column/lines in the viewport.
         let message_bar_lines = message_buffer.message().map_or(0, |m| m.text(&new_size).len());
-        let search_lines = usize::from(search_active);
-        new_size.reserve_lines(message_bar_lines + search_lines);
+        let footer_lines = usize::from(footer_active);
+        new_size.reserve_lines(message_bar_lines + footer_lines);
 
         // Update resize increments.
         if config.window.resize_increments {
@@ -653,6 +660,7 @@ impl Display {
         pty_resize_handle.on_resize(new_size.into());
 
         // Resize terminal.
+        terminal.set_window_size(new_size.into());
         terminal.resize(new_size);
 
         // Queue renderer update if terminal dimensions/padding changed.
@@ -753,6 +761,7 @@ impl Display {
     /// A reference to Term whose state is being drawn must be provided.
     ///
     /// This call may block if vsync is enabled.
+    #[allow(clippy::too_many_arguments)]
     pub fn draw<T: EventListener>(
         &mut self,
         mut terminal: MutexGuard<'_, Term<T>>,
@@ -760,11 +769,19 @@ impl Display {
         message_buffer: &MessageBuffer,
         config: &UiConfig,
         search_state: &SearchState,
+        key_sequence: &KeySequence,
+        custom_mode: Option<&str>,
     ) {
         // Collect renderable content before the terminal is dropped.
         let mut content = RenderableContent::new(config, self, &terminal, search_state);
         let mut grid_cells = Vec::new();
+        let mut graphics = Vec::new();
         for cell in &mut content {
+            if let Some(extra) = &cell.extra {
+                let point = cell.point;
+                let cell_graphics = extra.graphics.iter().cloned();
+                graphics.extend(cell_graphics.map(|graphic| RenderGraphic { point, graphic }));
+            }
             grid_cells.push(cell);
         }
         let selection_range = content.selection_range();
@@ -785,6 +802,8 @@ impl Display {
             self.update_damage(&mut terminal, selection_range, search_state);
         }
 
+        let scrollbar_rects = self.scrollbar_rects(config, &terminal, search_state, scheduler);
+
         // Drop terminal as early as possible to free lock.
         drop(terminal);
 
@@ -794,6 +813,11 @@ impl Display {
         self.renderer.clear(background_color, config.window_opacity());
         let mut lines = RenderLines::new();
 
+        // Graphics with a negative z-index are drawn below the text.
+        let (graphics_below, graphics): (Vec<_>, Vec<_>) =
+            graphics.into_iter().partition(|graphic| graphic.graphic.z_index < 0);
+        self.renderer.draw_graphics(&size_info, graphics_below);
+
         // Optimize loop hint comparator.
         let has_highlighted_hint =
             self.highlighted_hint.is_some() || self.vi_highlighted_hint.is_some();
@@ -839,6 +863,8 @@ impl Display {
             );
         }
 
+        self.renderer.draw_graphics(&size_info, graphics);
+
         let mut rects = lines.rects(&metrics, &size_info);
 
         if let Some(vi_cursor_point) = vi_cursor_point {
@@ -856,6 +882,8 @@ impl Display {
         // Draw cursor.
         rects.extend(cursor.rects(&size_info, config.terminal_config.cursor.thickness()));
 
+        rects.extend(scrollbar_rects);
+
         // Push visual bell after url/underline/strikeout rects.
         let visual_bell_intensity = self.visual_bell.intensity();
         if visual_bell_intensity != 0. {
@@ -878,10 +906,17 @@ impl Display {
                     Direction::Left => BACKWARD_SEARCH_LABEL,
                 };
 
-                let search_text = Self::format_search(regex, search_label, size_info.columns());
+                // Reserve space for the match count, unless it would hide the regex.
+                let columns = size_info.columns();
+                let match_label = search_state
+                    .match_label()
+                    .filter(|label| search_label.len() + label.len() + 2 < columns);
+                let label_width = match_label.as_ref().map_or(0, |label| label.len() + 1);
+
+                let search_text = Self::format_search(regex, search_label, columns - label_width);
 
                 // Render the search bar.
-                self.draw_search(config, &search_text);
+                self.draw_search(config, &search_text, match_label.as_deref());
 
                 // Draw search bar cursor.
                 let line = size_info.screen_lines();
@@ -900,6 +935,11 @@ impl Display {
                 Some(Point::new(line, column))
             },
             None => {
+                // Show the active binding mode in place of the search bar.
+                if let Some(mode) = custom_mode {
+                    self.draw_custom_mode(config, mode);
+                }
+
                 let num_lines = self.size_info.screen_lines();
                 term::point_to_viewport(display_offset, cursor_point)
                     .filter(|point| point.line < num_lines)
@@ -966,6 +1006,11 @@ impl Display {
 
         self.draw_render_timer(config);
 
+        // Show keys of an incomplete key sequence.
+        if !key_sequence.is_empty() {
+            self.draw_key_sequence(config, &key_sequence.label());
+        }
+
         // Draw hyperlink uri preview.
         if has_highlighted_hint {
             let cursor_point = vi_cursor_point.or(Some(cursor_point));
@@ -1242,10 +1287,11 @@ impl Display {
 
     /// Draw current search regex.
     #[inline(never)]
-    fn draw_search(&mut self, config: &UiConfig, text: &str) {
-        // Assure text length is at least num_cols.
+    fn draw_search(&mut self, config: &UiConfig, text: &str, match_label: Option<&str>) {
+        // Assure text length is at least num_cols, with the match label right-aligned.
         let num_cols = self.size_info.columns();
-        let text = format!("{:<1$}", text, num_cols);
+        let match_label = match_label.unwrap_or_default();
+        let text = format!("{:<1$}{2}", text, num_cols - match_label.len(), match_label);
 
         let point = Point::new(self.size_info.screen_lines(), Column(0));
 
@@ -1262,6 +1308,72 @@ impl Display {
         );
     }
 
+    /// Draw the active binding mode in the footer bar.
+    fn draw_custom_mode(&mut self, config: &UiConfig, mode: &str) {
+        if self.collect_damage() {
+            let point = Point::new(self.size_info.screen_lines(), Column(0));
+            let damage = self.damage_from_point(point, self.size_info.columns() as u32);
+            self.damage_rects.push(damage);
+        }
+
+        let text = format!("-- {} --", mode);
+        let text: String =
+            StrShortener::new(&text, self.size_info.columns(), ShortenDirection::Right, None)
+                .collect();
+        self.draw_search(config, &text, None);
+    }
+
+    /// Update the scrollbar and get its rects, if it is visible.
+    fn scrollbar_rects<T>(
+        &mut self,
+        config: &UiConfig,
+        terminal: &Term<T>,
+        search_state: &SearchState,
+        scheduler: &mut Scheduler,
+    ) -> Vec<RenderRect> {
+        let scrollbar_config = &config.window.scrollbar;
+        let display_offset = terminal.grid().display_offset();
+        self.scrollbar.update_display_offset(display_offset);
+
+        let geometry = ScrollbarGeometry::new(
+            scrollbar_config,
+            &self.size_info,
+            self.window.scale_factor as f32,
+            terminal.history_size(),
+            display_offset,
+        );
+        let geometry = match geometry.filter(|_| self.scrollbar.is_visible(scrollbar_config)) {
+            Some(geometry) => geometry,
+            None => return Vec::new(),
+        };
+
+        // Redraw once the scrollbar should be hidden again.
+        if let Some(timeout) = self.scrollbar.hide_timeout(scrollbar_config) {
+            let window_id = self.window.id();
+            let timer_id = TimerId::new(Topic::ScrollbarHide, window_id);
+            let event = Event::new(EventType::ScrollbarHide, window_id);
+            scheduler.unschedule(timer_id);
+            scheduler.schedule(event, timeout, false, timer_id);
+        }
+
+        // Damage the scrollbar for the next frame as well, so it is cleared once hidden.
+        if self.collect_damage() {
+            let x = geometry.x() as i32;
+            let width = self.size_info.width() as i32 - x;
+            let damage = DamageRect::new(x, 0, width, self.size_info.height() as i32);
+            self.damage_rects.push(damage);
+            self.next_frame_damage_rects.push(damage);
+        }
+
+        let search_lines = search_state
+            .match_scan()
+            .filter(|scan| scan.is_current(terminal))
+            .into_iter()
+            .flat_map(|scan| scan.lines(terminal));
+
+        geometry.rects(&config.colors, search_lines, terminal.prompt_lines())
+    }
+
     /// Draw render timer.
     #[inline(never)]
     fn draw_render_timer(&mut self, config: &UiConfig) {
@@ -1288,6 +1400,30 @@ impl Display {
         self.renderer.draw_string(point, fg, bg, timing.chars(), &self.size_info, glyph_cache);
     }
 
+    /// Draw pending keys in the bottom right corner.
+    #[inline(never)]
+    fn draw_key_sequence(&mut self, config: &UiConfig, label: &str) {
+        let num_cols = self.size_info.columns();
+        let text = format!(" {} ", label);
+        let text: String =
+            StrShortener::new(&text, num_cols, ShortenDirection::Left, None).collect();
+        let column = Column(num_cols.saturating_sub(text.chars().count()));
+        let point = Point::new(self.size_info.bottommost_line().0 as usize, column);
+
+        if self.collect_damage() {
+            let damage = self.damage_from_point(point, (num_cols - column.0) as u32);
+            self.damage_rects.push(damage);
+
+            // Damage the key sequence for the next frame, to clear it once it is complete.
+            self.next_frame_damage_rects.push(damage);
+        }
+
+        let fg = config.colors.footer_bar_foreground();
+        let bg = config.colors.footer_bar_background();
+        let glyph_cache = &mut self.glyph_cache;
+        self.renderer.draw_string(point, fg, bg, text.chars(), &self.size_info, glyph_cache);
+    }
+
     /// Draw an indicator for the position of a line in history.
     #[inline(never)]
     fn draw_line_indicator(
@@ -0,0 +1,331 @@
+use std::time::{Duration, Instant};
+
+use alacritty_terminal::grid::Dimensions;
+use alacritty_terminal::index::Line;
+use alacritty_terminal::term::color::Rgb;
+
+use crate::config::color::Colors;
+use crate::config::window::{ScrollbarConfig, ScrollbarMode};
+use crate::display::SizeInfo;
+use crate::renderer::rects::RenderRect;
+
+/// Opacity of the scrollbar thumb, to keep the text below it readable.
+const THUMB_ALPHA: f32 = 0.5;
+
+/// Scrollbar interaction state.
+#[derive(Default, Debug)]
+pub struct Scrollbar {
+    /// Last time the scrollbar was used or the viewport was scrolled.
+    last_activity: Option<Instant>,
+
+    /// Display offset during the last update.
+    display_offset: usize,
+
+    /// Mouse cursor is above the scrollbar.
+    hovered: bool,
+
+    /// Distance between the mouse and the top of the thumb while it is dragged.
+    drag_offset: Option<f32>,
+}
+
+impl Scrollbar {
+    /// Check if the scrollbar should be drawn.
+    pub fn is_visible(&self, config: &ScrollbarConfig) -> bool {
+        match config.mode {
+            ScrollbarMode::Never => false,
+            ScrollbarMode::Always => true,
+            ScrollbarMode::Auto => self.hide_timeout(config).is_some() || self.is_active(),
+        }
+    }
+
+    /// Time until an automatically hidden scrollbar disappears.
+    ///
+    /// Returns `None` while the scrollbar is used, or if it is not hidden automatically.
+    pub fn hide_timeout(&self, config: &ScrollbarConfig) -> Option<Duration> {
+        if config.mode != ScrollbarMode::Auto || self.is_active() {
+            return None;
+        }
+
+        let elapsed = self.last_activity?.elapsed();
+        config.hide_delay().checked_sub(elapsed).filter(|timeout| !timeout.is_zero())
+    }
+
+    /// Track changes of the display offset, to show the scrollbar while scrolling.
+    pub fn update_display_offset(&mut self, display_offset: usize) {
+        if self.display_offset != display_offset {
+            self.display_offset = display_offset;
+            self.last_activity = Some(Instant::now());
+        }
+    }
+
+    /// Check if the mouse cursor is above the scrollbar.
+    pub fn is_hovered(&self) -> bool {
+        self.hovered
+    }
+
+    /// Update the hover state, returning `true` if it has changed.
+    pub fn set_hovered(&mut self, hovered: bool) -> bool {
+        if self.hovered == hovered {
+            return false;
+        }
+
+        self.hovered = hovered;
+        self.last_activity = Some(Instant::now());
+
+        true
+    }
+
+    /// Start dragging the thumb at the mouse position.
+    ///
+    /// Clicking outside of the thumb centers it around the mouse first. Returns the display offset
+    /// for the new thumb position.
+    pub fn start_drag(&mut self, geometry: &ScrollbarGeometry, mouse_y: f32) -> usize {
+        let thumb_offset = mouse_y - geometry.thumb_y;
+        let drag_offset = if (0. ..geometry.thumb_height).contains(&thumb_offset) {
+            thumb_offset
+        } else {
+            geometry.thumb_height / 2.
+        };
+        self.drag_offset = Some(drag_offset);
+
+        geometry.display_offset_at(mouse_y - drag_offset)
+    }
+
+    /// Display offset for the mouse position while the thumb is dragged.
+    pub fn drag(&self, geometry: &ScrollbarGeometry, mouse_y: f32) -> Option<usize> {
+        self.drag_offset.map(|drag_offset| geometry.display_offset_at(mouse_y - drag_offset))
+    }
+
+    /// Stop dragging the thumb, returning `true` if it was dragged.
+    pub fn end_drag(&mut self) -> bool {
+        self.last_activity = Some(Instant::now());
+        self.drag_offset.take().is_some()
+    }
+
+    /// Check if the scrollbar is currently used by the mouse.
+    fn is_active(&self) -> bool {
+        self.hovered || self.drag_offset.is_some()
+    }
+}
+
+/// Position of the scrollbar and its thumb in the window.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct ScrollbarGeometry {
+    x: f32,
+    width: f32,
+    track_y: f32,
+    track_height: f32,
+    thumb_y: f32,
+    thumb_height: f32,
+    tick_height: f32,
+    history_size: usize,
+    total_lines: usize,
+}
+
+impl ScrollbarGeometry {
+    /// Compute the scrollbar layout, if there is any history to scroll through.
+    pub fn new(
+        config: &ScrollbarConfig,
+        size_info: &SizeInfo,
+        scale_factor: f32,
+        history_size: usize,
+        display_offset: usize,
+    ) -> Option<Self> {
+        let width = config.width(scale_factor).min(size_info.width());
+        if config.mode == ScrollbarMode::Never || history_size == 0 || width <= 0. {
+            return None;
+        }
+
+        let screen_lines = size_info.screen_lines();
+        let total_lines = history_size + screen_lines;
+
+        // Keep the thumb at least one line high, so it can always be grabbed.
+        let track_y = size_info.padding_y();
+        let track_height = screen_lines as f32 * size_info.cell_height();
+        let thumb_height = (track_height * screen_lines as f32 / total_lines as f32)
+            .max(size_info.cell_height())
+            .min(track_height);
+
+        // The thumb is at the bottom of the track without display offset.
+        let scrolled = (history_size - display_offset.min(history_size)) as f32;
+        let thumb_y = track_y + (track_height - thumb_height) * scrolled / history_size as f32;
+
+        Some(Self {
+            x: size_info.width() - width,
+            width,
+            track_y,
+            track_height,
+            thumb_y,
+            thumb_height,
+            tick_height: (size_info.cell_height() / 8.).max(1.).floor(),
+            history_size,
+            total_lines,
+        })
+    }
+
+    /// Check if a point in the window is above the scrollbar.
+    pub fn contains(&self, x: f32, y: f32) -> bool {
+        x >= self.x && y >= self.track_y && y < self.track_y + self.track_height
+    }
+
+    /// Rectangles for the scrollbar with ticks marking prompts and search matches.
+    ///
+    /// The lines of the ticks must be in ascending order.
+    pub fn rects(
+        &self,
+        colors: &Colors,
+        search_lines: impl Iterator<Item = Line>,
+        prompt_lines: impl Iterator<Item = Line>,
+    ) -> Vec<RenderRect> {
+        let mut rects = Vec::new();
+
+        if let Some(track) = colors.scrollbar_track() {
+            let track =
+                RenderRect::new(self.x, self.track_y, self.width, self.track_height, track, 1.);
+            rects.push(track);
+        }
+
+        let thumb_color = colors.scrollbar_thumb();
+        let (y, height) = (self.thumb_y, self.thumb_height);
+        rects.push(RenderRect::new(self.x, y, self.width, height, thumb_color, THUMB_ALPHA));
+
+        self.push_ticks(&mut rects, prompt_lines, colors.scrollbar_prompt());
+        self.push_ticks(&mut rects, search_lines, colors.scrollbar_search_match());
+
+        rects
+    }
+
+    /// Add ticks for lines, skipping lines which would cover the same pixels.
+    fn push_ticks(
+        &self,
+        rects: &mut Vec<RenderRect>,
+        lines: impl Iterator<Item = Line>,
+        color: Rgb,
+    ) {
+        let max_y = self.track_y + self.track_height - self.tick_height;
+
+        let mut last_y = None;
+        for line in lines {
+            let fraction = (line.0 + self.history_size as i32) as f32 / self.total_lines as f32;
+            let y = (self.track_y + self.track_height * fraction).round().min(max_y);
+            if last_y.map_or(false, |last_y| y - last_y < self.tick_height) {
+                continue;
+            }
+            last_y = Some(y);
+
+            rects.push(RenderRect::new(self.x, y, self.width, self.tick_height, color, 1.));
+        }
+    }
+
+    /// Display offset placing the top of the thumb at the specified position.
+    fn display_offset_at(&self, thumb_y: f32) -> usize {
+        let range = self.track_height - self.thumb_height;
+        if range <= 0. {
+            return 0;
+        }
+
+        let fraction = ((thumb_y - self.track_y) / range).clamp(0., 1.);
+        self.history_size - (fraction * self.history_size as f32).round() as usize
+    }
+
+    /// Left edge of the scrollbar.
+    pub fn x(&self) -> f32 {
+        self.x
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn geometry(history_size: usize, display_offset: usize) -> Option<ScrollbarGeometry> {
+        let mut config = ScrollbarConfig::default();
+        config.mode = ScrollbarMode::Always;
+        let size_info = SizeInfo::new(100., 100., 10., 10., 0., 0., false);
+        ScrollbarGeometry::new(&config, &size_info, 1., history_size, display_offset)
+    }
+
+    #[test]
+    fn thumb_position() {
+        assert_eq!(geometry(0, 0), None);
+
+        let bottom = geometry(30, 0).unwrap();
+        assert_eq!(bottom.thumb_height, 25.);
+        assert_eq!(bottom.thumb_y, 75.);
+        assert!(bottom.contains(95., 50.));
+        assert!(!bottom.contains(91., 50.));
+
+        let top = geometry(30, 30).unwrap();
+        assert_eq!(top.thumb_y, 0.);
+    }
+
+    #[test]
+    fn auto_hide() {
+        let mut config = ScrollbarConfig::default();
+        config.mode = ScrollbarMode::Auto;
+        let mut scrollbar = Scrollbar::default();
+
+        // Hidden until the viewport is scrolled.
+        assert!(!scrollbar.is_visible(&config));
+        assert_eq!(scrollbar.hide_timeout(&config), None);
+        scrollbar.update_display_offset(0);
+        assert!(!scrollbar.is_visible(&config));
+
+        scrollbar.update_display_offset(3);
+        assert!(scrollbar.is_visible(&config));
+        assert!(scrollbar
+            .hide_timeout(&config)
+            .map_or(false, |timeout| timeout <= config.hide_delay()));
+
+        // Hidden again once the delay has passed.
+        let expired = Instant::now() - config.hide_delay() - Duration::from_millis(1);
+        scrollbar.last_activity = Some(expired);
+        assert!(!scrollbar.is_visible(&config));
+        assert_eq!(scrollbar.hide_timeout(&config), None);
+
+        // Visible without timeout while hovered.
+        assert!(scrollbar.set_hovered(true));
+        assert!(!scrollbar.set_hovered(true));
+        assert!(scrollbar.is_visible(&config));
+        assert_eq!(scrollbar.hide_timeout(&config), None);
+
+        // Leaving the scrollbar restarts the delay.
+        assert!(scrollbar.set_hovered(false));
+        assert!(scrollbar.is_visible(&config));
+        assert!(scrollbar.hide_timeout(&config).is_some());
+
+        // Other modes never time out.
+        config.mode = ScrollbarMode::Always;
+        scrollbar.last_activity = Some(expired);
+        assert!(scrollbar.is_visible(&config));
+        assert_eq!(scrollbar.hide_timeout(&config), None);
+    }
+
+    #[test]
+    fn drag_thumb() {
+        let geometry = geometry(30, 0).unwrap();
+        let mut scrollbar = Scrollbar::default();
+
+        // Dragging the thumb keeps the distance between mouse and thumb.
+        assert_eq!(scrollbar.start_drag(&geometry, 80.), 0);
+        assert_eq!(scrollbar.drag(&geometry, 5.), Some(30));
+        assert_eq!(scrollbar.drag(&geometry, 42.5), Some(15));
+        assert!(scrollbar.end_drag());
+        assert_eq!(scrollbar.drag(&geometry, 5.), None);
+
+        // Clicking the track centers the thumb around the mouse.
+        assert_eq!(scrollbar.start_drag(&geometry, 12.5), 30);
+    }
+
+    #[test]
+    fn tick_positions() {
+        let geometry = geometry(30, 0).unwrap();
+        let search_lines = [Line(-30), Line(-29), Line(9)];
+        let rects =
+            geometry.rects(&Colors::default(), search_lines.into_iter(), [Line(0)].into_iter());
+
+        // Thumb, followed by the prompt and search match ticks.
+        let positions: Vec<_> = rects.iter().map(|rect| rect.y).collect();
+        assert_eq!(positions, [75., 75., 0., 3., 98.]);
+    }
+}
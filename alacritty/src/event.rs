@@ -6,58 +6,74 @@ use std::collections::{HashMap, HashSet, VecDeque};
 use std::error::Error;
 use std::ffi::OsStr;
 use std::fmt::Debug;
+use std::fs::{self, File};
 #[cfg(not(windows))]
 use std::os::unix::io::RawFd;
+#[cfg(unix)]
+use std::os::unix::net::UnixStream;
 use std::path::PathBuf;
 use std::rc::Rc;
-use std::time::{Duration, Instant};
-use std::{env, f32, mem};
+#[cfg(unix)]
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use std::{env, f32, iter, mem};
 
 use ahash::RandomState;
 use crossfont::{self, Size};
 use log::{debug, error, info, warn};
 use winit::event::{
-    ElementState, Event as WinitEvent, Ime, Modifiers, MouseButton, StartCause,
+    ElementState, Event as WinitEvent, Ime, KeyEvent, Modifiers, MouseButton, StartCause,
     Touch as TouchEvent, WindowEvent,
 };
 use winit::event_loop::{
     ControlFlow, DeviceEvents, EventLoop, EventLoopProxy, EventLoopWindowTarget,
 };
+use winit::keyboard::{Key, ModifiersState};
+use winit::platform::modifier_supplement::KeyEventExtModifierSupplement;
 use winit::window::raw_window_handle::HasRawDisplayHandle;
 use winit::window::WindowId;
 
 use alacritty_terminal::config::LOG_TARGET_CONFIG;
-use alacritty_terminal::event::{Event as TerminalEvent, EventListener, Notify};
-use alacritty_terminal::event_loop::Notifier;
+use alacritty_terminal::event::{Event as TerminalEvent, EventListener, Notify, WindowSize};
+use alacritty_terminal::event_loop::{Msg, Notifier};
 use alacritty_terminal::grid::{Dimensions, Scroll};
 use alacritty_terminal::index::{Boundary, Column, Direction, Line, Point, Side};
+use alacritty_terminal::recording::Recorder;
+use alacritty_terminal::replay::{Control as ReplayControl, Controller as ReplayController};
 use alacritty_terminal::selection::{Selection, SelectionType};
-use alacritty_terminal::term::search::{Match, RegexSearch};
+use alacritty_terminal::term::search::{Match, MatchScan, RegexSearch};
 use alacritty_terminal::term::{self, ClipboardType, Term, TermMode};
+use alacritty_terminal::vi_mode::InlineSearch;
 
 #[cfg(unix)]
-use crate::cli::IpcConfig;
-use crate::cli::{Options as CliOptions, WindowOptions};
+use crate::cli::{IpcAction, IpcConfig, IpcGetConfig, IpcGetText, IpcSendText};
+use crate::cli::{Options as CliOptions, TerminalOptions, WindowOptions};
 use crate::clipboard::Clipboard;
 use crate::config::ui_config::{HintAction, HintInternalAction};
 use crate::config::{self, UiConfig};
 #[cfg(not(windows))]
-use crate::daemon::foreground_process_path;
+use crate::daemon;
 use crate::daemon::spawn_daemon;
 use crate::display::hint::HintMatch;
 use crate::display::window::Window;
 use crate::display::{Display, Preedit, SizeInfo};
 use crate::input::{self, ActionContext as _, FONT_SIZE_STEP};
+#[cfg(unix)]
+use crate::ipc::{self, IpcEvent, SocketReply, Subscriber};
 use crate::message_bar::{Message, MessageBuffer};
 use crate::scheduler::{Scheduler, TimerId, Topic};
+use crate::session::{self, WindowSession};
 use crate::window_context::WindowContext;
 
-/// Duration after the last user input until an unlimited search is performed.
+/// Duration after the last user input until the entire history is searched.
 pub const TYPING_SEARCH_DELAY: Duration = Duration::from_millis(500);
 
 /// Maximum number of lines for the blocking search while still typing the search regex.
 const MAX_SEARCH_WHILE_TYPING: Option<usize> = Some(1000);
 
+/// Number of lines searched per event loop iteration while counting search matches.
+const SEARCH_SCAN_LINES: usize = 1000;
+
 /// Maximum number of search terms stored in the history.
 const MAX_SEARCH_HISTORY_SIZE: usize = 255;
 
@@ -96,9 +112,26 @@ pub enum EventType {
     CreateWindow(WindowOptions),
     #[cfg(unix)]
     IpcConfig(IpcConfig),
+    #[cfg(unix)]
+    IpcListWindows(Arc<UnixStream>),
+    #[cfg(unix)]
+    IpcGetText(IpcGetText, Arc<UnixStream>),
+    #[cfg(unix)]
+    IpcGetConfig(IpcGetConfig, Arc<UnixStream>),
+    #[cfg(unix)]
+    IpcSubscribe(Arc<UnixStream>),
+    #[cfg(unix)]
+    IpcAction(IpcAction, Arc<UnixStream>),
+    #[cfg(unix)]
+    IpcSendText(IpcSendText, Arc<UnixStream>),
     BlinkCursor,
     BlinkCursorTimeout,
-    SearchNext,
+    SearchScan,
+    KeySequenceTimeout,
+    ScrollbarHide,
+    /// Termination was requested by a signal.
+    #[cfg(unix)]
+    Terminate,
     Frame,
 }
 
@@ -125,6 +158,15 @@ pub struct SearchState {
     /// Focused match during active search.
     focused_match: Option<Match>,
 
+    /// One-based index of the focused match within all matches.
+    focused_index: Option<usize>,
+
+    /// Incremental count of all matches in the terminal.
+    scan: Option<MatchScan>,
+
+    /// Direction of the match focused once the scan completes without a focused match.
+    scan_direction: Direction,
+
     /// Search regex and history.
     ///
     /// During an active search, the first element is the user's current input.
@@ -158,6 +200,31 @@ impl SearchState {
         self.dfas.as_ref()
     }
 
+    /// Incremental scan for all matches of the active search.
+    pub fn match_scan(&self) -> Option<&MatchScan> {
+        self.scan.as_ref()
+    }
+
+    /// Match count shown in the search bar, like `match 3 of 12`.
+    pub fn match_label(&self) -> Option<String> {
+        let scan = self.scan.as_ref()?;
+        let count = scan.count();
+
+        let label = if !scan.is_complete() {
+            format!("{count}+ matches")
+        } else if count == 0 {
+            String::from("no matches")
+        } else if let Some(index) = self.focused_index {
+            format!("match {index} of {count}")
+        } else if count == 1 {
+            String::from("1 match")
+        } else {
+            format!("{count} matches")
+        };
+
+        Some(label)
+    }
+
     /// Search regex text if a search is active.
     fn regex_mut(&mut self) -> Option<&mut String> {
         self.history_index.and_then(move |index| self.history.get_mut(index))
@@ -170,6 +237,9 @@ impl Default for SearchState {
             direction: Direction::Right,
             display_offset_delta: Default::default(),
             focused_match: Default::default(),
+            focused_index: Default::default(),
+            scan: Default::default(),
+            scan_direction: Direction::Right,
             history_index: Default::default(),
             history: Default::default(),
             origin: Default::default(),
@@ -178,6 +248,224 @@ impl Default for SearchState {
     }
 }
 
+/// Maximum count for repeating vi mode motions.
+const MAX_VI_COUNT: usize = 10_000;
+
+/// Pending vi mode input, like counts and operators.
+#[derive(Default)]
+pub struct ViState {
+    /// Count typed before the next motion.
+    count: Option<usize>,
+
+    /// Pending yank operator, with the count typed before it.
+    pub yank: Option<usize>,
+
+    /// Pending text object after an operator, `true` for inner text objects.
+    pub text_object: Option<bool>,
+
+    /// Inline search waiting for its character, with its direction and whether it stops short.
+    pub inline_search_pending: Option<(Direction, bool)>,
+
+    /// Last inline search, repeated by `InlineSearchNext` and `InlineSearchPrevious`.
+    pub inline_search: Option<InlineSearch>,
+
+    /// Mark action waiting for the name of its mark.
+    pub mark_pending: Option<MarkAction>,
+}
+
+impl ViState {
+    /// Add a digit to the pending count.
+    pub fn push_count_digit(&mut self, digit: u32) {
+        let count = self.count.unwrap_or(0).saturating_mul(10).saturating_add(digit as usize);
+        self.count = Some(count.min(MAX_VI_COUNT));
+    }
+
+    /// Check if a count is being typed.
+    pub fn count_pending(&self) -> bool {
+        self.count.is_some()
+    }
+
+    /// Take the number of times the next motion should be repeated.
+    ///
+    /// This includes the count typed before a pending operator.
+    pub fn take_count(&mut self) -> usize {
+        let count = self.count.take().unwrap_or(1);
+        count.saturating_mul(self.yank.unwrap_or(1)).min(MAX_VI_COUNT)
+    }
+
+    /// Check if any input is waiting for completion.
+    pub fn is_pending(&self) -> bool {
+        self.count.is_some()
+            || self.yank.is_some()
+            || self.text_object.is_some()
+            || self.inline_search_pending.is_some()
+            || self.mark_pending.is_some()
+    }
+
+    /// Discard all incomplete input.
+    pub fn clear_pending(&mut self) {
+        self.count = None;
+        self.yank = None;
+        self.text_object = None;
+        self.inline_search_pending = None;
+        self.mark_pending = None;
+    }
+}
+
+/// Vi mode action taking the name of a mark.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum MarkAction {
+    /// Set the mark to the vi mode cursor position.
+    Set,
+    /// Jump to the mark's position.
+    Jump,
+    /// Jump to the first occupied cell in the mark's line.
+    JumpLine,
+}
+
+/// Keys pressed as part of an incomplete key sequence.
+pub struct KeySequence<K = KeyEvent> {
+    keys: Vec<(K, ModifiersState)>,
+}
+
+impl<K> Default for KeySequence<K> {
+    fn default() -> Self {
+        Self { keys: Vec::new() }
+    }
+}
+
+impl<K> KeySequence<K> {
+    /// Pressed keys, with the modifiers active during each key press.
+    pub fn keys(&self) -> &[(K, ModifiersState)] {
+        &self.keys
+    }
+
+    /// Add a key to the sequence.
+    pub fn push(&mut self, key: K, mods: ModifiersState) {
+        self.keys.push((key, mods));
+    }
+
+    /// Remove all keys from the sequence.
+    pub fn take(&mut self) -> Vec<(K, ModifiersState)> {
+        mem::take(&mut self.keys)
+    }
+
+    /// Check if no key sequence is in progress.
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+}
+
+impl KeySequence {
+    /// Human readable description of the pressed keys, like `Ctrl+a c`.
+    pub fn label(&self) -> String {
+        let mut label = String::new();
+        for (key, mods) in &self.keys {
+            if !label.is_empty() {
+                label.push(' ');
+            }
+
+            for (modifier, name) in [
+                (ModifiersState::CONTROL, "Ctrl+"),
+                (ModifiersState::ALT, "Alt+"),
+                (ModifiersState::SUPER, "Super+"),
+                (ModifiersState::SHIFT, "Shift+"),
+            ] {
+                if mods.contains(modifier) {
+                    label.push_str(name);
+                }
+            }
+
+            match key.key_without_modifiers() {
+                Key::Character(text) => label.push_str(&text),
+                key => label.push_str(&format!("{:?}", key)),
+            }
+        }
+        label
+    }
+}
+
+/// File format for exported terminal content.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// HTML document with inline styles.
+    Html,
+
+    /// Text with ANSI escape sequences.
+    Ansi,
+}
+
+/// Session recording state.
+pub struct Recording {
+    /// Channel to the PTY event loop, which writes the recording.
+    notifier: Notifier,
+
+    /// Path of the next recording.
+    path: Option<PathBuf>,
+
+    /// Whether input should be recorded.
+    record_input: bool,
+
+    /// Whether a recording is currently active.
+    active: bool,
+}
+
+impl Recording {
+    pub fn new(notifier: Notifier, options: &TerminalOptions) -> Self {
+        Self {
+            notifier,
+            path: options.record.clone(),
+            record_input: options.record_input,
+            active: false,
+        }
+    }
+
+    /// Start recording the session.
+    ///
+    /// The first recording is written to the path passed on the CLI, all others are written to
+    /// a new file in the current directory.
+    pub fn start(&mut self, window_size: WindowSize) {
+        let path = self.path.take().unwrap_or_else(|| {
+            let timestamp =
+                SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+            PathBuf::from(format!("alacritty-{timestamp}.cast"))
+        });
+
+        let recorder = File::create(&path)
+            .and_then(|file| Recorder::new(file, window_size, self.record_input));
+        match recorder {
+            Ok(recorder) => {
+                info!("Recording session to {:?}", path);
+                let _ = self.notifier.0.send(Msg::StartRecording(recorder));
+                self.active = true;
+            },
+            Err(err) => error!("Unable to record session to {:?}: {}", path, err),
+        }
+    }
+
+    /// Stop the active recording.
+    pub fn stop(&mut self) {
+        info!("Stopped session recording");
+        let _ = self.notifier.0.send(Msg::StopRecording);
+        self.active = false;
+    }
+
+    /// Reset the state after the PTY event loop stopped the recording.
+    pub fn on_stopped(&mut self) {
+        info!("Stopped session recording");
+        self.active = false;
+    }
+
+    /// Start or stop the recording.
+    pub fn toggle(&mut self, window_size: WindowSize) {
+        if self.active {
+            self.stop();
+        } else {
+            self.start(window_size);
+        }
+    }
+}
+
 pub struct ActionContext<'a, N, T> {
     pub notifier: &'a mut N,
     pub terminal: &'a mut Term<T>,
@@ -193,6 +481,11 @@ pub struct ActionContext<'a, N, T> {
     pub event_proxy: &'a EventLoopProxy<Event>,
     pub scheduler: &'a mut Scheduler,
     pub search_state: &'a mut SearchState,
+    pub vi_state: &'a mut ViState,
+    pub key_sequence: &'a mut KeySequence,
+    pub custom_mode: &'a mut Option<String>,
+    pub recording: &'a mut Recording,
+    pub replay: Option<&'a ReplayController>,
     pub font_size: &'a mut Size,
     pub dirty: &'a mut bool,
     pub occluded: &'a mut bool,
@@ -263,6 +556,51 @@ impl<'a, N: Notify + 'a, T: EventListener> input::ActionContext<T> for ActionCon
         self.clipboard.store(ty, text);
     }
 
+    /// Copy the HTML source of the text selection.
+    ///
+    /// The clipboard only supports plain text, so the markup is stored as text rather than as
+    /// formatted content.
+    fn copy_selection_html_source(&mut self) {
+        if self.selection_is_empty() {
+            return;
+        }
+
+        let palette = self.display.colors.palette();
+        if let Some(html) = self.terminal.selection_to_html(&palette) {
+            self.clipboard.store(ClipboardType::Clipboard, html);
+        }
+    }
+
+    #[inline]
+    fn vi_state(&mut self) -> &mut ViState {
+        self.vi_state
+    }
+
+    #[inline]
+    fn key_sequence(&mut self) -> &mut KeySequence {
+        self.key_sequence
+    }
+
+    #[inline]
+    fn custom_mode(&self) -> Option<&str> {
+        self.custom_mode.as_deref()
+    }
+
+    #[cfg(not(windows))]
+    fn foreground_process(&self) -> Option<daemon::ForegroundProcess> {
+        daemon::foreground_process(self.master_fd, self.shell_pid).ok()
+    }
+
+    fn set_custom_mode(&mut self, mode: Option<String>) {
+        // Showing or hiding the mode indicator changes the number of visible lines.
+        if self.custom_mode.is_some() != mode.is_some() {
+            self.display.pending_update.dirty = true;
+        }
+
+        *self.custom_mode = mode;
+        *self.dirty = true;
+    }
+
     fn selection_is_empty(&self) -> bool {
         self.terminal.selection.as_ref().map_or(true, Selection::is_empty)
     }
@@ -394,9 +732,7 @@ impl<'a, N: Notify + 'a, T: EventListener> input::ActionContext<T> for ActionCon
     #[cfg(not(windows))]
     fn create_new_window(&mut self, #[cfg(target_os = "macos")] tabbing_id: Option<String>) {
         let mut options = WindowOptions::default();
-        if let Ok(working_directory) = foreground_process_path(self.master_fd, self.shell_pid) {
-            options.terminal_options.working_directory = Some(working_directory);
-        }
+        options.terminal_options.working_directory = self.working_directory();
 
         #[cfg(target_os = "macos")]
         {
@@ -419,7 +755,7 @@ impl<'a, N: Notify + 'a, T: EventListener> input::ActionContext<T> for ActionCon
         S: AsRef<OsStr>,
     {
         #[cfg(not(windows))]
-        let result = spawn_daemon(program, args, self.master_fd, self.shell_pid);
+        let result = spawn_daemon(program, args, self.working_directory().as_deref());
         #[cfg(windows)]
         let result = spawn_daemon(program, args);
 
@@ -459,6 +795,7 @@ impl<'a, N: Notify + 'a, T: EventListener> input::ActionContext<T> for ActionCon
         self.search_state.history_index = Some(0);
         self.search_state.direction = direction;
         self.search_state.focused_match = None;
+        self.search_state.focused_index = None;
 
         // Store original search position as origin and reset location.
         if self.terminal.mode().contains(TermMode::VI) {
@@ -494,12 +831,19 @@ impl<'a, N: Notify + 'a, T: EventListener> input::ActionContext<T> for ActionCon
             return;
         }
 
-        // Force unlimited search if the previous one was interrupted.
-        let timer_id = TimerId::new(Topic::DelayedSearch, self.display.window.id());
-        if self.scheduler.scheduled(timer_id) {
+        // Force unlimited search if the match scan has not found a match yet.
+        let scan_complete =
+            self.search_state.scan.as_ref().map_or(false, |scan| scan.is_complete());
+        if self.search_state.focused_match.is_none() && !scan_complete {
             self.goto_match(None);
         }
 
+        // Allow returning to the search origin using the jump list.
+        let origin = self.search_state.origin;
+        if self.terminal.vi_mode_cursor.point != origin {
+            self.terminal.vi_marks.push_jump(origin);
+        }
+
         self.exit_search();
     }
 
@@ -604,10 +948,31 @@ impl<'a, N: Notify + 'a, T: EventListener> input::ActionContext<T> for ActionCon
         }
 
         // Search for the next match using the supplied direction.
+        let focused_index = self.search_state.focused_index;
         let search_direction = mem::replace(&mut self.search_state.direction, direction);
-        self.goto_match(None);
+        self.goto_match(MAX_SEARCH_WHILE_TYPING);
         self.search_state.direction = search_direction;
 
+        // Let the match scan find matches outside of the search limit.
+        if self.search_state.focused_match.is_none() {
+            self.start_search_scan(Duration::ZERO, direction);
+            return;
+        }
+
+        // Update the match index without searching the entire history again.
+        let scan = self.search_state.scan.as_ref();
+        match (scan, focused_index) {
+            (Some(scan), Some(index)) if scan.is_complete() && scan.is_current(self.terminal) => {
+                let count = scan.count();
+                let index = match direction {
+                    Direction::Right => index % count + 1,
+                    Direction::Left => (index + count - 2) % count + 1,
+                };
+                self.search_state.focused_index = Some(index);
+            },
+            _ => self.start_search_scan(Duration::ZERO, direction),
+        }
+
         // If we found a match, we set the search origin right in front of it to make sure that
         // after modifications to the regex the search is started without moving the focused match
         // around.
@@ -635,10 +1000,13 @@ impl<'a, N: Notify + 'a, T: EventListener> input::ActionContext<T> for ActionCon
 
     /// Find the next search match.
     fn search_next(&mut self, origin: Point, direction: Direction, side: Side) -> Option<Match> {
-        self.search_state
+        let regex_match = self
+            .search_state
             .dfas
             .as_ref()
-            .and_then(|dfas| self.terminal.search_next(dfas, origin, direction, side, None))
+            .and_then(|dfas| self.terminal.search_next(dfas, origin, direction, side, None));
+        self.terminal.grid_mut().release_compressed();
+        regex_match
     }
 
     #[inline]
@@ -835,10 +1203,50 @@ impl<'a, N: Notify + 'a, T: EventListener> input::ActionContext<T> for ActionCon
         self.window().set_ime_allowed(was_in_vi_mode);
 
         self.terminal.toggle_vi_mode();
+        self.vi_state.clear_pending();
 
         *self.dirty = true;
     }
 
+    /// Start or stop the session recording.
+    fn toggle_recording(&mut self) {
+        self.recording.toggle(self.display.size_info.into());
+    }
+
+    /// Write the entire scrollback history to a new file in the current directory.
+    fn save_scrollback(&mut self, format: ExportFormat) {
+        let start = Point::new(self.terminal.topmost_line(), Column(0));
+        let end = Point::new(self.terminal.bottommost_line(), self.terminal.last_column());
+
+        let (content, extension) = match format {
+            ExportFormat::Html => {
+                let palette = self.display.colors.palette();
+                let html = self.terminal.bounds_to_html(start, end, &palette);
+                (format!("<!DOCTYPE html>\n<meta charset=\"utf-8\">\n{html}\n"), "html")
+            },
+            ExportFormat::Ansi => (self.terminal.bounds_to_ansi(start, end), "txt"),
+        };
+
+        let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+        let path = PathBuf::from(format!("alacritty-{timestamp}.{extension}"));
+
+        match fs::write(&path, content) {
+            Ok(()) => info!("Saved scrollback to {:?}", path),
+            Err(err) => error!("Unable to save scrollback to {:?}: {}", path, err),
+        }
+    }
+
+    #[inline]
+    fn replay_active(&self) -> bool {
+        self.replay.is_some()
+    }
+
+    fn replay_control(&mut self, control: ReplayControl) {
+        if let Some(replay) = self.replay {
+            replay.send(control);
+        }
+    }
+
     fn message(&self) -> Option<&Message> {
         self.message_buffer.message()
     }
@@ -861,6 +1269,13 @@ impl<'a, N: Notify + 'a, T: EventListener> input::ActionContext<T> for ActionCon
 }
 
 impl<'a, N: Notify + 'a, T: EventListener> ActionContext<'a, N, T> {
+    /// Working directory for processes spawned from this terminal.
+    #[cfg(not(windows))]
+    fn working_directory(&self) -> Option<PathBuf> {
+        let reported = self.terminal.working_directory();
+        daemon::working_directory(reported, self.master_fd, self.shell_pid)
+    }
+
     fn update_search(&mut self) {
         let regex = match self.search_state.regex() {
             Some(regex) => regex,
@@ -876,12 +1291,16 @@ impl<'a, N: Notify + 'a, T: EventListener> ActionContext<'a, N, T> {
             // Stop search if there's nothing to search for.
             self.search_reset_state();
             self.search_state.dfas = None;
+            self.stop_search_scan();
         } else {
             // Create search dfas for the new regex string.
             self.search_state.dfas = RegexSearch::new(regex).ok();
 
             // Update search highlighting.
             self.goto_match(MAX_SEARCH_WHILE_TYPING);
+
+            // Count all matches once the user stopped typing.
+            self.start_search_scan(TYPING_SEARCH_DELAY, self.search_state.direction);
         }
 
         *self.dirty = true;
@@ -889,12 +1308,9 @@ impl<'a, N: Notify + 'a, T: EventListener> ActionContext<'a, N, T> {
 
     /// Reset terminal to the state before search was started.
     fn search_reset_state(&mut self) {
-        // Unschedule pending timers.
-        let timer_id = TimerId::new(Topic::DelayedSearch, self.display.window.id());
-        self.scheduler.unschedule(timer_id);
-
         // Clear focused match.
         self.search_state.focused_match = None;
+        self.search_state.focused_index = None;
 
         // The viewport reset logic is only needed for vi mode, since without it our origin is
         // always at the current display offset instead of at the vi cursor position which we need
@@ -925,44 +1341,116 @@ impl<'a, N: Notify + 'a, T: EventListener> ActionContext<'a, N, T> {
         let direction = self.search_state.direction;
         let clamped_origin = self.search_state.origin.grid_clamp(self.terminal, Boundary::Grid);
         match self.terminal.search_next(dfas, clamped_origin, direction, Side::Left, limit) {
-            Some(regex_match) => {
-                let old_offset = self.terminal.grid().display_offset() as i32;
+            Some(regex_match) => self.focus_match(regex_match),
+            // Reset viewport only when we know there is no match, to prevent unnecessary jumping.
+            None if limit.is_none() => self.search_reset_state(),
+            // Leave matches beyond the search limit to the match scan.
+            None => {
+                self.search_state.focused_match = None;
+                self.search_state.focused_index = None;
+            },
+        }
 
-                if self.terminal.mode().contains(TermMode::VI) {
-                    // Move vi cursor to the start of the match.
-                    self.terminal.vi_goto_point(*regex_match.start());
-                } else {
-                    // Select the match when vi mode is not active.
-                    self.terminal.scroll_to_point(*regex_match.start());
-                }
+        *self.dirty = true;
+    }
 
-                // Update the focused match.
-                self.search_state.focused_match = Some(regex_match);
+    /// Move the viewport or vi cursor to a search match and focus it.
+    fn focus_match(&mut self, regex_match: Match) {
+        let old_offset = self.terminal.grid().display_offset() as i32;
 
-                // Store number of lines the viewport had to be moved.
-                let display_offset = self.terminal.grid().display_offset();
-                self.search_state.display_offset_delta += old_offset - display_offset as i32;
+        if self.terminal.mode().contains(TermMode::VI) {
+            // Move vi cursor to the start of the match.
+            self.terminal.vi_goto_point(*regex_match.start());
+        } else {
+            // Select the match when vi mode is not active.
+            self.terminal.scroll_to_point(*regex_match.start());
+        }
 
-                // Since we found a result, we require no delayed re-search.
-                let timer_id = TimerId::new(Topic::DelayedSearch, self.display.window.id());
-                self.scheduler.unschedule(timer_id);
-            },
-            // Reset viewport only when we know there is no match, to prevent unnecessary jumping.
-            None if limit.is_none() => self.search_reset_state(),
+        // Update the focused match.
+        self.search_state.focused_match = Some(regex_match);
+        self.search_state.focused_index = None;
+
+        // Store number of lines the viewport had to be moved.
+        let display_offset = self.terminal.grid().display_offset();
+        self.search_state.display_offset_delta += old_offset - display_offset as i32;
+    }
+
+    /// Start counting all search matches after `delay`.
+    ///
+    /// When no match is focused once the scan is complete, the next match in `direction` is
+    /// focused instead.
+    fn start_search_scan(&mut self, delay: Duration, direction: Direction) {
+        self.stop_search_scan();
+
+        if self.search_state.dfas.is_none() {
+            return;
+        }
+
+        // Count matches relative to the focused match, or the search origin without one.
+        let pivot = match &self.search_state.focused_match {
+            Some(focused_match) => *focused_match.start(),
             None => {
-                // Schedule delayed search if we ran into our search limit.
-                let timer_id = TimerId::new(Topic::DelayedSearch, self.display.window.id());
-                if !self.scheduler.scheduled(timer_id) {
-                    let event = Event::new(EventType::SearchNext, self.display.window.id());
-                    self.scheduler.schedule(event, TYPING_SEARCH_DELAY, false, timer_id);
+                let origin = self.search_state.origin.grid_clamp(self.terminal, Boundary::Grid);
+                match direction {
+                    Direction::Right => origin,
+                    Direction::Left => origin.add(self.terminal, Boundary::Grid, 1),
                 }
+            },
+        };
 
-                // Clear focused match.
-                self.search_state.focused_match = None;
+        self.search_state.scan = Some(MatchScan::new(self.terminal, pivot));
+        self.search_state.scan_direction = direction;
+
+        let timer_id = TimerId::new(Topic::DelayedSearch, self.display.window.id());
+        let event = Event::new(EventType::SearchScan, self.display.window.id());
+        self.scheduler.schedule(event, delay, false, timer_id);
+    }
+
+    /// Search the next chunk of the terminal for matches.
+    fn advance_search_scan(&mut self) {
+        let (dfas, scan) = match (&self.search_state.dfas, &mut self.search_state.scan) {
+            (Some(dfas), Some(scan)) => (dfas, scan),
+            _ => return,
+        };
+
+        *self.dirty = true;
+
+        let complete = scan.advance(self.terminal, dfas, SEARCH_SCAN_LINES);
+        self.terminal.grid_mut().release_compressed();
+
+        // Yield to rendering and input between chunks.
+        if !complete {
+            let timer_id = TimerId::new(Topic::DelayedSearch, self.display.window.id());
+            let event = Event::new(EventType::SearchScan, self.display.window.id());
+            self.scheduler.schedule(event, Duration::ZERO, false, timer_id);
+            return;
+        }
+
+        let focused_start = self.search_state.focused_match.as_ref().map(|rm| *rm.start());
+        match focused_start {
+            // Focus matches which were beyond the search limit.
+            None => match scan.next_match(self.search_state.scan_direction) {
+                Some((regex_match, index)) => {
+                    self.focus_match(regex_match);
+                    self.search_state.focused_index = Some(index);
+                },
+                None => self.search_reset_state(),
             },
+            Some(start) if start == scan.pivot() => {
+                self.search_state.focused_index = scan.pivot_index();
+            },
+            // Recount if the focused match changed during the scan.
+            Some(_) => self.start_search_scan(Duration::ZERO, self.search_state.scan_direction),
         }
+    }
 
-        *self.dirty = true;
+    /// Stop counting search matches.
+    fn stop_search_scan(&mut self) {
+        let timer_id = TimerId::new(Topic::DelayedSearch, self.display.window.id());
+        self.scheduler.unschedule(timer_id);
+
+        self.search_state.scan = None;
+        self.search_state.focused_index = None;
     }
 
     /// Cleanup the search state.
@@ -976,6 +1464,8 @@ impl<'a, N: Notify + 'a, T: EventListener> ActionContext<'a, N, T> {
 
         // Clear focused match.
         self.search_state.focused_match = None;
+
+        self.stop_search_scan();
     }
 
     /// Update the cursor blinking state.
@@ -1178,7 +1668,9 @@ impl input::Processor<EventProxy, ActionContext<'_, Notifier, EventProxy>> {
     pub fn handle_event(&mut self, event: WinitEvent<Event>) {
         match event {
             WinitEvent::UserEvent(Event { payload, .. }) => match payload {
-                EventType::SearchNext => self.ctx.goto_match(None),
+                EventType::SearchScan => self.ctx.advance_search_scan(),
+                EventType::KeySequenceTimeout => self.flush_key_sequence(),
+                EventType::ScrollbarHide => *self.ctx.dirty = true,
                 EventType::Scroll(scroll) => self.ctx.scroll(scroll),
                 EventType::BlinkCursor => {
                     self.ctx.display.cursor_hidden ^= true;
@@ -1247,10 +1739,35 @@ impl input::Processor<EventProxy, ActionContext<'_, Notifier, EventProxy>> {
                     TerminalEvent::PtyWrite(text) => self.ctx.write_to_pty(text.into_bytes()),
                     TerminalEvent::MouseCursorDirty => self.reset_mouse_cursor(),
                     TerminalEvent::CursorBlinkingChange => self.ctx.update_cursor_blinking(),
-                    TerminalEvent::Exit | TerminalEvent::Wakeup => (),
+                    TerminalEvent::RecordingStopped => self.ctx.recording.on_stopped(),
+                    TerminalEvent::Exit
+                    | TerminalEvent::Wakeup
+                    | TerminalEvent::WorkingDirectory(_) => (),
+                },
+                #[cfg(unix)]
+                EventType::IpcConfig(_)
+                | EventType::IpcListWindows(_)
+                | EventType::IpcGetText(..)
+                | EventType::IpcGetConfig(..)
+                | EventType::IpcSubscribe(_) => (),
+                #[cfg(unix)]
+                EventType::IpcAction(ipc_action, stream) => match ipc::parse_action(&ipc_action) {
+                    Ok(action) => self.execute_action(&action),
+                    Err(err) => ipc::send_reply(stream, SocketReply::Error(err)),
+                },
+                #[cfg(unix)]
+                EventType::IpcSendText(send_text, stream) => match ipc::parse_text(&send_text) {
+                    Ok(text) if send_text.paste => {
+                        self.ctx.paste(&String::from_utf8_lossy(&text), true);
+                    },
+                    Ok(text) => {
+                        self.ctx.on_terminal_input_start();
+                        self.ctx.write_to_pty(text);
+                    },
+                    Err(err) => ipc::send_reply(stream, SocketReply::Error(err)),
                 },
                 #[cfg(unix)]
-                EventType::IpcConfig(_) => (),
+                EventType::Terminate => (),
                 EventType::Message(_)
                 | EventType::ConfigReload(_)
                 | EventType::CreateWindow(_)
@@ -1321,7 +1838,8 @@ impl input::Processor<EventProxy, ActionContext<'_, Notifier, EventProxy>> {
                     WindowEvent::CursorLeft { .. } => {
                         self.ctx.mouse.inside_text_area = false;
 
-                        if self.ctx.display().highlighted_hint.is_some() {
+                        let scrollbar_changed = self.ctx.display().scrollbar.set_hovered(false);
+                        if self.ctx.display().highlighted_hint.is_some() || scrollbar_changed {
                             *self.ctx.dirty = true;
                         }
                     },
@@ -1388,6 +1906,8 @@ pub struct Processor {
     windows: HashMap<WindowId, WindowContext, RandomState>,
     #[cfg(unix)]
     global_ipc_options: Vec<String>,
+    #[cfg(unix)]
+    ipc_subscribers: Vec<Subscriber>,
     cli_options: CliOptions,
     config: Rc<UiConfig>,
 }
@@ -1407,6 +1927,8 @@ impl Processor {
             windows: Default::default(),
             #[cfg(unix)]
             global_ipc_options: Default::default(),
+            #[cfg(unix)]
+            ipc_subscribers: Default::default(),
         }
     }
 
@@ -1423,6 +1945,11 @@ impl Processor {
         let window_context =
             WindowContext::initial(event_loop, proxy, self.config.clone(), options)?;
 
+        #[cfg(unix)]
+        self.notify_ipc_subscribers(IpcEvent::WindowCreated {
+            window_id: window_context.id().into(),
+        });
+
         self.windows.insert(window_context.id(), window_context);
 
         Ok(())
@@ -1449,21 +1976,89 @@ impl Processor {
             window_context.update_ipc_config(self.config.clone(), ipc_config);
         }
 
+        #[cfg(unix)]
+        self.notify_ipc_subscribers(IpcEvent::WindowCreated {
+            window_id: window_context.id().into(),
+        });
+
         self.windows.insert(window_context.id(), window_context);
         Ok(())
     }
 
+    /// Get the window targeted by an IPC message, defaulting to the focused window.
+    #[cfg(unix)]
+    fn ipc_window(&self, window_id: Option<u64>) -> Option<&WindowContext> {
+        match window_id {
+            Some(window_id) => self.windows.get(&WindowId::from(window_id)),
+            None => self.windows.values().find(|window_context| window_context.is_focused()),
+        }
+    }
+
+    /// Send an event to all IPC subscribers, removing disconnected ones.
+    #[cfg(unix)]
+    fn notify_ipc_subscribers(&mut self, event: IpcEvent) {
+        self.ipc_subscribers.retain(|subscriber| subscriber.send(event.clone()));
+    }
+
+    /// Forward window events relevant to IPC subscribers.
+    #[cfg(unix)]
+    fn handle_ipc_subscriptions(&mut self, event: &WinitEvent<Event>) {
+        if self.ipc_subscribers.is_empty() {
+            return;
+        }
+
+        let (window_id, event) = match event {
+            WinitEvent::WindowEvent { window_id, event: WindowEvent::Focused(focused) } => {
+                (*window_id, IpcEvent::Focus { window_id: (*window_id).into(), focused: *focused })
+            },
+            WinitEvent::UserEvent(Event {
+                window_id: Some(window_id),
+                payload: EventType::Terminal(event),
+            }) => {
+                let id = (*window_id).into();
+                let event = match event {
+                    TerminalEvent::Title(title) => {
+                        IpcEvent::Title { window_id: id, title: title.clone() }
+                    },
+                    TerminalEvent::ResetTitle => match self.windows.get(window_id) {
+                        Some(window_context) => {
+                            let title = window_context.config().window.identity.title.clone();
+                            IpcEvent::Title { window_id: id, title }
+                        },
+                        None => return,
+                    },
+                    TerminalEvent::Bell => IpcEvent::Bell { window_id: id },
+                    TerminalEvent::WorkingDirectory(cwd) => {
+                        IpcEvent::WorkingDirectory { window_id: id, cwd: cwd.path.clone() }
+                    },
+                    TerminalEvent::Exit => IpcEvent::Exit { window_id: id },
+                    _ => return,
+                };
+                (*window_id, event)
+            },
+            _ => return,
+        };
+
+        // Only report title changes which are applied to the window.
+        let is_title = matches!(event, IpcEvent::Title { .. });
+        match self.windows.get(&window_id) {
+            Some(window_context) if is_title && !window_context.dynamic_title() => (),
+            Some(_) => self.notify_ipc_subscribers(event),
+            None => (),
+        }
+    }
+
     /// Run the event loop.
     ///
     /// The result is exit code generate from the loop.
     pub fn run(
         &mut self,
         event_loop: EventLoop<Event>,
-        initial_window_options: WindowOptions,
+        initial_windows: Vec<WindowOptions>,
     ) -> Result<(), Box<dyn Error>> {
         let proxy = event_loop.create_proxy();
         let mut scheduler = Scheduler::new(proxy.clone());
-        let mut initial_window_options = Some(initial_window_options);
+        let mut initial_windows = Some(initial_windows);
 
         // NOTE: Since this takes a pointer to the winit event loop, it MUST be dropped first.
         let mut clipboard = unsafe { Clipboard::new(event_loop.raw_display_handle()) };
@@ -1481,13 +2076,20 @@ impl Processor {
                 return;
             }
 
+            #[cfg(unix)]
+            self.handle_ipc_subscriptions(&event);
+
             match event {
                 // The event loop just got initialized. Create a window.
                 WinitEvent::Resumed => {
                     // Creating window inside event loop is required for platforms like macOS to
                     // properly initialize state, like tab management. Othwerwise the first
                     // window won't handle tabs.
-                    let initial_window_options = match initial_window_options.take() {
+                    let mut initial_windows = match initial_windows.take() {
+                        Some(initial_windows) => initial_windows.into_iter(),
+                        None => return,
+                    };
+                    let initial_window_options = match initial_windows.next() {
                         Some(initial_window_options) => initial_window_options,
                         None => return,
                     };
@@ -1503,6 +2105,12 @@ impl Processor {
                         return;
                     }
 
+                    // Open the remaining restored windows with the initialized graphics platform.
+                    for options in initial_windows {
+                        let event = Event::new(EventType::CreateWindow(options), None);
+                        let _ = proxy.send_event(event);
+                    }
+
                     info!("Initialisation complete");
                 },
                 // NOTE: This event bypasses batching to minimize input latency.
@@ -1543,6 +2151,11 @@ impl Processor {
                     // Unschedule pending events.
                     scheduler.unschedule_window(window_context.id());
 
+                    #[cfg(unix)]
+                    self.notify_ipc_subscribers(IpcEvent::WindowClosed {
+                        window_id: window_context.id().into(),
+                    });
+
                     // Shutdown if no more terminals are open.
                     if self.windows.is_empty() {
                         // Write ref tests of last window to disk.
@@ -1550,9 +2163,24 @@ impl Processor {
                             window_context.write_ref_test_results();
                         }
 
+                        // Keep the last window if it was closed while its shell is still running.
+                        if self.config.save_session {
+                            let running = window_context.shell_running();
+                            Self::save_session(iter::once(&window_context).filter(|_| running));
+                        }
+
                         *control_flow = ControlFlow::Exit;
                     }
                 },
+                // Keep all open windows when terminated by a signal.
+                #[cfg(unix)]
+                WinitEvent::UserEvent(Event { payload: EventType::Terminate, .. }) => {
+                    if self.config.save_session {
+                        Self::save_session(self.windows.values());
+                    }
+
+                    *control_flow = ControlFlow::Exit;
+                },
                 WinitEvent::RedrawRequested(window_id) => {
                     let window_context = match self.windows.get_mut(&window_id) {
                         Some(window_context) => window_context,
@@ -1631,6 +2259,84 @@ impl Processor {
                         window_context.update_ipc_config(self.config.clone(), ipc_config.clone());
                     }
                 },
+                // Process IPC window list request.
+                #[cfg(unix)]
+                WinitEvent::UserEvent(Event {
+                    payload: EventType::IpcListWindows(stream), ..
+                }) => {
+                    let windows = self.windows.values().map(WindowContext::ipc_window_info);
+                    ipc::send_reply(stream, SocketReply::Windows(windows.collect()));
+                },
+                // Process IPC text request.
+                #[cfg(unix)]
+                WinitEvent::UserEvent(Event {
+                    payload: EventType::IpcGetText(get_text, stream),
+                    ..
+                }) => {
+                    let reply = match self.ipc_window(get_text.window_id) {
+                        Some(window_context) => {
+                            SocketReply::Text(window_context.ipc_text(get_text.scrollback))
+                        },
+                        None => SocketReply::Error(String::from("no matching window found")),
+                    };
+                    ipc::send_reply(stream, reply);
+                },
+                // Process IPC config request.
+                #[cfg(unix)]
+                WinitEvent::UserEvent(Event {
+                    payload: EventType::IpcGetConfig(get_config, stream),
+                    ..
+                }) => {
+                    let config = match get_config.window_id {
+                        Some(window_id) => self
+                            .windows
+                            .get(&WindowId::from(window_id))
+                            .map(|window| window.config()),
+                        None => Some(&*self.config),
+                    };
+                    let reply = match config {
+                        Some(config) => ipc::config_reply(config, get_config.option.as_deref()),
+                        None => SocketReply::Error(String::from("no matching window found")),
+                    };
+                    ipc::send_reply(stream, reply);
+                },
+                // Process IPC event subscription.
+                #[cfg(unix)]
+                WinitEvent::UserEvent(Event {
+                    payload: EventType::IpcSubscribe(stream), ..
+                }) => {
+                    self.ipc_subscribers.push(Subscriber::new(stream));
+                },
+                // Forward IPC input requests to their window.
+                #[cfg(unix)]
+                WinitEvent::UserEvent(Event {
+                    payload: payload @ (EventType::IpcAction(..) | EventType::IpcSendText(..)),
+                    ..
+                }) => {
+                    let (ipc_window_id, stream) = match &payload {
+                        EventType::IpcAction(ipc_action, stream) => (ipc_action.window_id, stream),
+                        EventType::IpcSendText(send_text, stream) => (send_text.window_id, stream),
+                        _ => unreachable!(),
+                    };
+
+                    let window_id = self.ipc_window(ipc_window_id).map(WindowContext::id);
+                    match window_id.and_then(|window_id| self.windows.get_mut(&window_id)) {
+                        Some(window_context) => {
+                            window_context.handle_event(
+                                event_loop,
+                                &proxy,
+                                &mut clipboard,
+                                &mut scheduler,
+                                Event::new(payload, window_id).into(),
+                            );
+                        },
+                        None => {
+                            let reply =
+                                SocketReply::Error(String::from("no matching window found"));
+                            ipc::send_reply(stream.clone(), reply);
+                        },
+                    }
+                },
                 // Create a new terminal window.
                 WinitEvent::UserEvent(Event {
                     payload: EventType::CreateWindow(options), ..
@@ -1679,6 +2385,15 @@ impl Processor {
         result.map_err(Into::into)
     }
 
+    /// Save the windows, replacing the previous session.
+    fn save_session<'a>(windows: impl Iterator<Item = &'a WindowContext>) {
+        let sessions: Vec<WindowSession> = windows.filter_map(WindowContext::session).collect();
+        match session::save(&sessions) {
+            Ok(path) => info!("Session saved to {:?}", path),
+            Err(err) => error!("Unable to save session: {}", err),
+        }
+    }
+
     /// Check if an event is irrelevant and can be skipped.
     fn skip_event(event: &WinitEvent<Event>) -> bool {
         match event {
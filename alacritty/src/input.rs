@@ -15,6 +15,7 @@ use std::mem;
 use std::time::{Duration, Instant};
 
 use log::debug;
+use once_cell::unsync::OnceCell;
 use winit::dpi::PhysicalPosition;
 use winit::event::{
     ElementState, KeyEvent, Modifiers, MouseButton, MouseScrollDelta, Touch as TouchEvent,
@@ -23,7 +24,7 @@ use winit::event::{
 use winit::event_loop::EventLoopWindowTarget;
 #[cfg(target_os = "macos")]
 use winit::keyboard::ModifiersKeyState;
-use winit::keyboard::{Key, ModifiersState};
+use winit::keyboard::{Key, KeyLocation, ModifiersState};
 #[cfg(target_os = "macos")]
 use winit::platform::macos::{EventLoopWindowTargetExtMacOS, OptionAsAlt};
 use winit::platform::modifier_supplement::KeyEventExtModifierSupplement;
@@ -32,21 +33,27 @@ use winit::window::CursorIcon;
 use alacritty_terminal::ansi::{ClearMode, Handler};
 use alacritty_terminal::event::EventListener;
 use alacritty_terminal::grid::{Dimensions, Scroll};
-use alacritty_terminal::index::{Boundary, Column, Direction, Point, Side};
-use alacritty_terminal::selection::SelectionType;
+use alacritty_terminal::index::{Boundary, Column, Direction, Line, Point, Side};
+use alacritty_terminal::replay::Control as ReplayControl;
+use alacritty_terminal::selection::{Selection, SelectionType};
 use alacritty_terminal::term::search::Match;
 use alacritty_terminal::term::{ClipboardType, Term, TermMode};
-use alacritty_terminal::vi_mode::ViMotion;
+use alacritty_terminal::vi_mode::{InlineSearch, TextObject, ViMotion};
 
 use crate::clipboard::Clipboard;
+use crate::config::window::ScrollbarMode;
 use crate::config::{
-    Action, BindingKey, BindingMode, MouseAction, SearchAction, UiConfig, ViAction,
+    Action, BindingKey, BindingMode, KeyBinding, KeyStroke, MouseAction, SearchAction, UiConfig,
+    ViAction,
 };
+use crate::daemon::ForegroundProcess;
 use crate::display::hint::HintMatch;
+use crate::display::scrollbar::ScrollbarGeometry;
 use crate::display::window::Window;
 use crate::display::{Display, SizeInfo};
 use crate::event::{
-    ClickState, Event, EventType, Mouse, TouchPurpose, TouchZoom, TYPING_SEARCH_DELAY,
+    ClickState, Event, EventType, ExportFormat, KeySequence, MarkAction, Mouse, TouchPurpose,
+    TouchZoom, ViState, TYPING_SEARCH_DELAY,
 };
 use crate::message_bar::{self, Message};
 use crate::scheduler::{Scheduler, TimerId, Topic};
@@ -83,6 +90,7 @@ pub trait ActionContext<T: EventListener> {
     fn mark_dirty(&mut self) {}
     fn size_info(&self) -> SizeInfo;
     fn copy_selection(&mut self, _ty: ClipboardType) {}
+    fn copy_selection_html_source(&mut self) {}
     fn start_selection(&mut self, _ty: SelectionType, _point: Point, _side: Side) {}
     fn toggle_selection(&mut self, _ty: SelectionType, _point: Point, _side: Side) {}
     fn update_selection(&mut self, _point: Point, _side: Side) {}
@@ -124,6 +132,21 @@ pub trait ActionContext<T: EventListener> {
     fn search_active(&self) -> bool;
     fn on_typing_start(&mut self) {}
     fn toggle_vi_mode(&mut self) {}
+    fn vi_state(&mut self) -> &mut ViState;
+    fn key_sequence(&mut self) -> &mut KeySequence;
+    fn custom_mode(&self) -> Option<&str> {
+        None
+    }
+    fn set_custom_mode(&mut self, _mode: Option<String>) {}
+    fn foreground_process(&self) -> Option<ForegroundProcess> {
+        None
+    }
+    fn toggle_recording(&mut self) {}
+    fn save_scrollback(&mut self, _format: ExportFormat) {}
+    fn replay_active(&self) -> bool {
+        false
+    }
+    fn replay_control(&mut self, _control: ReplayControl) {}
     fn hint_input(&mut self, _character: char) {}
     fn trigger_hint(&mut self, _hint: &HintMatch) {}
     fn expand_selection(&mut self) {}
@@ -150,6 +173,185 @@ impl Action {
             selection.include_all();
         }
     }
+
+    /// Move the vi mode cursor, applying pending counts and operators.
+    fn vi_motion<T, A, F>(ctx: &mut A, linewise: bool, inclusive: bool, mut motion: F)
+    where
+        A: ActionContext<T>,
+        T: EventListener,
+        F: FnMut(&mut Term<T>),
+    {
+        ctx.on_typing_start();
+
+        let count = ctx.vi_state().take_count();
+        let yank = ctx.vi_state().yank.take().is_some();
+
+        let origin = ctx.terminal().vi_mode_cursor.point;
+        for _ in 0..count {
+            motion(ctx.terminal_mut());
+        }
+
+        if yank {
+            let target = ctx.terminal().vi_mode_cursor.point;
+            let (start, mut end) = (min(origin, target), max(origin, target));
+
+            if linewise {
+                let last_column = ctx.terminal().last_column();
+                let start = Point::new(start.line, Column(0));
+                let end = Point::new(end.line, last_column);
+                Self::yank(ctx, start, end, SelectionType::Lines);
+            } else if inclusive || start != end {
+                // Exclusive motions stop right before their target.
+                if !inclusive {
+                    end = end.sub(ctx.terminal(), Boundary::None, 1);
+                }
+                Self::yank(ctx, start, end, SelectionType::Simple);
+            }
+
+            // Like in vim, the cursor is moved to the start of the yanked text.
+            ctx.terminal_mut().vi_goto_point(start);
+        }
+
+        ctx.mark_dirty();
+    }
+
+    /// Jump to the next match of an inline search.
+    fn vi_inline_search<T, A>(ctx: &mut A, search: InlineSearch, mut repeat: bool)
+    where
+        A: ActionContext<T>,
+        T: EventListener,
+    {
+        let inclusive = search.direction == Direction::Right;
+        Self::vi_motion(ctx, false, inclusive, |term| {
+            term.vi_inline_search(search, repeat);
+            repeat = true;
+        });
+    }
+
+    /// Handle the vi mode yank operator.
+    fn vi_yank<T, A>(ctx: &mut A)
+    where
+        A: ActionContext<T>,
+        T: EventListener,
+    {
+        // Copy active selections directly.
+        if !ctx.selection_is_empty() {
+            ctx.vi_state().clear_pending();
+            ctx.copy_selection(ClipboardType::Clipboard);
+            ctx.clear_selection();
+            return;
+        }
+
+        let count = ctx.vi_state().take_count();
+
+        // Wait for a motion or text object if no operator is pending yet.
+        if ctx.vi_state().yank.is_none() {
+            ctx.vi_state().yank = Some(count);
+            return;
+        }
+
+        // Yank entire lines with `yy`.
+        ctx.vi_state().clear_pending();
+        let term = ctx.terminal();
+        let line = term.vi_mode_cursor.point.line;
+        let start = Point::new(line, Column(0));
+        let end = Point::new(min(line + (count - 1), term.bottommost_line()), term.last_column());
+        Self::yank(ctx, start, end, SelectionType::Lines);
+    }
+
+    /// Move the vi mode cursor, recording its previous position in the jump list.
+    fn vi_jump<T, A>(ctx: &mut A, point: Point)
+    where
+        A: ActionContext<T>,
+        T: EventListener,
+    {
+        Self::push_vi_jump(ctx);
+        ctx.terminal_mut().vi_goto_point(point);
+    }
+
+    /// Record the vi mode cursor position in the jump list.
+    fn push_vi_jump<T, A>(ctx: &mut A)
+    where
+        A: ActionContext<T>,
+        T: EventListener,
+    {
+        let term = ctx.terminal_mut();
+        if term.mode().contains(TermMode::VI) {
+            let point = term.vi_mode_cursor.point;
+            term.vi_marks.push_jump(point);
+        }
+    }
+
+    /// Jump to a vi mode mark, applying pending operators.
+    fn vi_goto_mark<T, A>(ctx: &mut A, action: MarkAction, name: char)
+    where
+        A: ActionContext<T>,
+        T: EventListener,
+    {
+        let mut point = match ctx.terminal().vi_marks.mark(name) {
+            Some(point) => point,
+            None => {
+                ctx.vi_state().clear_pending();
+                return;
+            },
+        };
+
+        let linewise = action == MarkAction::JumpLine;
+        if linewise {
+            point.column = Column(0);
+        }
+
+        Self::push_vi_jump(ctx);
+        Self::vi_motion(ctx, linewise, false, |term| {
+            term.vi_goto_point(point);
+            if linewise {
+                term.vi_motion(ViMotion::FirstOccupied);
+            }
+        });
+    }
+
+    /// Copy the text between two points to the clipboard.
+    fn yank<T, A>(ctx: &mut A, start: Point, end: Point, ty: SelectionType)
+    where
+        A: ActionContext<T>,
+        T: EventListener,
+    {
+        let mut selection = Selection::new(ty, start, Side::Left);
+        selection.update(end, Side::Right);
+        ctx.terminal_mut().selection = Some(selection);
+
+        ctx.copy_selection(ClipboardType::Clipboard);
+        ctx.clear_selection();
+    }
+
+    fn scroll_to_prompt<T, A>(ctx: &mut A, direction: Direction)
+    where
+        A: ActionContext<T>,
+        T: EventListener,
+    {
+        // Ignore prompts in the topmost visible line.
+        let term = ctx.terminal();
+        let top_line = Line(-(term.grid().display_offset() as i32));
+        let origin = match direction {
+            Direction::Left => Point::new(top_line, Column(0)),
+            Direction::Right => Point::new(top_line, term.last_column()),
+        };
+
+        let prompt = match term.prompt_search(origin, direction) {
+            Some(prompt) => prompt,
+            None => return,
+        };
+
+        // Move the prompt to the top of the viewport.
+        ctx.scroll(Scroll::Delta(top_line.0 - prompt.line.0));
+
+        // Move vi mode cursor.
+        if ctx.terminal().mode().contains(TermMode::VI) {
+            ctx.terminal_mut().vi_goto_point(prompt);
+        }
+
+        ctx.mark_dirty();
+    }
 }
 
 trait Execute<T: EventListener> {
@@ -159,6 +361,34 @@ trait Execute<T: EventListener> {
 impl<T: EventListener> Execute<T> for Action {
     #[inline]
     fn execute<A: ActionContext<T>>(&self, ctx: &mut A) {
+        // Run all actions of a sequence before the next event is processed.
+        if let Action::Sequence(actions) = self {
+            for action in actions {
+                action.execute(ctx);
+            }
+            return;
+        }
+
+        // Pending vi mode counts and operators only apply to the actions completing them.
+        let completes_vi_input = matches!(
+            self,
+            Action::ViMotion(_)
+                | Action::Vi(
+                    ViAction::Yank
+                        | ViAction::InlineSearchForward
+                        | ViAction::InlineSearchBackward
+                        | ViAction::InlineSearchForwardShort
+                        | ViAction::InlineSearchBackwardShort
+                        | ViAction::InlineSearchNext
+                        | ViAction::InlineSearchPrevious
+                        | ViAction::GotoMark
+                        | ViAction::GotoMarkLine
+                )
+        );
+        if !completes_vi_input {
+            ctx.vi_state().clear_pending();
+        }
+
         match self {
             Action::Esc(s) => ctx.paste(s, false),
             Action::Command(program) => ctx.spawn_daemon(program.program(), program.args()),
@@ -170,15 +400,68 @@ impl<T: EventListener> Execute<T> for Action {
                 ctx.on_typing_start();
                 ctx.toggle_vi_mode()
             },
+            Action::EnterMode(mode) => ctx.set_custom_mode(Some(mode.clone())),
+            Action::LeaveMode => ctx.set_custom_mode(None),
+            Action::ToggleRecording => ctx.toggle_recording(),
+            Action::SaveScrollbackHtml => ctx.save_scrollback(ExportFormat::Html),
+            Action::SaveScrollbackAnsi => ctx.save_scrollback(ExportFormat::Ansi),
+            Action::ToggleReplayPause => ctx.replay_control(ReplayControl::TogglePause),
+            Action::ReplayStep => ctx.replay_control(ReplayControl::Step),
+            Action::IncreaseReplaySpeed => ctx.replay_control(ReplayControl::Faster),
+            Action::DecreaseReplaySpeed => ctx.replay_control(ReplayControl::Slower),
             action @ (Action::ViMotion(_) | Action::Vi(_))
                 if !ctx.terminal().mode().contains(TermMode::VI) =>
             {
                 debug!("Ignoring {action:?}: Vi mode inactive");
             },
             Action::ViMotion(motion) => {
-                ctx.on_typing_start();
-                ctx.terminal_mut().vi_motion(*motion);
-                ctx.mark_dirty();
+                let motion = *motion;
+                Self::vi_motion(ctx, motion.is_linewise(), motion.is_inclusive(), |term| {
+                    term.vi_motion(motion)
+                });
+            },
+            Action::Vi(ViAction::Yank) => Self::vi_yank(ctx),
+            Action::Vi(ViAction::InlineSearchForward) => {
+                ctx.vi_state().inline_search_pending = Some((Direction::Right, false));
+            },
+            Action::Vi(ViAction::InlineSearchBackward) => {
+                ctx.vi_state().inline_search_pending = Some((Direction::Left, false));
+            },
+            Action::Vi(ViAction::InlineSearchForwardShort) => {
+                ctx.vi_state().inline_search_pending = Some((Direction::Right, true));
+            },
+            Action::Vi(ViAction::InlineSearchBackwardShort) => {
+                ctx.vi_state().inline_search_pending = Some((Direction::Left, true));
+            },
+            Action::Vi(ViAction::SetMark) => ctx.vi_state().mark_pending = Some(MarkAction::Set),
+            Action::Vi(ViAction::GotoMark) => ctx.vi_state().mark_pending = Some(MarkAction::Jump),
+            Action::Vi(ViAction::GotoMarkLine) => {
+                ctx.vi_state().mark_pending = Some(MarkAction::JumpLine);
+            },
+            Action::Vi(ViAction::JumpBack) => {
+                let term = ctx.terminal_mut();
+                let point = term.vi_mode_cursor.point;
+                if let Some(point) = term.vi_marks.jump_back(point) {
+                    term.vi_goto_point(point);
+                    ctx.mark_dirty();
+                }
+            },
+            Action::Vi(ViAction::JumpForward) => {
+                let term = ctx.terminal_mut();
+                if let Some(point) = term.vi_marks.jump_forward() {
+                    term.vi_goto_point(point);
+                    ctx.mark_dirty();
+                }
+            },
+            Action::Vi(ViAction::InlineSearchNext) => {
+                if let Some(search) = ctx.vi_state().inline_search {
+                    Self::vi_inline_search(ctx, search, true);
+                }
+            },
+            Action::Vi(ViAction::InlineSearchPrevious) => {
+                if let Some(search) = ctx.vi_state().inline_search {
+                    Self::vi_inline_search(ctx, search.reversed(), true);
+                }
             },
             Action::Vi(ViAction::ToggleNormalSelection) => {
                 Self::toggle_selection(ctx, SelectionType::Simple);
@@ -192,6 +475,9 @@ impl<T: EventListener> Execute<T> for Action {
             Action::Vi(ViAction::ToggleSemanticSelection) => {
                 Self::toggle_selection(ctx, SelectionType::Semantic);
             },
+            Action::Vi(ViAction::ToggleOutputSelection) => {
+                Self::toggle_selection(ctx, SelectionType::Output);
+            },
             Action::Vi(ViAction::Open) => {
                 let hint = ctx.display().vi_highlighted_hint.take();
                 if let Some(hint) = &hint {
@@ -212,7 +498,7 @@ impl<T: EventListener> Execute<T> for Action {
                 };
 
                 if let Some(regex_match) = ctx.search_next(origin, direction, Side::Left) {
-                    ctx.terminal_mut().vi_goto_point(*regex_match.start());
+                    Self::vi_jump(ctx, *regex_match.start());
                     ctx.mark_dirty();
                 }
             },
@@ -228,7 +514,7 @@ impl<T: EventListener> Execute<T> for Action {
                 };
 
                 if let Some(regex_match) = ctx.search_next(origin, direction, Side::Left) {
-                    ctx.terminal_mut().vi_goto_point(*regex_match.start());
+                    Self::vi_jump(ctx, *regex_match.start());
                     ctx.mark_dirty();
                 }
             },
@@ -237,7 +523,7 @@ impl<T: EventListener> Execute<T> for Action {
                 let origin = terminal.vi_mode_cursor.point.sub(terminal, Boundary::None, 1);
 
                 if let Some(regex_match) = ctx.search_next(origin, Direction::Left, Side::Left) {
-                    ctx.terminal_mut().vi_goto_point(*regex_match.start());
+                    Self::vi_jump(ctx, *regex_match.start());
                     ctx.mark_dirty();
                 }
             },
@@ -246,7 +532,7 @@ impl<T: EventListener> Execute<T> for Action {
                 let origin = terminal.vi_mode_cursor.point.add(terminal, Boundary::None, 1);
 
                 if let Some(regex_match) = ctx.search_next(origin, Direction::Right, Side::Right) {
-                    ctx.terminal_mut().vi_goto_point(*regex_match.end());
+                    Self::vi_jump(ctx, *regex_match.end());
                     ctx.mark_dirty();
                 }
             },
@@ -285,6 +571,7 @@ impl<T: EventListener> Execute<T> for Action {
             Action::Copy => ctx.copy_selection(ClipboardType::Clipboard),
             #[cfg(not(any(target_os = "macos", windows)))]
             Action::CopySelection => ctx.copy_selection(ClipboardType::Selection),
+            Action::CopyHtmlSource => ctx.copy_selection_html_source(),
             Action::ClearSelection => ctx.clear_selection(),
             Action::Paste => {
                 let text = ctx.clipboard_mut().load(ClipboardType::Clipboard);
@@ -294,6 +581,13 @@ impl<T: EventListener> Execute<T> for Action {
                 let text = ctx.clipboard_mut().load(ClipboardType::Selection);
                 ctx.paste(&text, true);
             },
+            Action::CopyLastCommandOutput => {
+                let term = ctx.terminal();
+                if let Some(output) = term.last_command_output() {
+                    let text = term.bounds_to_string(*output.start(), *output.end());
+                    ctx.clipboard_mut().store(ClipboardType::Clipboard, text);
+                }
+            },
             Action::ToggleFullscreen => ctx.window().toggle_fullscreen(),
             Action::ToggleMaximized => ctx.window().toggle_maximized(),
             #[cfg(target_os = "macos")]
@@ -344,6 +638,7 @@ impl<T: EventListener> Execute<T> for Action {
             Action::ScrollLineUp => ctx.scroll(Scroll::Delta(1)),
             Action::ScrollLineDown => ctx.scroll(Scroll::Delta(-1)),
             Action::ScrollToTop => {
+                Self::push_vi_jump(ctx);
                 ctx.scroll(Scroll::Top);
 
                 // Move vi mode cursor.
@@ -353,6 +648,7 @@ impl<T: EventListener> Execute<T> for Action {
                 ctx.mark_dirty();
             },
             Action::ScrollToBottom => {
+                Self::push_vi_jump(ctx);
                 ctx.scroll(Scroll::Bottom);
 
                 // Move vi mode cursor.
@@ -364,6 +660,8 @@ impl<T: EventListener> Execute<T> for Action {
                 term.vi_motion(ViMotion::FirstOccupied);
                 ctx.mark_dirty();
             },
+            Action::ScrollToPreviousPrompt => Self::scroll_to_prompt(ctx, Direction::Left),
+            Action::ScrollToNextPrompt => Self::scroll_to_prompt(ctx, Direction::Right),
             Action::ClearHistory => ctx.terminal_mut().clear_screen(ClearMode::Saved),
             Action::ClearLogNotice => ctx.pop_message(),
             #[cfg(not(target_os = "macos"))]
@@ -400,7 +698,7 @@ impl<T: EventListener> Execute<T> for Action {
             Action::SelectTab9 => ctx.window().select_tab_at_index(8),
             #[cfg(target_os = "macos")]
             Action::SelectLastTab => ctx.window().select_last_tab(),
-            Action::ReceiveChar | Action::None => (),
+            Action::ReceiveChar | Action::None | Action::Sequence(_) => (),
         }
     }
 }
@@ -410,12 +708,22 @@ impl<T: EventListener, A: ActionContext<T>> Processor<T, A> {
         Self { ctx, _phantom: Default::default() }
     }
 
+    /// Execute an action as if its binding was triggered.
+    #[inline]
+    pub fn execute_action(&mut self, action: &Action) {
+        action.execute(&mut self.ctx);
+    }
+
     #[inline]
     pub fn mouse_moved(&mut self, position: PhysicalPosition<f64>) {
         let size_info = self.ctx.size_info();
 
         let (x, y) = position.into();
 
+        if self.scrollbar_mouse_moved(x as f32, y as f32) {
+            return;
+        }
+
         let lmb_pressed = self.ctx.mouse().left_button_state == ElementState::Pressed;
         let rmb_pressed = self.ctx.mouse().right_button_state == ElementState::Pressed;
         if !self.ctx.selection_is_empty() && (lmb_pressed || rmb_pressed) {
@@ -476,6 +784,85 @@ impl<T: EventListener, A: ActionContext<T>> Processor<T, A> {
         }
     }
 
+    /// Scrollbar layout for the current viewport, if the scrollbar is enabled.
+    fn scrollbar_geometry(&mut self) -> Option<ScrollbarGeometry> {
+        let config = self.ctx.config().window.scrollbar;
+        if config.mode == ScrollbarMode::Never {
+            return None;
+        }
+
+        let scale_factor = self.ctx.window().scale_factor as f32;
+        let size_info = self.ctx.size_info();
+        let history_size = self.ctx.terminal().history_size();
+        let display_offset = self.ctx.terminal().grid().display_offset();
+        ScrollbarGeometry::new(&config, &size_info, scale_factor, history_size, display_offset)
+    }
+
+    /// Update the scrollbar after mouse motion.
+    ///
+    /// Returns `true` if the motion was consumed by dragging the scrollbar.
+    fn scrollbar_mouse_moved(&mut self, x: f32, y: f32) -> bool {
+        if self.ctx.config().window.scrollbar.mode == ScrollbarMode::Never {
+            return false;
+        }
+
+        let geometry = self.scrollbar_geometry();
+        let scrollbar = &mut self.ctx.display().scrollbar;
+        let hovered = geometry.map_or(false, |geometry| geometry.contains(x, y));
+        let drag_offset = geometry.and_then(|geometry| scrollbar.drag(&geometry, y));
+        let hover_changed = scrollbar.set_hovered(hovered);
+
+        if hover_changed {
+            let mouse_state = self.cursor_state();
+            self.ctx.window().set_mouse_cursor(mouse_state);
+            self.ctx.mark_dirty();
+        }
+
+        match drag_offset {
+            Some(display_offset) => {
+                let old_offset = self.ctx.terminal().grid().display_offset();
+                self.ctx.scroll(Scroll::Delta(display_offset as i32 - old_offset as i32));
+                true
+            },
+            None => false,
+        }
+    }
+
+    /// Start dragging the scrollbar when it was clicked.
+    ///
+    /// Returns `true` if the scrollbar was clicked.
+    fn scrollbar_mouse_pressed(&mut self) -> bool {
+        let geometry = match self.scrollbar_geometry() {
+            Some(geometry) => geometry,
+            None => return false,
+        };
+
+        let config = self.ctx.config().window.scrollbar;
+        let (x, y) = (self.ctx.mouse().x as f32, self.ctx.mouse().y as f32);
+        let scrollbar = &mut self.ctx.display().scrollbar;
+        if !geometry.contains(x, y) || !scrollbar.is_visible(&config) {
+            return false;
+        }
+
+        let display_offset = scrollbar.start_drag(&geometry, y);
+        let old_offset = self.ctx.terminal().grid().display_offset();
+        self.ctx.scroll(Scroll::Delta(display_offset as i32 - old_offset as i32));
+        self.ctx.mark_dirty();
+
+        true
+    }
+
+    /// Stop dragging the scrollbar.
+    ///
+    /// Returns `true` if the scrollbar was dragged.
+    fn scrollbar_mouse_released(&mut self) -> bool {
+        if self.ctx.config().window.scrollbar.mode == ScrollbarMode::Never {
+            return false;
+        }
+
+        self.ctx.display().scrollbar.end_drag()
+    }
+
     /// Check which side of a cell an X coordinate lies on.
     fn cell_side(&self, x: usize) -> Side {
         let size_info = self.ctx.size_info();
@@ -966,11 +1353,16 @@ impl<T: EventListener, A: ActionContext<T>> Processor<T, A> {
             self.ctx.window().set_mouse_cursor(new_icon);
         } else {
             match state {
+                // Dragging the scrollbar takes precedence over all other mouse actions.
+                ElementState::Pressed
+                    if button == MouseButton::Left && self.scrollbar_mouse_pressed() => {},
                 ElementState::Pressed => {
                     // Process mouse press before bindings to update the `click_state`.
                     self.on_mouse_press(button);
                     self.process_mouse_bindings(button);
                 },
+                ElementState::Released
+                    if button == MouseButton::Left && self.scrollbar_mouse_released() => {},
                 ElementState::Released => self.on_mouse_release(button),
             }
         }
@@ -981,7 +1373,13 @@ impl<T: EventListener, A: ActionContext<T>> Processor<T, A> {
     /// The provided mode, mods, and key must match what is allowed by a binding
     /// for its action to be executed.
     fn process_mouse_bindings(&mut self, button: MouseButton) {
-        let mode = BindingMode::new(self.ctx.terminal().mode(), self.ctx.search_active());
+        let mode = BindingMode::new(
+            self.ctx.terminal().mode(),
+            self.ctx.search_active(),
+            self.ctx.replay_active(),
+        );
+        let custom_mode = self.ctx.custom_mode().map(String::from);
+        let foreground_process = OnceCell::new();
         let mouse_mode = self.ctx.mouse_mode();
         let mods = self.ctx.modifiers().state();
 
@@ -993,16 +1391,105 @@ impl<T: EventListener, A: ActionContext<T>> Processor<T, A> {
                 binding.mods |= ModifiersState::SHIFT;
             }
 
-            if binding.is_triggered_by(mode, mods, &button) {
+            if binding.is_triggered_by(mode, mods, &button)
+                && binding.custom_mode_matches(custom_mode.as_deref())
+                && binding.process_matches(|| {
+                    foreground_process.get_or_init(|| self.ctx.foreground_process()).as_ref()
+                })
+            {
                 binding.action.execute(&mut self.ctx);
             }
         }
     }
 
+    /// Process vi mode counts and the arguments of pending vi mode actions.
+    ///
+    /// Returns `true` if the key was consumed.
+    fn vi_input(&mut self, key: &KeyEvent, text: &str) -> bool {
+        let escape = key.logical_key == Key::Escape;
+        let mut chars = text.chars();
+        let character = match (chars.next(), chars.next()) {
+            (Some(character), None) => character,
+            _ if escape => '\x1b',
+            _ => return false,
+        };
+
+        // Complete pending inline searches.
+        if let Some((direction, stop_short)) = self.ctx.vi_state().inline_search_pending.take() {
+            if escape {
+                self.ctx.vi_state().clear_pending();
+                return true;
+            }
+
+            let search = InlineSearch { character, direction, stop_short };
+            self.ctx.vi_state().inline_search = Some(search);
+            Action::vi_inline_search(&mut self.ctx, search, false);
+            return true;
+        }
+
+        // Complete pending mark actions.
+        if let Some(action) = self.ctx.vi_state().mark_pending.take() {
+            if action == MarkAction::Set {
+                self.ctx.vi_state().clear_pending();
+                let term = self.ctx.terminal_mut();
+                let point = term.vi_mode_cursor.point;
+                term.vi_marks.set_mark(character, point);
+            } else if escape {
+                self.ctx.vi_state().clear_pending();
+            } else {
+                Action::vi_goto_mark(&mut self.ctx, action, character);
+            }
+
+            return true;
+        }
+
+        // Complete pending text objects.
+        if let Some(inner) = self.ctx.vi_state().text_object.take() {
+            self.ctx.vi_state().clear_pending();
+
+            let term = self.ctx.terminal();
+            let point = term.vi_mode_cursor.point;
+            let range = TextObject::from_char(character)
+                .and_then(|object| object.range(term, point, inner));
+            if let Some(range) = range {
+                Action::yank(&mut self.ctx, *range.start(), *range.end(), SelectionType::Simple);
+                self.ctx.terminal_mut().vi_goto_point(*range.start());
+                self.ctx.mark_dirty();
+            }
+
+            return true;
+        }
+
+        if escape && self.ctx.vi_state().is_pending() {
+            self.ctx.vi_state().clear_pending();
+            return true;
+        }
+
+        let mods = self.ctx.modifiers().state();
+        if mods.control_key() || mods.alt_key() || mods.super_key() {
+            return false;
+        }
+
+        let vi_state = self.ctx.vi_state();
+        match character {
+            'i' | 'a' if vi_state.yank.is_some() => vi_state.text_object = Some(character == 'i'),
+            '1'..='9' => vi_state.push_count_digit(character as u32 - '0' as u32),
+            '0' if vi_state.count_pending() => vi_state.push_count_digit(0),
+            _ => return false,
+        }
+
+        true
+    }
+
     /// Process key input.
     pub fn key_input(&mut self, key: KeyEvent) {
         // IME input will be applied on commit and shouldn't trigger key bindings.
-        if key.state == ElementState::Released || self.ctx.display().ime.preedit().is_some() {
+        if self.ctx.display().ime.preedit().is_some() {
+            return;
+        }
+
+        if key.state == ElementState::Released {
+            self.key_release(&key);
             return;
         }
 
@@ -1025,8 +1512,24 @@ impl<T: EventListener, A: ActionContext<T>> Processor<T, A> {
             }
         }
 
+        // Keys of a key sequence are only processed once the sequence is complete.
+        self.process_key_sequence(&key);
+    }
+
+    /// Process a key press which is not part of a key sequence.
+    fn process_key(&mut self, key: &KeyEvent) {
+        let text = key.text_with_all_modifiers().unwrap_or_default();
+
+        // Pending vi mode input takes precedence over key bindings.
+        if self.ctx.terminal().mode().contains(TermMode::VI)
+            && !self.ctx.search_active()
+            && self.vi_input(key, text)
+        {
+            return;
+        }
+
         // Key bindings suppress the character input.
-        if self.process_key_bindings(&key) {
+        if self.process_key_bindings(key) {
             return;
         }
 
@@ -1039,19 +1542,77 @@ impl<T: EventListener, A: ActionContext<T>> Processor<T, A> {
         }
 
         // Vi mode on its own doesn't have any input, the search input was done before.
-        if self.ctx.terminal().mode().contains(TermMode::VI) || text.is_empty() {
+        if self.ctx.terminal().mode().contains(TermMode::VI) {
             return;
         }
 
+        let bytes = match self.kitty_key_sequence(key, text) {
+            Some(bytes) => bytes,
+            None if text.is_empty() => return,
+            None => {
+                let mut bytes = Vec::with_capacity(text.len() + 1);
+                if self.alt_send_esc() && text.len() == 1 {
+                    bytes.push(b'\x1b');
+                }
+                bytes.extend_from_slice(text.as_bytes());
+                bytes
+            },
+        };
+
         self.ctx.on_terminal_input_start();
 
-        let mut bytes = Vec::with_capacity(text.len() + 1);
-        if self.alt_send_esc() && text.len() == 1 {
-            bytes.push(b'\x1b');
+        self.ctx.write_to_pty(bytes);
+    }
+
+    /// Process key release.
+    ///
+    /// Releases are only reported to the PTY when requested through the kitty keyboard protocol.
+    fn key_release(&mut self, key: &KeyEvent) {
+        let mode = *self.ctx.terminal().mode();
+        if !mode.contains(TermMode::REPORT_EVENT_TYPES)
+            || mode.contains(TermMode::VI)
+            || self.ctx.search_active()
+            || self.ctx.display().hint_state.active()
+        {
+            return;
         }
-        bytes.extend_from_slice(text.as_bytes());
 
-        self.ctx.write_to_pty(bytes);
+        if let Some(bytes) = self.kitty_key_sequence(key, "") {
+            self.ctx.write_to_pty(bytes);
+        }
+    }
+
+    /// Kitty keyboard protocol escape sequence for a key event.
+    ///
+    /// Returns `None` if the key should use its legacy encoding instead.
+    fn kitty_key_sequence(&mut self, key: &KeyEvent, text: &str) -> Option<Vec<u8>> {
+        let mode = *self.ctx.terminal().mode();
+        if !mode.intersects(TermMode::KITTY_KEYBOARD_PROTOCOL) {
+            return None;
+        }
+
+        // Don't report `Alt` when it isn't supposed to send escapes.
+        let mut mods = self.ctx.modifiers().state();
+        if !self.alt_send_esc() {
+            mods.remove(ModifiersState::ALT);
+        }
+
+        let event_type = match key.state {
+            ElementState::Released => KeyEventType::Release,
+            ElementState::Pressed if key.repeat => KeyEventType::Repeat,
+            ElementState::Pressed => KeyEventType::Press,
+        };
+
+        let unmodified_key = key.key_without_modifiers();
+        let kitty_key = KittyKey {
+            key: unmodified_key.as_ref(),
+            shifted_key: key.logical_key.as_ref(),
+            location: key.location,
+            text,
+            event_type,
+        };
+
+        kitty_key.sequence(mods, mode)
     }
 
     /// Whether we should send `ESC` due to `Alt` being pressed.
@@ -1071,12 +1632,108 @@ impl<T: EventListener, A: ActionContext<T>> Processor<T, A> {
                     && self.ctx.modifiers().ralt_state() == ModifiersKeyState::Pressed))
     }
 
+    /// Match a key press against the key sequences of all bindings.
+    fn process_key_sequence(&mut self, key: &KeyEvent) {
+        let mode = BindingMode::new(
+            self.ctx.terminal().mode(),
+            self.ctx.search_active(),
+            self.ctx.replay_active(),
+        );
+        let custom_mode = self.ctx.custom_mode().map(String::from);
+        let foreground_process = OnceCell::new();
+        let mods = self.ctx.modifiers().state();
+
+        let mut sequence = mem::take(self.ctx.key_sequence());
+        let was_pending = !sequence.is_empty();
+        let steps = {
+            let bindings: Vec<&KeyBinding> = self
+                .ctx
+                .config()
+                .key_bindings()
+                .iter()
+                .filter(|binding| {
+                    !binding.prefix.is_empty()
+                        && mode.contains(binding.mode)
+                        && !mode.intersects(binding.notmode)
+                        && binding.custom_mode_matches(custom_mode.as_deref())
+                        && binding.process_matches(|| {
+                            foreground_process
+                                .get_or_init(|| self.ctx.foreground_process())
+                                .as_ref()
+                        })
+                })
+                .collect();
+            advance_key_sequence(&mut sequence, &bindings, mode, key, mods)
+        };
+        let is_pending = !sequence.is_empty();
+        *self.ctx.key_sequence() = sequence;
+
+        if was_pending && !is_pending {
+            let timer_id = TimerId::new(Topic::KeySequence, self.ctx.window().id());
+            self.ctx.scheduler_mut().unschedule(timer_id);
+        }
+
+        if was_pending || is_pending {
+            self.ctx.mark_dirty();
+        }
+
+        self.process_sequence_steps(steps);
+    }
+
+    /// Abort the pending key sequence, processing its keys as individual key presses.
+    pub fn flush_key_sequence(&mut self) {
+        let timer_id = TimerId::new(Topic::KeySequence, self.ctx.window().id());
+        self.ctx.scheduler_mut().unschedule(timer_id);
+
+        if self.ctx.key_sequence().is_empty() {
+            return;
+        }
+
+        self.ctx.mark_dirty();
+
+        let steps = abort_key_sequence(self.ctx.key_sequence());
+        self.process_sequence_steps(steps);
+    }
+
+    /// Apply the result of matching key sequences.
+    fn process_sequence_steps(&mut self, steps: Vec<SequenceStep<KeyEvent>>) {
+        for step in steps {
+            match step {
+                SequenceStep::Wait => {
+                    let timeout = self.ctx.config().key_sequence_timeout();
+                    let window_id = self.ctx.window().id();
+                    let timer_id = TimerId::new(Topic::KeySequence, window_id);
+                    let event = Event::new(EventType::KeySequenceTimeout, window_id);
+                    let scheduler = self.ctx.scheduler_mut();
+                    scheduler.unschedule(timer_id);
+                    scheduler.schedule(event, timeout, false, timer_id);
+                },
+                SequenceStep::Execute(action) => action.execute(&mut self.ctx),
+                SequenceStep::Key(key, mods) => {
+                    // Restore the modifiers which were held while the key was pressed.
+                    let modifiers = *self.ctx.modifiers();
+                    if mods != modifiers.state() {
+                        *self.ctx.modifiers() = mods.into();
+                    }
+                    self.process_key(&key);
+                    *self.ctx.modifiers() = modifiers;
+                },
+            }
+        }
+    }
+
     /// Attempt to find a binding and execute its action.
     ///
     /// The provided mode, mods, and key must match what is allowed by a binding
     /// for its action to be executed.
     fn process_key_bindings(&mut self, key: &KeyEvent) -> bool {
-        let mode = BindingMode::new(self.ctx.terminal().mode(), self.ctx.search_active());
+        let mode = BindingMode::new(
+            self.ctx.terminal().mode(),
+            self.ctx.search_active(),
+            self.ctx.replay_active(),
+        );
+        let custom_mode = self.ctx.custom_mode().map(String::from);
+        let foreground_process = OnceCell::new();
         let mods = self.ctx.modifiers().state();
 
         // Don't suppress char if no bindings were triggered.
@@ -1085,23 +1742,20 @@ impl<T: EventListener, A: ActionContext<T>> Processor<T, A> {
         for i in 0..self.ctx.config().key_bindings().len() {
             let binding = &self.ctx.config().key_bindings()[i];
 
-            // We don't want the key without modifier, because it means something else most of
-            // the time. However what we want is to manually lowercase the character to account
-            // for both small and capital latters on regular characters at the same time.
-            let logical_key = if let Key::Character(ch) = key.logical_key.as_ref() {
-                Key::Character(ch.to_lowercase().into())
-            } else {
-                key.logical_key.clone()
-            };
-
-            let key = match (&binding.trigger, logical_key) {
-                (BindingKey::Scancode(_), _) => BindingKey::Scancode(key.physical_key),
-                (_, code) => BindingKey::Keycode { key: code, location: key.location },
-            };
+            // Bindings with a prefix are handled by the key sequence processing.
+            if !binding.prefix.is_empty() {
+                continue;
+            }
 
-            if binding.is_triggered_by(mode, mods, &key) {
+            let key = binding_key(&binding.trigger, key);
+            if binding.is_triggered_by(mode, mods, &key)
+                && binding.custom_mode_matches(custom_mode.as_deref())
+                && binding.process_matches(|| {
+                    foreground_process.get_or_init(|| self.ctx.foreground_process()).as_ref()
+                })
+            {
                 // Pass through the key if any of the bindings has the `ReceiveChar` action.
-                *suppress_chars.get_or_insert(true) &= binding.action != Action::ReceiveChar;
+                *suppress_chars.get_or_insert(true) &= !binding.action.receives_char();
 
                 // Binding was triggered; run the action.
                 binding.action.clone().execute(&mut self.ctx);
@@ -1113,8 +1767,9 @@ impl<T: EventListener, A: ActionContext<T>> Processor<T, A> {
 
     /// Check mouse icon state in relation to the message bar.
     fn message_bar_cursor_state(&self) -> Option<CursorIcon> {
-        // Since search is above the message bar, the button is offset by search's height.
-        let search_height = usize::from(self.ctx.search_active());
+        // Since the footer bar is above the message bar, the button is offset by its height.
+        let search_height =
+            usize::from(self.ctx.search_active() || self.ctx.custom_mode().is_some());
 
         // Calculate Y position of the end of the last terminal line.
         let size = self.ctx.size_info();
@@ -1147,6 +1802,10 @@ impl<T: EventListener, A: ActionContext<T>> Processor<T, A> {
 
         if let Some(mouse_state) = self.message_bar_cursor_state() {
             mouse_state
+        } else if self.ctx.config().window.scrollbar.mode != ScrollbarMode::Never
+            && self.ctx.display().scrollbar.is_hovered()
+        {
+            CursorIcon::Default
         } else if self.ctx.display().highlighted_hint.as_ref().map_or(false, hint_highlighted) {
             CursorIcon::Pointer
         } else if !self.ctx.modifiers().state().shift_key() && self.ctx.mouse_mode() {
@@ -1192,6 +1851,430 @@ impl<T: EventListener, A: ActionContext<T>> Processor<T, A> {
     }
 }
 
+/// Type of a key event in the kitty keyboard protocol.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum KeyEventType {
+    Press = 1,
+    Repeat = 2,
+    Release = 3,
+}
+
+/// Key event encoded using the kitty keyboard protocol.
+///
+/// See <https://sw.kovidgoyal.net/kitty/keyboard-protocol/> for the full specification.
+struct KittyKey<'a> {
+    /// Key without any modifiers applied.
+    key: Key<&'a str>,
+
+    /// Key with all modifiers applied.
+    shifted_key: Key<&'a str>,
+
+    /// Physical location of the key.
+    location: KeyLocation,
+
+    /// Text produced by the key.
+    text: &'a str,
+
+    /// Press, repeat or release.
+    event_type: KeyEventType,
+}
+
+impl<'a> KittyKey<'a> {
+    /// Build the escape sequence for the key.
+    ///
+    /// Returns `None` if the key doesn't need to be encoded with the active keyboard modes.
+    fn sequence(&self, mods: ModifiersState, mode: TermMode) -> Option<Vec<u8>> {
+        let report_all = mode.contains(TermMode::REPORT_ALL_KEYS_AS_ESC);
+        let disambiguate = report_all || mode.contains(TermMode::DISAMBIGUATE_ESC_CODES);
+        let event_type = if mode.contains(TermMode::REPORT_EVENT_TYPES) {
+            self.event_type
+        } else {
+            KeyEventType::Press
+        };
+
+        let (code, terminator) = self.code()?;
+
+        // Modifiers and lock keys are only reported when all keys are reported.
+        if !report_all && self.is_modifier() {
+            return None;
+        }
+
+        // Determine if the key needs to be encoded or can be sent using its legacy encoding.
+        let text_key = matches!(self.key, Key::Enter | Key::Tab | Key::Backspace);
+        let mods_without_shift = mods & !ModifiersState::SHIFT;
+        let encode = match self.key {
+            _ if report_all => true,
+            // Releases of keys generating text aren't reported.
+            _ if event_type == KeyEventType::Release => !text_key,
+            _ if !disambiguate => false,
+            Key::Escape => true,
+            _ if self.location == KeyLocation::Numpad => true,
+            _ if text_key => !mods.is_empty(),
+            Key::Character(_) | Key::Space => !mods_without_shift.is_empty(),
+            _ => true,
+        };
+
+        if !encode {
+            // Default bindings for `Backspace` are disabled while disambiguating.
+            return match self.key {
+                Key::Backspace if disambiguate && event_type != KeyEventType::Release => {
+                    Some(b"\x7f".to_vec())
+                },
+                _ => None,
+            };
+        }
+
+        let mut modifiers = 0;
+        if mods.shift_key() {
+            modifiers |= 0b0001;
+        }
+        if mods.alt_key() {
+            modifiers |= 0b0010;
+        }
+        if mods.control_key() {
+            modifiers |= 0b0100;
+        }
+        if mods.super_key() {
+            modifiers |= 0b1000;
+        }
+
+        // Report the shifted key as alternate key.
+        let mut key_code = code.to_string();
+        if mode.contains(TermMode::REPORT_ALTERNATE_KEYS) && terminator == 'u' && mods.shift_key() {
+            if let Some(shifted) =
+                Self::single_char(&self.shifted_key).filter(|&c| c as u32 != code)
+            {
+                key_code = format!("{code}:{}", shifted as u32);
+            }
+        }
+
+        // Associated text is only reported when reporting all keys.
+        let mut text = String::new();
+        if report_all
+            && mode.contains(TermMode::REPORT_ASSOCIATED_TEXT)
+            && event_type != KeyEventType::Release
+            && !self.text.chars().any(char::is_control)
+        {
+            let codepoints: Vec<String> =
+                self.text.chars().map(|c| (c as u32).to_string()).collect();
+            text = codepoints.join(":");
+        }
+
+        let mut sequence = String::from("\x1b[");
+        let with_modifiers =
+            modifiers != 0 || event_type != KeyEventType::Press || !text.is_empty();
+
+        // Legacy sequences like `CSI A` omit the key code without modifiers.
+        if terminator == 'u' || terminator == '~' || with_modifiers {
+            sequence.push_str(&key_code);
+        }
+
+        if with_modifiers {
+            sequence.push_str(&format!(";{}", modifiers + 1));
+            if event_type != KeyEventType::Press {
+                sequence.push_str(&format!(":{}", event_type as u8));
+            }
+        }
+
+        if !text.is_empty() {
+            sequence.push_str(&format!(";{text}"));
+        }
+
+        sequence.push(terminator);
+
+        Some(sequence.into_bytes())
+    }
+
+    /// Key code and terminator used for the key's escape sequence.
+    fn code(&self) -> Option<(u32, char)> {
+        if self.location == KeyLocation::Numpad {
+            if let Some(code) = self.numpad_code() {
+                return Some((code, 'u'));
+            }
+        }
+
+        let code = match self.key {
+            Key::Character(_) => {
+                let c = Self::single_char(&self.key)?;
+                (c.to_lowercase().next().unwrap_or(c) as u32, 'u')
+            },
+            Key::Space => (32, 'u'),
+            Key::Escape => (27, 'u'),
+            Key::Enter => (13, 'u'),
+            Key::Tab => (9, 'u'),
+            Key::Backspace => (127, 'u'),
+            Key::Insert => (2, '~'),
+            Key::Delete => (3, '~'),
+            Key::ArrowLeft => (1, 'D'),
+            Key::ArrowRight => (1, 'C'),
+            Key::ArrowUp => (1, 'A'),
+            Key::ArrowDown => (1, 'B'),
+            Key::PageUp => (5, '~'),
+            Key::PageDown => (6, '~'),
+            Key::Home => (1, 'H'),
+            Key::End => (1, 'F'),
+            Key::F1 => (1, 'P'),
+            Key::F2 => (1, 'Q'),
+            Key::F3 => (13, '~'),
+            Key::F4 => (1, 'S'),
+            Key::F5 => (15, '~'),
+            Key::F6 => (17, '~'),
+            Key::F7 => (18, '~'),
+            Key::F8 => (19, '~'),
+            Key::F9 => (20, '~'),
+            Key::F10 => (21, '~'),
+            Key::F11 => (23, '~'),
+            Key::F12 => (24, '~'),
+            Key::F13 => (57376, 'u'),
+            Key::F14 => (57377, 'u'),
+            Key::F15 => (57378, 'u'),
+            Key::F16 => (57379, 'u'),
+            Key::F17 => (57380, 'u'),
+            Key::F18 => (57381, 'u'),
+            Key::F19 => (57382, 'u'),
+            Key::F20 => (57383, 'u'),
+            Key::F21 => (57384, 'u'),
+            Key::F22 => (57385, 'u'),
+            Key::F23 => (57386, 'u'),
+            Key::F24 => (57387, 'u'),
+            Key::F25 => (57388, 'u'),
+            Key::F26 => (57389, 'u'),
+            Key::F27 => (57390, 'u'),
+            Key::F28 => (57391, 'u'),
+            Key::F29 => (57392, 'u'),
+            Key::F30 => (57393, 'u'),
+            Key::F31 => (57394, 'u'),
+            Key::F32 => (57395, 'u'),
+            Key::F33 => (57396, 'u'),
+            Key::F34 => (57397, 'u'),
+            Key::F35 => (57398, 'u'),
+            Key::CapsLock => (57358, 'u'),
+            Key::ScrollLock => (57359, 'u'),
+            Key::NumLock => (57360, 'u'),
+            Key::PrintScreen => (57361, 'u'),
+            Key::Pause => (57362, 'u'),
+            Key::ContextMenu => (57363, 'u'),
+            Key::MediaPlay => (57428, 'u'),
+            Key::MediaPause => (57429, 'u'),
+            Key::MediaPlayPause => (57430, 'u'),
+            Key::MediaStop => (57432, 'u'),
+            Key::MediaFastForward => (57433, 'u'),
+            Key::MediaRewind => (57434, 'u'),
+            Key::MediaTrackNext => (57435, 'u'),
+            Key::MediaTrackPrevious => (57436, 'u'),
+            Key::MediaRecord => (57437, 'u'),
+            Key::AudioVolumeDown => (57438, 'u'),
+            Key::AudioVolumeUp => (57439, 'u'),
+            Key::AudioVolumeMute => (57440, 'u'),
+            Key::Shift if self.location == KeyLocation::Right => (57447, 'u'),
+            Key::Control if self.location == KeyLocation::Right => (57448, 'u'),
+            Key::Alt if self.location == KeyLocation::Right => (57449, 'u'),
+            Key::Super if self.location == KeyLocation::Right => (57450, 'u'),
+            Key::Hyper if self.location == KeyLocation::Right => (57451, 'u'),
+            Key::Meta if self.location == KeyLocation::Right => (57452, 'u'),
+            Key::Shift => (57441, 'u'),
+            Key::Control => (57442, 'u'),
+            Key::Alt => (57443, 'u'),
+            Key::Super => (57444, 'u'),
+            Key::Hyper => (57445, 'u'),
+            Key::Meta => (57446, 'u'),
+            Key::AltGraph => (57453, 'u'),
+            _ => return None,
+        };
+
+        Some(code)
+    }
+
+    /// Key code for keys on the numpad.
+    fn numpad_code(&self) -> Option<u32> {
+        let code = match self.key {
+            Key::Character(_) => match Self::single_char(&self.key)? {
+                c @ '0'..='9' => 57399 + c as u32 - '0' as u32,
+                '.' => 57409,
+                '/' => 57410,
+                '*' => 57411,
+                '-' => 57412,
+                '+' => 57413,
+                '=' => 57415,
+                ',' => 57416,
+                _ => return None,
+            },
+            Key::Enter => 57414,
+            Key::ArrowLeft => 57417,
+            Key::ArrowRight => 57418,
+            Key::ArrowUp => 57419,
+            Key::ArrowDown => 57420,
+            Key::PageUp => 57421,
+            Key::PageDown => 57422,
+            Key::Home => 57423,
+            Key::End => 57424,
+            Key::Insert => 57425,
+            Key::Delete => 57426,
+            Key::Clear => 57427,
+            _ => return None,
+        };
+
+        Some(code)
+    }
+
+    /// Check if the key is a modifier or lock key.
+    fn is_modifier(&self) -> bool {
+        is_modifier(&self.key)
+    }
+
+    /// Character of a key producing exactly one character.
+    fn single_char(key: &Key<&str>) -> Option<char> {
+        let mut chars = match key {
+            Key::Character(text) => text.chars(),
+            _ => return None,
+        };
+
+        chars.next().filter(|_| chars.next().is_none())
+    }
+}
+
+/// Convert a key event to the representation used by a binding's key.
+fn binding_key(binding_key: &BindingKey, key: &KeyEvent) -> BindingKey {
+    match binding_key {
+        BindingKey::Scancode(_) => BindingKey::Scancode(key.physical_key),
+        BindingKey::Keycode { .. } => {
+            // We don't want the key without modifier, because it means something else most of
+            // the time. However what we want is to manually lowercase the character to account
+            // for both small and capital latters on regular characters at the same time.
+            let logical_key = if let Key::Character(ch) = key.logical_key.as_ref() {
+                Key::Character(ch.to_lowercase().into())
+            } else {
+                key.logical_key.clone()
+            };
+
+            BindingKey::Keycode { key: logical_key, location: key.location }
+        },
+    }
+}
+
+/// Check if a key is a modifier or lock key.
+fn is_modifier<S>(key: &Key<S>) -> bool {
+    matches!(
+        key,
+        Key::Shift
+            | Key::Control
+            | Key::Alt
+            | Key::AltGraph
+            | Key::Super
+            | Key::Hyper
+            | Key::Meta
+            | Key::CapsLock
+            | Key::NumLock
+            | Key::ScrollLock
+    )
+}
+
+/// Action resulting from matching a key press against key sequences.
+#[derive(Debug, PartialEq, Eq)]
+enum SequenceStep<K> {
+    /// Wait for the next key of the sequence.
+    Wait,
+    /// Run the action of a completed key sequence.
+    Execute(Action),
+    /// Process a key press on its own, with the modifiers held while it was pressed.
+    Key(K, ModifiersState),
+}
+
+/// Key press which can be part of a key sequence.
+trait SequenceKey: Clone {
+    /// Convert the key to the representation used by a binding's key.
+    fn binding_key(&self, key: &BindingKey) -> BindingKey;
+
+    /// Check if the key is a modifier or lock key.
+    fn is_modifier(&self) -> bool;
+
+    /// Check if the key press was generated by holding down the key.
+    fn is_repeat(&self) -> bool;
+}
+
+impl SequenceKey for KeyEvent {
+    fn binding_key(&self, key: &BindingKey) -> BindingKey {
+        binding_key(key, self)
+    }
+
+    fn is_modifier(&self) -> bool {
+        is_modifier(&self.logical_key)
+    }
+
+    fn is_repeat(&self) -> bool {
+        self.repeat
+    }
+}
+
+/// Match a key press against the key sequences of the bindings.
+///
+/// Keys which neither complete nor continue a sequence abort it.
+fn advance_key_sequence<K: SequenceKey>(
+    sequence: &mut KeySequence<K>,
+    bindings: &[&KeyBinding],
+    mode: BindingMode,
+    key: &K,
+    mods: ModifiersState,
+) -> Vec<SequenceStep<K>> {
+    let pending = sequence.keys();
+
+    // Modifiers and held down keys neither continue nor abort a pending sequence.
+    if !pending.is_empty() && (key.is_repeat() || key.is_modifier()) {
+        return Vec::new();
+    }
+
+    let mut steps = Vec::new();
+    let mut extended = false;
+    for binding in bindings.iter().filter(|binding| binding.prefix.len() >= pending.len()) {
+        let prefix_matches = binding
+            .prefix
+            .iter()
+            .zip(pending)
+            .all(|(stroke, (key, mods))| stroke_matches(stroke, key, *mods));
+        if !prefix_matches {
+            continue;
+        }
+
+        match binding.prefix.get(pending.len()) {
+            Some(stroke) => extended |= stroke_matches(stroke, key, mods),
+            None if binding.is_triggered_by(mode, mods, &key.binding_key(&binding.trigger)) => {
+                steps.push(SequenceStep::Execute(binding.action.clone()));
+            },
+            None => (),
+        }
+    }
+
+    // Run all bindings completed by this key.
+    if !steps.is_empty() {
+        sequence.take();
+        return steps;
+    }
+
+    if extended {
+        sequence.push(key.clone(), mods);
+        return vec![SequenceStep::Wait];
+    }
+
+    if pending.is_empty() {
+        return vec![SequenceStep::Key(key.clone(), mods)];
+    }
+
+    // Process the keys individually when they don't match any sequence.
+    let mut steps = abort_key_sequence(sequence);
+    steps.append(&mut advance_key_sequence(sequence, bindings, mode, key, mods));
+    steps
+}
+
+/// Abort a key sequence, processing all its keys on their own.
+fn abort_key_sequence<K>(sequence: &mut KeySequence<K>) -> Vec<SequenceStep<K>> {
+    sequence.take().into_iter().map(|(key, mods)| SequenceStep::Key(key, mods)).collect()
+}
+
+/// Check if a key press matches a key of a key sequence.
+fn stroke_matches<K: SequenceKey>(stroke: &KeyStroke, key: &K, mods: ModifiersState) -> bool {
+    stroke.mods == mods && stroke.key == key.binding_key(&stroke.key)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1217,6 +2300,8 @@ mod tests {
         pub clipboard: &'a mut Clipboard,
         pub message_buffer: &'a mut MessageBuffer,
         pub modifiers: Modifiers,
+        pub vi_state: ViState,
+        pub key_sequence: KeySequence,
         config: &'a UiConfig,
     }
 
@@ -1281,6 +2366,14 @@ mod tests {
             &mut self.modifiers
         }
 
+        fn vi_state(&mut self) -> &mut ViState {
+            &mut self.vi_state
+        }
+
+        fn key_sequence(&mut self) -> &mut KeySequence {
+            &mut self.key_sequence
+        }
+
         fn window(&mut self) -> &mut Window {
             unimplemented!();
         }
@@ -1354,6 +2447,8 @@ mod tests {
                     size_info: &size,
                     clipboard: &mut clipboard,
                     modifiers: Default::default(),
+                    vi_state: Default::default(),
+                    key_sequence: Default::default(),
                     message_buffer: &mut message_buffer,
                     config: &cfg,
                 };
@@ -1527,7 +2622,18 @@ mod tests {
 
     test_process_binding! {
         name: process_binding_nomode_shiftmod_require_shift,
-        binding: Binding { trigger: KEY, mods: ModifiersState::SHIFT, action: Action::from("\x1b[1;2D"), mode: BindingMode::empty(), notmode: BindingMode::empty() },
+        binding: Binding {
+            trigger: KEY,
+            mods: ModifiersState::SHIFT,
+            action: Action::from("\x1b[1;2D"),
+            mode: BindingMode::empty(),
+            notmode: BindingMode::empty(),
+            prefix: Vec::new(),
+            custom_mode: None,
+            custom_notmodes: Vec::new(),
+            processes: Vec::new(),
+            notprocesses: Vec::new(),
+        },
         triggers: true,
         mode: BindingMode::empty(),
         mods: ModifiersState::SHIFT,
@@ -1535,7 +2641,18 @@ mod tests {
 
     test_process_binding! {
         name: process_binding_nomode_nomod_require_shift,
-        binding: Binding { trigger: KEY, mods: ModifiersState::SHIFT, action: Action::from("\x1b[1;2D"), mode: BindingMode::empty(), notmode: BindingMode::empty() },
+        binding: Binding {
+            trigger: KEY,
+            mods: ModifiersState::SHIFT,
+            action: Action::from("\x1b[1;2D"),
+            mode: BindingMode::empty(),
+            notmode: BindingMode::empty(),
+            prefix: Vec::new(),
+            custom_mode: None,
+            custom_notmodes: Vec::new(),
+            processes: Vec::new(),
+            notprocesses: Vec::new(),
+        },
         triggers: false,
         mode: BindingMode::empty(),
         mods: ModifiersState::empty(),
@@ -1543,7 +2660,18 @@ mod tests {
 
     test_process_binding! {
         name: process_binding_nomode_controlmod,
-        binding: Binding { trigger: KEY, mods: ModifiersState::CONTROL, action: Action::from("\x1b[1;5D"), mode: BindingMode::empty(), notmode: BindingMode::empty() },
+        binding: Binding {
+            trigger: KEY,
+            mods: ModifiersState::CONTROL,
+            action: Action::from("\x1b[1;5D"),
+            mode: BindingMode::empty(),
+            notmode: BindingMode::empty(),
+            prefix: Vec::new(),
+            custom_mode: None,
+            custom_notmodes: Vec::new(),
+            processes: Vec::new(),
+            notprocesses: Vec::new(),
+        },
         triggers: true,
         mode: BindingMode::empty(),
         mods: ModifiersState::CONTROL,
@@ -1551,7 +2679,18 @@ mod tests {
 
     test_process_binding! {
         name: process_binding_nomode_nomod_require_not_appcursor,
-        binding: Binding { trigger: KEY, mods: ModifiersState::empty(), action: Action::from("\x1b[D"), mode: BindingMode::empty(), notmode: BindingMode::APP_CURSOR },
+        binding: Binding {
+            trigger: KEY,
+            mods: ModifiersState::empty(),
+            action: Action::from("\x1b[D"),
+            mode: BindingMode::empty(),
+            notmode: BindingMode::APP_CURSOR,
+            prefix: Vec::new(),
+            custom_mode: None,
+            custom_notmodes: Vec::new(),
+            processes: Vec::new(),
+            notprocesses: Vec::new(),
+        },
         triggers: true,
         mode: BindingMode::empty(),
         mods: ModifiersState::empty(),
@@ -1559,7 +2698,18 @@ mod tests {
 
     test_process_binding! {
         name: process_binding_appcursormode_nomod_require_appcursor,
-        binding: Binding { trigger: KEY, mods: ModifiersState::empty(), action: Action::from("\x1bOD"), mode: BindingMode::APP_CURSOR, notmode: BindingMode::empty() },
+        binding: Binding {
+            trigger: KEY,
+            mods: ModifiersState::empty(),
+            action: Action::from("\x1bOD"),
+            mode: BindingMode::APP_CURSOR,
+            notmode: BindingMode::empty(),
+            prefix: Vec::new(),
+            custom_mode: None,
+            custom_notmodes: Vec::new(),
+            processes: Vec::new(),
+            notprocesses: Vec::new(),
+        },
         triggers: true,
         mode: BindingMode::APP_CURSOR,
         mods: ModifiersState::empty(),
@@ -1567,7 +2717,18 @@ mod tests {
 
     test_process_binding! {
         name: process_binding_nomode_nomod_require_appcursor,
-        binding: Binding { trigger: KEY, mods: ModifiersState::empty(), action: Action::from("\x1bOD"), mode: BindingMode::APP_CURSOR, notmode: BindingMode::empty() },
+        binding: Binding {
+            trigger: KEY,
+            mods: ModifiersState::empty(),
+            action: Action::from("\x1bOD"),
+            mode: BindingMode::APP_CURSOR,
+            notmode: BindingMode::empty(),
+            prefix: Vec::new(),
+            custom_mode: None,
+            custom_notmodes: Vec::new(),
+            processes: Vec::new(),
+            notprocesses: Vec::new(),
+        },
         triggers: false,
         mode: BindingMode::empty(),
         mods: ModifiersState::empty(),
@@ -1575,7 +2736,18 @@ mod tests {
 
     test_process_binding! {
         name: process_binding_appcursormode_appkeypadmode_nomod_require_appcursor,
-        binding: Binding { trigger: KEY, mods: ModifiersState::empty(), action: Action::from("\x1bOD"), mode: BindingMode::APP_CURSOR, notmode: BindingMode::empty() },
+        binding: Binding {
+            trigger: KEY,
+            mods: ModifiersState::empty(),
+            action: Action::from("\x1bOD"),
+            mode: BindingMode::APP_CURSOR,
+            notmode: BindingMode::empty(),
+            prefix: Vec::new(),
+            custom_mode: None,
+            custom_notmodes: Vec::new(),
+            processes: Vec::new(),
+            notprocesses: Vec::new(),
+        },
         triggers: true,
         mode: BindingMode::APP_CURSOR | BindingMode::APP_KEYPAD,
         mods: ModifiersState::empty(),
@@ -1583,9 +2755,263 @@ mod tests {
 
     test_process_binding! {
         name: process_binding_fail_with_extra_mods,
-        binding: Binding { trigger: KEY, mods: ModifiersState::SUPER, action: Action::from("arst"), mode: BindingMode::empty(), notmode: BindingMode::empty() },
+        binding: Binding {
+            trigger: KEY,
+            mods: ModifiersState::SUPER,
+            action: Action::from("arst"),
+            mode: BindingMode::empty(),
+            notmode: BindingMode::empty(),
+            prefix: Vec::new(),
+            custom_mode: None,
+            custom_notmodes: Vec::new(),
+            processes: Vec::new(),
+            notprocesses: Vec::new(),
+        },
         triggers: false,
         mode: BindingMode::empty(),
         mods: ModifiersState::ALT | ModifiersState::SUPER,
     }
+
+    fn kitty_key<'a>(key: Key<&'a str>, text: &'a str, event_type: KeyEventType) -> KittyKey<'a> {
+        KittyKey {
+            key: key.clone(),
+            shifted_key: key,
+            location: KeyLocation::Standard,
+            text,
+            event_type,
+        }
+    }
+
+    #[test]
+    fn kitty_disambiguate_keys() {
+        let mode = TermMode::DISAMBIGUATE_ESC_CODES;
+        let none = ModifiersState::empty();
+
+        // Keys producing text are sent as is.
+        let key = kitty_key(Key::Character("a"), "a", KeyEventType::Press);
+        assert_eq!(key.sequence(none, mode), None);
+        assert_eq!(key.sequence(ModifiersState::SHIFT, mode), None);
+        let key = kitty_key(Key::Tab, "\t", KeyEventType::Press);
+        assert_eq!(key.sequence(none, mode), None);
+
+        // Ambiguous keys are encoded.
+        let key = kitty_key(Key::Escape, "\x1b", KeyEventType::Press);
+        assert_eq!(key.sequence(none, mode), Some(b"\x1b[27u".to_vec()));
+        let key = kitty_key(Key::Character("i"), "\t", KeyEventType::Press);
+        assert_eq!(key.sequence(ModifiersState::CONTROL, mode), Some(b"\x1b[105;5u".to_vec()));
+        let key = kitty_key(Key::Enter, "\r", KeyEventType::Press);
+        assert_eq!(key.sequence(ModifiersState::SHIFT, mode), Some(b"\x1b[13;2u".to_vec()));
+
+        // Functional keys keep their legacy terminators.
+        let key = kitty_key(Key::ArrowUp, "", KeyEventType::Press);
+        assert_eq!(key.sequence(none, mode), Some(b"\x1b[A".to_vec()));
+        assert_eq!(key.sequence(ModifiersState::CONTROL, mode), Some(b"\x1b[1;5A".to_vec()));
+        let key = kitty_key(Key::Delete, "", KeyEventType::Press);
+        assert_eq!(key.sequence(ModifiersState::ALT, mode), Some(b"\x1b[3;3~".to_vec()));
+
+        // Modifiers are only reported when reporting all keys.
+        let key = kitty_key(Key::Shift, "", KeyEventType::Press);
+        assert_eq!(key.sequence(ModifiersState::SHIFT, mode), None);
+    }
+
+    #[test]
+    fn kitty_report_event_types() {
+        let mode = TermMode::DISAMBIGUATE_ESC_CODES | TermMode::REPORT_EVENT_TYPES;
+        let none = ModifiersState::empty();
+
+        let key = kitty_key(Key::Character("a"), "", KeyEventType::Release);
+        assert_eq!(key.sequence(none, mode), Some(b"\x1b[97;1:3u".to_vec()));
+        let key = kitty_key(Key::Character("a"), "a", KeyEventType::Repeat);
+        assert_eq!(key.sequence(ModifiersState::CONTROL, mode), Some(b"\x1b[97;5:2u".to_vec()));
+        let key = kitty_key(Key::ArrowLeft, "", KeyEventType::Release);
+        assert_eq!(key.sequence(none, mode), Some(b"\x1b[1;1:3D".to_vec()));
+
+        // Releases of keys generating text aren't reported.
+        let key = kitty_key(Key::Enter, "", KeyEventType::Release);
+        assert_eq!(key.sequence(none, mode), None);
+
+        // Releases aren't reported without the flag.
+        let key = kitty_key(Key::Character("a"), "", KeyEventType::Release);
+        assert_eq!(key.sequence(none, TermMode::DISAMBIGUATE_ESC_CODES), None);
+    }
+
+    #[test]
+    fn kitty_report_all_keys() {
+        let mode = TermMode::REPORT_ALL_KEYS_AS_ESC
+            | TermMode::REPORT_ALTERNATE_KEYS
+            | TermMode::REPORT_ASSOCIATED_TEXT;
+
+        let key = kitty_key(Key::Character("a"), "a", KeyEventType::Press);
+        assert_eq!(key.sequence(ModifiersState::empty(), mode), Some(b"\x1b[97;1;97u".to_vec()));
+
+        let key = KittyKey {
+            shifted_key: Key::Character("A"),
+            ..kitty_key(Key::Character("a"), "A", KeyEventType::Press)
+        };
+        assert_eq!(key.sequence(ModifiersState::SHIFT, mode), Some(b"\x1b[97:65;2;65u".to_vec()));
+
+        let key = kitty_key(Key::Enter, "\r", KeyEventType::Press);
+        assert_eq!(key.sequence(ModifiersState::empty(), mode), Some(b"\x1b[13u".to_vec()));
+
+        let key = KittyKey {
+            location: KeyLocation::Right,
+            ..kitty_key(Key::Shift, "", KeyEventType::Press)
+        };
+        assert_eq!(key.sequence(ModifiersState::SHIFT, mode), Some(b"\x1b[57447;2u".to_vec()));
+    }
+
+    #[derive(Clone, Debug, PartialEq, Eq)]
+    struct SequenceTestKey {
+        key: Key,
+        repeat: bool,
+    }
+
+    impl SequenceKey for SequenceTestKey {
+        fn binding_key(&self, _key: &BindingKey) -> BindingKey {
+            BindingKey::Keycode { key: self.key.clone(), location: KeyLocation::Standard }
+        }
+
+        fn is_modifier(&self) -> bool {
+            is_modifier(&self.key)
+        }
+
+        fn is_repeat(&self) -> bool {
+            self.repeat
+        }
+    }
+
+    fn sequence_key(key: Key) -> SequenceTestKey {
+        SequenceTestKey { key, repeat: false }
+    }
+
+    fn character(text: &str) -> Key {
+        Key::Character(text.into())
+    }
+
+    /// Binding for the key sequence `Ctrl+a c`.
+    fn sequence_binding() -> KeyBinding {
+        Binding {
+            trigger: BindingKey::Keycode { key: character("c"), location: KeyLocation::Standard },
+            mods: ModifiersState::empty(),
+            action: Action::from("sequence"),
+            mode: BindingMode::empty(),
+            notmode: BindingMode::empty(),
+            prefix: vec![KeyStroke {
+                key: BindingKey::Keycode { key: character("a"), location: KeyLocation::Standard },
+                mods: ModifiersState::CONTROL,
+            }],
+            custom_mode: None,
+            custom_notmodes: Vec::new(),
+            processes: Vec::new(),
+            notprocesses: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn key_sequence_completion() {
+        let binding = sequence_binding();
+        let bindings = [&binding];
+        let mode = BindingMode::empty();
+        let mut sequence = KeySequence::default();
+
+        let prefix = sequence_key(character("a"));
+        let steps =
+            advance_key_sequence(&mut sequence, &bindings, mode, &prefix, ModifiersState::CONTROL);
+        assert_eq!(steps, vec![SequenceStep::Wait]);
+        assert_eq!(sequence.keys(), [(prefix, ModifiersState::CONTROL)]);
+
+        let trigger = sequence_key(character("c"));
+        let steps =
+            advance_key_sequence(&mut sequence, &bindings, mode, &trigger, ModifiersState::empty());
+        assert_eq!(steps, vec![SequenceStep::Execute(Action::from("sequence"))]);
+        assert!(sequence.is_empty());
+    }
+
+    #[test]
+    fn key_sequence_fallthrough() {
+        let binding = sequence_binding();
+        let bindings = [&binding];
+        let mode = BindingMode::empty();
+        let mut sequence = KeySequence::default();
+
+        // Keys without a pending sequence are processed immediately.
+        let key = sequence_key(character("x"));
+        let steps =
+            advance_key_sequence(&mut sequence, &bindings, mode, &key, ModifiersState::empty());
+        assert_eq!(steps, vec![SequenceStep::Key(key, ModifiersState::empty())]);
+        assert!(sequence.is_empty());
+
+        // Mismatching keys flush the pending keys with their original modifiers.
+        let prefix = sequence_key(character("a"));
+        advance_key_sequence(&mut sequence, &bindings, mode, &prefix, ModifiersState::CONTROL);
+        let key = sequence_key(character("x"));
+        let steps =
+            advance_key_sequence(&mut sequence, &bindings, mode, &key, ModifiersState::empty());
+        assert_eq!(
+            steps,
+            vec![
+                SequenceStep::Key(prefix.clone(), ModifiersState::CONTROL),
+                SequenceStep::Key(key, ModifiersState::empty()),
+            ]
+        );
+        assert!(sequence.is_empty());
+
+        // Mismatching keys can start a new sequence.
+        advance_key_sequence(&mut sequence, &bindings, mode, &prefix, ModifiersState::CONTROL);
+        let steps =
+            advance_key_sequence(&mut sequence, &bindings, mode, &prefix, ModifiersState::CONTROL);
+        assert_eq!(
+            steps,
+            vec![SequenceStep::Key(prefix.clone(), ModifiersState::CONTROL), SequenceStep::Wait,]
+        );
+        assert_eq!(sequence.keys(), [(prefix, ModifiersState::CONTROL)]);
+    }
+
+    #[test]
+    fn key_sequence_ignores_modifiers_and_repeats() {
+        let binding = sequence_binding();
+        let bindings = [&binding];
+        let mode = BindingMode::empty();
+        let mut sequence = KeySequence::default();
+
+        let prefix = sequence_key(character("a"));
+        advance_key_sequence(&mut sequence, &bindings, mode, &prefix, ModifiersState::CONTROL);
+
+        let modifier = sequence_key(Key::Shift);
+        let steps =
+            advance_key_sequence(&mut sequence, &bindings, mode, &modifier, ModifiersState::SHIFT);
+        assert_eq!(steps, Vec::new());
+
+        let repeat = SequenceTestKey { repeat: true, ..prefix.clone() };
+        let steps =
+            advance_key_sequence(&mut sequence, &bindings, mode, &repeat, ModifiersState::CONTROL);
+        assert_eq!(steps, Vec::new());
+        assert_eq!(sequence.keys(), [(prefix, ModifiersState::CONTROL)]);
+
+        let trigger = sequence_key(character("c"));
+        let steps =
+            advance_key_sequence(&mut sequence, &bindings, mode, &trigger, ModifiersState::empty());
+        assert_eq!(steps, vec![SequenceStep::Execute(Action::from("sequence"))]);
+    }
+
+    #[test]
+    fn key_sequence_timeout() {
+        let binding = sequence_binding();
+        let bindings = [&binding];
+        let mode = BindingMode::empty();
+        let mut sequence = KeySequence::default();
+
+        let prefix = sequence_key(character("a"));
+        advance_key_sequence(&mut sequence, &bindings, mode, &prefix, ModifiersState::CONTROL);
+
+        // Timeouts process the pending keys on their own.
+        let steps = abort_key_sequence(&mut sequence);
+        assert_eq!(steps, vec![SequenceStep::Key(prefix, ModifiersState::CONTROL)]);
+        assert!(sequence.is_empty());
+
+        let trigger = sequence_key(character("c"));
+        let steps =
+            advance_key_sequence(&mut sequence, &bindings, mode, &trigger, ModifiersState::empty());
+        assert_eq!(steps, vec![SequenceStep::Key(trigger, ModifiersState::empty())]);
+    }
 }
@@ -1,23 +1,133 @@
 //! Alacritty socket IPC.
 
 use std::ffi::OsStr;
-use std::io::{BufRead, BufReader, Error as IoError, ErrorKind, Result as IoResult, Write};
+use std::io::{BufRead, BufReader, Error as IoError, ErrorKind, Read, Result as IoResult, Write};
+use std::net::Shutdown;
 use std::os::unix::net::{UnixListener, UnixStream};
 use std::path::PathBuf;
+use std::sync::{mpsc, Arc};
 use std::{env, fs, process};
 
 use log::warn;
+use serde::{Deserialize, Serialize};
 use winit::event_loop::EventLoopProxy;
 use winit::window::WindowId;
 
+use alacritty_terminal::config::Program;
 use alacritty_terminal::thread;
 
-use crate::cli::{Options, SocketMessage};
+use crate::cli::{IpcAction, IpcSendText, Options, SocketMessage};
+use crate::config::{Action, UiConfig};
 use crate::event::{Event, EventType};
 
 /// Environment variable name for the IPC socket path.
 const ALACRITTY_SOCKET_ENV: &str = "ALACRITTY_SOCKET";
 
+/// Reply to an IPC socket message.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum SocketReply {
+    /// All open windows.
+    Windows(Vec<WindowInfo>),
+
+    /// Text content of a window.
+    Text(String),
+
+    /// Configuration as JSON.
+    Config(serde_json::Value),
+
+    /// Failure to handle the message.
+    Error(String),
+}
+
+/// Event streamed to IPC subscribers.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum IpcEvent {
+    /// New window was created.
+    WindowCreated { window_id: u64 },
+
+    /// Window was closed.
+    WindowClosed { window_id: u64 },
+
+    /// Window gained or lost keyboard focus.
+    Focus { window_id: u64, focused: bool },
+
+    /// Window title has changed.
+    Title { window_id: u64, title: String },
+
+    /// Terminal bell rang.
+    Bell { window_id: u64 },
+
+    /// Working directory reported by the shell has changed.
+    WorkingDirectory { window_id: u64, cwd: PathBuf },
+
+    /// Terminal was shut down.
+    Exit { window_id: u64 },
+}
+
+/// Socket connection subscribed to IPC events.
+#[derive(Debug)]
+pub struct Subscriber {
+    sender: mpsc::Sender<IpcEvent>,
+}
+
+impl Subscriber {
+    /// Start streaming events to a socket connection.
+    ///
+    /// Events are written on a separate thread, to avoid blocking on slow clients.
+    pub fn new(stream: Arc<UnixStream>) -> Self {
+        let (sender, receiver) = mpsc::channel::<IpcEvent>();
+
+        thread::spawn_named("socket subscriber", move || {
+            for event in receiver {
+                let mut event = match serde_json::to_string(&event) {
+                    Ok(event) => event,
+                    Err(err) => {
+                        warn!("Failed to serialize IPC event: {}", err);
+                        continue;
+                    },
+                };
+                event.push('\n');
+
+                // Stop once the subscriber has disconnected.
+                if (&*stream).write_all(event.as_bytes()).is_err() {
+                    break;
+                }
+            }
+        });
+
+        Self { sender }
+    }
+
+    /// Send an event to the subscriber.
+    ///
+    /// Returns `false` if the subscriber has disconnected.
+    pub fn send(&self, event: IpcEvent) -> bool {
+        self.sender.send(event).is_ok()
+    }
+}
+
+/// Window state reported through IPC.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct WindowInfo {
+    pub id: u64,
+    pub title: String,
+    pub focused: bool,
+
+    /// Window width in pixels.
+    pub width: u32,
+
+    /// Window height in pixels.
+    pub height: u32,
+
+    pub columns: usize,
+    pub lines: usize,
+
+    /// Working directory of the terminal's foreground process.
+    pub cwd: Option<PathBuf>,
+}
+
 /// Create an IPC socket.
 pub fn spawn_ipc_socket(options: &Options, event_proxy: EventLoopProxy<Event>) -> Option<PathBuf> {
     // Create the IPC socket and export its path as env variable if necessary.
@@ -71,6 +181,36 @@ pub fn spawn_ipc_socket(options: &Options, event_proxy: EventLoopProxy<Event>) -
                     let event = Event::new(EventType::IpcConfig(ipc_config), window_id);
                     let _ = event_proxy.send_event(event);
                 },
+                SocketMessage::ListWindows => {
+                    let stream = Arc::new(stream.into_inner());
+                    let event = Event::new(EventType::IpcListWindows(stream), None);
+                    let _ = event_proxy.send_event(event);
+                },
+                SocketMessage::GetText(get_text) => {
+                    let stream = Arc::new(stream.into_inner());
+                    let event = Event::new(EventType::IpcGetText(get_text, stream), None);
+                    let _ = event_proxy.send_event(event);
+                },
+                SocketMessage::GetConfig(get_config) => {
+                    let stream = Arc::new(stream.into_inner());
+                    let event = Event::new(EventType::IpcGetConfig(get_config, stream), None);
+                    let _ = event_proxy.send_event(event);
+                },
+                SocketMessage::Subscribe => {
+                    let stream = Arc::new(stream.into_inner());
+                    let event = Event::new(EventType::IpcSubscribe(stream), None);
+                    let _ = event_proxy.send_event(event);
+                },
+                SocketMessage::Action(ipc_action) => {
+                    let stream = Arc::new(stream.into_inner());
+                    let event = Event::new(EventType::IpcAction(ipc_action, stream), None);
+                    let _ = event_proxy.send_event(event);
+                },
+                SocketMessage::SendText(send_text) => {
+                    let stream = Arc::new(stream.into_inner());
+                    let event = Event::new(EventType::IpcSendText(send_text, stream), None);
+                    let _ = event_proxy.send_event(event);
+                },
             }
         }
     });
@@ -79,14 +219,129 @@ pub fn spawn_ipc_socket(options: &Options, event_proxy: EventLoopProxy<Event>) -
 }
 
 /// Send a message to the active Alacritty socket.
-pub fn send_message(socket: Option<PathBuf>, message: SocketMessage) -> IoResult<()> {
+///
+/// Returns the socket connection, which can be used to read the reply.
+pub fn send_message(socket: Option<PathBuf>, message: SocketMessage) -> IoResult<UnixStream> {
     let mut socket = find_socket(socket)?;
 
     let message = serde_json::to_string(&message)?;
     socket.write_all(message[..].as_bytes())?;
     let _ = socket.flush();
 
-    Ok(())
+    // Signal the end of the message, to allow reading the reply.
+    socket.shutdown(Shutdown::Write)?;
+
+    Ok(socket)
+}
+
+/// Read the reply to a message, if it has one.
+pub fn read_reply(mut socket: UnixStream) -> IoResult<Option<SocketReply>> {
+    let mut reply = String::new();
+    socket.read_to_string(&mut reply)?;
+
+    if reply.is_empty() {
+        return Ok(None);
+    }
+
+    Ok(Some(serde_json::from_str(&reply)?))
+}
+
+/// Send a reply to an IPC socket message.
+///
+/// The reply is written on a separate thread, to avoid blocking on slow clients.
+pub fn send_reply(stream: Arc<UnixStream>, reply: SocketReply) {
+    let reply = match serde_json::to_string(&reply) {
+        Ok(reply) => reply,
+        Err(err) => {
+            warn!("Failed to serialize IPC reply: {}", err);
+            return;
+        },
+    };
+
+    thread::spawn_named("socket reply", move || {
+        if let Err(err) = (&*stream).write_all(reply.as_bytes()) {
+            warn!("Failed to write IPC reply: {}", err);
+        }
+    });
+}
+
+/// Convert an IPC action request to its binding action.
+pub fn parse_action(ipc_action: &IpcAction) -> Result<Action, String> {
+    if let Some(chars) = &ipc_action.chars {
+        return Ok(Action::Esc(chars.clone()));
+    }
+
+    if let Some((program, args)) = ipc_action.command.split_first() {
+        let program = Program::WithArgs { program: program.clone(), args: args.to_vec() };
+        return Ok(Action::Command(program));
+    }
+
+    let name = ipc_action.action.clone().unwrap_or_default();
+    match Action::from_value(toml::Value::String(name)) {
+        Ok(action @ Action::Mouse(_)) => {
+            Err(format!("action `{}` is only available for mouse bindings", action))
+        },
+        Ok(action) => Ok(action),
+        Err(err) => Err(err.to_string()),
+    }
+}
+
+/// Get the bytes written to the terminal for an IPC text request.
+pub fn parse_text(send_text: &IpcSendText) -> Result<Vec<u8>, String> {
+    if !send_text.escape {
+        return Ok(send_text.text.as_bytes().to_vec());
+    }
+
+    let mut bytes = Vec::with_capacity(send_text.text.len());
+    let mut chars = send_text.text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            let mut buf = [0; 4];
+            bytes.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+            continue;
+        }
+
+        let byte = match chars.next() {
+            Some('n') => b'\n',
+            Some('r') => b'\r',
+            Some('t') => b'\t',
+            Some('e') => b'\x1b',
+            Some('\\') => b'\\',
+            Some('x') => {
+                let hex: String = chars.by_ref().take(2).collect();
+                match u8::from_str_radix(&hex, 16) {
+                    Ok(byte) if hex.len() == 2 => byte,
+                    _ => return Err(format!("invalid escape sequence: \\x{hex}")),
+                }
+            },
+            Some(c) => return Err(format!("invalid escape sequence: \\{c}")),
+            None => return Err(String::from("incomplete escape sequence at end of text")),
+        };
+        bytes.push(byte);
+    }
+
+    Ok(bytes)
+}
+
+/// Create the reply for a configuration request.
+///
+/// The option is a dot-separated path into the configuration, like `cursor.style`.
+pub fn config_reply(config: &UiConfig, option: Option<&str>) -> SocketReply {
+    let mut value = match serde_json::to_value(config) {
+        Ok(value) => value,
+        Err(err) => return SocketReply::Error(format!("unable to serialize config: {err}")),
+    };
+
+    for key in option.into_iter().flat_map(|option| option.split('.')) {
+        value = match value.get_mut(key) {
+            Some(value) => value.take(),
+            None => {
+                return SocketReply::Error(format!("unknown config option: {}", option.unwrap()))
+            },
+        };
+    }
+
+    SocketReply::Config(value)
 }
 
 /// Directory for the IPC socket file.
@@ -169,3 +424,87 @@ fn socket_prefix() -> String {
 fn socket_prefix() -> String {
     String::from("Alacritty")
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use alacritty_terminal::vi_mode::ViMotion;
+
+    use crate::config::{SearchAction, ViAction};
+
+    #[test]
+    fn config_reply_option() {
+        let config = UiConfig::default();
+
+        let reply = config_reply(&config, Some("scrolling.multiplier"));
+        assert_eq!(reply, SocketReply::Config(serde_json::json!(3)));
+
+        let reply = config_reply(&config, Some("font.normal"));
+        let family = serde_json::to_value(&config.font.normal().family).unwrap();
+        assert_eq!(
+            reply,
+            SocketReply::Config(serde_json::json!({ "family": family, "style": null }))
+        );
+
+        let reply = config_reply(&config, Some("font.missing"));
+        assert_eq!(reply, SocketReply::Error(String::from("unknown config option: font.missing")));
+
+        match config_reply(&config, None) {
+            SocketReply::Config(value) => assert!(value.get("window").is_some()),
+            reply => panic!("unexpected reply: {reply:?}"),
+        }
+    }
+
+    #[test]
+    fn parse_escaped_text() {
+        let parse = |text: &str, escape: bool| {
+            parse_text(&IpcSendText { text: text.into(), escape, ..Default::default() })
+        };
+
+        assert_eq!(parse("make test\\n", false), Ok(b"make test\\n".to_vec()));
+        assert_eq!(parse("make test\\n", true), Ok(b"make test\n".to_vec()));
+        assert_eq!(parse("\\r\\t\\e[1m\\\\\\x7fé", true), Ok("\r\t\x1b[1m\\\x7fé".into()));
+        assert_eq!(parse("\\xff", true), Ok(vec![0xff]));
+        assert!(parse("\\q", true).is_err());
+        assert!(parse("\\x1", true).is_err());
+        assert!(parse("\\xzz", true).is_err());
+        assert!(parse("a\\", true).is_err());
+    }
+
+    #[test]
+    fn parse_actions() {
+        let parse = |action: &str| {
+            parse_action(&IpcAction { action: Some(action.into()), ..Default::default() })
+        };
+
+        assert_eq!(parse("ScrollToTop"), Ok(Action::ScrollToTop));
+        assert_eq!(parse("Up"), Ok(Action::ViMotion(ViMotion::Up)));
+        assert_eq!(parse("SearchFocusNext"), Ok(SearchAction::SearchFocusNext.into()));
+        assert_eq!(parse("ToggleNormalSelection"), Ok(ViAction::ToggleNormalSelection.into()));
+        assert!(parse("ExpandSelection").is_err());
+        assert!(parse("Missing").is_err());
+
+        let chars = IpcAction { chars: Some("\x0c".into()), ..Default::default() };
+        assert_eq!(parse_action(&chars), Ok(Action::Esc("\x0c".into())));
+
+        let command = vec![String::from("notify-send"), String::from("-u"), String::from("low")];
+        let command = IpcAction { command, ..Default::default() };
+        let program = Program::WithArgs {
+            program: String::from("notify-send"),
+            args: vec![String::from("-u"), String::from("low")],
+        };
+        assert_eq!(parse_action(&command), Ok(Action::Command(program)));
+    }
+
+    #[test]
+    fn event_format() {
+        let event = IpcEvent::Title { window_id: 1, title: String::from("vim") };
+        let json = serde_json::to_string(&event).unwrap();
+        assert_eq!(json, r#"{"event":"title","window_id":1,"title":"vim"}"#);
+
+        let event = IpcEvent::WindowClosed { window_id: 2 };
+        let json = serde_json::to_string(&event).unwrap();
+        assert_eq!(json, r#"{"event":"window_closed","window_id":2}"#);
+    }
+}
@@ -2,7 +2,7 @@ use std::ffi::{CStr, CString, IntoStringError};
 use std::fmt::{self, Display, Formatter};
 use std::io;
 use std::mem::{self, MaybeUninit};
-use std::os::raw::{c_int, c_void};
+use std::os::raw::{c_char, c_int, c_void};
 use std::path::PathBuf;
 
 /// Error during working directory retrieval.
@@ -68,12 +68,41 @@ pub fn cwd(pid: c_int) -> Result<PathBuf, Error> {
     Ok(CString::from(c_str).into_string().map(PathBuf::from)?)
 }
 
+pub fn executable(pid: c_int) -> Result<PathBuf, Error> {
+    let mut buffer = [0 as c_char; sys::PROC_PIDPATHINFO_MAXSIZE];
+    let size = buffer.len() as u32;
+
+    let c_str = unsafe {
+        match sys::proc_pidpath(pid, buffer.as_mut_ptr() as *mut c_void, size) {
+            c if c <= 0 => return Err(io::Error::last_os_error().into()),
+            _ => CStr::from_ptr(buffer.as_ptr()),
+        }
+    };
+
+    Ok(CString::from(c_str).into_string().map(PathBuf::from)?)
+}
+
+pub fn name(pid: c_int) -> Result<String, Error> {
+    let mut buffer = [0 as c_char; sys::PROC_PIDPATHINFO_MAXSIZE];
+    let size = buffer.len() as u32;
+
+    let c_str = unsafe {
+        match sys::proc_name(pid, buffer.as_mut_ptr() as *mut c_void, size) {
+            c if c <= 0 => return Err(io::Error::last_os_error().into()),
+            _ => CStr::from_ptr(buffer.as_ptr()),
+        }
+    };
+
+    Ok(CString::from(c_str).into_string()?)
+}
+
 /// Bindings for libproc.
 #[allow(non_camel_case_types)]
 mod sys {
     use std::os::raw::{c_char, c_int, c_longlong, c_void};
 
     pub const PROC_PIDVNODEPATHINFO: c_int = 9;
+    pub const PROC_PIDPATHINFO_MAXSIZE: usize = 4096;
 
     type gid_t = c_int;
     type off_t = c_longlong;
@@ -143,6 +172,10 @@ mod sys {
             buffer: *mut c_void,
             buffersize: c_int,
         ) -> c_int;
+
+        pub fn proc_pidpath(pid: c_int, buffer: *mut c_void, buffersize: u32) -> c_int;
+
+        pub fn proc_name(pid: c_int, buffer: *mut c_void, buffersize: u32) -> c_int;
     }
 }
 
@@ -156,4 +189,9 @@ mod tests {
     fn cwd_matches_current_dir() {
         assert_eq!(cwd(process::id() as i32).ok(), env::current_dir().ok());
     }
+
+    #[test]
+    fn executable_matches_current_exe() {
+        assert_eq!(executable(process::id() as i32).ok(), env::current_exe().ok());
+    }
 }
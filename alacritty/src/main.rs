@@ -18,15 +18,20 @@ use std::error::Error;
 use std::fmt::Write as _;
 use std::fs;
 use std::io::{self, Write};
+#[cfg(unix)]
+use std::io::{BufRead, BufReader};
 use std::path::PathBuf;
+use std::sync::Arc;
 
-use log::info;
+use log::{error, info};
 #[cfg(windows)]
 use windows_sys::Win32::System::Console::{AttachConsole, FreeConsole, ATTACH_PARENT_PROCESS};
 use winit::event_loop::EventLoopBuilder as WinitEventLoopBuilder;
 #[cfg(all(feature = "x11", not(any(target_os = "macos", windows))))]
 use winit::platform::x11::EventLoopWindowTargetExtX11;
 
+use alacritty_terminal::index::Column;
+use alacritty_terminal::replay::Session;
 use alacritty_terminal::tty;
 
 mod cli;
@@ -47,6 +52,7 @@ mod migrate;
 mod panic;
 mod renderer;
 mod scheduler;
+mod session;
 mod string;
 mod window_context;
 
@@ -56,10 +62,13 @@ mod gl {
 }
 
 #[cfg(unix)]
-use crate::cli::MessageOptions;
+use crate::cli::{MessageOptions, SocketMessage};
 use crate::cli::{Options, Subcommands};
+use crate::config::window::Dimensions;
 use crate::config::{monitor, UiConfig};
 use crate::event::{Event, Processor};
+#[cfg(unix)]
+use crate::ipc::SocketReply;
 #[cfg(target_os = "macos")]
 use crate::macos::locale;
 
@@ -82,7 +91,7 @@ fn main() -> Result<(), Box<dyn Error>> {
         #[cfg(unix)]
         Some(Subcommands::Msg(options)) => msg(options)?,
         Some(Subcommands::Migrate(options)) => migrate::migrate(options),
-        None => alacritty(options)?,
+        Some(Subcommands::Replay(_)) | None => alacritty(options)?,
     }
 
     Ok(())
@@ -91,7 +100,25 @@ fn main() -> Result<(), Box<dyn Error>> {
 /// `msg` subcommand entrypoint.
 #[cfg(unix)]
 fn msg(options: MessageOptions) -> Result<(), Box<dyn Error>> {
-    ipc::send_message(options.socket, options.message).map_err(|err| err.into())
+    let subscribe = options.message == SocketMessage::Subscribe;
+    let socket = ipc::send_message(options.socket, options.message)?;
+
+    // Print events until Alacritty closes the connection.
+    if subscribe {
+        for event in BufReader::new(socket).lines() {
+            println!("{}", event?);
+        }
+        return Ok(());
+    }
+
+    match ipc::read_reply(socket)? {
+        Some(SocketReply::Error(err)) => Err(err.into()),
+        Some(reply) => {
+            println!("{}", serde_json::to_string(&reply)?);
+            Ok(())
+        },
+        None => Ok(()),
+    }
 }
 
 /// Temporary files stored for Alacritty.
@@ -141,9 +168,42 @@ fn alacritty(options: Options) -> Result<(), Box<dyn Error>> {
     info!("Running on Wayland");
 
     // Load configuration file.
-    let config = config::load(&options);
+    let mut config = config::load(&options);
     log_config_path(&config);
 
+    // Load the recording for replays, using its dimensions for the window.
+    let mut window_options = options.window_options.clone();
+    if let Some(Subcommands::Replay(replay_options)) = &options.subcommands {
+        let session = Session::load(&replay_options.file)?;
+        if let Some((columns, lines)) = session.size {
+            config.window.set_dimensions(Dimensions { columns: Column(columns), lines });
+        }
+        window_options.replay = Some(Arc::new(session));
+    }
+
+    // Recreate the windows saved when Alacritty was last closed.
+    let mut initial_windows = Vec::new();
+    if options.restore_session && window_options.replay.is_none() {
+        match session::load() {
+            Ok(sessions) => {
+                initial_windows.extend(sessions.into_iter().map(|session| {
+                    let mut window_options = window_options.clone();
+                    let terminal_options = &mut window_options.terminal_options;
+                    if terminal_options.working_directory.is_none() {
+                        terminal_options.working_directory = session.working_directory.clone();
+                    }
+                    window_options.session = Some(Arc::new(session));
+                    window_options
+                }));
+            },
+            Err(err) if err.kind() == io::ErrorKind::NotFound => info!("No saved session found"),
+            Err(err) => error!("Unable to restore session: {}", err),
+        }
+    }
+    if initial_windows.is_empty() {
+        initial_windows.push(window_options);
+    }
+
     // Update the log level from config.
     log::set_max_level(config.debug.log_level);
 
@@ -174,6 +234,12 @@ fn alacritty(options: Options) -> Result<(), Box<dyn Error>> {
         None
     };
 
+    // Save the session when Alacritty is terminated instead of closed.
+    #[cfg(unix)]
+    if config.save_session {
+        session::watch_signals(window_event_loop.create_proxy());
+    }
+
     // Setup automatic RAII cleanup for our files.
     let log_cleanup = log_file.filter(|_| !config.debug.persistent_logging);
     let _files = TemporaryFiles {
@@ -183,11 +249,10 @@ fn alacritty(options: Options) -> Result<(), Box<dyn Error>> {
     };
 
     // Event processor.
-    let window_options = options.window_options.clone();
     let mut processor = Processor::new(config, options, &window_event_loop);
 
     // Start event loop and block until shutdown.
-    let result = processor.run(window_event_loop, window_options);
+    let result = processor.run(window_event_loop, initial_windows);
 
     // This explicit drop is needed for Windows, ConPTY backend. Otherwise a deadlock can occur.
     // The cause:
@@ -0,0 +1,295 @@
+use std::collections::HashMap;
+use std::mem;
+use std::sync::{Arc, Weak};
+
+use ahash::RandomState;
+
+use alacritty_terminal::graphics::{GraphicCell, GraphicData, GraphicId};
+use alacritty_terminal::index::Point;
+
+use crate::display::SizeInfo;
+use crate::gl;
+use crate::gl::types::*;
+use crate::renderer::shader::{ShaderProgram, ShaderVersion};
+use crate::renderer::{self, cstr};
+
+static GRAPHICS_SHADER_F: &str = include_str!("../../res/graphics.f.glsl");
+static GRAPHICS_SHADER_V: &str = include_str!("../../res/graphics.v.glsl");
+
+/// Section of a graphic to be drawn in a cell.
+#[derive(Debug, Clone)]
+pub struct RenderGraphic {
+    /// Viewport position of the cell.
+    pub point: Point<usize>,
+
+    /// Section of the graphic covering the cell.
+    pub graphic: GraphicCell,
+}
+
+#[derive(Debug, Copy, Clone)]
+#[repr(C)]
+struct Vertex {
+    // Normalized screen coordinates.
+    x: f32,
+    y: f32,
+
+    // Texture coordinates.
+    u: f32,
+    v: f32,
+}
+
+/// Texture holding the pixels of a graphic.
+#[derive(Debug)]
+struct GraphicTexture {
+    id: GLuint,
+
+    /// Graphic uploaded to this texture, used to release the texture once it's gone.
+    graphic: Weak<GraphicData>,
+}
+
+#[derive(Debug)]
+pub struct GraphicsRenderer {
+    // GL buffer objects.
+    vao: GLuint,
+    vbo: GLuint,
+
+    program: ShaderProgram,
+    vertices: Vec<Vertex>,
+
+    /// Textures of all graphics drawn so far.
+    textures: HashMap<GraphicId, GraphicTexture, RandomState>,
+}
+
+impl GraphicsRenderer {
+    pub fn new(shader_version: ShaderVersion) -> Result<Self, renderer::Error> {
+        let mut vao: GLuint = 0;
+        let mut vbo: GLuint = 0;
+
+        let program =
+            ShaderProgram::new(shader_version, None, GRAPHICS_SHADER_V, GRAPHICS_SHADER_F)?;
+        let u_graphic = program.get_uniform_location(cstr!("graphic"))?;
+
+        unsafe {
+            // Allocate buffers.
+            gl::GenVertexArrays(1, &mut vao);
+            gl::GenBuffers(1, &mut vbo);
+
+            gl::BindVertexArray(vao);
+
+            // VBO binding is not part of VAO itself, but VBO binding is stored in attributes.
+            gl::BindBuffer(gl::ARRAY_BUFFER, vbo);
+
+            // Position.
+            gl::VertexAttribPointer(
+                0,
+                2,
+                gl::FLOAT,
+                gl::FALSE,
+                mem::size_of::<Vertex>() as i32,
+                std::ptr::null(),
+            );
+            gl::EnableVertexAttribArray(0);
+
+            // Texture coordinates.
+            gl::VertexAttribPointer(
+                1,
+                2,
+                gl::FLOAT,
+                gl::FALSE,
+                mem::size_of::<Vertex>() as i32,
+                (mem::size_of::<f32>() * 2) as *const _,
+            );
+            gl::EnableVertexAttribArray(1);
+
+            // Set the texture unit used by the sampler.
+            gl::UseProgram(program.id());
+            gl::Uniform1i(u_graphic, 0);
+            gl::UseProgram(0);
+
+            // Reset buffer bindings.
+            gl::BindVertexArray(0);
+            gl::BindBuffer(gl::ARRAY_BUFFER, 0);
+        }
+
+        Ok(Self { vao, vbo, program, vertices: Vec::new(), textures: HashMap::default() })
+    }
+
+    pub fn draw(&mut self, size_info: &SizeInfo, mut graphics: Vec<RenderGraphic>) {
+        // Release textures of graphics which are no longer part of the terminal.
+        self.textures.retain(|_, texture| {
+            let alive = texture.graphic.strong_count() > 0;
+            if !alive {
+                unsafe { gl::DeleteTextures(1, &texture.id) };
+            }
+            alive
+        });
+
+        if graphics.is_empty() {
+            return;
+        }
+
+        // Draw in stacking order, grouping cells sharing the same texture.
+        graphics.sort_by_key(|graphic| (graphic.graphic.z_index, graphic.graphic.texture.id));
+
+        unsafe {
+            gl::UseProgram(self.program.id());
+
+            // Bind VAO to enable vertex attribute slots.
+            gl::BindVertexArray(self.vao);
+
+            // Bind VBO only once for buffer data upload only.
+            gl::BindBuffer(gl::ARRAY_BUFFER, self.vbo);
+
+            gl::ActiveTexture(gl::TEXTURE0);
+        }
+
+        let half_width = size_info.width() / 2.;
+        let half_height = size_info.height() / 2.;
+
+        let mut remaining = graphics.as_slice();
+        while let Some(first) = remaining.first() {
+            let data = &first.graphic.texture;
+            let z_index = first.graphic.z_index;
+            let len = remaining.iter().take_while(|graphic| {
+                graphic.graphic.z_index == z_index && graphic.graphic.texture.id == data.id
+            });
+            let (batch, rest) = remaining.split_at(len.count());
+            remaining = rest;
+
+            let texture = self.textures.entry(data.id).or_insert_with(|| upload_texture(data));
+
+            self.vertices.clear();
+            for graphic in batch {
+                Self::add_graphic(&mut self.vertices, size_info, half_width, half_height, graphic);
+            }
+
+            unsafe {
+                gl::BindTexture(gl::TEXTURE_2D, texture.id);
+
+                gl::BufferData(
+                    gl::ARRAY_BUFFER,
+                    (self.vertices.len() * mem::size_of::<Vertex>()) as isize,
+                    self.vertices.as_ptr() as *const _,
+                    gl::STREAM_DRAW,
+                );
+
+                // Draw all vertices as list of triangles.
+                gl::DrawArrays(gl::TRIANGLES, 0, self.vertices.len() as i32);
+            }
+        }
+
+        unsafe {
+            // Disable program.
+            gl::UseProgram(0);
+
+            // Reset buffer and texture bindings to nothing.
+            gl::BindTexture(gl::TEXTURE_2D, 0);
+            gl::BindBuffer(gl::ARRAY_BUFFER, 0);
+            gl::BindVertexArray(0);
+        }
+    }
+
+    fn add_graphic(
+        vertices: &mut Vec<Vertex>,
+        size_info: &SizeInfo,
+        half_width: f32,
+        half_height: f32,
+        render_graphic: &RenderGraphic,
+    ) {
+        let graphic = &render_graphic.graphic;
+        let point = render_graphic.point;
+
+        // Calculate the cell's position and the graphic's origin in pixels.
+        let cell_width = size_info.cell_width();
+        let cell_height = size_info.cell_height();
+        let cell_left = size_info.padding_x() + point.column.0 as f32 * cell_width;
+        let cell_top = size_info.padding_y() + point.line as f32 * cell_height;
+        let origin_x = cell_left - graphic.x as f32;
+        let origin_y = cell_top - graphic.y as f32;
+
+        // Clip the graphic to the cell.
+        let graphic_width = graphic.width as f32;
+        let graphic_height = graphic.height as f32;
+        let left = cell_left.max(origin_x);
+        let top = cell_top.max(origin_y);
+        let right = (cell_left + cell_width).min(origin_x + graphic_width);
+        let bottom = (cell_top + cell_height).min(origin_y + graphic_height);
+        if right <= left || bottom <= top {
+            return;
+        }
+
+        // Calculate quad vertices positions in normalized device coordinates.
+        // NDC range from -1 to +1, with Y pointing up.
+        let x = left / half_width - 1.0;
+        let y = -top / half_height + 1.0;
+        let width = (right - left) / half_width;
+        let height = (bottom - top) / half_height;
+
+        // Calculate the cell's section of the texture.
+        let texture_width = graphic.texture.width as f32;
+        let texture_height = graphic.texture.height as f32;
+        let scale_x = graphic.source.width as f32 / graphic_width;
+        let scale_y = graphic.source.height as f32 / graphic_height;
+        let u = (graphic.source.x as f32 + (left - origin_x) * scale_x) / texture_width;
+        let v = (graphic.source.y as f32 + (top - origin_y) * scale_y) / texture_height;
+        let u_width = (right - left) * scale_x / texture_width;
+        let v_height = (bottom - top) * scale_y / texture_height;
+
+        // Make quad vertices.
+        let quad = [
+            Vertex { x, y, u, v },
+            Vertex { x, y: y - height, u, v: v + v_height },
+            Vertex { x: x + width, y, u: u + u_width, v },
+            Vertex { x: x + width, y: y - height, u: u + u_width, v: v + v_height },
+        ];
+
+        // Append the vertices to form two triangles.
+        vertices.push(quad[0]);
+        vertices.push(quad[1]);
+        vertices.push(quad[2]);
+        vertices.push(quad[2]);
+        vertices.push(quad[3]);
+        vertices.push(quad[1]);
+    }
+}
+
+impl Drop for GraphicsRenderer {
+    fn drop(&mut self) {
+        unsafe {
+            for texture in self.textures.values() {
+                gl::DeleteTextures(1, &texture.id);
+            }
+            gl::DeleteBuffers(1, &self.vbo);
+            gl::DeleteVertexArrays(1, &self.vao);
+        }
+    }
+}
+
+/// Upload a graphic's pixels to a new texture.
+fn upload_texture(graphic: &Arc<GraphicData>) -> GraphicTexture {
+    let mut id: GLuint = 0;
+    unsafe {
+        gl::GenTextures(1, &mut id);
+        gl::BindTexture(gl::TEXTURE_2D, id);
+
+        gl::TexImage2D(
+            gl::TEXTURE_2D,
+            0,
+            gl::RGBA as i32,
+            graphic.width as i32,
+            graphic.height as i32,
+            0,
+            gl::RGBA,
+            gl::UNSIGNED_BYTE,
+            graphic.pixels.as_ptr() as *const _,
+        );
+
+        // Graphics might be scaled, so interpolate between their pixels.
+        gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_S, gl::CLAMP_TO_EDGE as i32);
+        gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_T, gl::CLAMP_TO_EDGE as i32);
+        gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::LINEAR as i32);
+        gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::LINEAR as i32);
+    }
+
+    GraphicTexture { id, graphic: Arc::downgrade(graphic) }
+}
@@ -1,4 +1,5 @@
 use std::collections::HashMap;
+use std::rc::Rc;
 
 use ahash::RandomState;
 use crossfont::{
@@ -8,11 +9,14 @@ use crossfont::{
 use log::{error, info};
 use unicode_width::UnicodeWidthChar;
 
+use alacritty_terminal::term::cell::Flags;
+
 use crate::config::font::{Font, FontDescription};
 use crate::config::ui_config::Delta;
 use crate::gl::types::*;
 
 use super::builtin_font;
+use super::shaper::{ShapedCell, Shaper};
 
 /// `LoadGlyph` allows for copying a rasterized glyph into graphics memory.
 pub trait LoadGlyph {
@@ -76,10 +80,20 @@ pub struct GlyphCache {
 
     /// Whether to use the built-in font for box drawing characters.
     builtin_box_drawing: bool,
+
+    /// Shaper for ligatures and complex scripts.
+    shaper: Option<Shaper>,
+
+    /// Cache of buffered glyphs from the shaper.
+    shaped_cache: HashMap<(FontKey, u16), Glyph, RandomState>,
 }
 
 impl GlyphCache {
-    pub fn new(mut rasterizer: Rasterizer, font: &Font) -> Result<GlyphCache, crossfont::Error> {
+    pub fn new(
+        mut rasterizer: Rasterizer,
+        font: &Font,
+        scale_factor: f32,
+    ) -> Result<GlyphCache, crossfont::Error> {
         let (regular, bold, italic, bold_italic) = Self::compute_font_keys(font, &mut rasterizer)?;
 
         // Need to load at least one glyph for the face before calling metrics.
@@ -89,6 +103,12 @@ impl GlyphCache {
 
         let metrics = rasterizer.metrics(regular, font.size())?;
 
+        let keys = [regular, bold, italic, bold_italic];
+        let shaper = font
+            .shaping
+            .then(|| Shaper::new(&rasterizer, keys, font.size(), scale_factor))
+            .flatten();
+
         Ok(Self {
             cache: Default::default(),
             rasterizer,
@@ -101,6 +121,8 @@ impl GlyphCache {
             glyph_offset: font.glyph_offset,
             metrics,
             builtin_box_drawing: font.builtin_box_drawing,
+            shaper,
+            shaped_cache: Default::default(),
         })
     }
 
@@ -239,6 +261,67 @@ impl GlyphCache {
         *self.cache.entry(glyph_key).or_insert(glyph)
     }
 
+    /// Get the font key for a cell's style.
+    pub fn font_key(&self, flags: Flags) -> FontKey {
+        match flags & Flags::BOLD_ITALIC {
+            Flags::BOLD_ITALIC => self.bold_italic_key,
+            Flags::ITALIC => self.italic_key,
+            Flags::BOLD => self.bold_key,
+            _ => self.font_key,
+        }
+    }
+
+    /// Check if text is shaped before rendering.
+    pub fn shaping(&self) -> bool {
+        self.shaper.is_some()
+    }
+
+    /// Shape a run of text.
+    ///
+    /// The `cell_starts` are the byte offsets of each cell's characters in `text`.
+    ///
+    /// Returns `None` if the run cannot be shaped with this font.
+    pub fn shape(
+        &mut self,
+        font_key: FontKey,
+        text: &str,
+        cell_starts: &[usize],
+    ) -> Option<Rc<[ShapedCell]>> {
+        self.shaper.as_mut()?.shape(font_key, text, cell_starts)
+    }
+
+    /// Get a glyph produced by the shaper.
+    ///
+    /// If the glyph has never been loaded before, it will be rasterized and inserted into the
+    /// cache.
+    pub fn get_shaped<L: LoadGlyph + ?Sized>(
+        &mut self,
+        font_key: FontKey,
+        glyph_id: u16,
+        loader: &mut L,
+    ) -> Glyph {
+        if let Some(glyph) = self.shaped_cache.get(&(font_key, glyph_id)) {
+            return *glyph;
+        }
+
+        // Render glyphs of characters through the rasterizer, to match the unshaped text.
+        let shaper = self.shaper.as_ref();
+        let glyph = match shaper.and_then(|shaper| shaper.character(font_key, glyph_id)) {
+            Some(character) => {
+                let glyph_key = GlyphKey { font_key, size: self.font_size, character };
+                self.get(glyph_key, loader, false)
+            },
+            None => {
+                let rasterized = shaper
+                    .and_then(|shaper| shaper.rasterize(&mut self.rasterizer, font_key, glyph_id))
+                    .unwrap_or_default();
+                self.load_glyph(loader, rasterized)
+            },
+        };
+
+        *self.shaped_cache.entry((font_key, glyph_id)).or_insert(glyph)
+    }
+
     /// Load glyph into the atlas.
     ///
     /// This will apply all transforms defined for the glyph cache to the rasterized glyph before
@@ -267,6 +350,7 @@ impl GlyphCache {
     pub fn reset_glyph_cache<L: LoadGlyph>(&mut self, loader: &mut L) {
         loader.clear();
         self.cache = Default::default();
+        self.shaped_cache = Default::default();
 
         self.load_common_glyphs(loader);
     }
@@ -306,6 +390,13 @@ impl GlyphCache {
         self.metrics = metrics;
         self.builtin_box_drawing = font.builtin_box_drawing;
 
+        let keys = [regular, bold, italic, bold_italic];
+        let scale_factor = scale_factor as f32;
+        self.shaper = font
+            .shaping
+            .then(|| Shaper::new(&self.rasterizer, keys, font.size(), scale_factor))
+            .flatten();
+
         Ok(())
     }
 
@@ -13,12 +13,14 @@ mod builtin_font;
 mod gles2;
 mod glsl3;
 pub mod glyph_cache;
+mod shaper;
 
 use atlas::Atlas;
 pub use gles2::Gles2Renderer;
 pub use glsl3::Glsl3Renderer;
 pub use glyph_cache::GlyphCache;
 use glyph_cache::{Glyph, LoadGlyph};
+use shaper::ShapedCell;
 
 // NOTE: These flags must be in sync with their usage in the text.*.glsl shaders.
 bitflags! {
@@ -62,9 +64,22 @@ pub trait TextRenderer<'a> {
         cells: I,
     ) {
         self.with_api(size_info, |mut api| {
+            if !glyph_cache.shaping() {
+                for cell in cells {
+                    api.draw_cell(cell, glyph_cache, size_info);
+                }
+                return;
+            }
+
+            // Collect runs of adjacent cells with the same style for shaping.
+            let mut run: Vec<RenderableCell> = Vec::new();
             for cell in cells {
-                api.draw_cell(cell, glyph_cache, size_info);
+                if run.last().map_or(false, |last| !continues_run(last, &cell)) {
+                    api.draw_run(&mut run, glyph_cache, size_info);
+                }
+                run.push(cell);
             }
+            api.draw_run(&mut run, glyph_cache, size_info);
         })
     }
 
@@ -139,12 +154,7 @@ pub trait TextRenderApi<T: TextRenderBatch>: LoadGlyph {
         size_info: &SizeInfo,
     ) {
         // Get font key for cell.
-        let font_key = match cell.flags & Flags::BOLD_ITALIC {
-            Flags::BOLD_ITALIC => glyph_cache.bold_italic_key,
-            Flags::ITALIC => glyph_cache.italic_key,
-            Flags::BOLD => glyph_cache.bold_key,
-            _ => glyph_cache.font_key,
-        };
+        let font_key = glyph_cache.font_key(cell.flags);
 
         // Ignore hidden cells and render tabs as spaces to prevent font issues.
         let hidden = cell.flags.contains(Flags::HIDDEN);
@@ -170,6 +180,74 @@ pub trait TextRenderApi<T: TextRenderBatch>: LoadGlyph {
             }
         }
     }
+
+    /// Shape and draw a run of cells, leaving the run empty.
+    fn draw_run(
+        &mut self,
+        run: &mut Vec<RenderableCell>,
+        glyph_cache: &mut GlyphCache,
+        size_info: &SizeInfo,
+    ) {
+        let zerowidth = |cell: &RenderableCell| {
+            cell.extra.as_ref().and_then(|extra| extra.zerowidth.as_ref()).map_or(0, Vec::len)
+        };
+
+        // Only multiple characters can be affected by shaping.
+        let shaped = if run.len() > 1 || run.first().map_or(false, |cell| zerowidth(cell) > 0) {
+            let mut text = String::new();
+            let mut cell_starts = Vec::with_capacity(run.len());
+            for cell in run.iter() {
+                cell_starts.push(text.len());
+                text.push(if cell.character == '\t' { ' ' } else { cell.character });
+                if let Some(zerowidth) =
+                    cell.extra.as_ref().and_then(|extra| extra.zerowidth.as_ref())
+                {
+                    text.extend(zerowidth);
+                }
+            }
+
+            let font_key = glyph_cache.font_key(run[0].flags);
+            glyph_cache.shape(font_key, &text, &cell_starts)
+        } else {
+            None
+        };
+
+        let shaped = match shaped {
+            Some(shaped) => shaped,
+            None => {
+                for cell in run.drain(..) {
+                    self.draw_cell(cell, glyph_cache, size_info);
+                }
+                return;
+            },
+        };
+
+        for (cell, shaped) in run.drain(..).zip(shaped.iter()) {
+            let font_key = glyph_cache.font_key(cell.flags);
+            let glyphs = match shaped {
+                ShapedCell::Unchanged => {
+                    self.draw_cell(cell, glyph_cache, size_info);
+                    continue;
+                },
+                ShapedCell::Glyphs(glyphs) => glyphs.as_slice(),
+                ShapedCell::Covered => &[],
+            };
+
+            // Draw the background of cells without any glyphs.
+            if glyphs.is_empty() {
+                let glyph_key = GlyphKey { font_key, size: glyph_cache.font_size, character: ' ' };
+                let glyph = glyph_cache.get(glyph_key, self, true);
+                self.add_render_item(&cell, &glyph, size_info);
+            }
+
+            for shaped_glyph in glyphs {
+                let mut glyph = glyph_cache.get_shaped(font_key, shaped_glyph.id, self);
+                glyph.left += shaped_glyph.x.round() as i16;
+                glyph.top += shaped_glyph.y.round() as i16;
+                self.add_render_item(&cell, &glyph, size_info);
+            }
+        }
+    }
 }
 
 pub trait TextShader {
@@ -196,6 +274,18 @@ impl<'a> LoadGlyph for LoaderApi<'a> {
     }
 }
 
+/// Check if a cell can be shaped together with the preceding cell.
+fn continues_run(previous: &RenderableCell, cell: &RenderableCell) -> bool {
+    let width = if previous.flags.contains(Flags::WIDE_CHAR) { 2 } else { 1 };
+
+    previous.point.line == cell.point.line
+        && previous.point.column + width == cell.point.column
+        && previous.flags & Flags::BOLD_ITALIC == cell.flags & Flags::BOLD_ITALIC
+        && previous.fg == cell.fg
+        && !previous.flags.contains(Flags::HIDDEN)
+        && !cell.flags.contains(Flags::HIDDEN)
+}
+
 fn update_projection(u_projection: GLint, size: &SizeInfo) {
     let width = size.width();
     let height = size.height();
@@ -219,3 +309,50 @@ fn update_projection(u_projection: GLint, size: &SizeInfo) {
         gl::Uniform4f(u_projection, offset_x, offset_y, scale_x, scale_y);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use alacritty_terminal::index::{Column, Point};
+    use alacritty_terminal::term::color::Rgb;
+
+    fn cell(line: usize, column: usize, flags: Flags) -> RenderableCell {
+        RenderableCell {
+            character: 'a',
+            point: Point::new(line, Column(column)),
+            fg: Rgb::default(),
+            bg: Rgb::default(),
+            bg_alpha: 0.,
+            underline: Rgb::default(),
+            flags,
+            extra: None,
+        }
+    }
+
+    #[test]
+    fn continues_adjacent_cells() {
+        let previous = cell(0, 0, Flags::empty());
+        assert!(continues_run(&previous, &cell(0, 1, Flags::empty())));
+        assert!(continues_run(&previous, &cell(0, 1, Flags::UNDERLINE)));
+
+        // Wide characters span two columns.
+        let previous = cell(0, 0, Flags::WIDE_CHAR);
+        assert!(!continues_run(&previous, &cell(0, 1, Flags::empty())));
+        assert!(continues_run(&previous, &cell(0, 2, Flags::empty())));
+    }
+
+    #[test]
+    fn breaks_run() {
+        let previous = cell(0, 1, Flags::empty());
+        assert!(!continues_run(&previous, &cell(0, 3, Flags::empty())));
+        assert!(!continues_run(&previous, &cell(1, 2, Flags::empty())));
+        assert!(!continues_run(&previous, &cell(0, 2, Flags::BOLD)));
+        assert!(!continues_run(&previous, &cell(0, 2, Flags::HIDDEN)));
+        assert!(!continues_run(&cell(0, 1, Flags::HIDDEN), &cell(0, 2, Flags::empty())));
+
+        let mut colored = cell(0, 2, Flags::empty());
+        colored.fg = Rgb::new(255, 0, 0);
+        assert!(!continues_run(&previous, &colored));
+    }
+}
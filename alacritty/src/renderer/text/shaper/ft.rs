@@ -0,0 +1,24 @@
+//! Font faces for shaping, shared with crossfont's FreeType rasterizer.
+
+use std::fs;
+
+use crossfont::{FontKey, RasterizedGlyph, Rasterizer, Size};
+
+/// Read the font file backing a rasterizer's font face.
+///
+/// Returns the font data together with the index of the face in the file.
+pub fn face_data(rasterizer: &Rasterizer, font_key: FontKey) -> Option<(Vec<u8>, u32)> {
+    let location = rasterizer.face_location(font_key)?;
+    let data = fs::read(&location.path).ok()?;
+    Some((data, location.index as u32))
+}
+
+/// Rasterize a glyph by its index in the font face.
+pub fn rasterize(
+    rasterizer: &mut Rasterizer,
+    font_key: FontKey,
+    size: Size,
+    glyph_id: u16,
+) -> Option<RasterizedGlyph> {
+    rasterizer.get_glyph_by_index(font_key, size, u32::from(glyph_id)).ok()
+}
@@ -0,0 +1,429 @@
+//! Text shaping for ligatures and complex scripts.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::rc::Rc;
+
+use ahash::RandomState;
+use crossfont::{FontKey, RasterizedGlyph, Rasterizer, Size};
+use log::{info, warn};
+use rustybuzz::ttf_parser;
+use rustybuzz::{Direction, UnicodeBuffer};
+
+#[cfg(not(any(target_os = "macos", windows)))]
+mod ft;
+
+/// Font faces for shaping are only available from the FreeType rasterizer.
+#[cfg(any(target_os = "macos", windows))]
+mod ft {
+    use crossfont::{FontKey, RasterizedGlyph, Rasterizer, Size};
+
+    pub fn face_data(_: &Rasterizer, _: FontKey) -> Option<(Vec<u8>, u32)> {
+        None
+    }
+
+    pub fn rasterize(_: &mut Rasterizer, _: FontKey, _: Size, _: u16) -> Option<RasterizedGlyph> {
+        None
+    }
+}
+
+/// Maximum number of shaped runs kept in the cache, per font.
+const MAX_CACHED_RUNS: usize = 4096;
+
+/// Glyph positioned relative to the origin of the cell it's drawn in.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct ShapedGlyph {
+    /// Glyph index in the font.
+    pub id: u16,
+
+    /// Horizontal offset from the cell origin, in pixels.
+    pub x: f32,
+
+    /// Vertical offset from the baseline, in pixels.
+    pub y: f32,
+}
+
+/// Shaping result for a single cell of a run.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ShapedCell {
+    /// Cell is rendered like it would be without shaping.
+    Unchanged,
+
+    /// Glyphs of the clusters starting in this cell.
+    Glyphs(Vec<ShapedGlyph>),
+
+    /// Cell is covered by the glyphs of a preceding cell.
+    Covered,
+}
+
+/// Font face and its glyphs which can be rendered through the regular glyph cache.
+struct ShapingFont {
+    /// Font file data.
+    data: Vec<u8>,
+
+    /// Index of the face in the font file.
+    index: u32,
+
+    /// Characters by the index of their glyph in the font.
+    characters: HashMap<u16, char, RandomState>,
+}
+
+impl ShapingFont {
+    fn new((data, index): (Vec<u8>, u32)) -> Option<Self> {
+        let ttf_face = ttf_parser::Face::parse(&data, index).ok()?;
+
+        let mut characters = HashMap::default();
+        for subtable in ttf_face.tables().cmap.into_iter().flat_map(|cmap| cmap.subtables) {
+            if !subtable.is_unicode() {
+                continue;
+            }
+
+            subtable.codepoints(|codepoint| {
+                let glyph_id = subtable.glyph_index(codepoint);
+                if let (Some(glyph_id), Some(character)) = (glyph_id, char::from_u32(codepoint)) {
+                    characters.entry(glyph_id.0).or_insert(character);
+                }
+            });
+        }
+
+        Some(Self { data, index, characters })
+    }
+}
+
+/// Text shaper for the primary fonts.
+///
+/// Only glyphs which are substituted or repositioned by shaping are produced by the shaper,
+/// everything else is still rendered through the regular glyph cache.
+pub struct Shaper {
+    /// Font faces by their rasterizer key.
+    fonts: HashMap<FontKey, ShapingFont, RandomState>,
+
+    /// Font size.
+    size: Size,
+
+    /// Size of an em in pixels.
+    pixel_size: f32,
+
+    /// Shaped runs by their text.
+    cache: HashMap<FontKey, HashMap<String, Rc<[ShapedCell]>, RandomState>, RandomState>,
+}
+
+impl fmt::Debug for Shaper {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Shaper")
+            .field("fonts", &self.fonts.len())
+            .field("pixel_size", &self.pixel_size)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Shaper {
+    /// Load the rasterizer's font faces for the regular, bold, italic and bold italic font keys.
+    ///
+    /// Returns `None` if no font face could be loaded.
+    pub fn new(
+        rasterizer: &Rasterizer,
+        keys: [FontKey; 4],
+        size: Size,
+        scale_factor: f32,
+    ) -> Option<Self> {
+        let mut fonts = HashMap::default();
+        for key in keys {
+            if fonts.contains_key(&key) {
+                continue;
+            }
+
+            match ft::face_data(rasterizer, key).and_then(ShapingFont::new) {
+                Some(font) => {
+                    fonts.insert(key, font);
+                },
+                None => warn!("Unable to load font face {:?} for shaping", key),
+            }
+        }
+
+        if fonts.is_empty() {
+            return None;
+        }
+
+        info!("Text shaping enabled for {} font faces", fonts.len());
+
+        let pixel_size = pixel_size(size, scale_factor);
+        Some(Self { fonts, size, pixel_size, cache: HashMap::default() })
+    }
+
+    /// Shape a run of cells.
+    ///
+    /// The `cell_starts` are the byte offsets of each cell's characters in `text`.
+    ///
+    /// Returns `None` if there is no font face for the font key.
+    pub fn shape(
+        &mut self,
+        font_key: FontKey,
+        text: &str,
+        cell_starts: &[usize],
+    ) -> Option<Rc<[ShapedCell]>> {
+        let font = self.fonts.get(&font_key)?;
+
+        let cache = self.cache.entry(font_key).or_default();
+        if let Some(cells) = cache.get(text) {
+            return Some(cells.clone());
+        }
+
+        let face = rustybuzz::Face::from_slice(&font.data, font.index)?;
+        let cells: Rc<[ShapedCell]> = shape_run(&face, self.pixel_size, text, cell_starts).into();
+
+        if cache.len() >= MAX_CACHED_RUNS {
+            cache.clear();
+        }
+        cache.insert(text.to_owned(), cells.clone());
+
+        Some(cells)
+    }
+
+    /// Character which is rendered with a glyph.
+    ///
+    /// Glyphs with a character are rasterized through the regular glyph cache, so they look
+    /// exactly like the unshaped text.
+    pub fn character(&self, font_key: FontKey, glyph_id: u16) -> Option<char> {
+        self.fonts.get(&font_key)?.characters.get(&glyph_id).copied()
+    }
+
+    /// Rasterize a glyph without a character, like ligatures, by its index in the font.
+    pub fn rasterize(
+        &self,
+        rasterizer: &mut Rasterizer,
+        font_key: FontKey,
+        glyph_id: u16,
+    ) -> Option<RasterizedGlyph> {
+        if !self.fonts.contains_key(&font_key) {
+            return None;
+        }
+
+        ft::rasterize(rasterizer, font_key, self.size, glyph_id)
+    }
+}
+
+/// Shape a run of text and map the resulting glyphs to their cells.
+fn shape_run(
+    face: &rustybuzz::Face<'_>,
+    pixel_size: f32,
+    text: &str,
+    cell_starts: &[usize],
+) -> Vec<ShapedCell> {
+    // Cells are always laid out from left to right, even for right-to-left scripts.
+    let mut buffer = UnicodeBuffer::new();
+    buffer.push_str(text);
+    buffer.set_direction(Direction::LeftToRight);
+    buffer.guess_segment_properties();
+
+    let output = rustybuzz::shape(face, &[], buffer);
+    let scale = pixel_size / face.units_per_em() as f32;
+    let glyphs: Vec<_> = output
+        .glyph_infos()
+        .iter()
+        .zip(output.glyph_positions())
+        .map(|(info, position)| RunGlyph {
+            id: info.glyph_id as u16,
+            cluster: info.cluster as usize,
+            x_advance: position.x_advance as f32 * scale,
+            x_offset: position.x_offset as f32 * scale,
+            y_offset: position.y_offset as f32 * scale,
+        })
+        .collect();
+
+    map_glyphs(&glyphs, text, cell_starts, |c| face.glyph_index(c).map(|id| id.0))
+}
+
+/// Glyph of a shaped run, positioned in pixels.
+#[derive(Debug, Copy, Clone)]
+struct RunGlyph {
+    id: u16,
+    cluster: usize,
+    x_advance: f32,
+    x_offset: f32,
+    y_offset: f32,
+}
+
+/// Map the glyphs of a shaped run to the cells they're drawn in.
+///
+/// The `default_glyph` is the glyph used for a character without shaping.
+fn map_glyphs(
+    glyphs: &[RunGlyph],
+    text: &str,
+    cell_starts: &[usize],
+    default_glyph: impl Fn(char) -> Option<u16>,
+) -> Vec<ShapedCell> {
+    // Index of the cell containing the byte at `offset`.
+    let cell_at = |offset: usize| cell_starts.partition_point(|start| *start <= offset) - 1;
+
+    let mut cells = vec![ShapedCell::Unchanged; cell_starts.len()];
+
+    let mut start = 0;
+    while start < glyphs.len() {
+        // Group all clusters which touch the same cells.
+        let first_cell = cell_at(glyphs[start].cluster);
+        let mut last_cell = first_cell;
+        let mut end = start;
+        while end < glyphs.len() && cell_at(glyphs[end].cluster) <= last_cell {
+            let next_cluster = glyphs[end..]
+                .iter()
+                .find(|glyph| glyph.cluster != glyphs[end].cluster)
+                .map_or(text.len(), |glyph| glyph.cluster);
+            last_cell = last_cell.max(cell_at(next_cluster - 1));
+            end += 1;
+        }
+
+        let group = &glyphs[start..end];
+        start = end;
+
+        // Fall back to the regular glyph cache for glyphs missing from the font.
+        if group.iter().any(|glyph| glyph.id == 0) {
+            continue;
+        }
+
+        // Keep single characters rendered with their default glyph unchanged.
+        let cell_start = cell_starts[first_cell];
+        let cell_end = cell_starts.get(first_cell + 1).copied().unwrap_or(text.len());
+        let mut cell_chars = text[cell_start..cell_end].chars();
+        let character = cell_chars.next();
+        if first_cell == last_cell
+            && group.len() == 1
+            && cell_chars.next().is_none()
+            && group[0].x_offset == 0.
+            && group[0].y_offset == 0.
+            && character.and_then(&default_glyph) == Some(group[0].id)
+        {
+            continue;
+        }
+
+        let mut pen = 0.;
+        let shaped = group
+            .iter()
+            .map(|glyph| {
+                let shaped =
+                    ShapedGlyph { id: glyph.id, x: pen + glyph.x_offset, y: glyph.y_offset };
+                pen += glyph.x_advance;
+                shaped
+            })
+            .collect();
+
+        cells[first_cell] = ShapedCell::Glyphs(shaped);
+        for cell in &mut cells[first_cell + 1..=last_cell] {
+            *cell = ShapedCell::Covered;
+        }
+    }
+
+    cells
+}
+
+/// Size of an em in pixels.
+fn pixel_size(size: Size, scale_factor: f32) -> f32 {
+    size.as_f32_pts() * scale_factor * 96. / 72.
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Default glyphs of the test font.
+    fn default_glyph(c: char) -> Option<u16> {
+        match c {
+            'a' => Some(1),
+            'b' => Some(2),
+            '-' => Some(3),
+            '>' => Some(4),
+            'e' => Some(5),
+            '\u{301}' => Some(6),
+            _ => None,
+        }
+    }
+
+    fn glyph(id: u16, cluster: usize, x_advance: f32) -> RunGlyph {
+        RunGlyph { id, cluster, x_advance, x_offset: 0., y_offset: 0. }
+    }
+
+    #[test]
+    fn map_ligature() {
+        let glyphs = [glyph(1, 0, 10.), glyph(9, 1, 20.), glyph(2, 3, 10.)];
+        let cells = map_glyphs(&glyphs, "a->b", &[0, 1, 2, 3], default_glyph);
+
+        assert_eq!(
+            cells,
+            vec![
+                ShapedCell::Unchanged,
+                ShapedCell::Glyphs(vec![ShapedGlyph { id: 9, x: 0., y: 0. }]),
+                ShapedCell::Covered,
+                ShapedCell::Unchanged,
+            ]
+        );
+    }
+
+    #[test]
+    fn map_combining_mark() {
+        let mark = RunGlyph { x_offset: -8., y_offset: 2., ..glyph(6, 0, 0.) };
+        let glyphs = [glyph(5, 0, 10.), mark, glyph(2, 3, 10.)];
+        let cells = map_glyphs(&glyphs, "e\u{301}b", &[0, 3], default_glyph);
+
+        assert_eq!(
+            cells,
+            vec![
+                ShapedCell::Glyphs(vec![
+                    ShapedGlyph { id: 5, x: 0., y: 0. },
+                    ShapedGlyph { id: 6, x: 2., y: 2. },
+                ]),
+                ShapedCell::Unchanged,
+            ]
+        );
+    }
+
+    #[test]
+    fn map_substituted_glyph() {
+        // Contextual alternates replace a glyph without changing its cluster.
+        let kerned = RunGlyph { x_offset: 1., ..glyph(1, 1, 10.) };
+        let glyphs = [glyph(7, 0, 10.), kerned];
+        let cells = map_glyphs(&glyphs, "aa", &[0, 1], default_glyph);
+
+        assert_eq!(
+            cells,
+            vec![
+                ShapedCell::Glyphs(vec![ShapedGlyph { id: 7, x: 0., y: 0. }]),
+                ShapedCell::Glyphs(vec![ShapedGlyph { id: 1, x: 1., y: 0. }]),
+            ]
+        );
+    }
+
+    #[test]
+    fn map_missing_glyph() {
+        // Clusters with missing glyphs are left to the fallback fonts of the glyph cache.
+        let glyphs = [glyph(0, 0, 10.), glyph(9, 1, 20.)];
+        let cells = map_glyphs(&glyphs, "x->", &[0, 1, 2], default_glyph);
+
+        assert_eq!(
+            cells,
+            vec![
+                ShapedCell::Unchanged,
+                ShapedCell::Glyphs(vec![ShapedGlyph { id: 9, x: 0., y: 0. }]),
+                ShapedCell::Covered,
+            ]
+        );
+    }
+
+    #[test]
+    fn map_cluster_across_cells() {
+        // Clusters merged by shaping cover all cells they're touching.
+        let glyphs = [glyph(1, 0, 5.), glyph(8, 0, 5.), glyph(2, 2, 10.)];
+        let cells = map_glyphs(&glyphs, "a-b", &[0, 1, 2], default_glyph);
+
+        assert_eq!(
+            cells,
+            vec![
+                ShapedCell::Glyphs(vec![
+                    ShapedGlyph { id: 1, x: 0., y: 0. },
+                    ShapedGlyph { id: 8, x: 5., y: 0. },
+                ]),
+                ShapedCell::Covered,
+                ShapedCell::Unchanged,
+            ]
+        );
+    }
+}
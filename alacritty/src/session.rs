@@ -0,0 +1,207 @@
+//! Persistence of terminal windows across restarts.
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufReader, BufWriter, Read, Write};
+#[cfg(unix)]
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::{Path, PathBuf};
+
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::{Deserialize, Serialize};
+#[cfg(unix)]
+use {
+    log::{info, warn},
+    signal_hook::consts::{SIGHUP, SIGTERM},
+    signal_hook::iterator::Signals,
+    winit::event_loop::EventLoopProxy,
+};
+
+use alacritty_terminal::grid::Grid;
+use alacritty_terminal::term::cell::Cell;
+#[cfg(unix)]
+use alacritty_terminal::thread;
+
+#[cfg(unix)]
+use crate::event::{Event, EventType};
+
+/// Name of the file storing the saved windows.
+const SESSION_FILE: &str = "session.json.gz";
+
+/// Saved state of a terminal window.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+pub struct WindowSession {
+    /// Window title at the time it was closed.
+    pub title: String,
+
+    /// Working directory of the shell.
+    pub working_directory: Option<PathBuf>,
+
+    /// Content of the primary screen, including its scrollback history.
+    pub grid: Grid<Cell>,
+}
+
+/// Write the saved windows to disk, replacing any previous session.
+///
+/// The session file is removed when there are no windows to save.
+pub fn save(windows: &[WindowSession]) -> io::Result<PathBuf> {
+    let path = session_path()?;
+
+    if windows.is_empty() {
+        match fs::remove_file(&path) {
+            Err(err) if err.kind() != io::ErrorKind::NotFound => return Err(err),
+            _ => return Ok(path),
+        }
+    }
+
+    write_file(&path, windows)?;
+
+    Ok(path)
+}
+
+/// Atomically replace the session file.
+///
+/// The session is written to a temporary file next to it, which is only readable by the user,
+/// so an interrupted save never leaves a truncated session behind.
+fn write_file(path: &Path, windows: &[WindowSession]) -> io::Result<()> {
+    let mut tmp_name = path.file_name().unwrap_or_default().to_owned();
+    tmp_name.push(".tmp");
+    let tmp_path = path.with_file_name(tmp_name);
+
+    let mut options = OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    options.mode(0o600);
+
+    let result = options.open(&tmp_path).and_then(|file| {
+        let mut writer = BufWriter::new(file);
+        write(&mut writer, windows)?;
+        writer.into_inner().map_err(|err| err.into_error())?.sync_all()?;
+        fs::rename(&tmp_path, path)
+    });
+
+    if result.is_err() {
+        let _ = fs::remove_file(&tmp_path);
+    }
+
+    result
+}
+
+/// Load the saved windows, removing them from disk.
+pub fn load() -> io::Result<Vec<WindowSession>> {
+    let path = session_path()?;
+
+    let file = File::open(&path)?;
+    let windows = read(BufReader::new(file))?;
+    fs::remove_file(&path)?;
+
+    Ok(windows)
+}
+
+/// Serialize windows as gzip compressed JSON.
+fn write<W: Write>(writer: W, windows: &[WindowSession]) -> io::Result<()> {
+    let mut encoder = GzEncoder::new(writer, Compression::default());
+    serde_json::to_writer(&mut encoder, windows)?;
+    encoder.finish()?.flush()
+}
+
+/// Deserialize windows from gzip compressed JSON.
+fn read<R: Read>(reader: R) -> io::Result<Vec<WindowSession>> {
+    let windows = serde_json::from_reader(GzDecoder::new(reader))?;
+    Ok(windows)
+}
+
+/// Save the session before Alacritty is terminated by a signal, like on logout or shutdown.
+#[cfg(unix)]
+pub fn watch_signals(proxy: EventLoopProxy<Event>) {
+    let mut signals = match Signals::new([SIGTERM, SIGHUP]) {
+        Ok(signals) => signals,
+        Err(err) => {
+            warn!("Unable to save session on termination: {}", err);
+            return;
+        },
+    };
+
+    thread::spawn_named("session signal handler", move || {
+        if let Some(signal) = signals.forever().next() {
+            info!("Received signal {}, saving session", signal);
+            let _ = proxy.send_event(Event::new(EventType::Terminate, None));
+        }
+    });
+}
+
+/// Location of the session file.
+#[cfg(not(windows))]
+fn session_path() -> io::Result<PathBuf> {
+    let xdg = xdg::BaseDirectories::with_prefix("alacritty")
+        .map_err(|err| io::Error::new(io::ErrorKind::NotFound, err))?;
+    xdg.place_state_file(SESSION_FILE)
+}
+
+/// Location of the session file.
+#[cfg(windows)]
+fn session_path() -> io::Result<PathBuf> {
+    let data_dir = dirs::data_local_dir().ok_or_else(|| {
+        io::Error::new(io::ErrorKind::NotFound, "unable to find local data directory")
+    })?;
+
+    let session_dir = data_dir.join("alacritty");
+    fs::create_dir_all(&session_dir)?;
+
+    Ok(session_dir.join(SESSION_FILE))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use alacritty_terminal::index::{Column, Line};
+
+    #[test]
+    fn roundtrip() {
+        let mut grid: Grid<Cell> = Grid::new(3, 5, 100);
+        grid[Line(0)][Column(0)].c = 'a';
+        grid.truncate();
+
+        let windows = vec![WindowSession {
+            title: String::from("Alacritty"),
+            working_directory: Some(PathBuf::from("/tmp")),
+            grid,
+        }];
+
+        let mut buffer = Vec::new();
+        write(&mut buffer, &windows).unwrap();
+
+        assert_eq!(read(buffer.as_slice()).unwrap(), windows);
+    }
+
+    #[test]
+    fn write_file_replaces_session() {
+        let dir = std::env::temp_dir().join(format!("alacritty-session-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(SESSION_FILE);
+        fs::write(&path, "previous session").unwrap();
+
+        let windows = vec![WindowSession {
+            title: String::from("Alacritty"),
+            working_directory: None,
+            grid: Grid::new(3, 5, 100),
+        }];
+        write_file(&path, &windows).unwrap();
+
+        let saved = read(File::open(&path).unwrap());
+        let entries = fs::read_dir(&dir).unwrap().count();
+        #[cfg(unix)]
+        let mode = {
+            use std::os::unix::fs::PermissionsExt;
+            fs::metadata(&path).unwrap().permissions().mode() & 0o777
+        };
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(saved.unwrap(), windows);
+        assert_eq!(entries, 1);
+        #[cfg(unix)]
+        assert_eq!(mode, 0o600);
+    }
+}
@@ -6,6 +6,8 @@ use std::io::Write;
 use std::mem;
 #[cfg(not(windows))]
 use std::os::unix::io::{AsRawFd, RawFd};
+#[cfg(not(windows))]
+use std::process;
 use std::rc::Rc;
 use std::sync::Arc;
 
@@ -26,7 +28,8 @@ use alacritty_config::SerdeReplace;
 use alacritty_terminal::event::Event as TerminalEvent;
 use alacritty_terminal::event_loop::{EventLoop as PtyEventLoop, Msg, Notifier};
 use alacritty_terminal::grid::{Dimensions, Scroll};
-use alacritty_terminal::index::Direction;
+use alacritty_terminal::index::{Column, Direction, Line, Point};
+use alacritty_terminal::replay::{Controller as ReplayController, Player};
 use alacritty_terminal::sync::FairMutex;
 use alacritty_terminal::term::test::TermSize;
 use alacritty_terminal::term::{Term, TermMode};
@@ -37,12 +40,20 @@ use crate::cli::IpcConfig;
 use crate::cli::WindowOptions;
 use crate::clipboard::Clipboard;
 use crate::config::UiConfig;
+#[cfg(not(windows))]
+use crate::daemon;
 use crate::display::window::Window;
 use crate::display::Display;
-use crate::event::{ActionContext, Event, EventProxy, Mouse, SearchState, TouchPurpose};
+use crate::event::{
+    ActionContext, Event, EventProxy, KeySequence, Mouse, Recording, SearchState, TouchPurpose,
+    ViState,
+};
+#[cfg(unix)]
+use crate::ipc::WindowInfo;
 use crate::logging::LOG_TARGET_IPC_CONFIG;
 use crate::message_bar::MessageBuffer;
 use crate::scheduler::Scheduler;
+use crate::session::WindowSession;
 use crate::{input, renderer};
 
 /// Event context for one individual Alacritty window.
@@ -55,6 +66,11 @@ pub struct WindowContext {
     cursor_blink_timed_out: bool,
     modifiers: Modifiers,
     search_state: SearchState,
+    vi_state: ViState,
+    key_sequence: KeySequence,
+    custom_mode: Option<String>,
+    recording: Recording,
+    replay: Option<ReplayController>,
     notifier: Notifier,
     font_size: Size,
     mouse: Mouse,
@@ -171,7 +187,7 @@ impl WindowContext {
         // Create a display.
         //
         // The display manages a window and can draw the terminal.
-        let display = Display::new(window, context, &config)?;
+        let mut display = Display::new(window, context, &config)?;
 
         info!(
             "PTY dimensions: {:?} x {:?}",
@@ -186,41 +202,67 @@ impl WindowContext {
         // This object contains all of the state about what's being displayed. It's
         // wrapped in a clonable mutex since both the I/O loop and display need to
         // access it.
-        let terminal = Term::new(&config.terminal_config, &display.size_info, event_proxy.clone());
+        let mut terminal =
+            Term::new(&config.terminal_config, &display.size_info, event_proxy.clone());
+        terminal.set_window_size(display.size_info.into());
+
+        // Show the content of a saved window above the new shell.
+        if let Some(session) = &options.session {
+            terminal.restore_grid(session.grid.clone());
+            display.window.set_title(session.title.clone());
+        }
+
         let terminal = Arc::new(FairMutex::new(terminal));
 
-        // Create the PTY.
+        // Create the I/O thread.
         //
-        // The PTY forks a process to run the shell on the slave side of the
-        // pseudoterminal. A file descriptor for the master side is retained for
-        // reading/writing to the shell.
-        let pty = tty::new(&pty_config, display.size_info.into(), display.window.id().into())?;
-
-        #[cfg(not(windows))]
-        let master_fd = pty.file().as_raw_fd();
+        // Recorded sessions are replayed without spawning any child process. Otherwise the PTY
+        // forks a process to run the shell on the slave side of the pseudoterminal, and its I/O
+        // is ran on another thread as to not occupy cycles used by the renderer and input
+        // processing. Note that access to the terminal state is synchronized since the I/O loop
+        // updates the state, and the display consumes it periodically.
+        let mut replay = None;
+        // Without a PTY, the working directory of Alacritty itself is used for new windows.
         #[cfg(not(windows))]
-        let shell_pid = pty.child().id();
+        let (mut master_fd, mut shell_pid) = (-1, process::id());
+        let (loop_tx, spawn_io_thread): (_, Box<dyn FnOnce()>) = match options.replay {
+            Some(session) => {
+                let player = Player::new(Arc::clone(&terminal), event_proxy.clone(), session);
+                replay = Some(player.controller());
+                (player.channel(), Box::new(move || drop(player.spawn())))
+            },
+            None => {
+                let window_id = display.window.id().into();
+                let pty = tty::new(&pty_config, display.size_info.into(), window_id)?;
+
+                #[cfg(not(windows))]
+                {
+                    master_fd = pty.file().as_raw_fd();
+                    shell_pid = pty.child().id();
+                }
 
-        // Create the pseudoterminal I/O loop.
-        //
-        // PTY I/O is ran on another thread as to not occupy cycles used by the
-        // renderer and input processing. Note that access to the terminal state is
-        // synchronized since the I/O loop updates the state, and the display
-        // consumes it periodically.
-        let event_loop = PtyEventLoop::new(
-            Arc::clone(&terminal),
-            event_proxy.clone(),
-            pty,
-            pty_config.hold,
-            config.debug.ref_test,
-        );
+                let event_loop = PtyEventLoop::new(
+                    Arc::clone(&terminal),
+                    event_proxy.clone(),
+                    pty,
+                    pty_config.hold,
+                    config.debug.ref_test,
+                );
+
+                // The event loop channel allows write requests from the event processor
+                // to be sent to the pty loop and ultimately written to the pty.
+                (event_loop.channel(), Box::new(move || drop(event_loop.spawn())))
+            },
+        };
 
-        // The event loop channel allows write requests from the event processor
-        // to be sent to the pty loop and ultimately written to the pty.
-        let loop_tx = event_loop.channel();
+        // Start recording the session if requested.
+        let mut recording = Recording::new(Notifier(loop_tx.clone()), &options.terminal_options);
+        if options.terminal_options.record.is_some() {
+            recording.start(display.size_info.into());
+        }
 
         // Kick off the I/O thread.
-        let _io_thread = event_loop.spawn();
+        spawn_io_thread();
 
         // Start cursor blinking, in case `Focused` isn't sent on startup.
         if config.terminal_config.cursor.style().blinking {
@@ -241,9 +283,14 @@ impl WindowContext {
             shell_pid,
             config,
             notifier: Notifier(loop_tx),
+            recording,
+            replay,
             cursor_blink_timed_out: Default::default(),
             message_buffer: Default::default(),
             search_state: Default::default(),
+            vi_state: Default::default(),
+            key_sequence: Default::default(),
+            custom_mode: Default::default(),
             event_queue: Default::default(),
             ipc_config: Default::default(),
             modifiers: Default::default(),
@@ -341,6 +388,12 @@ impl WindowContext {
         // Update hint keys.
         self.display.hint_state.update_alphabet(self.config.hints.alphabet());
 
+        // Leave binding modes which are no longer declared.
+        if self.custom_mode.as_ref().map_or(false, |mode| !self.config.has_custom_mode(mode)) {
+            self.custom_mode = None;
+            self.display.pending_update.dirty = true;
+        }
+
         // Update cursor blinking.
         let event = Event::new(TerminalEvent::CursorBlinkingChange.into(), None);
         self.event_queue.push(event.into());
@@ -399,6 +452,8 @@ impl WindowContext {
             &self.message_buffer,
             &self.config,
             &self.search_state,
+            &self.key_sequence,
+            self.custom_mode.as_deref(),
         );
     }
 
@@ -434,6 +489,11 @@ impl WindowContext {
             cursor_blink_timed_out: &mut self.cursor_blink_timed_out,
             message_buffer: &mut self.message_buffer,
             search_state: &mut self.search_state,
+            vi_state: &mut self.vi_state,
+            key_sequence: &mut self.key_sequence,
+            custom_mode: &mut self.custom_mode,
+            recording: &mut self.recording,
+            replay: self.replay.as_ref(),
             modifiers: &mut self.modifiers,
             font_size: &mut self.font_size,
             notifier: &mut self.notifier,
@@ -469,6 +529,7 @@ impl WindowContext {
                 &self.message_buffer,
                 &self.search_state,
                 old_is_searching,
+                self.custom_mode.is_some(),
                 &self.config,
             );
             self.dirty = true;
@@ -498,6 +559,93 @@ impl WindowContext {
         self.display.window.id()
     }
 
+    /// Current configuration of this window, including IPC overrides.
+    pub fn config(&self) -> &UiConfig {
+        &self.config
+    }
+
+    /// Check if terminal title changes are applied to the window.
+    pub fn dynamic_title(&self) -> bool {
+        !self.preserve_title && self.config.window.dynamic_title
+    }
+
+    /// Check if this window has keyboard focus.
+    pub fn is_focused(&self) -> bool {
+        self.terminal.lock().is_focused
+    }
+
+    /// State of this window reported through IPC.
+    #[cfg(unix)]
+    pub fn ipc_window_info(&self) -> WindowInfo {
+        let terminal = self.terminal.lock();
+        let cwd =
+            daemon::working_directory(terminal.working_directory(), self.master_fd, self.shell_pid);
+
+        let size_info = &self.display.size_info;
+        WindowInfo {
+            id: self.id().into(),
+            title: self.display.window.title().to_owned(),
+            focused: terminal.is_focused,
+            width: size_info.width() as u32,
+            height: size_info.height() as u32,
+            columns: size_info.columns(),
+            lines: size_info.screen_lines(),
+            cwd,
+        }
+    }
+
+    /// Text content of this window's viewport or its entire scrollback history.
+    #[cfg(unix)]
+    pub fn ipc_text(&self, scrollback: bool) -> String {
+        let terminal = self.terminal.lock();
+
+        let (start, end) = if scrollback {
+            (terminal.topmost_line(), terminal.bottommost_line())
+        } else {
+            let start = Line(-(terminal.grid().display_offset() as i32));
+            (start, start + (terminal.screen_lines() - 1))
+        };
+
+        let end = Point::new(end, terminal.last_column());
+        terminal.bounds_to_string(Point::new(start, Column(0)), end)
+    }
+
+    /// Check if the shell of this window is still running.
+    pub fn shell_running(&self) -> bool {
+        #[cfg(not(windows))]
+        let running = unsafe { libc::kill(self.shell_pid as libc::pid_t, 0) } == 0;
+        #[cfg(windows)]
+        let running = true;
+
+        running
+    }
+
+    /// Saved state of this window.
+    ///
+    /// Returns `None` for replays, since they have no shell which could be restored.
+    pub fn session(&self) -> Option<WindowSession> {
+        if self.replay.is_some() {
+            return None;
+        }
+
+        let terminal = self.terminal.lock();
+
+        let mut grid = terminal.primary_grid().clone();
+        grid.truncate();
+
+        #[cfg(not(windows))]
+        let working_directory =
+            daemon::working_directory(terminal.working_directory(), self.master_fd, self.shell_pid);
+        #[cfg(windows)]
+        let working_directory = terminal.working_directory().map(|cwd| cwd.path.clone());
+
+        Some(WindowSession {
+            title: self.display.window.title().to_owned(),
+            working_directory,
+            grid,
+        })
+    }
+
     /// Write the ref test results to the disk.
     pub fn write_ref_test_results(&self) {
         // Dump grid state.
@@ -527,6 +675,7 @@ impl WindowContext {
     }
 
     /// Submit the pending changes to the `Display`.
+    #[allow(clippy::too_many_arguments)]
     fn submit_display_update(
         terminal: &mut Term<EventProxy>,
         display: &mut Display,
@@ -534,6 +683,7 @@ impl WindowContext {
         message_buffer: &MessageBuffer,
         search_state: &SearchState,
         old_is_searching: bool,
+        custom_mode_active: bool,
         config: &UiConfig,
     ) {
         // Compute cursor positions before resize.
@@ -549,7 +699,7 @@ impl WindowContext {
             terminal,
             notifier,
             message_buffer,
-            search_state.history_index.is_some(),
+            search_state.history_index.is_some() || custom_mode_active,
             config,
         );
 
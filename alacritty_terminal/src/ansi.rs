@@ -1,7 +1,661 @@
 //! ANSI Terminal Stream Parsing.
 
+use std::cell::Cell;
+use std::path::PathBuf;
+use std::time::Duration;
+use std::{mem, str};
+
+use bitflags::bitflags;
+use log::debug;
+use serde::{Deserialize, Serialize};
+
+use crate::graphics::kitty::GraphicsCommand;
+use crate::graphics::{sixel, GraphicData};
+
 pub use vte::ansi::*;
 
+/// Maximum time before a synchronized update is aborted.
+const SYNC_UPDATE_TIMEOUT: Duration = Duration::from_millis(150);
+
+/// Maximum number of bytes read in one synchronized update (2MiB).
+const SYNC_BUFFER_SIZE: usize = 0x20_0000;
+
+/// Number of bytes in the synchronized update DCS sequence before the passthrough parameters.
+const SYNC_ESCAPE_START_LEN: usize = 5;
+
+/// Start of the DCS sequence for beginning synchronized updates.
+const SYNC_START_ESCAPE_START: [u8; SYNC_ESCAPE_START_LEN] = [b'\x1b', b'P', b'=', b'1', b's'];
+
+/// Start of the DCS sequence for terminating synchronized updates.
+const SYNC_END_ESCAPE_START: [u8; SYNC_ESCAPE_START_LEN] = [b'\x1b', b'P', b'=', b'2', b's'];
+
+/// Maximum number of bytes in an APC sequence (8MiB).
+const APC_BUFFER_SIZE: usize = 0x80_0000;
+
+/// Maximum number of bytes in an OSC sequence dispatched to the [`ExtendedHandler`] (64KiB).
+const OSC_BUFFER_SIZE: usize = 0x1_0000;
+
+/// Start of the OSC sequences dispatched to the [`ExtendedHandler`].
+const EXTENDED_OSC_PREFIXES: [&[u8]; 2] = [b"7;", b"133;"];
+
+bitflags! {
+    /// Kitty keyboard protocol progressive enhancement flags.
+    #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+    pub struct KeyboardModes: u8 {
+        /// No keyboard protocol mode is set.
+        const NO_MODE                 = 0b0000_0000;
+        /// Report `Esc`, `alt` + `key`, `ctrl` + `key`, `ctrl` + `alt` + `key`, `shift` +
+        /// `alt` + `key` keys using `CSI u` sequence instead of raw ones.
+        const DISAMBIGUATE_ESC_CODES  = 0b0000_0001;
+        /// Report key presses, release, and repetition alongside the escape. Key events
+        /// that generate text, like `Enter` or `Tab`, don't report release events.
+        const REPORT_EVENT_TYPES      = 0b0000_0010;
+        /// Report alternate key codes, like the shifted variant of a key.
+        const REPORT_ALTERNATE_KEYS   = 0b0000_0100;
+        /// Report every key as an escape sequence, including the ones generating text.
+        const REPORT_ALL_KEYS_AS_ESC  = 0b0000_1000;
+        /// Report the text generated by a key alongside its escape sequence.
+        const REPORT_ASSOCIATED_TEXT  = 0b0001_0000;
+    }
+}
+
+/// Describes how the new [`KeyboardModes`] should be applied.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum KeyboardModesApplyBehavior {
+    /// Replace the active flags with the new ones.
+    #[default]
+    Replace,
+    /// Merge the given flags with the active ones.
+    Union,
+    /// Remove the given flags from the active ones.
+    Difference,
+}
+
+/// Shell integration marks, set through OSC 133.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SemanticMark {
+    /// Start of the prompt (`OSC 133 ; A`).
+    PromptStart,
+    /// End of the prompt and start of the user's command (`OSC 133 ; B`).
+    CommandStart,
+    /// End of the command and start of its output (`OSC 133 ; C`).
+    OutputStart,
+    /// End of the command's output, with its optional exit code (`OSC 133 ; D`).
+    CommandEnd(Option<i32>),
+}
+
+/// Working directory reported by the shell through OSC 7.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WorkingDirectory {
+    /// Host the directory is located on.
+    pub hostname: Option<String>,
+    /// Absolute path of the directory.
+    pub path: PathBuf,
+}
+
+impl WorkingDirectory {
+    /// Parse a `file://hostname/path` URI.
+    pub fn from_uri(uri: &[u8]) -> Option<Self> {
+        let uri = uri.strip_prefix(b"file://")?;
+        let path_start = uri.iter().position(|&b| b == b'/')?;
+        let (hostname, path) = uri.split_at(path_start);
+
+        let hostname = match std::str::from_utf8(hostname).ok()? {
+            "" => None,
+            hostname => Some(hostname.to_owned()),
+        };
+        let path = String::from_utf8(percent_decode(path)).ok()?;
+
+        Some(Self { hostname, path: PathBuf::from(path) })
+    }
+}
+
+/// Decode `%XX` escapes in a URI component.
+fn percent_decode(input: &[u8]) -> Vec<u8> {
+    let hex = |b: u8| (b as char).to_digit(16).map(|digit| digit as u8);
+
+    let mut decoded = Vec::with_capacity(input.len());
+    let mut i = 0;
+    while i < input.len() {
+        match input[i..] {
+            [b'%', high, low, ..] if hex(high).is_some() && hex(low).is_some() => {
+                decoded.push(hex(high).unwrap() << 4 | hex(low).unwrap());
+                i += 3;
+            },
+            [byte, ..] => {
+                decoded.push(byte);
+                i += 1;
+            },
+            [] => unreachable!(),
+        }
+    }
+
+    decoded
+}
+
+/// Handler for escape sequences which aren't understood by VTE's [`Handler`].
+///
+/// Like [`Handler`], all methods have a default implementation which ignores the sequence.
+pub trait ExtendedHandler {
+    /// Push a new set of kitty keyboard protocol modes onto the stack.
+    fn push_keyboard_mode(&mut self, _mode: KeyboardModes) {}
+
+    /// Pop the given number of kitty keyboard protocol modes from the stack.
+    fn pop_keyboard_modes(&mut self, _to_pop: u16) {}
+
+    /// Report the active kitty keyboard protocol modes.
+    fn report_keyboard_mode(&mut self) {}
+
+    /// Modify the active kitty keyboard protocol modes.
+    fn set_keyboard_mode(&mut self, _mode: KeyboardModes, _behavior: KeyboardModesApplyBehavior) {}
+
+    /// Place a shell integration mark at the cursor position.
+    fn set_semantic_mark(&mut self, _mark: SemanticMark) {}
+
+    /// Update the working directory reported by the shell.
+    fn set_working_directory(&mut self, _working_directory: WorkingDirectory) {}
+
+    /// Insert a graphic at the cursor position.
+    fn insert_graphic(&mut self, _graphic: GraphicData) {}
+
+    /// Handle a kitty graphics protocol command.
+    fn kitty_graphics(&mut self, _command: GraphicsCommand) {}
+}
+
+/// Terminal escape sequence processor.
+///
+/// This drives VTE's [`vte::ansi::Processor`] for all sequences it supports. Sequences VTE
+/// doesn't know about are picked up by a lightweight [`ExtendedParser`] and dispatched to the
+/// [`ExtendedHandler`]. Synchronized updates are buffered here, so both always observe bytes in
+/// the same order.
+#[derive(Default)]
+pub struct Processor {
+    /// Processor for all sequences supported by VTE.
+    processor: vte::ansi::Processor<SyncRequest>,
+
+    /// Parser for the sequences dispatched to the [`ExtendedHandler`].
+    parser: ExtendedParser,
+
+    /// State for synchronized terminal updates.
+    sync_state: SyncState,
+}
+
+impl Processor {
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Synchronized update timeout.
+    pub fn sync_timeout(&self) -> &StdSyncHandler {
+        &self.sync_state.timeout
+    }
+
+    /// Process a new byte from the PTY.
+    #[inline]
+    pub fn advance<H>(&mut self, handler: &mut H, byte: u8)
+    where
+        H: Handler + ExtendedHandler,
+    {
+        if self.sync_state.timeout.pending_timeout() {
+            self.advance_sync(handler, byte);
+        } else {
+            self.advance_unsynced(handler, byte);
+        }
+    }
+
+    /// End a synchronized update.
+    pub fn stop_sync<H>(&mut self, handler: &mut H)
+    where
+        H: Handler + ExtendedHandler,
+    {
+        // Process all synchronized bytes.
+        let mut buffer = mem::take(&mut self.sync_state.buffer);
+        for byte in buffer.drain(..) {
+            self.advance_unsynced(handler, byte);
+        }
+
+        // Resetting state after processing makes sure we don't interpret buffered sync escapes.
+        self.sync_state.buffer = buffer;
+        self.sync_state.timeout.clear_timeout();
+    }
+
+    /// Number of bytes in the synchronization buffer.
+    #[inline]
+    pub fn sync_bytes_count(&self) -> usize {
+        self.sync_state.buffer.len()
+    }
+
+    /// Process a new byte outside of a synchronized update.
+    #[inline]
+    fn advance_unsynced<H>(&mut self, handler: &mut H, byte: u8)
+    where
+        H: Handler + ExtendedHandler,
+    {
+        self.processor.advance(handler, byte);
+        self.parser.advance(handler, byte);
+
+        // Take over synchronized updates started by VTE, without writing the flag for every byte.
+        let requested = &self.processor.sync_timeout().requested;
+        if requested.get() {
+            requested.set(false);
+            self.sync_state.timeout.set_timeout(SYNC_UPDATE_TIMEOUT);
+        }
+    }
+
+    /// Process a new byte during a synchronized update.
+    #[cold]
+    fn advance_sync<H>(&mut self, handler: &mut H, byte: u8)
+    where
+        H: Handler + ExtendedHandler,
+    {
+        self.sync_state.buffer.push(byte);
+
+        // Handle sync DCS escape sequences.
+        match self.sync_state.pending_dcs {
+            Some(_) => self.advance_sync_dcs_end(handler, byte),
+            None => self.advance_sync_dcs_start(),
+        }
+    }
+
+    /// Find the start of sync DCS sequences.
+    fn advance_sync_dcs_start(&mut self) {
+        // Get the last few bytes for comparison.
+        let len = self.sync_state.buffer.len();
+        let offset = len.saturating_sub(SYNC_ESCAPE_START_LEN);
+        let end = &self.sync_state.buffer[offset..];
+
+        // Check for extension/termination of the synchronized update.
+        if end == SYNC_START_ESCAPE_START {
+            self.sync_state.pending_dcs = Some(SyncDcs::Start);
+        } else if end == SYNC_END_ESCAPE_START || len >= SYNC_BUFFER_SIZE - 1 {
+            self.sync_state.pending_dcs = Some(SyncDcs::End);
+        }
+    }
+
+    /// Parse the DCS termination sequence for synchronized updates.
+    fn advance_sync_dcs_end<H>(&mut self, handler: &mut H, byte: u8)
+    where
+        H: Handler + ExtendedHandler,
+    {
+        match byte {
+            // Ignore DCS passthrough characters.
+            0x00..=0x17 | 0x19 | 0x1c..=0x7f | 0xa0..=0xff => (),
+            // Cancel the DCS sequence.
+            0x18 | 0x1a | 0x80..=0x9f => self.sync_state.pending_dcs = None,
+            // Dispatch on ESC.
+            0x1b => match self.sync_state.pending_dcs.take() {
+                Some(SyncDcs::Start) => self.sync_state.timeout.set_timeout(SYNC_UPDATE_TIMEOUT),
+                Some(SyncDcs::End) => self.stop_sync(handler),
+                None => (),
+            },
+        }
+    }
+}
+
+/// State for synchronized terminal updates.
+#[derive(Default)]
+struct SyncState {
+    /// Handler for synchronized updates.
+    timeout: StdSyncHandler,
+
+    /// Sync DCS waiting for termination sequence.
+    pending_dcs: Option<SyncDcs>,
+
+    /// Bytes read during the synchronized update.
+    buffer: Vec<u8>,
+}
+
+/// Pending synchronized update DCS sequence.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum SyncDcs {
+    /// Begin of the synchronized update.
+    Start,
+
+    /// End of the synchronized update.
+    End,
+}
+
+/// Synchronized update tracking for the VTE processor.
+///
+/// This never reports a pending timeout, so VTE doesn't buffer any bytes itself. Instead the
+/// start of a synchronized update is recorded, to let the [`Processor`] take care of it.
+#[derive(Default)]
+struct SyncRequest {
+    requested: Cell<bool>,
+}
+
+impl Timeout for SyncRequest {
+    #[inline]
+    fn set_timeout(&mut self, _duration: Duration) {
+        self.requested.set(true);
+    }
+
+    #[inline]
+    fn clear_timeout(&mut self) {
+        self.requested.set(false);
+    }
+
+    #[inline]
+    fn pending_timeout(&self) -> bool {
+        false
+    }
+}
+
+/// Parser for the escape sequences dispatched to the [`ExtendedHandler`].
+///
+/// This follows VTE's state transitions, but only keeps track of the sequences VTE ignores:
+///
+///  - `CSI = u`, `CSI > u`, `CSI < u` and `CSI ? u` for the kitty keyboard protocol
+///  - `OSC 7` and `OSC 133` for shell integration
+///  - `DCS q` for sixel images
+///  - `APC` for the kitty graphics protocol
+///
+/// All other sequences return to the ground state immediately, since every escape starts with
+/// `ESC` no matter which state VTE is in. This keeps the parser cheap for regular output.
+#[derive(Default)]
+struct ExtendedParser {
+    state: ExtendedState,
+
+    /// Numeric parameters of the active CSI or DCS sequence.
+    params: Vec<u16>,
+
+    /// Parameter which is currently being parsed.
+    param: u16,
+
+    /// Whether the parser is inside of a CSI or DCS subparameter.
+    subparam: bool,
+
+    /// Content of the active OSC or APC sequence.
+    buffer: Vec<u8>,
+
+    /// Decoder for the active sixel DCS sequence.
+    sixel_parser: Option<Box<sixel::Parser>>,
+}
+
+/// State of the [`ExtendedParser`].
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+enum ExtendedState {
+    /// Outside of any escape sequence, or inside of one which is ignored.
+    #[default]
+    Ground,
+
+    /// Start of an escape sequence.
+    Escape,
+
+    /// Start of a CSI sequence.
+    CsiEntry,
+
+    /// Parameters of a CSI sequence with the given private marker.
+    CsiParam(u8),
+
+    /// Content of an OSC sequence.
+    Osc,
+
+    /// Parameters of a DCS sequence.
+    DcsParam,
+
+    /// Content of a sixel DCS sequence.
+    Sixel,
+
+    /// Content of an APC sequence.
+    Apc,
+
+    /// Escape inside an APC sequence, which might be the string terminator.
+    ApcEscape,
+}
+
+impl ExtendedParser {
+    /// Process a new byte, dispatching completed sequences to the handler.
+    #[inline]
+    fn advance<H: ExtendedHandler>(&mut self, handler: &mut H, byte: u8) {
+        // Skip everything outside of escapes without any further checks.
+        if byte != 0x1b && matches!(self.state, ExtendedState::Ground) {
+            return;
+        }
+
+        self.advance_sequence(handler, byte);
+    }
+
+    /// Process a new byte inside of an escape sequence.
+    #[inline(never)]
+    fn advance_sequence<H: ExtendedHandler>(&mut self, handler: &mut H, byte: u8) {
+        match (self.state, byte) {
+            // Content of APC sequences is dropped without a string terminator.
+            (ExtendedState::Apc, 0x1b) => self.state = ExtendedState::ApcEscape,
+            (ExtendedState::Apc, 0x18 | 0x1a) => self.state = ExtendedState::Ground,
+            (ExtendedState::Apc, _) if self.buffer.len() >= APC_BUFFER_SIZE => {
+                // Sequences exceeding the maximum size are dropped entirely.
+                self.buffer = Vec::new();
+                self.state = ExtendedState::Ground;
+            },
+            (ExtendedState::Apc, _) => self.buffer.push(byte),
+            (ExtendedState::ApcEscape, b'\\') => {
+                self.state = ExtendedState::Ground;
+                self.apc_dispatch(handler);
+            },
+            // Like VTE, ESC, CAN and SUB terminate all other sequences.
+            (_, 0x1b) => {
+                self.terminate(handler);
+                self.state = ExtendedState::Escape;
+            },
+            (_, 0x18 | 0x1a) => {
+                self.terminate(handler);
+                self.state = ExtendedState::Ground;
+            },
+            (ExtendedState::Escape | ExtendedState::ApcEscape, _) => self.advance_escape(byte),
+            (ExtendedState::CsiEntry, _) => self.advance_csi_entry(byte),
+            (ExtendedState::CsiParam(marker), _) => self.advance_csi_param(handler, marker, byte),
+            (ExtendedState::Osc, _) => self.advance_osc(handler, byte),
+            (ExtendedState::DcsParam, _) => self.advance_dcs_param(byte),
+            (ExtendedState::Sixel, _) => self.advance_sixel(handler, byte),
+            (ExtendedState::Ground, _) => unreachable!(),
+        }
+    }
+
+    /// Process a byte following an escape.
+    fn advance_escape(&mut self, byte: u8) {
+        self.state = match byte {
+            // C0 controls are executed without leaving the escape.
+            0x00..=0x1f => return,
+            b'[' => ExtendedState::CsiEntry,
+            b']' => ExtendedState::Osc,
+            b'P' => ExtendedState::DcsParam,
+            b'_' => ExtendedState::Apc,
+            _ => ExtendedState::Ground,
+        };
+
+        self.params.clear();
+        self.param = 0;
+        self.subparam = false;
+        self.buffer.clear();
+    }
+
+    /// Process the first byte of a CSI sequence.
+    fn advance_csi_entry(&mut self, byte: u8) {
+        self.state = match byte {
+            0x00..=0x1f | 0x7f..=0xff => return,
+            // Only sequences with a private marker are handled.
+            b'<' | b'=' | b'>' | b'?' => ExtendedState::CsiParam(byte),
+            _ => ExtendedState::Ground,
+        };
+    }
+
+    /// Process a byte of a CSI sequence with a private marker.
+    fn advance_csi_param<H: ExtendedHandler>(&mut self, handler: &mut H, marker: u8, byte: u8) {
+        match byte {
+            b'0'..=b'9' | b':' | b';' => self.param(byte),
+            0x00..=0x1f | 0x7f..=0xff => (),
+            b'u' => {
+                self.state = ExtendedState::Ground;
+                self.params.push(self.param);
+                self.csi_dispatch(handler, marker);
+            },
+            // Sequences with intermediates or other final bytes are ignored.
+            _ => self.state = ExtendedState::Ground,
+        }
+    }
+
+    /// Process a byte of an OSC sequence.
+    fn advance_osc<H: ExtendedHandler>(&mut self, handler: &mut H, byte: u8) {
+        match byte {
+            0x07 => {
+                self.state = ExtendedState::Ground;
+                self.osc_dispatch(handler);
+            },
+            0x00..=0x1f => (),
+            _ if self.buffer.len() >= OSC_BUFFER_SIZE => self.state = ExtendedState::Ground,
+            _ => {
+                self.buffer.push(byte);
+
+                // Stop collecting once the sequence can't be one of the handled ones.
+                let is_extended = |prefix: &&[u8]| {
+                    let len = prefix.len().min(self.buffer.len());
+                    prefix[..len] == self.buffer[..len]
+                };
+                if !EXTENDED_OSC_PREFIXES.iter().any(is_extended) {
+                    self.state = ExtendedState::Ground;
+                }
+            },
+        }
+    }
+
+    /// Process a byte of the DCS parameters.
+    fn advance_dcs_param(&mut self, byte: u8) {
+        match byte {
+            b'0'..=b'9' | b':' | b';' => self.param(byte),
+            0x00..=0x1f | 0x7f..=0xff => (),
+            b'q' => {
+                self.params.push(self.param);
+                self.sixel_parser = Some(Box::new(sixel::Parser::new(&self.params)));
+                self.state = ExtendedState::Sixel;
+            },
+            // Sequences with private markers, intermediates or other final bytes are ignored.
+            _ => self.state = ExtendedState::Ground,
+        }
+    }
+
+    /// Process a byte of a sixel image.
+    fn advance_sixel<H: ExtendedHandler>(&mut self, handler: &mut H, byte: u8) {
+        match (byte, &mut self.sixel_parser) {
+            (0x9c, _) => {
+                self.terminate(handler);
+                self.state = ExtendedState::Ground;
+            },
+            (0x00..=0x7e, Some(parser)) => parser.advance(byte),
+            _ => (),
+        }
+    }
+
+    /// Collect a CSI or DCS parameter byte.
+    fn param(&mut self, byte: u8) {
+        match byte {
+            b';' => {
+                self.params.push(mem::take(&mut self.param));
+                self.subparam = false;
+            },
+            // Like VTE's handler, only the first subparameter is used.
+            b':' => self.subparam = true,
+            _ if self.subparam => (),
+            _ => self.param = self.param.saturating_mul(10).saturating_add((byte - b'0') as u16),
+        }
+    }
+
+    /// Finish the active sequence when it's terminated early or by a string terminator.
+    fn terminate<H: ExtendedHandler>(&mut self, handler: &mut H) {
+        match self.state {
+            ExtendedState::Osc => self.osc_dispatch(handler),
+            // Like VTE's unhook, sixel images are inserted even if they're cancelled.
+            ExtendedState::Sixel => {
+                let graphic = match self.sixel_parser.take() {
+                    Some(parser) => parser.finish(),
+                    None => return,
+                };
+
+                match graphic {
+                    Some(graphic) => handler.insert_graphic(graphic),
+                    None => debug!("Ignoring empty sixel image"),
+                }
+            },
+            _ => (),
+        }
+    }
+
+    /// Dispatch a CSI sequence with a private marker.
+    fn csi_dispatch<H: ExtendedHandler>(&mut self, handler: &mut H, marker: u8) {
+        let mut params_iter = self.params.iter();
+        let mut next_param_or = |default: u16| match params_iter.next() {
+            Some(&param) if param != 0 => param,
+            _ => default,
+        };
+
+        match marker {
+            b'?' => handler.report_keyboard_mode(),
+            b'=' => {
+                let mode = KeyboardModes::from_bits_truncate(next_param_or(0) as u8);
+                let behavior = match next_param_or(1) {
+                    3 => KeyboardModesApplyBehavior::Difference,
+                    2 => KeyboardModesApplyBehavior::Union,
+                    // Default mode is replace.
+                    _ => KeyboardModesApplyBehavior::Replace,
+                };
+                handler.set_keyboard_mode(mode, behavior);
+            },
+            b'>' => {
+                let mode = KeyboardModes::from_bits_truncate(next_param_or(0) as u8);
+                handler.push_keyboard_mode(mode);
+            },
+            b'<' => handler.pop_keyboard_modes(next_param_or(1)),
+            _ => unreachable!(),
+        }
+    }
+
+    /// Dispatch an OSC 7 or OSC 133 sequence.
+    fn osc_dispatch<H: ExtendedHandler>(&mut self, handler: &mut H) {
+        let params: Vec<&[u8]> = self.buffer.split(|&b| b == b';').collect();
+        match params.as_slice() {
+            [b"7", uri @ ..] => {
+                // Restore semicolons in the path, which are used as parameter separators.
+                let uri = uri.join(&b';');
+                match WorkingDirectory::from_uri(&uri) {
+                    Some(working_directory) => handler.set_working_directory(working_directory),
+                    None => debug!("Invalid OSC 7 URI: {:?}", String::from_utf8_lossy(&uri)),
+                }
+            },
+            [b"133", kind, rest @ ..] => {
+                let mark = match *kind {
+                    b"A" => SemanticMark::PromptStart,
+                    b"B" => SemanticMark::CommandStart,
+                    b"C" => SemanticMark::OutputStart,
+                    b"D" => {
+                        let exit_code = rest
+                            .first()
+                            .and_then(|code| str::from_utf8(code).ok())
+                            .and_then(|code| code.parse().ok());
+                        SemanticMark::CommandEnd(exit_code)
+                    },
+                    _ => return,
+                };
+                handler.set_semantic_mark(mark);
+            },
+            _ => (),
+        }
+    }
+
+    /// Dispatch an APC sequence.
+    fn apc_dispatch<H: ExtendedHandler>(&mut self, handler: &mut H) {
+        if self.buffer.is_empty() {
+            return;
+        }
+
+        match self.buffer.strip_prefix(b"G").map(GraphicsCommand::parse) {
+            Some(Some(command)) => handler.kitty_graphics(command),
+            Some(None) => debug!("Invalid kitty graphics command"),
+            None => debug!("Unsupported APC sequence"),
+        }
+    }
+}
+
 #[derive(Debug, Eq, PartialEq, Copy, Clone, Hash)]
 pub struct CursorShapeShim(CursorShape);
 
@@ -51,6 +705,20 @@ impl<'de> serde::Deserialize<'de> for CursorShapeShim {
     }
 }
 
+impl serde::Serialize for CursorShapeShim {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let shape = match self.0 {
+            CursorShape::Underline => "Underline",
+            CursorShape::Beam => "Beam",
+            _ => "Block",
+        };
+        serializer.serialize_str(shape)
+    }
+}
+
 impl alacritty_config::SerdeReplace for CursorShapeShim {
     fn replace(&mut self, value: toml::Value) -> Result<(), Box<dyn std::error::Error>> {
         *self = serde::Deserialize::deserialize(value)?;
@@ -58,3 +726,152 @@ impl alacritty_config::SerdeReplace for CursorShapeShim {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Default)]
+    struct MockHandler {
+        attr: Option<Attr>,
+        keyboard_modes: Vec<KeyboardModes>,
+        keyboard_mode_changes: Vec<(KeyboardModes, KeyboardModesApplyBehavior)>,
+        popped_keyboard_modes: u16,
+        keyboard_mode_reports: usize,
+        marks: Vec<SemanticMark>,
+        working_directory: Option<WorkingDirectory>,
+        graphics: usize,
+        kitty_graphics: usize,
+        text: String,
+        restored_cursor: bool,
+    }
+
+    impl Handler for MockHandler {
+        fn input(&mut self, c: char) {
+            self.text.push(c);
+        }
+
+        fn terminal_attribute(&mut self, attr: Attr) {
+            self.attr = Some(attr);
+        }
+
+        fn restore_cursor_position(&mut self) {
+            self.restored_cursor = true;
+        }
+    }
+
+    impl ExtendedHandler for MockHandler {
+        fn push_keyboard_mode(&mut self, mode: KeyboardModes) {
+            self.keyboard_modes.push(mode);
+        }
+
+        fn pop_keyboard_modes(&mut self, to_pop: u16) {
+            self.popped_keyboard_modes += to_pop;
+        }
+
+        fn report_keyboard_mode(&mut self) {
+            self.keyboard_mode_reports += 1;
+        }
+
+        fn set_keyboard_mode(&mut self, mode: KeyboardModes, behavior: KeyboardModesApplyBehavior) {
+            self.keyboard_mode_changes.push((mode, behavior));
+        }
+
+        fn set_semantic_mark(&mut self, mark: SemanticMark) {
+            self.marks.push(mark);
+        }
+
+        fn set_working_directory(&mut self, working_directory: WorkingDirectory) {
+            self.working_directory = Some(working_directory);
+        }
+
+        fn insert_graphic(&mut self, _graphic: GraphicData) {
+            self.graphics += 1;
+        }
+
+        fn kitty_graphics(&mut self, _command: GraphicsCommand) {
+            self.kitty_graphics += 1;
+        }
+    }
+
+    fn parse(bytes: &[u8]) -> MockHandler {
+        let mut parser = Processor::new();
+        let mut handler = MockHandler::default();
+        for byte in bytes {
+            parser.advance(&mut handler, *byte);
+        }
+        handler
+    }
+
+    #[test]
+    fn dispatch_vte_and_extended_sequences() {
+        let handler = parse(b"\x1b]133;A\x07a\x1b[>1u\x1b[1mb\x1b]133;D;1\x1b\\\x1b[2b");
+
+        assert_eq!(handler.text, "abbb");
+        assert_eq!(handler.attr, Some(Attr::Bold));
+        assert_eq!(handler.keyboard_modes, [KeyboardModes::DISAMBIGUATE_ESC_CODES]);
+        assert_eq!(handler.marks, [SemanticMark::PromptStart, SemanticMark::CommandEnd(Some(1))]);
+    }
+
+    #[test]
+    fn dispatch_sixel() {
+        let handler = parse(b"\x1bPq#0;2;100;0;0#0~~\x1b\\x\x1bP$qm\x1b\\");
+
+        assert_eq!(handler.graphics, 1);
+        assert_eq!(handler.text, "x");
+    }
+
+    #[test]
+    fn synchronized_update() {
+        let mut parser = Processor::new();
+        let mut handler = MockHandler::default();
+        for byte in b"\x1bP=1s\x1b\\abc" {
+            parser.advance(&mut handler, *byte);
+        }
+
+        // The update starts at the string terminator's `ESC`, buffering its trailing `\`.
+        assert!(handler.text.is_empty());
+        assert_eq!(parser.sync_bytes_count(), 4);
+
+        parser.stop_sync(&mut handler);
+        assert_eq!(handler.text, "abc");
+    }
+
+    #[test]
+    fn dispatch_keyboard_modes() {
+        let handler = parse(b"\x1b[?u\x1b[=5;2u\x1b[=1u\x1b[>1:3u\x1b[<2u\x1b[<u\x1b[>1$u\x1b[u");
+
+        assert_eq!(handler.keyboard_mode_reports, 1);
+        assert_eq!(
+            handler.keyboard_mode_changes,
+            [
+                (
+                    KeyboardModes::DISAMBIGUATE_ESC_CODES | KeyboardModes::REPORT_ALTERNATE_KEYS,
+                    KeyboardModesApplyBehavior::Union
+                ),
+                (KeyboardModes::DISAMBIGUATE_ESC_CODES, KeyboardModesApplyBehavior::Replace),
+            ]
+        );
+        assert_eq!(handler.keyboard_modes, [KeyboardModes::DISAMBIGUATE_ESC_CODES]);
+        assert_eq!(handler.popped_keyboard_modes, 3);
+        assert!(handler.restored_cursor);
+    }
+
+    #[test]
+    fn dispatch_osc() {
+        let handler = parse(b"\x1b]7;file://host/a;b%20c\x1b\\\x1b]1337;A\x07\x1b]133;B\x18");
+
+        let working_directory =
+            WorkingDirectory { hostname: Some("host".into()), path: PathBuf::from("/a;b c") };
+        assert_eq!(handler.working_directory, Some(working_directory));
+        assert_eq!(handler.marks, [SemanticMark::CommandStart]);
+    }
+
+    #[test]
+    fn dispatch_apc() {
+        let handler = parse(b"\x1b_Ga=d\x1b\\\x1b_Ga=d\x18\x1b\\\x1b_Ga=d\x1b[1m");
+
+        assert_eq!(handler.kitty_graphics, 1);
+        assert_eq!(handler.attr, Some(Attr::Bold));
+    }
+}
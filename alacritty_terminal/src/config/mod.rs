@@ -2,7 +2,7 @@ use std::cmp;
 use std::collections::HashMap;
 use std::path::PathBuf;
 
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
 use alacritty_config_derive::{ConfigDeserialize, SerdeReplace};
 
@@ -18,7 +18,7 @@ pub const LOG_TARGET_CONFIG: &str = "alacritty_config_derive";
 const MIN_BLINK_INTERVAL: u64 = 10;
 
 /// Top-level config type.
-#[derive(ConfigDeserialize, Clone, Debug, PartialEq, Default)]
+#[derive(ConfigDeserialize, Serialize, Clone, Debug, PartialEq, Default)]
 pub struct Config {
     /// TERM env variable.
     pub env: HashMap<String, String>,
@@ -35,16 +35,17 @@ pub struct Config {
     pub terminal: Terminal,
 
     #[config(flatten)]
+    #[serde(flatten)]
     pub pty_config: PtyConfig,
 }
 
-#[derive(ConfigDeserialize, Clone, Debug, PartialEq, Eq, Default)]
+#[derive(ConfigDeserialize, Serialize, Clone, Debug, PartialEq, Eq, Default)]
 pub struct Terminal {
     // OSC 52 handling (clipboard handling).
     pub osc52: Osc52,
 }
 
-#[derive(ConfigDeserialize, Clone, Debug, PartialEq, Eq, Default)]
+#[derive(ConfigDeserialize, Serialize, Clone, Debug, PartialEq, Eq, Default)]
 pub enum Osc52 {
     /// The handling of the escape sequence is disabled.
     Disabled,
@@ -60,7 +61,7 @@ pub enum Osc52 {
     CopyPaste,
 }
 
-#[derive(ConfigDeserialize, Clone, Debug, PartialEq, Eq, Default)]
+#[derive(ConfigDeserialize, Serialize, Clone, Debug, PartialEq, Eq, Default)]
 pub struct PtyConfig {
     /// Path to a shell program to run on startup.
     pub shell: Option<Program>,
@@ -70,6 +71,7 @@ pub struct PtyConfig {
 
     /// Remain open after child process exits.
     #[config(skip)]
+    #[serde(skip)]
     pub hold: bool,
 }
 
@@ -79,7 +81,7 @@ impl PtyConfig {
     }
 }
 
-#[derive(ConfigDeserialize, Clone, Debug, PartialEq, Eq)]
+#[derive(ConfigDeserialize, Serialize, Clone, Debug, PartialEq, Eq)]
 pub struct Selection {
     pub semantic_escape_chars: String,
     pub save_to_clipboard: bool,
@@ -94,7 +96,7 @@ impl Default for Selection {
     }
 }
 
-#[derive(ConfigDeserialize, Copy, Clone, Debug, PartialEq)]
+#[derive(ConfigDeserialize, Serialize, Copy, Clone, Debug, PartialEq)]
 pub struct Cursor {
     pub style: ConfigCursorStyle,
     pub vi_mode_style: Option<ConfigCursorStyle>,
@@ -151,7 +153,7 @@ impl Cursor {
     }
 }
 
-#[derive(SerdeReplace, Deserialize, Debug, Copy, Clone, PartialEq, Eq)]
+#[derive(SerdeReplace, Deserialize, Serialize, Debug, Copy, Clone, PartialEq, Eq)]
 #[serde(untagged, deny_unknown_fields)]
 pub enum ConfigCursorStyle {
     Shape(CursorShapeShim),
@@ -190,7 +192,7 @@ impl From<ConfigCursorStyle> for CursorStyle {
     }
 }
 
-#[derive(ConfigDeserialize, Default, Debug, Copy, Clone, PartialEq, Eq)]
+#[derive(ConfigDeserialize, Serialize, Default, Debug, Copy, Clone, PartialEq, Eq)]
 pub enum CursorBlinking {
     Never,
     #[default]
@@ -215,7 +217,7 @@ impl From<CursorBlinking> for bool {
     }
 }
 
-#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
 #[serde(untagged, deny_unknown_fields)]
 pub enum Program {
     Just(String),
@@ -243,7 +245,7 @@ impl Program {
 }
 
 /// Wrapper around f32 that represents a percentage value between 0.0 and 1.0.
-#[derive(SerdeReplace, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[derive(SerdeReplace, Deserialize, Serialize, Clone, Copy, Debug, PartialEq)]
 pub struct Percentage(f32);
 
 impl Default for Percentage {
@@ -2,6 +2,8 @@ use std::borrow::Cow;
 use std::fmt::{self, Debug, Formatter};
 use std::sync::Arc;
 
+use crate::ansi::WorkingDirectory;
+use crate::grid::Dimensions;
 use crate::term::color::Rgb;
 use crate::term::ClipboardType;
 
@@ -20,6 +22,9 @@ pub enum Event {
     /// Reset to the default window title.
     ResetTitle,
 
+    /// Working directory reported by the shell has changed.
+    WorkingDirectory(WorkingDirectory),
+
     /// Request to store a text string in the clipboard.
     ClipboardStore(ClipboardType, String),
 
@@ -50,6 +55,9 @@ pub enum Event {
     /// Terminal bell ring.
     Bell,
 
+    /// Session recording was stopped, since it could not be written.
+    RecordingStopped,
+
     /// Shutdown request.
     Exit,
 }
@@ -66,8 +74,10 @@ impl Debug for Event {
             Event::CursorBlinkingChange => write!(f, "CursorBlinkingChange"),
             Event::MouseCursorDirty => write!(f, "MouseCursorDirty"),
             Event::ResetTitle => write!(f, "ResetTitle"),
+            Event::WorkingDirectory(cwd) => write!(f, "WorkingDirectory({cwd:?})"),
             Event::Wakeup => write!(f, "Wakeup"),
             Event::Bell => write!(f, "Bell"),
+            Event::RecordingStopped => write!(f, "RecordingStopped"),
             Event::Exit => write!(f, "Exit"),
         }
     }
@@ -89,6 +99,20 @@ pub struct WindowSize {
     pub cell_height: u16,
 }
 
+impl Dimensions for WindowSize {
+    fn total_lines(&self) -> usize {
+        self.screen_lines()
+    }
+
+    fn screen_lines(&self) -> usize {
+        self.num_lines as usize
+    }
+
+    fn columns(&self) -> usize {
+        self.num_cols as usize
+    }
+}
+
 /// Types that are interested in when the display is resized.
 pub trait OnResize {
     fn on_resize(&mut self, window_size: WindowSize);
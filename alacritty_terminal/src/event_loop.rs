@@ -16,6 +16,7 @@ use mio::{self, Events, PollOpt, Ready};
 use mio_extras::channel::{self, Receiver, Sender};
 
 use crate::event::{self, Event, EventListener, WindowSize};
+use crate::recording::Recorder;
 use crate::sync::FairMutex;
 use crate::term::Term;
 use crate::{ansi, thread, tty};
@@ -37,6 +38,12 @@ pub enum Msg {
 
     /// Instruction to resize the PTY.
     Resize(WindowSize),
+
+    /// Start recording the session, replacing any active recording.
+    StartRecording(Recorder),
+
+    /// Stop recording the session.
+    StopRecording,
 }
 
 /// The main event!.. loop.
@@ -52,6 +59,7 @@ pub struct EventLoop<T: tty::EventedPty, U: EventListener> {
     event_proxy: U,
     hold: bool,
     ref_test: bool,
+    recorder: Option<Recorder>,
 }
 
 /// Helper type which tracks how much of a buffer has been written.
@@ -168,6 +176,7 @@ where
             event_proxy,
             hold,
             ref_test,
+            recorder: None,
         }
     }
 
@@ -181,8 +190,20 @@ where
     fn drain_recv_channel(&mut self, state: &mut State) -> bool {
         while let Ok(msg) = self.rx.try_recv() {
             match msg {
-                Msg::Input(input) => state.write_list.push_back(input),
-                Msg::Resize(window_size) => self.pty.on_resize(window_size),
+                Msg::Input(input) => {
+                    Self::record(&mut self.recorder, &self.event_proxy, |recorder| {
+                        recorder.input(&input)
+                    });
+                    state.write_list.push_back(input);
+                },
+                Msg::Resize(window_size) => {
+                    Self::record(&mut self.recorder, &self.event_proxy, |recorder| {
+                        recorder.resize(window_size)
+                    });
+                    self.pty.on_resize(window_size);
+                },
+                Msg::StartRecording(recorder) => self.recorder = Some(recorder),
+                Msg::StopRecording => self.recorder = None,
                 Msg::Shutdown => return false,
             }
         }
@@ -190,6 +211,20 @@ where
         true
     }
 
+    /// Write an event to the active session recording.
+    ///
+    /// The recording is stopped if writing fails.
+    fn record<F>(recorder: &mut Option<Recorder>, event_proxy: &U, f: F)
+    where
+        F: FnOnce(&mut Recorder) -> io::Result<()>,
+    {
+        if let Some(Err(err)) = recorder.as_mut().map(f) {
+            error!("Unable to write session recording: {}", err);
+            event_proxy.send_event(Event::RecordingStopped);
+            *recorder = None;
+        }
+    }
+
     /// Returns a `bool` indicating whether or not the event loop should continue running.
     #[inline]
     fn channel_event(&mut self, token: mio::Token, state: &mut State) -> bool {
@@ -254,6 +289,11 @@ where
                 writer.write_all(&buf[..unprocessed]).unwrap();
             }
 
+            // Write a copy of the bytes to the session recording.
+            Self::record(&mut self.recorder, &self.event_proxy, |recorder| {
+                recorder.output(&buf[..unprocessed])
+            });
+
             // Parse the incoming bytes.
             for byte in &buf[..unprocessed] {
                 state.parser.advance(&mut **terminal, *byte);
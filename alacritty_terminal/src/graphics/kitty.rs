@@ -0,0 +1,789 @@
+//! Kitty graphics protocol.
+//!
+//! Commands are sent through `APC G <control data> ; <payload> ST` escape sequences, with the
+//! control data being a comma-separated list of `key=value` pairs. Images are transmitted
+//! directly as base64 encoded payload, optionally zlib compressed and split into multiple chunks.
+//!
+//! See <https://sw.kovidgoyal.net/kitty/graphics-protocol/> for the full specification.
+
+use std::collections::HashMap;
+use std::fmt::{self, Display, Formatter};
+use std::io::{Cursor, Read};
+use std::ops::Range;
+use std::sync::Arc;
+use std::{cmp, str};
+
+use flate2::read::ZlibDecoder;
+
+use crate::graphics::{GraphicData, GraphicRect, KittyPlacement, MAX_GRAPHIC_DIMENSIONS};
+use crate::grid::{Dimensions, Grid};
+use crate::index::Line;
+use crate::term::cell::Cell;
+
+/// Maximum size of all stored images in bytes, before the oldest images are evicted.
+const STORAGE_QUOTA: usize = 320 * 1024 * 1024;
+
+/// Maximum size of the decoded pixels of a single image.
+const MAX_IMAGE_SIZE: usize = MAX_GRAPHIC_DIMENSIONS * MAX_GRAPHIC_DIMENSIONS * 4;
+
+/// Maximum size of a base64 encoded transmission, including all its chunks.
+const MAX_TRANSMISSION_SIZE: usize = MAX_IMAGE_SIZE / 3 * 4 + 4;
+
+/// Minimum number of indexed placements before the index is rebuilt from the grid.
+const MIN_REINDEX_LEN: usize = 256;
+
+/// Action performed by a graphics command.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum Action {
+    /// Transmit image data (`a=t`).
+    #[default]
+    Transmit,
+    /// Transmit image data and display it (`a=T`).
+    TransmitAndDisplay,
+    /// Check if image data could be loaded, without storing it (`a=q`).
+    Query,
+    /// Display a previously transmitted image (`a=p`).
+    Display,
+    /// Delete images or placements (`a=d`).
+    Delete,
+    /// Animation control, which is not supported (`a=f`, `a=a` and `a=c`).
+    Animation,
+}
+
+/// Parsed graphics command.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GraphicsCommand {
+    /// Action to perform (`a`).
+    pub action: Action,
+
+    /// Response suppression, `1` suppresses `OK` responses and `2` suppresses errors too (`q`).
+    pub quiet: u8,
+
+    /// Pixel format, `24` for RGB, `32` for RGBA and `100` for PNG (`f`).
+    pub format: u32,
+
+    /// Transmission medium, only direct transmission `d` is supported (`t`).
+    pub medium: u8,
+
+    /// Compression of the image data, `z` for zlib (`o`).
+    pub compression: Option<u8>,
+
+    /// Whether more chunks of this transmission will follow (`m`).
+    pub more: bool,
+
+    /// Image ID (`i`).
+    pub image_id: u32,
+
+    /// Image number (`I`).
+    pub image_number: u32,
+
+    /// Placement ID (`p`).
+    pub placement_id: u32,
+
+    /// Width of the raw pixel data (`s`).
+    pub width: u32,
+
+    /// Height of the raw pixel data (`v`).
+    pub height: u32,
+
+    /// Left edge of the displayed image section, or the deleted column (`x`).
+    pub x: u32,
+
+    /// Top edge of the displayed image section, or the deleted line (`y`).
+    pub y: u32,
+
+    /// Width of the displayed image section (`w`).
+    pub source_width: u32,
+
+    /// Height of the displayed image section (`h`).
+    pub source_height: u32,
+
+    /// Horizontal offset inside the first cell, in pixels (`X`).
+    pub cell_x_offset: u32,
+
+    /// Vertical offset inside the first cell, in pixels (`Y`).
+    pub cell_y_offset: u32,
+
+    /// Number of columns the image is scaled to (`c`).
+    pub columns: u32,
+
+    /// Number of lines the image is scaled to (`r`).
+    pub rows: u32,
+
+    /// Cursor movement policy, `1` keeps the cursor in place (`C`).
+    pub cursor_movement: u32,
+
+    /// Stacking order relative to text and other images (`z`).
+    pub z_index: i32,
+
+    /// Whether a virtual placement for unicode placeholders should be created (`U`).
+    pub unicode_placeholder: bool,
+
+    /// Targets of a delete command (`d`).
+    pub delete: u8,
+
+    /// Base64 encoded image data.
+    pub payload: Vec<u8>,
+}
+
+impl Default for GraphicsCommand {
+    fn default() -> Self {
+        Self {
+            format: 32,
+            medium: b'd',
+            delete: b'a',
+            action: Default::default(),
+            quiet: Default::default(),
+            compression: Default::default(),
+            more: Default::default(),
+            image_id: Default::default(),
+            image_number: Default::default(),
+            placement_id: Default::default(),
+            width: Default::default(),
+            height: Default::default(),
+            x: Default::default(),
+            y: Default::default(),
+            source_width: Default::default(),
+            source_height: Default::default(),
+            cell_x_offset: Default::default(),
+            cell_y_offset: Default::default(),
+            columns: Default::default(),
+            rows: Default::default(),
+            cursor_movement: Default::default(),
+            z_index: Default::default(),
+            unicode_placeholder: Default::default(),
+            payload: Default::default(),
+        }
+    }
+}
+
+impl GraphicsCommand {
+    /// Parse the content of an APC sequence following its `G` prefix.
+    pub fn parse(data: &[u8]) -> Option<Self> {
+        let (control, payload) = match data.iter().position(|&b| b == b';') {
+            Some(index) => (&data[..index], &data[index + 1..]),
+            None => (data, &[][..]),
+        };
+
+        let mut command = Self { payload: payload.to_vec(), ..Self::default() };
+
+        for pair in control.split(|&b| b == b',').filter(|pair| !pair.is_empty()) {
+            let (key, value) = match pair {
+                [key, b'=', value @ ..] if !value.is_empty() => (*key, value),
+                _ => return None,
+            };
+
+            let number = || str::from_utf8(value).ok()?.parse::<u32>().ok();
+            let character = || match value {
+                [character] => Some(*character),
+                _ => None,
+            };
+
+            match key {
+                b'a' => {
+                    command.action = match character()? {
+                        b't' => Action::Transmit,
+                        b'T' => Action::TransmitAndDisplay,
+                        b'q' => Action::Query,
+                        b'p' => Action::Display,
+                        b'd' => Action::Delete,
+                        b'f' | b'a' | b'c' => Action::Animation,
+                        _ => return None,
+                    }
+                },
+                b'q' => command.quiet = number()? as u8,
+                b'f' => command.format = number()?,
+                b't' => command.medium = character()?,
+                b'o' => command.compression = Some(character()?),
+                b'm' => command.more = number()? == 1,
+                b'i' => command.image_id = number()?,
+                b'I' => command.image_number = number()?,
+                b'p' => command.placement_id = number()?,
+                b's' => command.width = number()?,
+                b'v' => command.height = number()?,
+                b'x' => command.x = number()?,
+                b'y' => command.y = number()?,
+                b'w' => command.source_width = number()?,
+                b'h' => command.source_height = number()?,
+                b'X' => command.cell_x_offset = number()?,
+                b'Y' => command.cell_y_offset = number()?,
+                b'c' => command.columns = number()?,
+                b'r' => command.rows = number()?,
+                b'C' => command.cursor_movement = number()?,
+                b'z' => command.z_index = str::from_utf8(value).ok()?.parse().ok()?,
+                b'U' => command.unicode_placeholder = number()? == 1,
+                b'd' => command.delete = character()?,
+                // Ignore unknown keys for forward compatibility.
+                _ => (),
+            }
+        }
+
+        Some(command)
+    }
+
+    /// Response to the client, if it requested one.
+    pub fn response(&self, image_id: u32, result: Result<(), Error>) -> Option<String> {
+        // Only commands with an image ID or number receive a response.
+        if self.image_id == 0 && self.image_number == 0 {
+            return None;
+        }
+
+        let message = match result {
+            Ok(()) if self.quiet == 0 => String::from("OK"),
+            Err(err) if self.quiet < 2 => err.to_string(),
+            _ => return None,
+        };
+
+        let mut response = format!("\x1b_Gi={image_id}");
+        if self.image_number != 0 {
+            response.push_str(&format!(",I={}", self.image_number));
+        }
+        if self.placement_id != 0 {
+            response.push_str(&format!(",p={}", self.placement_id));
+        }
+        response.push_str(&format!(";{message}\x1b\\"));
+
+        Some(response)
+    }
+
+    /// Decode the image data of a transmission.
+    pub fn load(&self) -> Result<GraphicData, Error> {
+        if self.medium != b'd' {
+            return Err(Error::InvalidArgument("Unsupported transmission medium"));
+        }
+
+        let data = base64::decode(&self.payload)
+            .map_err(|_| Error::InvalidArgument("Invalid base64 image data"))?;
+
+        let data = match self.compression {
+            Some(b'z') => {
+                let mut decompressed = Vec::new();
+                ZlibDecoder::new(data.as_slice())
+                    .take(MAX_IMAGE_SIZE as u64 + 1)
+                    .read_to_end(&mut decompressed)
+                    .map_err(|_| Error::InvalidArgument("Invalid zlib image data"))?;
+                decompressed
+            },
+            Some(_) => return Err(Error::InvalidArgument("Unsupported compression")),
+            None => data,
+        };
+
+        match self.format {
+            24 => self.load_raw(&data, 3),
+            32 => self.load_raw(&data, 4),
+            100 => load_png(&data),
+            _ => Err(Error::InvalidArgument("Unsupported image format")),
+        }
+    }
+
+    /// Section of the image which should be displayed, clamped to its dimensions.
+    pub fn source_rect(&self, data: &GraphicData) -> GraphicRect {
+        let width = data.width as u32;
+        let height = data.height as u32;
+
+        let x = cmp::min(self.x, width);
+        let y = cmp::min(self.y, height);
+        let source_width = match self.source_width {
+            0 => width - x,
+            source_width => cmp::min(source_width, width - x),
+        };
+        let source_height = match self.source_height {
+            0 => height - y,
+            source_height => cmp::min(source_height, height - y),
+        };
+
+        GraphicRect { x, y, width: source_width, height: source_height }
+    }
+
+    /// Load raw RGB or RGBA pixels.
+    fn load_raw(&self, data: &[u8], bytes_per_pixel: usize) -> Result<GraphicData, Error> {
+        let width = self.width as usize;
+        let height = self.height as usize;
+        if width == 0 || height == 0 {
+            return Err(Error::InvalidArgument("Missing image dimensions"));
+        } else if width > MAX_GRAPHIC_DIMENSIONS || height > MAX_GRAPHIC_DIMENSIONS {
+            return Err(Error::InvalidArgument("Image dimensions are too large"));
+        }
+
+        let size = width * height * bytes_per_pixel;
+        if data.len() < size {
+            return Err(Error::NoData("Insufficient image data"));
+        }
+
+        let pixels = match bytes_per_pixel {
+            3 => {
+                data[..size].chunks_exact(3).flat_map(|rgb| [rgb[0], rgb[1], rgb[2], 255]).collect()
+            },
+            _ => data[..size].to_vec(),
+        };
+
+        Ok(GraphicData::new(width, height, pixels))
+    }
+}
+
+/// Decode a PNG image into RGBA pixels.
+fn load_png(data: &[u8]) -> Result<GraphicData, Error> {
+    let invalid = |_| Error::InvalidArgument("Invalid PNG image data");
+
+    let limits = png::Limits { bytes: MAX_IMAGE_SIZE };
+    let mut decoder = png::Decoder::new_with_limits(Cursor::new(data), limits);
+    decoder.set_transformations(png::Transformations::EXPAND | png::Transformations::STRIP_16);
+
+    let mut reader = decoder.read_info().map_err(invalid)?;
+    let mut buffer = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut buffer).map_err(invalid)?;
+
+    let width = info.width as usize;
+    let height = info.height as usize;
+    if width > MAX_GRAPHIC_DIMENSIONS || height > MAX_GRAPHIC_DIMENSIONS {
+        return Err(Error::InvalidArgument("Image dimensions are too large"));
+    }
+
+    let buffer = &buffer[..info.line_size * height];
+    let pixels = match info.color_type {
+        png::ColorType::Rgba => buffer.to_vec(),
+        png::ColorType::Rgb => {
+            buffer.chunks_exact(3).flat_map(|p| [p[0], p[1], p[2], 255]).collect()
+        },
+        png::ColorType::GrayscaleAlpha => {
+            buffer.chunks_exact(2).flat_map(|p| [p[0], p[0], p[0], p[1]]).collect()
+        },
+        png::ColorType::Grayscale => buffer.iter().flat_map(|&p| [p, p, p, 255]).collect(),
+        png::ColorType::Indexed => return Err(Error::InvalidArgument("Invalid PNG image data")),
+    };
+
+    Ok(GraphicData::new(width, height, pixels))
+}
+
+/// Errors reported back to the client.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Error {
+    /// Invalid or unsupported command parameters.
+    InvalidArgument(&'static str),
+
+    /// Missing or incomplete image data.
+    NoData(&'static str),
+
+    /// Referenced image does not exist.
+    NotFound(&'static str),
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::InvalidArgument(message) => write!(f, "EINVAL:{message}"),
+            Error::NoData(message) => write!(f, "ENODATA:{message}"),
+            Error::NotFound(message) => write!(f, "ENOENT:{message}"),
+        }
+    }
+}
+
+/// Transmitted image.
+#[derive(Debug)]
+struct Image {
+    /// Decoded pixels.
+    data: Arc<GraphicData>,
+
+    /// Image number assigned by the client.
+    number: u32,
+
+    /// Transmission order, used to find the newest image for a number and evict the oldest.
+    generation: u64,
+}
+
+/// Storage for all transmitted images.
+#[derive(Debug, Default)]
+pub struct ImageStore {
+    /// Images by their ID.
+    images: HashMap<u32, Image>,
+
+    /// Chunked transmission waiting for its remaining data.
+    pending: Option<GraphicsCommand>,
+
+    /// Total size of all stored images.
+    size: usize,
+
+    /// Counter for the transmission order.
+    generation: u64,
+
+    /// Last image ID which was assigned automatically.
+    last_image_id: u32,
+
+    /// Counter for unique placement IDs.
+    last_placement_id: u64,
+}
+
+impl ImageStore {
+    /// Combine chunked transmissions into a single command.
+    ///
+    /// Returns the command once all its chunks have been received.
+    pub fn feed(&mut self, mut command: GraphicsCommand) -> Option<GraphicsCommand> {
+        if let Some(mut pending) = self.pending.take() {
+            // Only the first chunk contains the control data.
+            pending.payload.append(&mut command.payload);
+            pending.more = command.more;
+            command = pending;
+        }
+
+        if command.payload.len() > MAX_TRANSMISSION_SIZE {
+            command.more = false;
+            command.payload.clear();
+        }
+
+        if command.more {
+            self.pending = Some(command);
+            None
+        } else {
+            Some(command)
+        }
+    }
+
+    /// Store an image, replacing any image with the same ID.
+    pub fn insert(&mut self, id: u32, number: u32, data: GraphicData) -> Arc<GraphicData> {
+        self.remove(id);
+
+        self.generation += 1;
+        self.size += data.pixels.len();
+
+        let data = Arc::new(data);
+        let image = Image { data: data.clone(), number, generation: self.generation };
+        self.images.insert(id, image);
+
+        // Evict the oldest images once the quota is exceeded.
+        while self.size > STORAGE_QUOTA && self.images.len() > 1 {
+            let oldest = self.images.iter().min_by_key(|(_, image)| image.generation);
+            let oldest = *oldest.unwrap().0;
+            self.remove(oldest);
+        }
+
+        data
+    }
+
+    /// Remove an image from the store.
+    pub fn remove(&mut self, id: u32) {
+        if let Some(image) = self.images.remove(&id) {
+            self.size -= image.data.pixels.len();
+        }
+    }
+
+    /// Remove all images for which `f` returns `false`.
+    pub fn retain<F: FnMut(u32) -> bool>(&mut self, mut f: F) {
+        let size = &mut self.size;
+        self.images.retain(|id, image| {
+            let retain = f(*id);
+            if !retain {
+                *size -= image.data.pixels.len();
+            }
+            retain
+        });
+    }
+
+    /// Remove all images which aren't displayed in any placement.
+    pub fn remove_unused(&mut self, ids: &[u32]) {
+        for id in ids {
+            let unused =
+                self.images.get(id).map_or(false, |image| Arc::strong_count(&image.data) == 1);
+            if unused {
+                self.remove(*id);
+            }
+        }
+    }
+
+    /// Get an image by its ID.
+    pub fn get(&self, id: u32) -> Option<Arc<GraphicData>> {
+        self.images.get(&id).map(|image| image.data.clone())
+    }
+
+    /// Get the ID of the newest image with the specified number.
+    pub fn find_number(&self, number: u32) -> Option<u32> {
+        self.images
+            .iter()
+            .filter(|(_, image)| image.number == number)
+            .max_by_key(|(_, image)| image.generation)
+            .map(|(id, _)| *id)
+    }
+
+    /// Check if an image with the specified ID exists.
+    pub fn contains(&self, id: u32) -> bool {
+        self.images.contains_key(&id)
+    }
+
+    /// Pick an unused image ID.
+    ///
+    /// Automatic IDs are assigned from the top of the ID space, to avoid collisions with the IDs
+    /// picked by clients.
+    pub fn next_image_id(&mut self) -> u32 {
+        loop {
+            self.last_image_id = self.last_image_id.wrapping_sub(1);
+            if self.last_image_id != 0 && !self.contains(self.last_image_id) {
+                return self.last_image_id;
+            }
+        }
+    }
+
+    /// Get a new unique placement ID.
+    pub fn next_placement_id(&mut self) -> u64 {
+        self.last_placement_id += 1;
+        self.last_placement_id
+    }
+}
+
+/// Lines covered by the placements in a grid.
+///
+/// This allows finding the cells of a placement without searching the entire grid. Lines are
+/// moved together with the grid content, like the vi mode marks. Since they only limit the search
+/// for a placement, placements which can't be tracked reliably, like after a resize, are searched
+/// for in the entire grid instead.
+#[derive(Debug, Default)]
+pub struct PlacementIndex {
+    /// Placements by their unique ID, with the lines they cover if they're known.
+    placements: HashMap<u64, (KittyPlacement, Option<Range<Line>>)>,
+
+    /// Number of placements after the index was last rebuilt.
+    reindex_len: usize,
+}
+
+impl PlacementIndex {
+    /// Add a placement covering the specified lines.
+    pub fn insert(&mut self, placement: KittyPlacement, lines: Range<Line>) {
+        self.placements.insert(placement.id, (placement, Some(lines)));
+    }
+
+    /// Extend the lines of a placement to include `line`, adding it if necessary.
+    pub fn extend(&mut self, placement: KittyPlacement, line: Line) {
+        let (_, lines) = self.placements.entry(placement.id).or_insert((placement, None));
+        *lines = match lines.take() {
+            Some(lines) => Some(cmp::min(lines.start, line)..cmp::max(lines.end, line + 1)),
+            None => Some(line..line + 1),
+        };
+    }
+
+    /// Check if the index should be rebuilt from the grid.
+    ///
+    /// Placements whose cells were overwritten are only removed from the index once it's rebuilt,
+    /// so this must be done periodically to keep the index from growing indefinitely.
+    pub fn needs_reindex(&self) -> bool {
+        self.placements.len() > cmp::max(self.reindex_len * 2, MIN_REINDEX_LEN)
+    }
+
+    /// Mark the index as rebuilt.
+    pub fn reindexed(&mut self) {
+        self.reindex_len = self.placements.len();
+    }
+
+    /// Remove all placements matching `predicate`.
+    ///
+    /// Returns the removed placements with the lines they cover, if they're known.
+    pub fn remove<F>(&mut self, mut predicate: F) -> Vec<(KittyPlacement, Option<Range<Line>>)>
+    where
+        F: FnMut(&KittyPlacement) -> bool,
+    {
+        let mut removed = Vec::new();
+        self.placements.retain(|_, (placement, lines)| {
+            let matches = predicate(placement);
+            if matches {
+                removed.push((*placement, lines.take()));
+            }
+            !matches
+        });
+        removed
+    }
+
+    /// Move placements within `range` by `delta` lines upwards.
+    ///
+    /// This follows the rotation of the grid's scrolling region. Placements which were rotated out
+    /// of the region or the grid are removed, since their cells have been cleared.
+    pub fn rotate(&mut self, grid: &Grid<Cell>, range: &Range<Line>, delta: i32) {
+        let topmost_line = grid.topmost_line() + grid.compressed_lines();
+        let in_region = |line: Line| (line >= range.start || range.start == 0) && line < range.end;
+        self.placements.retain(|_, (_, lines)| {
+            let known_lines = match lines {
+                Some(known_lines) => known_lines,
+                None => return true,
+            };
+
+            match (in_region(known_lines.start), in_region(known_lines.end - 1)) {
+                (true, true) => {
+                    known_lines.start -= delta;
+                    known_lines.end -= delta;
+
+                    // Drop placements which have left the scrolling region entirely.
+                    if (known_lines.end <= range.start && range.start != 0)
+                        || known_lines.start >= range.end
+                    {
+                        return false;
+                    }
+                },
+                // Placements reaching across the entire region are split up.
+                (false, false)
+                    if known_lines.start < range.start && known_lines.end > range.end =>
+                {
+                    *lines = None
+                },
+                (false, false) => (),
+                // Placements crossing one of the region's boundaries are split up.
+                _ => *lines = None,
+            }
+
+            lines.as_ref().map_or(true, |lines| lines.end > topmost_line)
+        });
+    }
+
+    /// Stop tracking the lines of all placements.
+    ///
+    /// This should be called whenever the grid content is moved in ways which can't be followed.
+    pub fn invalidate(&mut self) {
+        for (_, lines) in self.placements.values_mut() {
+            *lines = None;
+        }
+    }
+
+    /// Remove placements above the topmost line of the grid.
+    pub fn truncate(&mut self, grid: &Grid<Cell>) {
+        let topmost_line = grid.topmost_line() + grid.compressed_lines();
+        self.placements
+            .retain(|_, (_, lines)| lines.as_ref().map_or(true, |lines| lines.end > topmost_line));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_command() {
+        let command = GraphicsCommand::parse(b"a=T,f=24,s=2,v=1,i=31,z=-5,q=1;AAAA").unwrap();
+
+        assert_eq!(command.action, Action::TransmitAndDisplay);
+        assert_eq!(command.format, 24);
+        assert_eq!((command.width, command.height), (2, 1));
+        assert_eq!(command.image_id, 31);
+        assert_eq!(command.z_index, -5);
+        assert_eq!(command.quiet, 1);
+        assert_eq!(command.payload, b"AAAA");
+
+        assert!(GraphicsCommand::parse(b"a=x").is_none());
+        assert!(GraphicsCommand::parse(b"i=-1").is_none());
+        assert!(GraphicsCommand::parse(b"i").is_none());
+        assert_eq!(GraphicsCommand::parse(b"").unwrap(), GraphicsCommand::default());
+    }
+
+    #[test]
+    fn load_rgb() {
+        // Two RGB pixels, red and green.
+        let payload = base64::encode([255, 0, 0, 0, 255, 0]);
+        let command = format!("f=24,s=2,v=1;{payload}");
+        let data = GraphicsCommand::parse(command.as_bytes()).unwrap().load().unwrap();
+
+        assert_eq!((data.width, data.height), (2, 1));
+        assert_eq!(data.pixels, [255, 0, 0, 255, 0, 255, 0, 255]);
+
+        let command = GraphicsCommand::parse(b"f=24,s=2,v=2;AAAA").unwrap();
+        assert_eq!(command.load(), Err(Error::NoData("Insufficient image data")));
+    }
+
+    #[test]
+    fn load_compressed() {
+        let mut encoder = flate2::write::ZlibEncoder::new(Vec::new(), flate2::Compression::fast());
+        std::io::Write::write_all(&mut encoder, &[1, 2, 3, 4]).unwrap();
+        let payload = base64::encode(encoder.finish().unwrap());
+
+        let command = format!("s=1,v=1,o=z;{payload}");
+        let data = GraphicsCommand::parse(command.as_bytes()).unwrap().load().unwrap();
+        assert_eq!(data.pixels, [1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn load_png() {
+        let mut png = Vec::new();
+        let mut encoder = png::Encoder::new(&mut png, 1, 2);
+        encoder.set_color(png::ColorType::Grayscale);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder.write_header().unwrap().write_image_data(&[0, 255]).unwrap();
+        let payload = base64::encode(png);
+
+        let command = format!("f=100;{payload}");
+        let data = GraphicsCommand::parse(command.as_bytes()).unwrap().load().unwrap();
+        assert_eq!((data.width, data.height), (1, 2));
+        assert_eq!(data.pixels, [0, 0, 0, 255, 255, 255, 255, 255]);
+    }
+
+    #[test]
+    fn chunked_transmission() {
+        let mut store = ImageStore::default();
+
+        let first = GraphicsCommand::parse(b"a=T,i=1,m=1;AAAA").unwrap();
+        assert!(store.feed(first).is_none());
+
+        let last = GraphicsCommand::parse(b"m=0;BBBB").unwrap();
+        let command = store.feed(last).unwrap();
+        assert_eq!(command.action, Action::TransmitAndDisplay);
+        assert_eq!(command.image_id, 1);
+        assert_eq!(command.payload, b"AAAABBBB");
+    }
+
+    #[test]
+    fn responses() {
+        let command = GraphicsCommand::parse(b"i=3,p=7").unwrap();
+        assert_eq!(command.response(3, Ok(())).unwrap(), "\x1b_Gi=3,p=7;OK\x1b\\");
+
+        let command = GraphicsCommand::parse(b"I=4").unwrap();
+        let response = command.response(9, Err(Error::NotFound("Image not found")));
+        assert_eq!(response.unwrap(), "\x1b_Gi=9,I=4;ENOENT:Image not found\x1b\\");
+
+        let command = GraphicsCommand::parse(b"i=3,q=1").unwrap();
+        assert!(command.response(3, Ok(())).is_none());
+        assert!(command.response(3, Err(Error::NoData(""))).is_some());
+
+        let command = GraphicsCommand::parse(b"").unwrap();
+        assert!(command.response(0, Ok(())).is_none());
+    }
+
+    #[test]
+    fn image_store() {
+        let mut store = ImageStore::default();
+        let data = || GraphicData::new(1, 1, vec![0; 4]);
+
+        store.insert(1, 5, data());
+        store.insert(2, 5, data());
+        assert_eq!(store.find_number(5), Some(2));
+
+        let id = store.next_image_id();
+        assert_eq!(id, u32::MAX);
+        store.insert(id, 0, data());
+        assert_ne!(store.next_image_id(), id);
+
+        // Referenced images are kept.
+        let _placed = store.get(1).unwrap();
+        store.remove_unused(&[1, 2]);
+        assert!(store.contains(1));
+        assert!(!store.contains(2));
+    }
+
+    #[test]
+    fn placement_index() {
+        let mut grid = Grid::<Cell>::new(5, 1, 2);
+        let placement = |id| KittyPlacement { id, image_id: 1, placement_id: 0 };
+
+        let mut index = PlacementIndex::default();
+        index.insert(placement(1), Line(0)..Line(2));
+        index.insert(placement(2), Line(3)..Line(5));
+
+        // Placements crossing the scrolling region's boundary are no longer tracked.
+        let region = Line(1)..Line(5);
+        grid.scroll_up(&region, 1);
+        index.rotate(&grid, &region, 1);
+        let mut placements = index.remove(|_| true);
+        placements.sort_by_key(|(placement, _)| placement.id);
+        assert_eq!(placements, [(placement(1), None), (placement(2), Some(Line(2)..Line(4)))]);
+
+        // Placements are dropped once they're rotated out of the history.
+        index.insert(placement(3), Line(0)..Line(1));
+        let region = Line(0)..Line(5);
+        for _ in 0..3 {
+            grid.scroll_up(&region, 1);
+            index.rotate(&grid, &region, 1);
+        }
+        assert!(index.remove(|_| true).is_empty());
+    }
+}
@@ -0,0 +1,197 @@
+//! Inline graphics.
+
+use std::fmt::{self, Debug, Formatter};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+pub mod kitty;
+pub mod sixel;
+
+/// Maximum width and height of a graphic, in pixels.
+pub const MAX_GRAPHIC_DIMENSIONS: usize = 4096;
+
+/// Counter for the unique graphic identifiers.
+static GRAPHIC_ID: AtomicU64 = AtomicU64::new(0);
+
+/// Unique identifier for the pixel data of a graphic.
+///
+/// This is used by the renderer to keep track of the uploaded textures.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct GraphicId(u64);
+
+impl GraphicId {
+    fn next() -> Self {
+        Self(GRAPHIC_ID.fetch_add(1, Ordering::Relaxed))
+    }
+}
+
+/// Decoded graphic pixels.
+#[derive(Clone, PartialEq, Eq)]
+pub struct GraphicData {
+    /// Unique identifier of this graphic.
+    pub id: GraphicId,
+
+    /// Width in pixels.
+    pub width: usize,
+
+    /// Height in pixels.
+    pub height: usize,
+
+    /// Non-premultiplied RGBA pixels, with 4 bytes per pixel.
+    pub pixels: Vec<u8>,
+}
+
+impl GraphicData {
+    pub fn new(width: usize, height: usize, pixels: Vec<u8>) -> Self {
+        debug_assert_eq!(pixels.len(), width * height * 4);
+        Self { id: GraphicId::next(), width, height, pixels }
+    }
+}
+
+impl Debug for GraphicData {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("GraphicData")
+            .field("id", &self.id)
+            .field("width", &self.width)
+            .field("height", &self.height)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Rectangle inside a graphic, in pixels.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct GraphicRect {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// Kitty graphics protocol placement a graphic is part of.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct KittyPlacement {
+    /// Unique identifier of the placement.
+    pub id: u64,
+
+    /// Client identifier of the placed image.
+    pub image_id: u32,
+
+    /// Client identifier of the placement, zero if it has none.
+    pub placement_id: u32,
+}
+
+/// Graphic placed in the grid.
+#[derive(Debug, Clone)]
+pub struct GraphicPlacement {
+    /// Pixels of the graphic.
+    pub texture: Arc<GraphicData>,
+
+    /// Section of the texture which is displayed.
+    pub source: GraphicRect,
+
+    /// Horizontal offset from the top-left corner of the first cell, in pixels.
+    pub offset_x: u32,
+
+    /// Vertical offset from the top-left corner of the first cell, in pixels.
+    pub offset_y: u32,
+
+    /// Displayed width, in pixels.
+    pub width: u32,
+
+    /// Displayed height, in pixels.
+    pub height: u32,
+
+    /// Stacking order, graphics with a negative z-index are drawn below the text.
+    pub z_index: i32,
+
+    /// Kitty placement identifiers.
+    pub kitty: Option<KittyPlacement>,
+}
+
+impl GraphicPlacement {
+    /// Place the entire graphic at its original size.
+    pub fn new(texture: Arc<GraphicData>) -> Self {
+        let width = texture.width as u32;
+        let height = texture.height as u32;
+        Self {
+            source: GraphicRect { x: 0, y: 0, width, height },
+            texture,
+            width,
+            height,
+            offset_x: 0,
+            offset_y: 0,
+            z_index: 0,
+            kitty: None,
+        }
+    }
+
+    /// Number of columns and lines covered by the placement.
+    pub fn cells(&self, cell_width: usize, cell_height: usize) -> (usize, usize) {
+        let width = self.offset_x as usize + self.width as usize;
+        let height = self.offset_y as usize + self.height as usize;
+        ((width + cell_width - 1) / cell_width, (height + cell_height - 1) / cell_height)
+    }
+
+    /// Section of the placement covering the cell at the specified offset from the first cell.
+    pub fn cell(
+        &self,
+        column: usize,
+        line: usize,
+        cell_width: usize,
+        cell_height: usize,
+    ) -> GraphicCell {
+        GraphicCell {
+            texture: self.texture.clone(),
+            source: self.source,
+            x: (column * cell_width) as i32 - self.offset_x as i32,
+            y: (line * cell_height) as i32 - self.offset_y as i32,
+            width: self.width,
+            height: self.height,
+            z_index: self.z_index,
+            kitty: self.kitty,
+        }
+    }
+}
+
+/// Section of a graphic covering a single grid cell.
+#[derive(Debug, Clone)]
+pub struct GraphicCell {
+    /// Pixels of the graphic.
+    pub texture: Arc<GraphicData>,
+
+    /// Section of the texture which is displayed.
+    pub source: GraphicRect,
+
+    /// Horizontal position of the cell relative to the graphic's top-left corner, in pixels.
+    pub x: i32,
+
+    /// Vertical position of the cell relative to the graphic's top-left corner, in pixels.
+    pub y: i32,
+
+    /// Displayed width of the entire graphic, in pixels.
+    pub width: u32,
+
+    /// Displayed height of the entire graphic, in pixels.
+    pub height: u32,
+
+    /// Stacking order, graphics with a negative z-index are drawn below the text.
+    pub z_index: i32,
+
+    /// Kitty placement identifiers.
+    pub kitty: Option<KittyPlacement>,
+}
+
+impl PartialEq for GraphicCell {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.texture, &other.texture)
+            && self.source == other.source
+            && self.x == other.x
+            && self.y == other.y
+            && self.width == other.width
+            && self.height == other.height
+            && self.z_index == other.z_index
+            && self.kitty == other.kitty
+    }
+}
+
+impl Eq for GraphicCell {}
@@ -0,0 +1,384 @@
+//! Sixel image decoding.
+//!
+//! Sixel data is sent through a `DCS P1 ; P2 ; P3 q <data> ST` escape sequence. Every data
+//! character describes a column of six vertical pixels, which are drawn in the active color at
+//! the current position. The supported control functions are:
+//!
+//!  - `" Pan ; Pad ; Ph ; Pv` raster attributes, defining the minimum image size.
+//!  - `# Pc ; Pu ; Px ; Py ; Pz` color introducer, to select or define a color register.
+//!  - `! Pn <data>` repeat introducer, drawing the data character `Pn` times.
+//!  - `$` graphics carriage return.
+//!  - `-` graphics new line.
+
+use std::cmp;
+use std::mem;
+
+use crate::graphics::{GraphicData, MAX_GRAPHIC_DIMENSIONS};
+
+/// Number of available color registers.
+const PALETTE_SIZE: usize = 256;
+
+/// Maximum number of parameters for a single control function.
+const MAX_PARAMS: usize = 5;
+
+/// RGBA pixel value.
+type Pixel = [u8; 4];
+
+/// Fully transparent pixel, used for positions without any sixel data.
+const TRANSPARENT: Pixel = [0, 0, 0, 0];
+
+/// Default VT340 color palette, in RGB percentages.
+const VT340_PALETTE: [[u16; 3]; 16] = [
+    [0, 0, 0],
+    [20, 20, 80],
+    [80, 13, 13],
+    [20, 80, 20],
+    [80, 20, 80],
+    [20, 80, 80],
+    [80, 80, 20],
+    [53, 53, 53],
+    [26, 26, 26],
+    [33, 33, 60],
+    [60, 26, 26],
+    [33, 60, 33],
+    [60, 33, 60],
+    [33, 60, 60],
+    [60, 60, 33],
+    [80, 80, 80],
+];
+
+/// Control function which is currently collecting its parameters.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+enum State {
+    #[default]
+    Ground,
+    Raster,
+    Color,
+    Repeat,
+}
+
+/// Incremental sixel decoder.
+#[derive(Debug)]
+pub struct Parser {
+    /// Pending control function.
+    state: State,
+
+    /// Parameters of the pending control function.
+    params: Vec<u16>,
+
+    /// Color registers.
+    palette: Vec<Pixel>,
+
+    /// Active color register.
+    color: usize,
+
+    /// Horizontal position of the next sixel.
+    x: usize,
+
+    /// Top line of the current sixel band.
+    y: usize,
+
+    /// Image width, in pixels.
+    width: usize,
+
+    /// Image height, in pixels.
+    height: usize,
+
+    /// Pixel rows drawn so far.
+    rows: Vec<Vec<Pixel>>,
+
+    /// Leave pixels without any sixel data transparent.
+    transparent: bool,
+}
+
+impl Parser {
+    /// Create a decoder for the parameters of the sixel DCS sequence.
+    ///
+    /// The second parameter selects if pixels without any sixel data should be transparent,
+    /// rather than using the background color in the first color register.
+    pub fn new(params: &[u16]) -> Self {
+        let mut palette = vec![[0, 0, 0, u8::MAX]; PALETTE_SIZE];
+        for (register, rgb) in palette.iter_mut().zip(VT340_PALETTE) {
+            *register = rgb_from_percent(rgb[0], rgb[1], rgb[2]);
+        }
+
+        Self {
+            palette,
+            transparent: params.get(1) == Some(&1),
+            state: Default::default(),
+            params: Vec::with_capacity(MAX_PARAMS),
+            color: Default::default(),
+            x: Default::default(),
+            y: Default::default(),
+            width: Default::default(),
+            height: Default::default(),
+            rows: Default::default(),
+        }
+    }
+
+    /// Process a byte of sixel data.
+    pub fn advance(&mut self, byte: u8) {
+        if self.state == State::Ground {
+            self.ground(byte);
+            return;
+        }
+
+        match byte {
+            b'0'..=b'9' => {
+                if self.params.is_empty() {
+                    self.params.push(0);
+                }
+
+                let param = self.params.last_mut().unwrap();
+                *param = param.saturating_mul(10).saturating_add((byte - b'0') as u16);
+            },
+            b';' if self.params.len() < MAX_PARAMS => {
+                if self.params.is_empty() {
+                    self.params.push(0);
+                }
+                self.params.push(0);
+            },
+            b';' => (),
+            _ => self.dispatch(byte),
+        }
+    }
+
+    /// Finish decoding, returning the image if anything was drawn.
+    pub fn finish(mut self) -> Option<GraphicData> {
+        // Flush pending raster and color attributes.
+        if self.state != State::Ground {
+            self.dispatch(b'\0');
+        }
+
+        if self.width == 0 || self.height == 0 {
+            return None;
+        }
+
+        let background = if self.transparent { TRANSPARENT } else { self.palette[0] };
+
+        let mut pixels = Vec::with_capacity(self.width * self.height * 4);
+        for y in 0..self.height {
+            let row = self.rows.get(y).map(Vec::as_slice).unwrap_or_default();
+            for x in 0..self.width {
+                let pixel = row.get(x).copied().unwrap_or(TRANSPARENT);
+                let pixel = if pixel == TRANSPARENT { background } else { pixel };
+                pixels.extend_from_slice(&pixel);
+            }
+        }
+
+        Some(GraphicData::new(self.width, self.height, pixels))
+    }
+
+    /// Process a byte outside of any control function.
+    fn ground(&mut self, byte: u8) {
+        match byte {
+            b'"' => self.state = State::Raster,
+            b'#' => self.state = State::Color,
+            b'!' => self.state = State::Repeat,
+            b'$' => self.x = 0,
+            b'-' => {
+                self.x = 0;
+                self.y = self.y.saturating_add(6);
+            },
+            b'?'..=b'~' => self.draw(byte, 1),
+            _ => (),
+        }
+    }
+
+    /// Execute the pending control function, once all its parameters were read.
+    fn dispatch(&mut self, byte: u8) {
+        let state = mem::take(&mut self.state);
+        let params = mem::take(&mut self.params);
+        let param = |index: usize| params.get(index).copied().unwrap_or(0);
+
+        // Data character of the repeat introducer, which must not be drawn again.
+        let mut consumed = false;
+
+        match state {
+            State::Repeat if (b'?'..=b'~').contains(&byte) => {
+                self.draw(byte, cmp::max(param(0), 1) as usize);
+                consumed = true;
+            },
+            State::Raster => {
+                let width = cmp::min(param(2) as usize, MAX_GRAPHIC_DIMENSIONS);
+                let height = cmp::min(param(3) as usize, MAX_GRAPHIC_DIMENSIONS);
+                self.width = cmp::max(self.width, width);
+                self.height = cmp::max(self.height, height);
+            },
+            State::Color => {
+                let register = param(0) as usize % PALETTE_SIZE;
+                if params.len() >= 5 {
+                    let (x, y, z) = (param(2), param(3), param(4));
+                    match param(1) {
+                        1 => self.palette[register] = rgb_from_hls(x, y, z),
+                        2 => self.palette[register] = rgb_from_percent(x, y, z),
+                        _ => (),
+                    }
+                }
+                self.color = register;
+            },
+            State::Repeat | State::Ground => (),
+        }
+
+        // Reuse the parameter allocation.
+        self.params = params;
+        self.params.clear();
+
+        if !consumed {
+            self.ground(byte);
+        }
+    }
+
+    /// Draw a sixel data character `repeat` times at the current position.
+    fn draw(&mut self, byte: u8, repeat: usize) {
+        let bits = byte - b'?';
+        let start = cmp::min(self.x, MAX_GRAPHIC_DIMENSIONS);
+        let end = cmp::min(self.x.saturating_add(repeat), MAX_GRAPHIC_DIMENSIONS);
+
+        for bit in 0..6 {
+            let y = self.y.saturating_add(bit);
+            if bits & (1 << bit) == 0 || y >= MAX_GRAPHIC_DIMENSIONS {
+                continue;
+            }
+
+            if self.rows.len() <= y {
+                self.rows.resize_with(y + 1, Vec::new);
+            }
+
+            let row = &mut self.rows[y];
+            if row.len() < end {
+                row.resize(end, TRANSPARENT);
+            }
+            row[start..end].fill(self.palette[self.color]);
+
+            self.height = cmp::max(self.height, y + 1);
+        }
+
+        self.x = self.x.saturating_add(repeat);
+        self.width = cmp::max(self.width, end);
+    }
+}
+
+/// Convert RGB percentages to an opaque pixel.
+fn rgb_from_percent(r: u16, g: u16, b: u16) -> Pixel {
+    let convert = |value: u16| (cmp::min(value, 100) as u32 * 255 / 100) as u8;
+    [convert(r), convert(g), convert(b), u8::MAX]
+}
+
+/// Convert a DEC HLS color to an opaque pixel.
+///
+/// Unlike the usual HLS color space, DEC places blue at a hue of 0 degrees and red at 120.
+fn rgb_from_hls(hue: u16, lightness: u16, saturation: u16) -> Pixel {
+    let hue = ((hue % 360) as f32 + 240.) % 360. / 360.;
+    let lightness = cmp::min(lightness, 100) as f32 / 100.;
+    let saturation = cmp::min(saturation, 100) as f32 / 100.;
+
+    if saturation == 0. {
+        let value = (lightness * 255.).round() as u8;
+        return [value, value, value, u8::MAX];
+    }
+
+    let q = if lightness < 0.5 {
+        lightness * (1. + saturation)
+    } else {
+        lightness + saturation - lightness * saturation
+    };
+    let p = 2. * lightness - q;
+
+    let channel = |t: f32| {
+        let t = t.rem_euclid(1.);
+        let value = if t < 1. / 6. {
+            p + (q - p) * 6. * t
+        } else if t < 1. / 2. {
+            q
+        } else if t < 2. / 3. {
+            p + (q - p) * (2. / 3. - t) * 6.
+        } else {
+            p
+        };
+        (value * 255.).round() as u8
+    };
+
+    [channel(hue + 1. / 3.), channel(hue), channel(hue - 1. / 3.), u8::MAX]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decode(params: &[u16], data: &[u8]) -> Option<GraphicData> {
+        let mut parser = Parser::new(params);
+        for &byte in data {
+            parser.advance(byte);
+        }
+        parser.finish()
+    }
+
+    fn pixel(graphic: &GraphicData, x: usize, y: usize) -> Pixel {
+        let index = (y * graphic.width + x) * 4;
+        graphic.pixels[index..index + 4].try_into().unwrap()
+    }
+
+    #[test]
+    fn single_sixel() {
+        let graphic = decode(&[0, 1], b"#2;2;100;0;0~").unwrap();
+
+        assert_eq!(graphic.width, 1);
+        assert_eq!(graphic.height, 6);
+        for y in 0..6 {
+            assert_eq!(pixel(&graphic, 0, y), [255, 0, 0, 255]);
+        }
+    }
+
+    #[test]
+    fn repeat_and_newline() {
+        let graphic = decode(&[0, 1], b"#1;2;0;0;100!3@-A").unwrap();
+
+        assert_eq!(graphic.width, 3);
+        assert_eq!(graphic.height, 8);
+        for x in 0..3 {
+            assert_eq!(pixel(&graphic, x, 0), [0, 0, 255, 255]);
+            assert_eq!(pixel(&graphic, x, 1), TRANSPARENT);
+        }
+        assert_eq!(pixel(&graphic, 0, 7), [0, 0, 255, 255]);
+        assert_eq!(pixel(&graphic, 1, 7), TRANSPARENT);
+    }
+
+    #[test]
+    fn carriage_return_overlay() {
+        let graphic = decode(&[0, 1], b"#1;2;100;0;0@$#2;2;0;100;0A").unwrap();
+
+        assert_eq!(graphic.width, 1);
+        assert_eq!(pixel(&graphic, 0, 0), [255, 0, 0, 255]);
+        assert_eq!(pixel(&graphic, 0, 1), [0, 255, 0, 255]);
+    }
+
+    #[test]
+    fn raster_attributes() {
+        let graphic = decode(&[0, 0], b"\"1;1;4;12#0;2;0;0;100").unwrap();
+
+        assert_eq!(graphic.width, 4);
+        assert_eq!(graphic.height, 12);
+        assert_eq!(pixel(&graphic, 3, 11), [0, 0, 255, 255]);
+    }
+
+    #[test]
+    fn hls_colors() {
+        assert_eq!(rgb_from_hls(0, 50, 100), [0, 0, 255, 255]);
+        assert_eq!(rgb_from_hls(120, 50, 100), [255, 0, 0, 255]);
+        assert_eq!(rgb_from_hls(240, 50, 100), [0, 255, 0, 255]);
+        assert_eq!(rgb_from_hls(0, 100, 0), [255, 255, 255, 255]);
+    }
+
+    #[test]
+    fn empty_image() {
+        assert!(decode(&[], b"").is_none());
+        assert!(decode(&[], b"#1;2;100;100;100").is_none());
+    }
+
+    #[test]
+    fn dimensions_are_capped() {
+        let graphic = decode(&[0, 1], b"!99999~").unwrap();
+        assert_eq!(graphic.width, MAX_GRAPHIC_DIMENSIONS);
+    }
+}
@@ -0,0 +1,688 @@
+//! Compressed storage for the oldest lines of the scrollback history.
+
+use std::borrow::Cow;
+use std::cmp::min;
+use std::collections::VecDeque;
+use std::fmt::{self, Debug, Formatter};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::thread::Builder;
+
+use flate2::read::DeflateDecoder;
+use flate2::write::DeflateEncoder;
+use flate2::Compression;
+use log::{error, warn};
+use once_cell::sync::{Lazy, OnceCell};
+use serde::de::DeserializeOwned;
+use serde::{ser, Deserialize, Deserializer, Serialize, Serializer};
+
+use super::Row;
+use crate::ansi::SemanticMark;
+use crate::index::Column;
+
+/// Number of lines compressed together.
+pub const CHUNK_SIZE: usize = 1_000;
+
+/// Maximum number of chunks kept decompressed after they have been accessed.
+const MAX_DECOMPRESSED_CHUNKS: usize = 4;
+
+/// Work executed on the compression thread.
+type Job = Box<dyn FnOnce() + Send>;
+
+/// Queue of the thread compressing history chunks.
+///
+/// If the thread cannot be spawned or has panicked, sending to the queue fails and rows are kept
+/// uncompressed instead.
+static COMPRESSION_QUEUE: Lazy<Mutex<Sender<Job>>> = Lazy::new(|| {
+    let (sender, receiver) = mpsc::channel::<Job>();
+    let spawned = Builder::new().name("history compression".into()).spawn(move || {
+        for job in receiver {
+            job();
+        }
+    });
+
+    if let Err(err) = spawned {
+        error!("Unable to spawn history compression thread: {}", err);
+    }
+
+    Mutex::new(sender)
+});
+
+/// Compressed history lines.
+///
+/// Lines are stored in chunks of [`CHUNK_SIZE`] rows, which are compressed on a background thread
+/// and decompressed on demand when they are accessed. Since indexing only requires a shared
+/// reference, decompressed chunks are only released again by [`Archive::release`]. Traversals of
+/// many lines should use [`Archive::chunk`] instead, which does not keep the rows in memory.
+///
+/// Compressed rows are not reflowed when the number of columns changes, they are truncated or
+/// padded with empty cells when they are decompressed instead.
+pub struct Archive<T> {
+    /// Compressed chunks, ordered from oldest to newest.
+    chunks: VecDeque<Chunk<T>>,
+
+    /// Number of discarded rows at the start of the oldest chunk.
+    skip: usize,
+
+    /// Number of columns of decompressed rows.
+    columns: usize,
+
+    /// Conversion between rows and their compressed representation.
+    ///
+    /// This is initialized with the first chunk, since only compression requires the cells to be
+    /// serializable.
+    codec: Option<Codec<T>>,
+
+    /// Counter used to find the least recently accessed chunks.
+    clock: AtomicUsize,
+
+    /// Number of chunks which are currently decompressed.
+    decompressed: AtomicUsize,
+}
+
+impl<T> Default for Archive<T> {
+    fn default() -> Self {
+        Self {
+            chunks: Default::default(),
+            skip: 0,
+            columns: 0,
+            codec: None,
+            clock: Default::default(),
+            decompressed: Default::default(),
+        }
+    }
+}
+
+impl<T: Clone> Clone for Archive<T> {
+    fn clone(&self) -> Self {
+        let chunks = self.chunks.clone();
+        let count = chunks.iter().filter(|chunk| chunk.rows.get().is_some()).count();
+
+        Self {
+            chunks,
+            skip: self.skip,
+            columns: self.columns,
+            codec: self.codec,
+            clock: AtomicUsize::new(self.clock.load(Ordering::Relaxed)),
+            decompressed: AtomicUsize::new(count),
+        }
+    }
+}
+
+/// Compares the decompressed rows of all chunks.
+impl<T: PartialEq> PartialEq for Archive<T> {
+    fn eq(&self, other: &Self) -> bool {
+        // Archives of the same length always have the same number of discarded rows.
+        self.len() == other.len()
+            && (0..self.chunks.len()).all(|index| {
+                let skip = if index == 0 { self.skip } else { 0 };
+                self.with_rows(index, |rows| {
+                    other.with_rows(index, |other_rows| rows[skip..] == other_rows[skip..])
+                })
+            })
+    }
+}
+
+impl<T> Debug for Archive<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Archive")
+            .field("len", &self.len())
+            .field("chunks", &self.chunks.len())
+            .field("decompressed", &self.decompressed)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<T> Archive<T> {
+    /// Number of compressed lines.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.chunks.len() * CHUNK_SIZE - self.skip
+    }
+
+    /// Access a row by its distance from the newest compressed line.
+    pub fn get(&self, age: usize) -> &Row<T> {
+        let (chunk, index) = self.locate(age);
+        &self.rows(chunk)[index]
+    }
+
+    /// Decompressed rows of a chunk ordered from oldest to newest, without keeping them in memory.
+    pub fn chunk(&self, index: usize) -> Cow<'_, [Row<T>]>
+    where
+        T: Clone,
+    {
+        let chunk = &self.chunks[index];
+        match chunk.rows.get() {
+            Some(rows) => Cow::Borrowed(rows.as_slice()),
+            None => Cow::Owned(self.decode(&chunk.data)),
+        }
+    }
+
+    /// Mutably access a row by its distance from the newest compressed line.
+    pub fn get_mut(&mut self, age: usize) -> &mut Row<T> {
+        let (chunk, index) = self.locate(age);
+        self.rows(chunk);
+
+        let chunk = &mut self.chunks[chunk];
+        chunk.dirty = true;
+        &mut chunk.rows.get_mut().unwrap()[index]
+    }
+
+    /// Remove the `count` oldest lines.
+    pub fn discard(&mut self, count: usize) {
+        debug_assert!(count <= self.len());
+
+        self.skip += count;
+        while self.skip >= CHUNK_SIZE {
+            let chunk = self.chunks.pop_front().unwrap();
+            if chunk.rows.get().is_some() {
+                *self.decompressed.get_mut() -= 1;
+            }
+            self.skip -= CHUNK_SIZE;
+        }
+
+        if self.chunks.is_empty() {
+            self.skip = 0;
+        }
+    }
+
+    /// Remove the newest chunk, returning its rows ordered from oldest to newest.
+    pub fn pop(&mut self) -> Option<Vec<Row<T>>> {
+        let chunk = self.chunks.pop_back()?;
+        let mut rows = match chunk.rows.into_inner() {
+            Some(rows) => {
+                *self.decompressed.get_mut() -= 1;
+                rows
+            },
+            None => self.decode(&chunk.data),
+        };
+
+        if self.chunks.is_empty() {
+            rows.drain(..self.skip);
+            self.skip = 0;
+        }
+
+        Some(rows)
+    }
+
+    /// Update the number of columns of decompressed rows.
+    pub fn set_columns(&mut self, columns: usize) {
+        if self.columns != columns {
+            self.release_all();
+            self.columns = columns;
+        }
+    }
+
+    /// Release memory of decompressed chunks, keeping only the most recently accessed ones.
+    pub fn release(&mut self) {
+        if *self.decompressed.get_mut() <= MAX_DECOMPRESSED_CHUNKS {
+            return;
+        }
+
+        let mut decompressed: Vec<usize> =
+            (0..self.chunks.len()).filter(|&i| self.chunks[i].rows.get().is_some()).collect();
+        decompressed.sort_unstable_by_key(|&i| self.chunks[i].last_access.load(Ordering::Relaxed));
+
+        let count = decompressed.len() - MAX_DECOMPRESSED_CHUNKS;
+        for index in decompressed.into_iter().take(count) {
+            self.compress(index);
+        }
+    }
+
+    /// Release memory of all decompressed chunks.
+    fn release_all(&mut self) {
+        for index in 0..self.chunks.len() {
+            self.compress(index);
+        }
+    }
+
+    /// Drop the decompressed rows of a chunk, updating its compressed data if necessary.
+    fn compress(&mut self, index: usize) {
+        let chunk = &mut self.chunks[index];
+        let rows = match chunk.rows.take() {
+            Some(rows) => rows,
+            None => return,
+        };
+
+        if chunk.dirty {
+            chunk.data = (self.codec.unwrap().compress)(rows);
+            chunk.dirty = false;
+        }
+
+        *self.decompressed.get_mut() -= 1;
+    }
+
+    /// Decompressed rows of a chunk.
+    fn rows(&self, index: usize) -> &[Row<T>] {
+        let chunk = &self.chunks[index];
+
+        let time = self.clock.fetch_add(1, Ordering::Relaxed) + 1;
+        chunk.last_access.store(time, Ordering::Relaxed);
+
+        chunk.rows.get_or_init(|| {
+            self.decompressed.fetch_add(1, Ordering::Relaxed);
+            self.decode(&chunk.data)
+        })
+    }
+
+    /// Call `f` with the rows of a chunk, without keeping them in memory.
+    fn with_rows<R>(&self, index: usize, f: impl FnOnce(&[Row<T>]) -> R) -> R {
+        let chunk = &self.chunks[index];
+        match chunk.rows.get() {
+            Some(rows) => f(rows),
+            None => f(&self.decode(&chunk.data)),
+        }
+    }
+
+    /// Decompress rows, resizing them to the current number of columns.
+    fn decode(&self, data: &CompressedRows<T>) -> Vec<Row<T>> {
+        (self.codec.unwrap().decode)(data, self.columns)
+    }
+
+    /// Index of the chunk containing the row at `age` and of the row within it.
+    #[inline]
+    pub fn locate(&self, age: usize) -> (usize, usize) {
+        debug_assert!(age < self.len());
+
+        let chunk = self.chunks.len() - 1 - age / CHUNK_SIZE;
+        (chunk, CHUNK_SIZE - 1 - age % CHUNK_SIZE)
+    }
+}
+
+impl<T: Clone + Default + Serialize + DeserializeOwned + Send + 'static> Archive<T> {
+    /// Compress rows ordered from oldest to newest, appending them as the newest lines.
+    pub fn push(&mut self, rows: Vec<Row<T>>) {
+        debug_assert_eq!(rows.len(), CHUNK_SIZE);
+
+        self.set_columns(rows[0].len());
+
+        let codec = *self.codec.get_or_insert_with(Codec::new);
+        self.chunks.push_back(Chunk {
+            data: (codec.compress)(rows),
+            rows: OnceCell::new(),
+            dirty: false,
+            last_access: AtomicUsize::new(0),
+        });
+    }
+}
+
+/// Serialized as the discarded rows, columns and compressed data of all chunks.
+impl<T: Serialize> Serialize for Archive<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        // Modified rows are only compressed once their chunk is released.
+        let chunks = self
+            .chunks
+            .iter()
+            .map(|chunk| match chunk.rows.get() {
+                Some(rows) if chunk.dirty => encode(rows).map(Cow::Owned),
+                _ => chunk.data.encoded(),
+            })
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(ser::Error::custom)?;
+
+        (self.skip, self.columns, chunks).serialize(serializer)
+    }
+}
+
+impl<'de, T> Deserialize<'de> for Archive<T>
+where
+    T: Clone + Default + Serialize + DeserializeOwned + Send + 'static,
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let (skip, columns, chunks): (usize, usize, Vec<Vec<u8>>) =
+            Deserialize::deserialize(deserializer)?;
+
+        let chunks = chunks
+            .into_iter()
+            .map(|data| Chunk {
+                data: Arc::new(CompressedRows::from(data)),
+                rows: OnceCell::new(),
+                dirty: false,
+                last_access: AtomicUsize::new(0),
+            })
+            .collect();
+
+        Ok(Self { chunks, skip, columns, codec: Some(Codec::new()), ..Default::default() })
+    }
+}
+
+/// Lines compressed together.
+struct Chunk<T> {
+    /// Compressed rows, shared with the compression thread.
+    data: Arc<CompressedRows<T>>,
+
+    /// Decompressed rows, ordered from oldest to newest.
+    rows: OnceCell<Vec<Row<T>>>,
+
+    /// Whether the decompressed rows have been modified.
+    dirty: bool,
+
+    /// Clock value of the last access.
+    last_access: AtomicUsize,
+}
+
+impl<T: Clone> Clone for Chunk<T> {
+    fn clone(&self) -> Self {
+        // Unmodified rows can be decompressed again, so only the compressed data is copied.
+        let rows = if self.dirty { self.rows.clone() } else { OnceCell::new() };
+
+        Self {
+            data: self.data.clone(),
+            rows,
+            dirty: self.dirty,
+            last_access: AtomicUsize::new(self.last_access.load(Ordering::Relaxed)),
+        }
+    }
+}
+
+/// Rows of a chunk, which are replaced by their compressed data on the compression thread.
+struct CompressedRows<T> {
+    /// Compressed rows, set once the compression thread is done.
+    data: OnceCell<Vec<u8>>,
+
+    /// Rows waiting for compression, ordered from oldest to newest.
+    pending: Mutex<Option<Vec<Row<T>>>>,
+}
+
+impl<T> From<Vec<u8>> for CompressedRows<T> {
+    fn from(data: Vec<u8>) -> Self {
+        Self { data: OnceCell::from(data), pending: Mutex::new(None) }
+    }
+}
+
+impl<T> CompressedRows<T> {
+    /// Rows which are not compressed yet.
+    ///
+    /// This waits for the compression thread if it's currently compressing the rows.
+    fn pending(&self) -> MutexGuard<'_, Option<Vec<Row<T>>>> {
+        // Rows are never modified while the lock is held, so they're still valid after a panic.
+        self.pending.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Compressed data, compressing the rows if the compression thread hasn't done so yet.
+    fn encoded(&self) -> bincode::Result<Cow<'_, [u8]>>
+    where
+        T: Serialize,
+    {
+        if let Some(data) = self.data.get() {
+            return Ok(Cow::Borrowed(data));
+        }
+
+        match (&*self.pending(), self.data.get()) {
+            (_, Some(data)) => Ok(Cow::Borrowed(data)),
+            (Some(rows), None) => encode(rows).map(Cow::Owned),
+            (None, None) => Ok(Cow::Owned(Vec::new())),
+        }
+    }
+}
+
+/// Functions converting between rows and their compressed representation.
+struct Codec<T> {
+    compress: fn(Vec<Row<T>>) -> Arc<CompressedRows<T>>,
+    decode: fn(&CompressedRows<T>, usize) -> Vec<Row<T>>,
+}
+
+impl<T: Clone + Default + Serialize + DeserializeOwned + Send + 'static> Codec<T> {
+    fn new() -> Self {
+        Self { compress: compress::<T>, decode: decode::<T> }
+    }
+}
+
+impl<T> Clone for Codec<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for Codec<T> {}
+
+/// Serialized row as number of columns, occupied cells, trailing cell and marks.
+///
+/// All cells after the occupied ones are identical, so only one of them is stored.
+type EncodedRow<'a, T> = (usize, &'a [T], Option<&'a T>, &'a [(Column, SemanticMark)]);
+type DecodedRow<T> = (usize, Vec<T>, Option<T>, Vec<(Column, SemanticMark)>);
+
+/// Queue rows for compression on the compression thread.
+///
+/// The rows are kept uncompressed when the compression thread is not running.
+fn compress<T: Serialize + Send + 'static>(rows: Vec<Row<T>>) -> Arc<CompressedRows<T>> {
+    let compressed =
+        Arc::new(CompressedRows { data: OnceCell::new(), pending: Mutex::new(Some(rows)) });
+
+    let shared = compressed.clone();
+    let job: Job = Box::new(move || {
+        let mut pending = shared.pending();
+        match pending.as_deref().map(encode) {
+            Some(Ok(data)) => {
+                let _ = shared.data.set(data);
+                *pending = None;
+            },
+            Some(Err(err)) => warn!("Unable to compress history: {}", err),
+            None => (),
+        }
+    });
+
+    let queue = COMPRESSION_QUEUE.lock().unwrap_or_else(PoisonError::into_inner);
+    if queue.send(job).is_err() {
+        warn!("History compression thread is gone, keeping history uncompressed");
+    }
+
+    compressed
+}
+
+/// Serialize and compress rows.
+fn encode<T: Serialize>(rows: &[Row<T>]) -> bincode::Result<Vec<u8>> {
+    let rows: Vec<EncodedRow<'_, T>> = rows
+        .iter()
+        .map(|row| {
+            let occ = min(row.occ, row.len());
+            (row.len(), &row[..Column(occ)], row.last(), row.marks())
+        })
+        .collect();
+
+    let mut encoder = DeflateEncoder::new(Vec::new(), Compression::fast());
+    bincode::serialize_into(&mut encoder, &rows)?;
+    Ok(encoder.finish()?)
+}
+
+/// Decompress rows, resizing them to `columns`.
+///
+/// Rows which cannot be decompressed are replaced by empty rows.
+fn decode<T>(compressed: &CompressedRows<T>, columns: usize) -> Vec<Row<T>>
+where
+    T: Clone + Default + DeserializeOwned,
+{
+    let pending = compressed.pending();
+    let data = match (&*pending, compressed.data.get()) {
+        (_, Some(data)) => data.as_slice(),
+        (Some(rows), None) => return rows.iter().map(|row| resize(row, columns)).collect(),
+        (None, None) => &[],
+    };
+
+    let rows: Vec<DecodedRow<T>> = match bincode::deserialize_from(DeflateDecoder::new(data)) {
+        Ok(rows) => rows,
+        Err(err) => {
+            error!("Unable to decompress history: {}", err);
+            return vec![Row::new(columns); CHUNK_SIZE];
+        },
+    };
+
+    rows.into_iter()
+        .map(|(len, mut cells, fill, marks)| {
+            let occ = min(cells.len(), columns);
+            cells.truncate(columns);
+            cells.resize(min(len, columns), fill.unwrap_or_default());
+            cells.resize_with(columns, T::default);
+
+            let mut row = Row::from_vec(cells, occ);
+            row.append_marks(marks, 0);
+            row
+        })
+        .collect()
+}
+
+/// Copy of a row truncated or padded to `columns`, like a decompressed row.
+fn resize<T: Clone + Default>(row: &Row<T>, columns: usize) -> Row<T> {
+    if row.len() == columns {
+        return row.clone();
+    }
+
+    let mut cells = row[..Column(min(row.len(), columns))].to_vec();
+    cells.resize_with(columns, T::default);
+
+    let mut resized = Row::from_vec(cells, min(row.occ, columns));
+    resized.append_marks(row.marks().to_vec(), 0);
+    resized
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rows(start: usize) -> Vec<Row<char>> {
+        (start..start + CHUNK_SIZE)
+            .map(|i| {
+                let c = char::from_digit((i % 10) as u32, 10).unwrap();
+                let mut row = Row::new(3);
+                row[Column(0)] = c;
+                row[Column(2)] = c;
+                row
+            })
+            .collect()
+    }
+
+    #[test]
+    fn roundtrip() {
+        let mut archive = Archive::default();
+        archive.push(rows(0));
+        archive.push(rows(CHUNK_SIZE));
+
+        assert_eq!(archive.len(), 2 * CHUNK_SIZE);
+        assert_eq!(archive.get(0)[Column(0)], '9');
+        assert_eq!(archive.get(3)[Column(2)], '6');
+        assert_eq!(archive.get(2 * CHUNK_SIZE - 1)[Column(0)], '0');
+        assert_eq!(archive.get(0).len(), 3);
+    }
+
+    #[test]
+    fn release_modified() {
+        let mut archive = Archive::default();
+        for i in 0..MAX_DECOMPRESSED_CHUNKS + 1 {
+            archive.push(rows(i * CHUNK_SIZE));
+        }
+
+        archive.get_mut(archive.len() - 1)[Column(1)] = 'x';
+        for i in 0..MAX_DECOMPRESSED_CHUNKS {
+            let _ = archive.get(i * CHUNK_SIZE);
+        }
+        assert_eq!(*archive.decompressed.get_mut(), MAX_DECOMPRESSED_CHUNKS + 1);
+
+        archive.release();
+        assert_eq!(*archive.decompressed.get_mut(), MAX_DECOMPRESSED_CHUNKS);
+        assert!(archive.chunks[0].rows.get().is_none());
+        assert_eq!(archive.get(archive.len() - 1)[Column(1)], 'x');
+    }
+
+    #[test]
+    fn chunk_without_caching() {
+        let mut archive = Archive::default();
+        archive.push(rows(0));
+        archive.push(rows(CHUNK_SIZE));
+
+        let (chunk, index) = archive.locate(CHUNK_SIZE + 3);
+        assert_eq!((chunk, index), (0, CHUNK_SIZE - 4));
+        let rows = archive.chunk(chunk);
+        assert!(matches!(rows, Cow::Owned(_)));
+        assert_eq!(rows[index][Column(0)], '6');
+        assert_eq!(*archive.decompressed.get_mut(), 0);
+
+        let _ = archive.get(0);
+        assert!(matches!(archive.chunk(1), Cow::Borrowed(_)));
+    }
+
+    #[test]
+    fn discard_and_pop() {
+        let mut archive = Archive::default();
+        archive.push(rows(0));
+        archive.push(rows(CHUNK_SIZE));
+
+        archive.discard(CHUNK_SIZE + 2);
+        assert_eq!(archive.len(), CHUNK_SIZE - 2);
+        assert_eq!(archive.get(archive.len() - 1)[Column(0)], '2');
+
+        let popped = archive.pop().unwrap();
+        assert_eq!(popped.len(), CHUNK_SIZE - 2);
+        assert_eq!(popped[0][Column(0)], '2');
+        assert_eq!(archive.len(), 0);
+        assert!(archive.pop().is_none());
+    }
+
+    #[test]
+    fn serde() {
+        let mut archive = Archive::default();
+        archive.push(rows(0));
+        archive.push(rows(CHUNK_SIZE));
+        archive.discard(2);
+        archive.get_mut(0)[Column(1)] = 'x';
+
+        let serialized = serde_json::to_string(&archive).unwrap();
+        let archive: Archive<char> = serde_json::from_str(&serialized).unwrap();
+
+        assert_eq!(archive.len(), 2 * CHUNK_SIZE - 2);
+        assert_eq!(archive.get(0)[Column(1)], 'x');
+        assert_eq!(archive.get(archive.len() - 1)[Column(0)], '2');
+    }
+
+    #[test]
+    fn compare_rows() {
+        let mut archive = Archive::default();
+        archive.push(rows(0));
+        archive.push(rows(CHUNK_SIZE));
+        archive.discard(CHUNK_SIZE + 3);
+
+        let mut other = Archive::default();
+        other.push(rows(0));
+        assert!(archive != other);
+
+        other.discard(3);
+        assert!(archive == other);
+
+        other.get_mut(5)[Column(1)] = 'x';
+        assert!(archive != other);
+
+        archive.get_mut(5)[Column(1)] = 'x';
+        archive.release_all();
+        assert!(archive == other);
+    }
+
+    #[test]
+    fn uncompressed_rows() {
+        // Rows stay uncompressed when the compression thread is unavailable.
+        let compressed =
+            CompressedRows { data: OnceCell::new(), pending: Mutex::new(Some(rows(0))) };
+
+        let rows = decode(&compressed, 5);
+        assert_eq!(rows.len(), CHUNK_SIZE);
+        assert_eq!(rows[3].len(), 5);
+        assert_eq!(rows[3][Column(2)], '3');
+
+        let data = compressed.encoded().unwrap();
+        let compressed: CompressedRows<char> = CompressedRows::from(data.into_owned());
+        assert_eq!(decode(&compressed, 3)[3][Column(2)], '3');
+    }
+
+    #[test]
+    fn resize_columns() {
+        let mut archive = Archive::default();
+        archive.push(rows(0));
+
+        archive.set_columns(5);
+        assert_eq!(archive.get(0).len(), 5);
+        assert_eq!(archive.get(0)[Column(2)], '9');
+
+        archive.set_columns(1);
+        assert_eq!(archive.get(0).len(), 1);
+        assert_eq!(archive.get(0)[Column(0)], '9');
+    }
+}
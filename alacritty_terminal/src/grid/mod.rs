@@ -3,12 +3,14 @@
 use std::cmp::{max, min};
 use std::ops::{Bound, Deref, Index, IndexMut, Range, RangeBounds};
 
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 
 use crate::ansi::{CharsetIndex, StandardCharset};
 use crate::index::{Column, Line, Point};
 use crate::term::cell::{Flags, ResetDiscriminant};
 
+mod archive;
 pub mod resize;
 mod row;
 mod storage;
@@ -16,7 +18,8 @@ mod storage;
 mod tests;
 
 pub use self::row::Row;
-use self::storage::Storage;
+pub use self::storage::RowReader;
+use self::storage::{Storage, MAX_UNCOMPRESSED_LINES};
 
 pub trait GridCell: Sized {
     /// Check if the cell contains any content.
@@ -105,6 +108,7 @@ pub enum Scroll {
 ///                        columns
 /// ```
 #[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(bound(deserialize = "T: Clone + Default + Serialize + DeserializeOwned + Send + 'static"))]
 pub struct Grid<T> {
     /// Current cursor for writing data.
     #[serde(skip)]
@@ -152,12 +156,18 @@ impl<T: GridCell + Default + PartialEq + Clone> Grid<T> {
     pub fn update_history(&mut self, history_size: usize) {
         let current_history_size = self.history_size();
         if current_history_size > history_size {
-            self.raw.shrink_lines(current_history_size - history_size);
+            self.raw.shrink_history(current_history_size - history_size);
         }
         self.display_offset = min(self.display_offset, history_size);
         self.max_scroll_limit = history_size;
     }
 
+    /// Maximum number of lines in the scrollback history.
+    #[inline]
+    pub fn max_scroll_limit(&self) -> usize {
+        self.max_scroll_limit
+    }
+
     pub fn scroll_display(&mut self, scroll: Scroll) {
         self.display_offset = match scroll {
             Scroll::Delta(count) => {
@@ -168,9 +178,37 @@ impl<T: GridCell + Default + PartialEq + Clone> Grid<T> {
             Scroll::Top => self.history_size(),
             Scroll::Bottom => 0,
         };
+
+        self.raw.release_compressed();
+    }
+
+    /// Release memory used by recently accessed compressed history lines.
+    #[inline]
+    pub fn release_compressed(&mut self) {
+        self.raw.release_compressed();
+    }
+
+    /// Compress the oldest history lines to reduce memory usage.
+    ///
+    /// Only the most recent [`MAX_UNCOMPRESSED_LINES`] lines of history are kept uncompressed.
+    pub fn compress_history(&mut self)
+    where
+        T: Serialize + DeserializeOwned + Send + 'static,
+    {
+        self.raw.compress_history(MAX_UNCOMPRESSED_LINES, self.columns);
+    }
+
+    /// Number of history lines which are stored compressed.
+    #[inline]
+    pub fn compressed_lines(&self) -> usize {
+        self.raw.compressed_len()
     }
 
     fn increase_scroll_limit(&mut self, count: usize) {
+        // Make room for the new lines by dropping the oldest compressed lines.
+        let overflow = (self.history_size() + count).saturating_sub(self.max_scroll_limit);
+        self.raw.discard_compressed(overflow);
+
         let count = min(count, self.max_scroll_limit - self.history_size());
         if count != 0 {
             self.raw.initialize(count, self.columns);
@@ -296,7 +334,8 @@ impl<T: GridCell + Default + PartialEq + Clone> Grid<T> {
         }
     }
 
-    pub fn clear_viewport<D>(&mut self)
+    /// Returns the number of lines rotated into the scrollback history.
+    pub fn clear_viewport<D>(&mut self) -> usize
     where
         T: ResetDiscriminant<D>,
         D: PartialEq,
@@ -320,6 +359,8 @@ impl<T: GridCell + Default + PartialEq + Clone> Grid<T> {
         for line in (0..(self.lines - positions)).map(Line::from) {
             self.raw[line].reset(&self.cursor.template);
         }
+
+        positions
     }
 
     /// Completely reset the grid state.
@@ -372,7 +413,7 @@ impl<T> Grid<T> {
     #[inline]
     pub fn clear_history(&mut self) {
         // Explicitly purge all lines from history.
-        self.raw.shrink_lines(self.history_size());
+        self.raw.shrink_history(self.history_size());
 
         // Reset display offset.
         self.display_offset = 0;
@@ -404,6 +445,29 @@ impl<T> Grid<T> {
         GridIterator { grid: self, point, end }
     }
 
+    /// Cursor over all cells in the grid starting at a specific point.
+    ///
+    /// Unlike [`Grid::iter_from`], this does not keep compressed history lines in memory, so it
+    /// should be used for traversals which might span large parts of the history.
+    #[inline]
+    pub fn cursor_from(&self, point: Point) -> GridCursor<'_, T>
+    where
+        T: Clone,
+    {
+        let end = Point::new(self.bottommost_line(), self.last_column());
+        let start = Point::new(self.topmost_line(), Column(0));
+        GridCursor { reader: self.reader(), point, start, end }
+    }
+
+    /// Reader for traversing many lines without keeping compressed history lines in memory.
+    #[inline]
+    pub fn reader(&self) -> RowReader<'_, T>
+    where
+        T: Clone,
+    {
+        self.raw.reader()
+    }
+
     /// Iterate over all visible cells.
     ///
     /// This is slightly more optimized than calling `Grid::iter_from` in combination with
@@ -440,6 +504,8 @@ impl<T: PartialEq> PartialEq for Grid<T> {
     }
 }
 
+impl<T: Eq> Eq for Grid<T> {}
+
 impl<T> Index<Line> for Grid<T> {
     type Output = Row<T>;
 
@@ -600,6 +666,74 @@ impl<'a, T> Iterator for GridIterator<'a, T> {
     }
 }
 
+/// Cursor over the cells of the grid.
+///
+/// Unlike a [`GridIterator`], cells are only borrowed until the cursor is moved, which allows
+/// reading compressed history lines without keeping them in memory.
+pub struct GridCursor<'a, T: Clone> {
+    reader: RowReader<'a, T>,
+
+    /// Current position of the cursor within the grid.
+    point: Point,
+
+    /// First cell of the grid.
+    start: Point,
+
+    /// Last cell of the grid.
+    end: Point,
+}
+
+impl<'a, T: Clone> GridCursor<'a, T> {
+    /// Current cursor position.
+    pub fn point(&self) -> Point {
+        self.point
+    }
+
+    /// Cell at the current cursor position.
+    pub fn cell(&mut self) -> &T {
+        &self.reader.row(self.point.line)[self.point.column]
+    }
+
+    /// Access any row of the grid.
+    pub fn row(&mut self, line: Line) -> &Row<T> {
+        self.reader.row(line)
+    }
+
+    /// Move to the next cell, returning `None` at the end of the grid.
+    pub fn next_cell(&mut self) -> Option<&T> {
+        if self.point >= self.end {
+            return None;
+        }
+
+        match self.point {
+            Point { column, .. } if column == self.end.column => {
+                self.point.column = Column(0);
+                self.point.line += 1;
+            },
+            _ => self.point.column += Column(1),
+        }
+
+        Some(self.cell())
+    }
+
+    /// Move to the previous cell, returning `None` at the start of the grid.
+    pub fn prev_cell(&mut self) -> Option<&T> {
+        if self.point == self.start {
+            return None;
+        }
+
+        match self.point {
+            Point { column: Column(0), .. } => {
+                self.point.column = self.end.column;
+                self.point.line -= 1;
+            },
+            _ => self.point.column -= Column(1),
+        }
+
+        Some(self.cell())
+    }
+}
+
 /// Bidirectional iterator.
 pub trait BidirectionalIterator: Iterator {
     fn prev(&mut self) -> Option<Self::Item>;
@@ -147,7 +147,7 @@ impl<T: GridCell + Default + PartialEq + Clone> Grid<T> {
             let len = min(row.len(), num_wrapped);
 
             // Insert leading spacer when there's not enough room for reflowing wide char.
-            let mut cells = if row[Column(len - 1)].flags().contains(Flags::WIDE_CHAR) {
+            let (mut cells, marks) = if row[Column(len - 1)].flags().contains(Flags::WIDE_CHAR) {
                 num_wrapped -= 1;
 
                 let mut cells = row.front_split_off(len - 1);
@@ -156,13 +156,14 @@ impl<T: GridCell + Default + PartialEq + Clone> Grid<T> {
                 spacer.flags_mut().insert(Flags::LEADING_WIDE_CHAR_SPACER);
                 cells.push(spacer);
 
-                cells
+                (cells, row.front_split_marks_off(len - 1))
             } else {
-                row.front_split_off(len)
+                (row.front_split_off(len), row.front_split_marks_off(len))
             };
 
             // Add removed cells to previous row and reflow content.
             last_row.append(&mut cells);
+            last_row.append_marks(marks, last_len);
 
             let cursor_buffer_line = self.lines - self.cursor.point.line.0 as usize - 1;
 
@@ -182,6 +183,9 @@ impl<T: GridCell + Default + PartialEq + Clone> Grid<T> {
                 let line_delta = self.cursor.point.line - target.line;
 
                 if line_delta != 0 && row.is_clear() {
+                    // Keep marks of the removed line.
+                    let offset = last_row.len();
+                    last_row.append_marks(row.split_marks_off(0), offset);
                     continue;
                 }
 
@@ -197,6 +201,10 @@ impl<T: GridCell + Default + PartialEq + Clone> Grid<T> {
                     self.cursor.point.line += 1;
                 }
 
+                // Keep marks of the removed line.
+                let offset = last_row.len();
+                last_row.append_marks(row.split_marks_off(0), offset);
+
                 // Don't push line into the new buffer.
                 continue;
             }
@@ -253,6 +261,7 @@ impl<T: GridCell + Default + PartialEq + Clone> Grid<T> {
 
         let mut new_raw = Vec::with_capacity(self.raw.len());
         let mut buffered: Option<Vec<T>> = None;
+        let mut buffered_marks = Vec::new();
 
         let mut rows = self.raw.take_all();
         for (i, mut row) in rows.drain(..).enumerate().rev() {
@@ -266,6 +275,7 @@ impl<T: GridCell + Default + PartialEq + Clone> Grid<T> {
                 }
 
                 row.append_front(buffered);
+                row.append_marks(mem::take(&mut buffered_marks), 0);
             }
 
             loop {
@@ -280,6 +290,8 @@ impl<T: GridCell + Default + PartialEq + Clone> Grid<T> {
                             Vec::new()
                         } else {
                             // Since it fits, just push the existing line without any reflow.
+                            let marks = row.split_marks_off(columns);
+                            row.append_marks(marks, columns);
                             new_raw.push(row);
                             break;
                         }
@@ -287,6 +299,7 @@ impl<T: GridCell + Default + PartialEq + Clone> Grid<T> {
                 };
 
                 // Insert spacer if a wide char would be wrapped into the last column.
+                let mut split = columns;
                 if row.len() >= columns
                     && row[Column(columns - 1)].flags().contains(Flags::WIDE_CHAR)
                 {
@@ -295,13 +308,18 @@ impl<T: GridCell + Default + PartialEq + Clone> Grid<T> {
 
                     let wide_char = mem::replace(&mut row[Column(columns - 1)], spacer);
                     wrapped.insert(0, wide_char);
+                    split -= 1;
                 }
 
+                // Move marks along with the wrapped cells.
+                let marks = row.split_marks_off(split);
+
                 // Remove wide char spacer before shrinking.
                 let len = wrapped.len();
                 if len > 0 && wrapped[len - 1].flags().contains(Flags::LEADING_WIDE_CHAR_SPACER) {
                     if len == 1 {
                         row[Column(columns - 1)].flags_mut().insert(Flags::WRAPLINE);
+                        row.append_marks(marks, split);
                         new_raw.push(row);
                         break;
                     } else {
@@ -331,6 +349,7 @@ impl<T: GridCell + Default + PartialEq + Clone> Grid<T> {
 
                     // Add removed cells to start of next row.
                     buffered = Some(wrapped);
+                    buffered_marks = marks;
                     break;
                 } else {
                     // Reflow cursor if a line below it is deleted.
@@ -354,6 +373,7 @@ impl<T: GridCell + Default + PartialEq + Clone> Grid<T> {
                         wrapped.resize_with(columns, T::default);
                     }
                     row = Row::from_vec(wrapped, occ);
+                    row.append_marks(marks, 0);
 
                     if i < self.display_offset {
                         // Since we added a new line, rotate up the viewport.
@@ -365,7 +385,9 @@ impl<T: GridCell + Default + PartialEq + Clone> Grid<T> {
 
         // Reverse iterator and use it as the new grid storage.
         let mut reversed: Vec<Row<T>> = new_raw.drain(..).rev().collect();
-        reversed.truncate(self.max_scroll_limit + self.lines);
+        // Compressed lines are kept above the reflowed ones.
+        let max_len = self.max_scroll_limit + self.lines;
+        reversed.truncate(max_len - self.raw.len());
         self.raw.replace_inner(reversed);
 
         // Clamp display offset in case some lines went off.
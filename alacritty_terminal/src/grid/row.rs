@@ -2,10 +2,11 @@
 
 use std::cmp::{max, min};
 use std::ops::{Index, IndexMut, Range, RangeFrom, RangeFull, RangeTo, RangeToInclusive};
-use std::{ptr, slice};
+use std::{mem, ptr, slice};
 
 use serde::{Deserialize, Serialize};
 
+use crate::ansi::SemanticMark;
 use crate::grid::GridCell;
 use crate::index::Column;
 use crate::term::cell::ResetDiscriminant;
@@ -20,6 +21,13 @@ pub struct Row<T> {
     /// This is the upper bound on the number of elements in the row, which have been modified
     /// since the last reset. All cells after this point are guaranteed to be equal.
     pub(crate) occ: usize,
+
+    /// Shell integration marks, ordered by their column.
+    ///
+    /// This is boxed since most rows have no marks, to keep the size of [`Row`] small.
+    #[allow(clippy::box_collection)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    marks: Option<Box<Vec<(Column, SemanticMark)>>>,
 }
 
 impl<T: PartialEq> PartialEq for Row<T> {
@@ -50,7 +58,7 @@ impl<T: Clone + Default> Row<T> {
             inner.set_len(columns);
         }
 
-        Row { inner, occ: 0 }
+        Row { inner, occ: 0, marks: None }
     }
 
     /// Increase the number of columns in the row.
@@ -109,6 +117,7 @@ impl<T: Clone + Default> Row<T> {
         }
 
         self.occ = 0;
+        self.marks = None;
     }
 }
 
@@ -116,7 +125,7 @@ impl<T: Clone + Default> Row<T> {
 impl<T> Row<T> {
     #[inline]
     pub fn from_vec(vec: Vec<T>, occ: usize) -> Row<T> {
-        Row { inner: vec, occ }
+        Row { inner: vec, occ, marks: None }
     }
 
     #[inline]
@@ -148,6 +157,10 @@ impl<T> Row<T> {
     pub fn append_front(&mut self, mut vec: Vec<T>) {
         self.occ += vec.len();
 
+        for (column, _) in self.marks.iter_mut().flat_map(|marks| marks.iter_mut()) {
+            *column += vec.len();
+        }
+
         vec.append(&mut self.inner);
         self.inner = vec;
     }
@@ -161,6 +174,60 @@ impl<T> Row<T> {
         self.inner.iter().all(GridCell::is_empty)
     }
 
+    /// Shell integration marks placed on this row.
+    #[inline]
+    pub fn marks(&self) -> &[(Column, SemanticMark)] {
+        self.marks.as_deref().map_or(&[], Vec::as_slice)
+    }
+
+    /// Place a shell integration mark, replacing any mark of the same kind in that column.
+    pub fn set_mark(&mut self, column: Column, mark: SemanticMark) {
+        let marks = self.marks.get_or_insert_with(Default::default);
+        marks.retain(|&(col, old)| {
+            col != column || mem::discriminant(&old) != mem::discriminant(&mark)
+        });
+
+        let index = marks.partition_point(|&(col, _)| col <= column);
+        marks.insert(index, (column, mark));
+    }
+
+    /// Remove all marks at or after `at`, returning them relative to that column.
+    pub(crate) fn split_marks_off(&mut self, at: usize) -> Vec<(Column, SemanticMark)> {
+        let remaining = match &mut self.marks {
+            Some(marks) => marks,
+            None => return Vec::new(),
+        };
+
+        let index = remaining.partition_point(|&(col, _)| col.0 < at);
+        let mut marks = remaining.split_off(index);
+        if remaining.is_empty() {
+            self.marks = None;
+        }
+
+        for (column, _) in &mut marks {
+            *column -= at;
+        }
+        marks
+    }
+
+    /// Remove all marks before `at`, moving the remaining marks to the left.
+    pub(crate) fn front_split_marks_off(&mut self, at: usize) -> Vec<(Column, SemanticMark)> {
+        let marks = self.split_marks_off(at);
+        let marks = (!marks.is_empty()).then(|| Box::new(marks));
+        let front = mem::replace(&mut self.marks, marks);
+        front.map_or_else(Vec::new, |front| *front)
+    }
+
+    /// Append marks to the row, moving them `offset` columns to the right.
+    ///
+    /// Marks beyond the end of the row are clamped to its last column.
+    pub(crate) fn append_marks(&mut self, marks: Vec<(Column, SemanticMark)>, offset: usize) {
+        let last_column = Column(self.inner.len().saturating_sub(1));
+        for (column, mark) in marks {
+            self.set_mark(min(column + offset, last_column), mark);
+        }
+    }
+
     #[inline]
     pub fn front_split_off(&mut self, at: usize) -> Vec<T> {
         self.occ = self.occ.saturating_sub(at);
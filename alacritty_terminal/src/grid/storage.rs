@@ -1,16 +1,22 @@
-use std::cmp::{max, PartialEq};
+use std::borrow::Cow;
+use std::cmp::{max, min, PartialEq};
 use std::mem;
 use std::mem::MaybeUninit;
 use std::ops::{Index, IndexMut};
 
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 
+use super::archive::{Archive, CHUNK_SIZE};
 use super::Row;
 use crate::index::Line;
 
 /// Maximum number of buffered lines outside of the grid for performance optimization.
 const MAX_CACHE_SIZE: usize = 1_000;
 
+/// Number of history lines kept uncompressed.
+pub const MAX_UNCOMPRESSED_LINES: usize = 100_000;
+
 /// A ring buffer for optimizing indexing and rotation.
 ///
 /// The [`Storage::rotate`] and [`Storage::rotate_down`] functions are fast modular additions on
@@ -24,10 +30,14 @@ const MAX_CACHE_SIZE: usize = 1_000;
 /// implementation is provided. Anything from [`Vec`] that should be exposed must be done so
 /// manually.
 ///
+/// History lines beyond the ring buffer are stored compressed in an [`Archive`], while still being
+/// accessible through the same indices.
+///
 /// [`slice::rotate_left`]: https://doc.rust-lang.org/std/primitive.slice.html#method.rotate_left
 /// [`Deref`]: std::ops::Deref
 /// [`zero`]: #structfield.zero
 #[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(bound(deserialize = "T: Clone + Default + Serialize + DeserializeOwned + Send + 'static"))]
 pub struct Storage<T> {
     inner: Vec<Row<T>>,
 
@@ -47,7 +57,13 @@ pub struct Storage<T> {
     /// having to truncate the raw `inner` buffer.
     /// As long as `len` is bigger than `inner`, it is also possible to grow the scrollback buffer
     /// without any additional insertions.
+    ///
+    /// This does not include the compressed lines in the `archive`.
     len: usize,
+
+    /// Compressed lines above the oldest line of the ring buffer.
+    #[serde(default)]
+    archive: Archive<T>,
 }
 
 impl<T: PartialEq> PartialEq for Storage<T> {
@@ -56,7 +72,7 @@ impl<T: PartialEq> PartialEq for Storage<T> {
         assert_eq!(self.zero, 0);
         assert_eq!(other.zero, 0);
 
-        self.inner == other.inner && self.len == other.len
+        self.inner == other.inner && self.len == other.len && self.archive == other.archive
     }
 }
 
@@ -70,7 +86,7 @@ impl<T> Storage<T> {
         let mut inner = Vec::with_capacity(visible_lines);
         inner.resize_with(visible_lines, || Row::new(columns));
 
-        Storage { inner, zero: 0, visible_lines, len: visible_lines }
+        Storage { inner, zero: 0, visible_lines, len: visible_lines, archive: Archive::default() }
     }
 
     /// Increase the number of lines in the buffer.
@@ -83,6 +99,15 @@ impl<T> Storage<T> {
         let growage = next - self.visible_lines;
 
         let columns = self[Line(0)].len();
+
+        // Move compressed lines back into the ring buffer, so they can become visible.
+        while self.len - self.visible_lines < growage {
+            match self.archive.pop() {
+                Some(rows) => self.push_oldest(rows, columns),
+                None => break,
+            }
+        }
+
         self.initialize(growage, columns);
 
         // Update visible lines.
@@ -111,6 +136,81 @@ impl<T> Storage<T> {
         }
     }
 
+    /// Remove the oldest lines from the history, starting with the compressed ones.
+    #[inline]
+    pub fn shrink_history(&mut self, shrinkage: usize) {
+        let compressed = min(shrinkage, self.archive.len());
+        self.archive.discard(compressed);
+
+        if shrinkage > compressed {
+            self.shrink_lines(shrinkage - compressed);
+        }
+    }
+
+    /// Remove up to `count` of the oldest compressed lines.
+    #[inline]
+    pub fn discard_compressed(&mut self, count: usize) {
+        self.archive.discard(min(count, self.archive.len()));
+    }
+
+    /// Number of compressed history lines.
+    #[inline]
+    pub fn compressed_len(&self) -> usize {
+        self.archive.len()
+    }
+
+    /// Release memory used by recently accessed compressed lines.
+    #[inline]
+    pub fn release_compressed(&mut self) {
+        self.archive.release();
+    }
+
+    /// Reader for traversing many lines without keeping compressed lines in memory.
+    #[inline]
+    pub fn reader(&self) -> RowReader<'_, T>
+    where
+        T: Clone,
+    {
+        RowReader { storage: self, chunk: None }
+    }
+
+    /// Compress the oldest lines, keeping at least `uncompressed` history lines in the ring buffer.
+    pub fn compress_history(&mut self, uncompressed: usize, columns: usize)
+    where
+        T: Clone + Default + Serialize + DeserializeOwned + Send + 'static,
+    {
+        while self.len >= self.visible_lines + uncompressed + CHUNK_SIZE {
+            let inner_len = self.inner.len();
+            let rows = (self.len - CHUNK_SIZE..self.len)
+                .rev()
+                .map(|positive| {
+                    let index = (self.zero + positive) % inner_len;
+                    mem::replace(&mut self.inner[index], Row::new(columns))
+                })
+                .collect();
+
+            self.archive.push(rows);
+            self.len -= CHUNK_SIZE;
+        }
+
+        self.archive.release();
+    }
+
+    /// Add rows ordered from oldest to newest above the oldest line of the ring buffer.
+    fn push_oldest(&mut self, rows: Vec<Row<T>>, columns: usize)
+    where
+        T: Clone + Default,
+    {
+        let len = self.len;
+        self.initialize(rows.len(), columns);
+
+        let inner_len = self.inner.len();
+        for (i, row) in rows.into_iter().rev().enumerate() {
+            let index = (self.zero + len + i) % inner_len;
+            self.inner[index] = row;
+        }
+    }
+
     /// Truncate the invisible elements from the raw buffer.
     #[inline]
     pub fn truncate(&mut self) {
@@ -137,7 +237,7 @@ impl<T> Storage<T> {
 
     #[inline]
     pub fn len(&self) -> usize {
-        self.len
+        self.len + self.archive.len()
     }
 
     /// Swap implementation for Row<T>.
@@ -146,10 +246,10 @@ impl<T> Storage<T> {
     /// swap than going through slice::swap.
     ///
     /// The default implementation from swap generates 8 movups and 4 movaps
-    /// instructions. This implementation achieves the swap in only 8 movups
-    /// instructions.
+    /// instructions, plus the moves for the trailing qword. This implementation
+    /// achieves the swap in only 8 movups instructions and those moves.
     pub fn swap(&mut self, a: Line, b: Line) {
-        debug_assert_eq!(mem::size_of::<Row<T>>(), mem::size_of::<usize>() * 4);
+        debug_assert_eq!(mem::size_of::<Row<T>>(), mem::size_of::<usize>() * 5);
 
         let a = self.compute_index(a);
         let b = self.compute_index(b);
@@ -165,7 +265,7 @@ impl<T> Storage<T> {
             //
             // The optimizer unrolls this loop and vectorizes it.
             let mut tmp: MaybeUninit<usize>;
-            for i in 0..4 {
+            for i in 0..5 {
                 tmp = *a_ptr.offset(i);
                 *a_ptr.offset(i) = *b_ptr.offset(i);
                 *b_ptr.offset(i) = tmp;
@@ -195,12 +295,16 @@ impl<T> Storage<T> {
     /// Update the raw storage buffer.
     #[inline]
     pub fn replace_inner(&mut self, vec: Vec<Row<T>>) {
+        if let Some(row) = vec.first() {
+            self.archive.set_columns(row.len());
+        }
+
         self.len = vec.len();
         self.inner = vec;
         self.zero = 0;
     }
 
-    /// Remove all rows from storage.
+    /// Remove all uncompressed rows from storage.
     #[inline]
     pub fn take_all(&mut self) -> Vec<Row<T>> {
         self.truncate();
@@ -213,12 +317,18 @@ impl<T> Storage<T> {
         buffer
     }
 
-    /// Compute actual index in underlying storage given the requested index.
+    /// Distance of a line from the bottommost line.
     #[inline]
-    fn compute_index(&self, requested: Line) -> usize {
+    fn positive_index(&self, requested: Line) -> usize {
         debug_assert!(requested.0 < self.visible_lines as i32);
 
-        let positive = -(requested - self.visible_lines).0 as usize - 1;
+        -(requested - self.visible_lines).0 as usize - 1
+    }
+
+    /// Compute actual index in underlying storage given the requested index.
+    #[inline]
+    fn compute_index(&self, requested: Line) -> usize {
+        let positive = self.positive_index(requested);
 
         debug_assert!(positive < self.len);
 
@@ -252,6 +362,11 @@ impl<T> Index<Line> for Storage<T> {
 
     #[inline]
     fn index(&self, index: Line) -> &Self::Output {
+        let positive = self.positive_index(index);
+        if positive >= self.len {
+            return self.archive.get(positive - self.len);
+        }
+
         let index = self.compute_index(index);
         &self.inner[index]
     }
@@ -260,13 +375,49 @@ impl<T> Index<Line> for Storage<T> {
 impl<T> IndexMut<Line> for Storage<T> {
     #[inline]
     fn index_mut(&mut self, index: Line) -> &mut Self::Output {
+        let positive = self.positive_index(index);
+        if positive >= self.len {
+            return self.archive.get_mut(positive - self.len);
+        }
+
         let index = self.compute_index(index);
         &mut self.inner[index]
     }
 }
 
+/// Sequential access to the rows of a [`Storage`].
+///
+/// Compressed lines are decompressed one chunk at a time, without storing them in the archive.
+pub struct RowReader<'a, T: Clone> {
+    storage: &'a Storage<T>,
+
+    /// Index and rows of the last accessed chunk.
+    chunk: Option<(usize, Cow<'a, [Row<T>]>)>,
+}
+
+impl<'a, T: Clone> RowReader<'a, T> {
+    /// Access a row, which is only borrowed until the next one is read.
+    pub fn row(&mut self, line: Line) -> &Row<T> {
+        let storage = self.storage;
+        let positive = storage.positive_index(line);
+        if positive < storage.len {
+            return &storage.inner[storage.compute_index(line)];
+        }
+
+        let (chunk, index) = storage.archive.locate(positive - storage.len);
+        if self.chunk.as_ref().map_or(true, |(current, _)| *current != chunk) {
+            self.chunk = Some((chunk, storage.archive.chunk(chunk)));
+        }
+
+        &self.chunk.as_ref().unwrap().1[index]
+    }
+}
+
 #[cfg(test)]
 mod tests {
+    use std::borrow::Cow;
+
+    use crate::grid::archive::CHUNK_SIZE;
     use crate::grid::row::Row;
     use crate::grid::storage::{Storage, MAX_CACHE_SIZE};
     use crate::grid::GridCell;
@@ -356,6 +507,7 @@ mod tests {
             zero: 0,
             visible_lines: 3,
             len: 3,
+            archive: Default::default(),
         };
 
         // Grow buffer.
@@ -367,6 +519,7 @@ mod tests {
             zero: 0,
             visible_lines: 4,
             len: 4,
+            archive: Default::default(),
         };
         expected.inner.append(&mut vec![filled_row('\0'); MAX_CACHE_SIZE]);
 
@@ -397,6 +550,7 @@ mod tests {
             zero: 1,
             visible_lines: 3,
             len: 3,
+            archive: Default::default(),
         };
 
         // Grow buffer.
@@ -408,6 +562,7 @@ mod tests {
             zero: 0,
             visible_lines: 4,
             len: 4,
+            archive: Default::default(),
         };
         expected.inner.append(&mut vec![filled_row('\0'); MAX_CACHE_SIZE]);
 
@@ -435,6 +590,7 @@ mod tests {
             zero: 1,
             visible_lines: 3,
             len: 3,
+            archive: Default::default(),
         };
 
         // Shrink buffer.
@@ -446,6 +602,7 @@ mod tests {
             zero: 1,
             visible_lines: 2,
             len: 2,
+            archive: Default::default(),
         };
         assert_eq!(storage.visible_lines, expected.visible_lines);
         assert_eq!(storage.inner, expected.inner);
@@ -471,6 +628,7 @@ mod tests {
             zero: 0,
             visible_lines: 3,
             len: 3,
+            archive: Default::default(),
         };
 
         // Shrink buffer.
@@ -482,6 +640,7 @@ mod tests {
             zero: 0,
             visible_lines: 2,
             len: 2,
+            archive: Default::default(),
         };
         assert_eq!(storage.visible_lines, expected.visible_lines);
         assert_eq!(storage.inner, expected.inner);
@@ -520,6 +679,7 @@ mod tests {
             zero: 2,
             visible_lines: 6,
             len: 6,
+            archive: Default::default(),
         };
 
         // Shrink buffer.
@@ -538,6 +698,7 @@ mod tests {
             zero: 2,
             visible_lines: 2,
             len: 2,
+            archive: Default::default(),
         };
         assert_eq!(storage.visible_lines, expected.visible_lines);
         assert_eq!(storage.inner, expected.inner);
@@ -572,6 +733,7 @@ mod tests {
             zero: 2,
             visible_lines: 1,
             len: 2,
+            archive: Default::default(),
         };
 
         // Truncate buffer.
@@ -583,6 +745,7 @@ mod tests {
             zero: 0,
             visible_lines: 1,
             len: 2,
+            archive: Default::default(),
         };
         assert_eq!(storage.visible_lines, expected.visible_lines);
         assert_eq!(storage.inner, expected.inner);
@@ -607,6 +770,7 @@ mod tests {
             zero: 2,
             visible_lines: 1,
             len: 2,
+            archive: Default::default(),
         };
 
         // Truncate buffer.
@@ -618,6 +782,7 @@ mod tests {
             zero: 0,
             visible_lines: 1,
             len: 2,
+            archive: Default::default(),
         };
         assert_eq!(storage.visible_lines, expected.visible_lines);
         assert_eq!(storage.inner, expected.inner);
@@ -664,6 +829,7 @@ mod tests {
             zero: 2,
             visible_lines: 0,
             len: 6,
+            archive: Default::default(),
         };
 
         // Shrink buffer.
@@ -682,6 +848,7 @@ mod tests {
             zero: 2,
             visible_lines: 0,
             len: 3,
+            archive: Default::default(),
         };
         assert_eq!(storage.inner, shrinking_expected.inner);
         assert_eq!(storage.zero, shrinking_expected.zero);
@@ -703,6 +870,7 @@ mod tests {
             zero: 2,
             visible_lines: 0,
             len: 4,
+            archive: Default::default(),
         };
 
         assert_eq!(storage.inner, growing_expected.inner);
@@ -725,6 +893,7 @@ mod tests {
             zero: 2,
             visible_lines: 0,
             len: 6,
+            archive: Default::default(),
         };
 
         // Initialize additional lines.
@@ -742,7 +911,13 @@ mod tests {
         ];
         let expected_init_size = std::cmp::max(init_size, MAX_CACHE_SIZE);
         expected_inner.append(&mut vec![filled_row('\0'); expected_init_size]);
-        let expected_storage = Storage { inner: expected_inner, zero: 0, visible_lines: 0, len: 9 };
+        let expected_storage = Storage {
+            inner: expected_inner,
+            zero: 0,
+            visible_lines: 0,
+            len: 9,
+            archive: Default::default(),
+        };
 
         assert_eq!(storage.len, expected_storage.len);
         assert_eq!(storage.zero, expected_storage.zero);
@@ -756,6 +931,7 @@ mod tests {
             zero: 2,
             visible_lines: 0,
             len: 3,
+            archive: Default::default(),
         };
 
         storage.rotate(2);
@@ -763,6 +939,85 @@ mod tests {
         assert!(storage.zero < storage.inner.len());
     }
 
+    #[test]
+    fn compress_history() {
+        let count = 2 * CHUNK_SIZE + 10;
+        let mut storage = compressed_storage(count);
+
+        assert_eq!(storage.len, 11);
+        assert_eq!(storage.archive.len(), 2 * CHUNK_SIZE);
+        assert_eq!(storage.len(), count + 1);
+        for i in 0..=count {
+            assert_eq!(storage[Line(-(i as i32))], filled_row(digit(i)));
+        }
+
+        storage[Line(-(count as i32))] = filled_row('x');
+        assert_eq!(storage[Line(-(count as i32))], filled_row('x'));
+    }
+
+    #[test]
+    fn read_compressed() {
+        let count = 2 * CHUNK_SIZE + 10;
+        let storage = compressed_storage(count);
+
+        let mut reader = storage.reader();
+        for i in 0..=count {
+            assert_eq!(reader.row(Line(-(i as i32))), &filled_row(digit(i)));
+        }
+        assert!(matches!(storage.archive.chunk(0), Cow::Owned(_)));
+    }
+
+    #[test]
+    fn grow_from_compressed_history() {
+        let count = 2 * CHUNK_SIZE + 10;
+        let mut storage = compressed_storage(count);
+
+        // Pull lines from history into the visible area, like `Grid::grow_lines`.
+        storage.grow_visible_lines(20);
+        storage.shrink_lines(19);
+
+        assert_eq!(storage.archive.len(), CHUNK_SIZE);
+        assert_eq!(storage.len(), count + 1);
+        for i in 0..=count {
+            assert_eq!(storage[Line(19 - i as i32)], filled_row(digit(i)));
+        }
+    }
+
+    #[test]
+    fn shrink_compressed_history() {
+        let count = 2 * CHUNK_SIZE + 10;
+        let mut storage = compressed_storage(count);
+
+        storage.shrink_history(CHUNK_SIZE + 5);
+        assert_eq!(storage.len, 11);
+        assert_eq!(storage.len(), count + 1 - CHUNK_SIZE - 5);
+
+        let oldest = count - CHUNK_SIZE - 5;
+        assert_eq!(storage[Line(-(oldest as i32))], filled_row(digit(oldest)));
+
+        storage.shrink_history(CHUNK_SIZE);
+        assert_eq!(storage.archive.len(), 0);
+        assert_eq!(storage.len(), 6);
+        assert_eq!(storage[Line(-5)], filled_row('5'));
+    }
+
+    /// Create storage with a single visible line and `count` lines of history, compressing all
+    /// but the most recent ones.
+    fn compressed_storage(count: usize) -> Storage<char> {
+        let mut storage = Storage::<char>::with_capacity(1, 1);
+        storage.initialize(count, 1);
+        for i in 0..=count {
+            storage[Line(-(i as i32))] = filled_row(digit(i));
+        }
+
+        storage.compress_history(5, 1);
+        storage
+    }
+
+    fn digit(i: usize) -> char {
+        char::from_digit((i % 10) as u32, 10).unwrap()
+    }
+
     fn filled_row(content: char) -> Row<char> {
         let mut row = Row::new(1);
         row[Column(0)] = content;
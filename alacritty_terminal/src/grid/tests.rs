@@ -2,6 +2,7 @@
 
 use super::*;
 
+use crate::ansi::{Color, SemanticMark};
 use crate::term::cell::Cell;
 
 impl GridCell for usize {
@@ -54,6 +55,52 @@ fn scroll_up() {
     assert_eq!(grid[Line(9)].occ, 0);
 }
 
+// Compressed history lines are discarded first once the history limit is reached.
+#[test]
+fn scroll_up_compressed_history() {
+    let history = MAX_UNCOMPRESSED_LINES + 2 * archive::CHUNK_SIZE;
+    let mut grid = Grid::<usize>::new(1, 1, history);
+
+    let count = history + archive::CHUNK_SIZE + 5;
+    for i in 1..=count {
+        grid.scroll_up::<usize>(&(Line(0)..Line(1)), 1);
+        grid.compress_history();
+        grid[Line(0)][Column(0)] = i;
+    }
+
+    assert_eq!(grid.history_size(), history);
+    assert_eq!(grid.compressed_lines(), 2 * archive::CHUNK_SIZE - 5);
+    for i in 0..=history {
+        assert_eq!(grid[Line(-(i as i32))][Column(0)], count - i);
+    }
+}
+
+// Compressed history lines are resized without reflow.
+#[test]
+fn resize_compressed_history() {
+    let history = MAX_UNCOMPRESSED_LINES + archive::CHUNK_SIZE;
+    let mut grid = Grid::<Cell>::new(1, 2, history);
+
+    for _ in 0..history {
+        grid[Line(0)][Column(0)].c = 'a';
+        grid[Line(0)][Column(1)].c = 'b';
+        grid.scroll_up(&(Line(0)..Line(1)), 1);
+        grid.compress_history();
+    }
+    assert_eq!(grid.compressed_lines(), archive::CHUNK_SIZE);
+
+    grid.resize(true, 1, 3);
+    assert_eq!(grid.history_size(), history);
+    assert_eq!(grid[Line(-(history as i32))].len(), 3);
+    assert_eq!(grid[Line(-(history as i32))][Column(1)].c, 'b');
+
+    grid.resize(true, 1, 1);
+    assert_eq!(grid.history_size(), history);
+    assert_eq!(grid.compressed_lines(), archive::CHUNK_SIZE);
+    assert_eq!(grid[Line(-(history as i32))].len(), 1);
+    assert_eq!(grid[Line(-(history as i32))][Column(0)].c, 'a');
+}
+
 // Scroll down moves lines downward.
 #[test]
 fn scroll_down() {
@@ -348,6 +395,81 @@ fn shrink_reflow_disabled() {
     assert_eq!(grid[Line(0)][Column(1)], cell('2'));
 }
 
+#[test]
+fn scroll_up_marks() {
+    let mut grid = Grid::<Cell>::new(2, 2, 10);
+    grid[Line(0)].set_mark(Column(0), SemanticMark::PromptStart);
+    grid[Line(1)].set_mark(Column(1), SemanticMark::OutputStart);
+
+    grid.scroll_up::<Color>(&(Line(0)..Line(2)), 2);
+
+    assert_eq!(grid[Line(-2)].marks(), &[(Column(0), SemanticMark::PromptStart)]);
+    assert_eq!(grid[Line(-1)].marks(), &[(Column(1), SemanticMark::OutputStart)]);
+    assert!(grid[Line(0)].marks().is_empty());
+    assert!(grid[Line(1)].marks().is_empty());
+}
+
+#[test]
+fn shrink_reflow_marks() {
+    let mut grid = Grid::<Cell>::new(1, 5, 2);
+    for i in 0..5 {
+        grid[Line(0)][Column(i)] = cell('x');
+    }
+    grid[Line(0)].set_mark(Column(0), SemanticMark::PromptStart);
+    grid[Line(0)].set_mark(Column(2), SemanticMark::CommandStart);
+    grid[Line(0)].set_mark(Column(4), SemanticMark::OutputStart);
+
+    grid.resize(true, 1, 2);
+
+    assert_eq!(grid[Line(-2)].marks(), &[(Column(0), SemanticMark::PromptStart)]);
+    assert_eq!(grid[Line(-1)].marks(), &[(Column(0), SemanticMark::CommandStart)]);
+    assert_eq!(grid[Line(0)].marks(), &[(Column(0), SemanticMark::OutputStart)]);
+
+    grid.resize(true, 1, 5);
+
+    assert_eq!(
+        grid[Line(-1)].marks(),
+        &[
+            (Column(0), SemanticMark::PromptStart),
+            (Column(2), SemanticMark::CommandStart),
+            (Column(4), SemanticMark::OutputStart),
+        ]
+    );
+}
+
+#[test]
+fn grow_reflow_marks() {
+    let mut grid = Grid::<Cell>::new(2, 2, 0);
+    grid[Line(0)][Column(0)] = cell('1');
+    grid[Line(0)][Column(1)] = wrap_cell('2');
+    grid[Line(1)][Column(0)] = cell('3');
+    grid[Line(0)].set_mark(Column(1), SemanticMark::CommandStart);
+    grid[Line(1)].set_mark(Column(0), SemanticMark::OutputStart);
+    grid[Line(1)].set_mark(Column(1), SemanticMark::CommandEnd(None));
+
+    grid.resize(true, 2, 3);
+
+    assert_eq!(
+        grid[Line(0)].marks(),
+        &[
+            (Column(1), SemanticMark::CommandStart),
+            (Column(2), SemanticMark::OutputStart),
+            (Column(2), SemanticMark::CommandEnd(None)),
+        ]
+    );
+    assert!(grid[Line(1)].marks().is_empty());
+}
+
+#[test]
+fn shrink_reflow_disabled_marks() {
+    let mut grid = Grid::<Cell>::new(1, 5, 2);
+    grid[Line(0)].set_mark(Column(4), SemanticMark::PromptStart);
+
+    grid.resize(false, 1, 2);
+
+    assert_eq!(grid[Line(0)].marks(), &[(Column(1), SemanticMark::PromptStart)]);
+}
+
 // https://github.com/rust-lang/rust-clippy/pull/6375
 #[allow(clippy::all)]
 fn cell(c: char) -> Cell {
@@ -0,0 +1,392 @@
+//! Headless terminal for driving programs without a window.
+//!
+//! This ties together the PTY, the [`EventLoop`] and the [`Term`], making it possible to run
+//! programs and inspect their output in tests or other automation.
+
+use std::borrow::Cow;
+use std::error::Error as StdError;
+use std::fmt::{self, Display, Formatter};
+use std::io;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+use mio_extras::channel::Sender;
+use regex_automata::dfa::dense::BuildError;
+
+use crate::config::{Config, PtyConfig};
+use crate::event::{Event, EventListener, Notify, OnResize, WindowSize};
+use crate::event_loop::{EventLoop, Msg, Notifier, State};
+use crate::grid::Dimensions;
+use crate::index::{Column, Line, Point};
+use crate::sync::FairMutex;
+use crate::term::cell::Flags;
+use crate::term::search::{Match, RegexSearch};
+use crate::term::{ClipboardType, Term};
+use crate::tty;
+
+/// Errors while waiting for a headless terminal.
+#[derive(Debug)]
+pub enum Error {
+    /// Condition was not met before the timeout.
+    Timeout,
+
+    /// Program exited before the condition was met.
+    Exited,
+
+    /// Invalid regex pattern.
+    Regex(Box<BuildError>),
+
+    /// PTY creation failed.
+    Io(io::Error),
+}
+
+impl StdError for Error {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        match self {
+            Error::Regex(err) => err.source(),
+            Error::Io(err) => err.source(),
+            Error::Timeout | Error::Exited => None,
+        }
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Timeout => write!(f, "Timed out waiting for terminal"),
+            Error::Exited => write!(f, "Program exited while waiting for terminal"),
+            Error::Regex(err) => write!(f, "Invalid regex: {}", err),
+            Error::Io(err) => write!(f, "Unable to create PTY: {}", err),
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(val: io::Error) -> Self {
+        Error::Io(val)
+    }
+}
+
+/// Terminal state shared with the event listener.
+struct SharedState {
+    /// Number of updates of the terminal content.
+    generation: u64,
+
+    /// Time of the last update of the terminal content.
+    last_update: Instant,
+
+    /// Whether the program has exited.
+    exited: bool,
+
+    /// Window title requested by the program.
+    title: Option<String>,
+
+    /// Contents of the clipboard and selection.
+    clipboard: String,
+    selection: String,
+
+    /// Size reported to the program.
+    window_size: WindowSize,
+
+    /// Channel for writing responses to the PTY.
+    sender: Option<Sender<Msg>>,
+}
+
+/// Event listener of a headless terminal.
+#[derive(Clone)]
+pub struct HeadlessListener {
+    state: Arc<(Mutex<SharedState>, Condvar)>,
+}
+
+impl HeadlessListener {
+    fn lock(&self) -> MutexGuard<'_, SharedState> {
+        self.state.0.lock().unwrap_or_else(|err| err.into_inner())
+    }
+
+    /// Write a response to the PTY.
+    fn write(state: &SharedState, text: String) {
+        if let Some(sender) = &state.sender {
+            Notifier(sender.clone()).notify(text.into_bytes());
+        }
+    }
+}
+
+impl EventListener for HeadlessListener {
+    fn send_event(&self, event: Event) {
+        let mut state = self.lock();
+
+        match event {
+            Event::Wakeup => {
+                state.generation += 1;
+                state.last_update = Instant::now();
+            },
+            Event::Exit => state.exited = true,
+            Event::Title(title) => state.title = Some(title),
+            Event::ResetTitle => state.title = None,
+            Event::PtyWrite(text) => Self::write(&state, text),
+            Event::ClipboardStore(ClipboardType::Clipboard, text) => state.clipboard = text,
+            Event::ClipboardStore(ClipboardType::Selection, text) => state.selection = text,
+            Event::ClipboardLoad(clipboard_type, format) => {
+                let text = match clipboard_type {
+                    ClipboardType::Clipboard => format(&state.clipboard),
+                    ClipboardType::Selection => format(&state.selection),
+                };
+                Self::write(&state, text);
+            },
+            Event::TextAreaSizeRequest(format) => {
+                let text = format(state.window_size);
+                Self::write(&state, text);
+            },
+            _ => return,
+        }
+
+        self.state.1.notify_all();
+    }
+}
+
+/// Terminal running a program without any window.
+pub struct Headless {
+    terminal: Arc<FairMutex<Term<HeadlessListener>>>,
+    listener: HeadlessListener,
+    notifier: Notifier,
+    io_thread: Option<JoinHandle<(EventLoop<tty::Pty, HeadlessListener>, State)>>,
+}
+
+impl Headless {
+    /// Spawn the configured shell in a new headless terminal.
+    ///
+    /// The terminal keeps its content after the program has exited.
+    pub fn new(config: &Config, pty_config: &PtyConfig, size: WindowSize) -> Result<Self, Error> {
+        let state = SharedState {
+            generation: 0,
+            last_update: Instant::now(),
+            exited: false,
+            title: None,
+            clipboard: String::new(),
+            selection: String::new(),
+            window_size: size,
+            sender: None,
+        };
+        let listener = HeadlessListener { state: Arc::new((Mutex::new(state), Condvar::new())) };
+
+        let mut terminal = Term::new(config, &size, listener.clone());
+        terminal.set_window_size(size);
+        let terminal = Arc::new(FairMutex::new(terminal));
+
+        let pty = tty::new(pty_config, size, 0)?;
+        let event_loop = EventLoop::new(terminal.clone(), listener.clone(), pty, false, false);
+
+        let sender = event_loop.channel();
+        listener.lock().sender = Some(sender.clone());
+
+        let io_thread = Some(event_loop.spawn());
+
+        Ok(Self { terminal, listener, notifier: Notifier(sender), io_thread })
+    }
+
+    /// Access the terminal state.
+    pub fn terminal(&self) -> &Arc<FairMutex<Term<HeadlessListener>>> {
+        &self.terminal
+    }
+
+    /// Write bytes to the program's input.
+    pub fn send<B: Into<Cow<'static, [u8]>>>(&self, bytes: B) {
+        self.notifier.notify(bytes);
+    }
+
+    /// Write text to the program's input.
+    pub fn send_text(&self, text: &str) {
+        self.send(text.as_bytes().to_vec());
+    }
+
+    /// Resize the terminal and its PTY.
+    pub fn resize(&mut self, columns: u16, lines: u16) {
+        let mut window_size = self.listener.lock().window_size;
+        window_size.num_cols = columns;
+        window_size.num_lines = lines;
+        self.listener.lock().window_size = window_size;
+
+        let mut terminal = self.terminal.lock();
+        terminal.resize(window_size);
+        terminal.set_window_size(window_size);
+        drop(terminal);
+
+        self.notifier.on_resize(window_size);
+    }
+
+    /// Window title requested by the program.
+    pub fn title(&self) -> Option<String> {
+        self.listener.lock().title.clone()
+    }
+
+    /// Check if the program has exited.
+    pub fn exited(&self) -> bool {
+        self.listener.lock().exited
+    }
+
+    /// Text of the visible lines, with trailing whitespace removed.
+    pub fn snapshot(&self) -> String {
+        let terminal = self.terminal.lock();
+
+        let mut lines = Vec::with_capacity(terminal.screen_lines());
+        for line in (0..terminal.screen_lines() as i32).map(Line) {
+            let mut text = String::new();
+            for cell in &terminal.grid()[line][..] {
+                if cell.flags.contains(Flags::WIDE_CHAR_SPACER) {
+                    continue;
+                }
+
+                text.push(cell.c);
+                text.extend(cell.zerowidth().into_iter().flatten());
+            }
+            lines.push(text.trim_end().to_owned());
+        }
+
+        lines.join("\n")
+    }
+
+    /// Current cursor position.
+    pub fn cursor(&self) -> Point {
+        self.terminal.lock().grid().cursor.point
+    }
+
+    /// Wait until `predicate` is true for the terminal.
+    ///
+    /// The predicate is checked again every time the terminal content changes.
+    pub fn wait_for<F>(&self, timeout: Duration, mut predicate: F) -> Result<(), Error>
+    where
+        F: FnMut(&Term<HeadlessListener>) -> bool,
+    {
+        let deadline = Instant::now() + timeout;
+
+        loop {
+            let generation = self.listener.lock().generation;
+
+            if predicate(&self.terminal.lock()) {
+                return Ok(());
+            }
+
+            let mut state = self.listener.lock();
+            while state.generation == generation {
+                if state.exited {
+                    return Err(Error::Exited);
+                }
+
+                let remaining = deadline.saturating_duration_since(Instant::now());
+                if remaining.is_zero() {
+                    return Err(Error::Timeout);
+                }
+
+                let condvar = &self.listener.state.1;
+                state =
+                    condvar.wait_timeout(state, remaining).unwrap_or_else(|err| err.into_inner()).0;
+            }
+        }
+    }
+
+    /// Wait until the visible lines contain a match for `regex`.
+    pub fn wait_for_regex(&self, regex: &str, timeout: Duration) -> Result<Match, Error> {
+        let regex = RegexSearch::new(regex).map_err(Error::Regex)?;
+
+        let mut found = None;
+        self.wait_for(timeout, |terminal| {
+            let start = Point::new(Line(0), Column(0));
+            let end = Point::new(terminal.bottommost_line(), terminal.last_column());
+            found = terminal.regex_search_right(&regex, start, end);
+            found.is_some()
+        })?;
+
+        Ok(found.unwrap())
+    }
+
+    /// Wait until the cursor is at `point`.
+    pub fn wait_for_cursor(&self, point: Point, timeout: Duration) -> Result<(), Error> {
+        self.wait_for(timeout, |terminal| terminal.grid().cursor.point == point)
+    }
+
+    /// Wait until the terminal content did not change for `idle`.
+    pub fn wait_for_idle(&self, idle: Duration, timeout: Duration) -> Result<(), Error> {
+        let deadline = Instant::now() + timeout;
+
+        let mut state = self.listener.lock();
+        loop {
+            let now = Instant::now();
+            let idle_until = state.last_update + idle;
+            if idle_until <= now || state.exited {
+                return Ok(());
+            }
+
+            if deadline <= now {
+                return Err(Error::Timeout);
+            }
+
+            let wait = idle_until.min(deadline) - now;
+            let condvar = &self.listener.state.1;
+            state = condvar.wait_timeout(state, wait).unwrap_or_else(|err| err.into_inner()).0;
+        }
+    }
+
+    /// Wait until the program has exited.
+    pub fn wait_for_exit(&self, timeout: Duration) -> Result<(), Error> {
+        match self.wait_for(timeout, |_| false) {
+            Err(Error::Exited) => Ok(()),
+            result => result,
+        }
+    }
+}
+
+impl Drop for Headless {
+    fn drop(&mut self) {
+        let _ = self.notifier.0.send(Msg::Shutdown);
+        if let Some(io_thread) = self.io_thread.take() {
+            let _ = io_thread.join();
+        }
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    use crate::config::Program;
+
+    const TIMEOUT: Duration = Duration::from_secs(10);
+
+    fn spawn(script: &str) -> Headless {
+        let args = vec![String::from("-c"), script.into()];
+        let program = Program::WithArgs { program: String::from("/bin/sh"), args };
+        let pty_config = PtyConfig { shell: Some(program), ..PtyConfig::default() };
+        let size = WindowSize { num_cols: 20, num_lines: 5, cell_width: 1, cell_height: 1 };
+
+        Headless::new(&Config::default(), &pty_config, size).unwrap()
+    }
+
+    #[test]
+    fn input_and_output() {
+        let headless = spawn("printf 'name? '; read name; printf 'hello %s' \"$name\"; read _");
+
+        headless.wait_for_regex("name\\? ", TIMEOUT).unwrap();
+        headless.send_text("alacritty\r");
+
+        let found = headless.wait_for_regex("hello [a-z]+", TIMEOUT).unwrap();
+        assert_eq!(*found.start(), Point::new(Line(1), Column(0)));
+        headless.wait_for_cursor(Point::new(Line(1), Column(15)), TIMEOUT).unwrap();
+        assert_eq!(headless.snapshot(), "name? alacritty\nhello alacritty\n\n\n");
+
+        headless.send_text("\r");
+        headless.wait_for_exit(TIMEOUT).unwrap();
+        assert!(headless.exited());
+    }
+
+    #[test]
+    fn resize() {
+        let mut headless = spawn("read _; stty size; read _");
+
+        headless.resize(30, 8);
+        headless.send_text("\r");
+
+        headless.wait_for_regex("8 30", TIMEOUT).unwrap();
+        assert_eq!(headless.terminal().lock().columns(), 30);
+    }
+}
@@ -8,8 +8,12 @@ pub mod ansi;
 pub mod config;
 pub mod event;
 pub mod event_loop;
+pub mod graphics;
 pub mod grid;
+pub mod headless;
 pub mod index;
+pub mod recording;
+pub mod replay;
 pub mod selection;
 pub mod sync;
 pub mod term;
@@ -0,0 +1,257 @@
+//! Recording of terminal sessions in the asciicast v2 format.
+//!
+//! See <https://docs.asciinema.org/manual/asciicast/v2/> for the format specification.
+
+use std::fmt::{self, Debug, Formatter};
+use std::io::{self, Write};
+use std::str;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+use serde::Serialize;
+
+use crate::event::WindowSize;
+
+/// Version of the asciicast format.
+const ASCIICAST_VERSION: u8 = 2;
+
+/// Header of an asciicast file.
+#[derive(Serialize)]
+struct Header {
+    version: u8,
+    width: u16,
+    height: u16,
+    timestamp: u64,
+}
+
+/// Writer for asciicast v2 recordings.
+pub struct Recorder {
+    writer: Box<dyn Write + Send>,
+    start: Instant,
+    record_input: bool,
+
+    /// Current terminal dimensions in cells.
+    columns: u16,
+    lines: u16,
+
+    /// Incomplete UTF-8 sequences at the end of the last output and input.
+    output: Vec<u8>,
+    input: Vec<u8>,
+}
+
+impl Recorder {
+    /// Start a new recording, writing the asciicast header to `writer`.
+    ///
+    /// Input events are only recorded when `record_input` is `true`.
+    pub fn new<W>(writer: W, window_size: WindowSize, record_input: bool) -> io::Result<Self>
+    where
+        W: Write + Send + 'static,
+    {
+        let mut recorder = Self {
+            writer: Box::new(writer),
+            start: Instant::now(),
+            columns: window_size.num_cols,
+            lines: window_size.num_lines,
+            record_input,
+            output: Vec::new(),
+            input: Vec::new(),
+        };
+
+        let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+        let header = Header {
+            version: ASCIICAST_VERSION,
+            width: recorder.columns,
+            height: recorder.lines,
+            timestamp,
+        };
+
+        let mut line = serde_json::to_vec(&header)?;
+        line.push(b'\n');
+        recorder.writer.write_all(&line)?;
+
+        Ok(recorder)
+    }
+
+    /// Record bytes read from the PTY.
+    pub fn output(&mut self, bytes: &[u8]) -> io::Result<()> {
+        let text = decode_utf8(&mut self.output, bytes);
+        self.write_event("o", &text)
+    }
+
+    /// Record bytes written to the PTY.
+    pub fn input(&mut self, bytes: &[u8]) -> io::Result<()> {
+        if !self.record_input {
+            return Ok(());
+        }
+
+        let text = decode_utf8(&mut self.input, bytes);
+        self.write_event("i", &text)
+    }
+
+    /// Record a change of the terminal dimensions.
+    pub fn resize(&mut self, window_size: WindowSize) -> io::Result<()> {
+        if window_size.num_cols == self.columns && window_size.num_lines == self.lines {
+            return Ok(());
+        }
+
+        self.columns = window_size.num_cols;
+        self.lines = window_size.num_lines;
+
+        let size = format!("{}x{}", self.columns, self.lines);
+        self.write_event("r", &size)
+    }
+
+    /// Write a single event line.
+    fn write_event(&mut self, event_type: &str, data: &str) -> io::Result<()> {
+        if data.is_empty() {
+            return Ok(());
+        }
+
+        // Timestamps are stored in seconds, with microsecond precision.
+        let time = self.start.elapsed().as_micros() as f64 / 1_000_000.;
+
+        let mut line = serde_json::to_vec(&(time, event_type, data))?;
+        line.push(b'\n');
+        self.writer.write_all(&line)
+    }
+}
+
+impl Debug for Recorder {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Recorder")
+            .field("start", &self.start)
+            .field("record_input", &self.record_input)
+            .field("columns", &self.columns)
+            .field("lines", &self.lines)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Convert bytes to text, keeping incomplete UTF-8 sequences at the end in `pending`.
+///
+/// Invalid UTF-8 is replaced with the replacement character.
+fn decode_utf8(pending: &mut Vec<u8>, bytes: &[u8]) -> String {
+    pending.extend_from_slice(bytes);
+
+    let mut text = String::new();
+    loop {
+        match str::from_utf8(pending) {
+            Ok(valid) => {
+                text.push_str(valid);
+                pending.clear();
+                break;
+            },
+            Err(err) => {
+                let valid_up_to = err.valid_up_to();
+                text.push_str(str::from_utf8(&pending[..valid_up_to]).unwrap());
+
+                match err.error_len() {
+                    Some(invalid_len) => {
+                        text.push(char::REPLACEMENT_CHARACTER);
+                        pending.drain(..valid_up_to + invalid_len);
+                    },
+                    None => {
+                        pending.drain(..valid_up_to);
+                        break;
+                    },
+                }
+            },
+        }
+    }
+
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::sync::{Arc, Mutex};
+
+    use serde_json::Value;
+
+    #[derive(Clone, Default)]
+    struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Buffer {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl Buffer {
+        fn lines(&self) -> Vec<Value> {
+            let buffer = self.0.lock().unwrap();
+            let text = str::from_utf8(&buffer).unwrap();
+            text.lines().map(|line| serde_json::from_str(line).unwrap()).collect()
+        }
+    }
+
+    fn window_size(num_cols: u16, num_lines: u16) -> WindowSize {
+        WindowSize { num_cols, num_lines, cell_width: 1, cell_height: 1 }
+    }
+
+    #[test]
+    fn header() {
+        let buffer = Buffer::default();
+        Recorder::new(buffer.clone(), window_size(80, 24), false).unwrap();
+
+        let lines = buffer.lines();
+        assert_eq!(lines.len(), 1);
+        assert_eq!(lines[0]["version"], 2);
+        assert_eq!(lines[0]["width"], 80);
+        assert_eq!(lines[0]["height"], 24);
+        assert!(lines[0]["timestamp"].is_u64());
+    }
+
+    #[test]
+    fn events() {
+        let buffer = Buffer::default();
+        let mut recorder = Recorder::new(buffer.clone(), window_size(80, 24), true).unwrap();
+
+        recorder.output(b"\x1b[1mbold\x1b[0m\r\n").unwrap();
+        recorder.input(b"ls\r").unwrap();
+        recorder.resize(window_size(80, 24)).unwrap();
+        recorder.resize(window_size(100, 30)).unwrap();
+
+        let lines = buffer.lines();
+        assert_eq!(lines.len(), 4);
+        assert_eq!(lines[1][1], "o");
+        assert_eq!(lines[1][2], "\x1b[1mbold\x1b[0m\r\n");
+        assert_eq!(lines[2][1], "i");
+        assert_eq!(lines[2][2], "ls\r");
+        assert_eq!(lines[3][1], "r");
+        assert_eq!(lines[3][2], "100x30");
+        assert!(lines[1][0].as_f64().unwrap() <= lines[3][0].as_f64().unwrap());
+    }
+
+    #[test]
+    fn input_disabled() {
+        let buffer = Buffer::default();
+        let mut recorder = Recorder::new(buffer.clone(), window_size(80, 24), false).unwrap();
+
+        recorder.input(b"secret\r").unwrap();
+
+        assert_eq!(buffer.lines().len(), 1);
+    }
+
+    #[test]
+    fn split_utf8() {
+        let buffer = Buffer::default();
+        let mut recorder = Recorder::new(buffer.clone(), window_size(80, 24), false).unwrap();
+
+        let bytes = "a\u{1f600}b".as_bytes();
+        recorder.output(&bytes[..3]).unwrap();
+        recorder.output(&bytes[3..]).unwrap();
+        recorder.output(b"\xffc").unwrap();
+
+        let lines = buffer.lines();
+        assert_eq!(lines.len(), 4);
+        assert_eq!(lines[1][2], "a");
+        assert_eq!(lines[2][2], "\u{1f600}b");
+        assert_eq!(lines[3][2], "\u{fffd}c");
+    }
+}
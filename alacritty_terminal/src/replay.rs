@@ -0,0 +1,483 @@
+//! Replay of recorded terminal sessions.
+//!
+//! Both asciicast v2 recordings and the raw byte streams written by `--ref-test` are supported.
+
+use std::error::Error as StdError;
+use std::fmt::{self, Display, Formatter};
+use std::fs;
+use std::io::{self, ErrorKind};
+use std::path::Path;
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+use log::{error, info};
+use mio::{self, Events, PollOpt, Ready};
+use mio_extras::channel::{self, Receiver, Sender};
+use serde_json::Value;
+
+use crate::event::{Event, EventListener};
+use crate::event_loop::Msg;
+use crate::recording::Recorder;
+use crate::sync::FairMutex;
+use crate::term::Term;
+use crate::{ansi, thread};
+
+/// Delay between lines of recordings without timing information.
+const RAW_LINE_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Slowest playback speed.
+const MIN_SPEED: f64 = 1. / 16.;
+
+/// Fastest playback speed.
+const MAX_SPEED: f64 = 16.;
+
+/// Errors while loading a recording.
+#[derive(Debug)]
+pub enum Error {
+    /// Unable to read the recording.
+    Io(io::Error),
+
+    /// Invalid line in an asciicast recording.
+    Invalid(usize, String),
+}
+
+impl StdError for Error {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        match self {
+            Error::Io(err) => err.source(),
+            Error::Invalid(..) => None,
+        }
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io(err) => write!(f, "Unable to read recording: {}", err),
+            Error::Invalid(line, err) => write!(f, "Invalid recording on line {}: {}", line, err),
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(val: io::Error) -> Self {
+        Error::Io(val)
+    }
+}
+
+/// Bytes written to the terminal at a specific time.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Frame {
+    /// Time since the start of the recording.
+    pub time: Duration,
+
+    /// Bytes written to the terminal.
+    pub data: Vec<u8>,
+}
+
+/// Recorded terminal session.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Session {
+    /// Terminal dimensions at the start of the recording, as columns and lines.
+    pub size: Option<(usize, usize)>,
+
+    /// Terminal output.
+    pub frames: Vec<Frame>,
+}
+
+impl Session {
+    /// Load a recording from a file.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        Self::parse(&fs::read(path)?)
+    }
+
+    /// Parse an asciicast v2 or raw recording.
+    pub fn parse(bytes: &[u8]) -> Result<Self, Error> {
+        let first_line = bytes.split(|&b| b == b'\n').next().unwrap_or_default();
+        match serde_json::from_slice::<Value>(first_line) {
+            Ok(Value::Object(header)) if header.contains_key("version") => {
+                Self::parse_asciicast(bytes)
+            },
+            _ => Ok(Self::parse_raw(bytes)),
+        }
+    }
+
+    /// Parse an asciicast v2 recording.
+    fn parse_asciicast(bytes: &[u8]) -> Result<Self, Error> {
+        let text = String::from_utf8_lossy(bytes);
+        let mut lines = text.lines().enumerate().map(|(i, line)| (i + 1, line));
+
+        let (_, header) = lines.next().unwrap_or_default();
+        let header: Value =
+            serde_json::from_str(header).map_err(|err| Error::Invalid(1, err.to_string()))?;
+
+        let version = header["version"].as_u64();
+        if version != Some(2) {
+            let err = format!("unsupported asciicast version {}", header["version"]);
+            return Err(Error::Invalid(1, err));
+        }
+
+        let columns = header["width"].as_u64();
+        let lines_count = header["height"].as_u64();
+        let size = columns.zip(lines_count).map(|(cols, lines)| (cols as usize, lines as usize));
+
+        let mut frames: Vec<Frame> = Vec::new();
+        for (line_number, line) in lines.filter(|(_, line)| !line.trim().is_empty()) {
+            let (time, event_type, data): (f64, String, String) = serde_json::from_str(line)
+                .map_err(|err| Error::Invalid(line_number, err.to_string()))?;
+
+            // Only terminal output is replayed.
+            if event_type != "o" {
+                continue;
+            }
+
+            // Clamp timestamps to make sure frames are never out of order.
+            let time = Duration::from_secs_f64(time.clamp(0., u32::MAX as f64));
+            let time = frames.last().map_or(time, |frame| frame.time.max(time));
+
+            frames.push(Frame { time, data: data.into_bytes() });
+        }
+
+        Ok(Self { size, frames })
+    }
+
+    /// Parse a recording without timing information.
+    ///
+    /// Every line is replayed as a separate frame after a fixed delay.
+    fn parse_raw(bytes: &[u8]) -> Self {
+        let frames = bytes
+            .split_inclusive(|&b| b == b'\n')
+            .enumerate()
+            .map(|(i, line)| Frame { time: RAW_LINE_INTERVAL * i as u32, data: line.to_vec() })
+            .collect();
+
+        Self { size: None, frames }
+    }
+}
+
+/// Playback controls.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Control {
+    /// Pause or resume playback.
+    TogglePause,
+
+    /// Pause playback and play the next frame.
+    Step,
+
+    /// Double the playback speed.
+    Faster,
+
+    /// Halve the playback speed.
+    Slower,
+}
+
+/// Channel for controlling a running [`Player`].
+pub struct Controller(pub Sender<Control>);
+
+impl Controller {
+    pub fn send(&self, control: Control) {
+        let _ = self.0.send(control);
+    }
+}
+
+/// Position in the recording.
+struct Clock {
+    /// Recording time at the last change of the clock.
+    position: Duration,
+
+    /// Time of the last change of the clock.
+    anchor: Instant,
+
+    /// Playback speed multiplier.
+    speed: f64,
+
+    paused: bool,
+}
+
+impl Clock {
+    fn new() -> Self {
+        Self { position: Duration::ZERO, anchor: Instant::now(), speed: 1., paused: false }
+    }
+
+    /// Current recording time.
+    fn now(&self) -> Duration {
+        if self.paused {
+            self.position
+        } else {
+            self.position + self.anchor.elapsed().mul_f64(self.speed)
+        }
+    }
+
+    /// Move to a new recording time.
+    fn set(&mut self, position: Duration) {
+        self.position = position;
+        self.anchor = Instant::now();
+    }
+
+    /// Time until the recording time `position` is reached.
+    fn until(&self, position: Duration) -> Duration {
+        position.saturating_sub(self.now()).div_f64(self.speed)
+    }
+}
+
+/// Playback of a recorded session into a terminal.
+///
+/// Replaces the PTY [`EventLoop`] for terminals without a child process.
+///
+/// [`EventLoop`]: crate::event_loop::EventLoop
+pub struct Player<U: EventListener> {
+    poll: mio::Poll,
+    rx: Receiver<Msg>,
+    tx: Sender<Msg>,
+    control_rx: Receiver<Control>,
+    control_tx: Sender<Control>,
+    terminal: Arc<FairMutex<Term<U>>>,
+    event_proxy: U,
+    session: Arc<Session>,
+    parser: ansi::Processor,
+    clock: Clock,
+    next_frame: usize,
+    recorder: Option<Recorder>,
+}
+
+impl<U> Player<U>
+where
+    U: EventListener + Send + 'static,
+{
+    /// Create a new player.
+    pub fn new(
+        terminal: Arc<FairMutex<Term<U>>>,
+        event_proxy: U,
+        session: Arc<Session>,
+    ) -> Player<U> {
+        let (tx, rx) = channel::channel();
+        let (control_tx, control_rx) = channel::channel();
+        Player {
+            poll: mio::Poll::new().expect("create mio Poll"),
+            rx,
+            tx,
+            control_rx,
+            control_tx,
+            terminal,
+            event_proxy,
+            session,
+            parser: ansi::Processor::new(),
+            clock: Clock::new(),
+            next_frame: 0,
+            recorder: None,
+        }
+    }
+
+    /// Channel accepting the messages usually sent to the PTY.
+    ///
+    /// All input is discarded, but the replayed output can be recorded.
+    pub fn channel(&self) -> Sender<Msg> {
+        self.tx.clone()
+    }
+
+    /// Channel for controlling the playback.
+    pub fn controller(&self) -> Controller {
+        Controller(self.control_tx.clone())
+    }
+
+    pub fn spawn(mut self) -> JoinHandle<()> {
+        thread::spawn_named("replay", move || {
+            let channel_token = mio::Token(0);
+            let control_token = mio::Token(1);
+            self.poll
+                .register(&self.rx, channel_token, Ready::readable(), PollOpt::edge())
+                .unwrap();
+            self.poll
+                .register(&self.control_rx, control_token, Ready::readable(), PollOpt::edge())
+                .unwrap();
+
+            let mut events = Events::with_capacity(16);
+
+            loop {
+                while let Ok(msg) = self.rx.try_recv() {
+                    match msg {
+                        Msg::StartRecording(recorder) => self.recorder = Some(recorder),
+                        Msg::StopRecording => self.recorder = None,
+                        Msg::Resize(window_size) => {
+                            self.record(|recorder| recorder.resize(window_size))
+                        },
+                        Msg::Shutdown => return,
+                        Msg::Input(_) => (),
+                    }
+                }
+
+                while let Ok(control) = self.control_rx.try_recv() {
+                    self.control(control);
+                }
+
+                self.play_due_frames();
+
+                // Wake up for the next frame, or when a synchronized update timeout was reached.
+                let sync_timeout = self.parser.sync_timeout().sync_timeout();
+                let sync_timeout =
+                    sync_timeout.map(|st| st.saturating_duration_since(Instant::now()));
+                let frame_timeout = match self.session.frames.get(self.next_frame) {
+                    Some(frame) if !self.clock.paused => Some(self.clock.until(frame.time)),
+                    _ => None,
+                };
+                let timeout = match (frame_timeout, sync_timeout) {
+                    (Some(frame), Some(sync)) => Some(frame.min(sync)),
+                    (frame, sync) => frame.or(sync),
+                };
+
+                if let Err(err) = self.poll.poll(&mut events, timeout) {
+                    match err.kind() {
+                        ErrorKind::Interrupted => continue,
+                        _ => panic!("Replay polling error: {err:?}"),
+                    }
+                }
+
+                // Handle synchronized update timeout.
+                let sync_deadline = self.parser.sync_timeout().sync_timeout();
+                if sync_deadline.map_or(false, |deadline| deadline <= Instant::now()) {
+                    self.parser.stop_sync(&mut *self.terminal.lock());
+                    self.event_proxy.send_event(Event::Wakeup);
+                }
+            }
+        })
+    }
+
+    /// Apply a playback control.
+    fn control(&mut self, control: Control) {
+        let now = self.clock.now();
+        match control {
+            Control::TogglePause => {
+                self.clock.paused = !self.clock.paused;
+                self.clock.set(now);
+            },
+            Control::Step => {
+                self.clock.paused = true;
+                let time = self.session.frames.get(self.next_frame).map(|frame| frame.time);
+                self.clock.set(time.unwrap_or(now));
+            },
+            Control::Faster => {
+                self.clock.speed = (self.clock.speed * 2.).min(MAX_SPEED);
+                self.clock.set(now);
+            },
+            Control::Slower => {
+                self.clock.speed = (self.clock.speed / 2.).max(MIN_SPEED);
+                self.clock.set(now);
+            },
+        }
+
+        info!(
+            "Replay {} at {}x speed",
+            if self.clock.paused { "paused" } else { "playing" },
+            self.clock.speed
+        );
+    }
+
+    /// Write an event to the active session recording.
+    fn record<F>(&mut self, f: F)
+    where
+        F: FnOnce(&mut Recorder) -> io::Result<()>,
+    {
+        if let Some(Err(err)) = self.recorder.as_mut().map(f) {
+            error!("Unable to write session recording: {}", err);
+            self.recorder = None;
+        }
+    }
+
+    /// Write all frames up to the current recording time to the terminal.
+    fn play_due_frames(&mut self) {
+        let now = self.clock.now();
+        let session = self.session.clone();
+        let frames = &session.frames[self.next_frame..];
+        let due = frames.iter().take_while(|frame| frame.time <= now).count();
+        if due == 0 {
+            return;
+        }
+
+        let mut terminal = self.terminal.lock();
+        let mut processed = 0;
+        for frame in &frames[..due] {
+            for byte in &frame.data {
+                self.parser.advance(&mut *terminal, *byte);
+            }
+            processed += frame.data.len();
+        }
+        drop(terminal);
+
+        for frame in &frames[..due] {
+            self.record(|recorder| recorder.output(&frame.data));
+        }
+
+        self.next_frame += due;
+        if self.next_frame == session.frames.len() {
+            info!("Replay finished");
+        }
+
+        // Queue terminal redraw unless all processed bytes were synchronized.
+        if self.parser.sync_bytes_count() < processed {
+            self.event_proxy.send_event(Event::Wakeup);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_asciicast() {
+        let recording = concat!(
+            "{\"version\": 2, \"width\": 80, \"height\": 24, \"timestamp\": 0}\n",
+            "[0.5, \"o\", \"hello \"]\n",
+            "[0.75, \"i\", \"x\"]\n",
+            "[1.0, \"r\", \"100x30\"]\n",
+            "\n",
+            "[0.25, \"o\", \"world\\r\\n\"]\n",
+        );
+
+        let session = Session::parse(recording.as_bytes()).unwrap();
+
+        assert_eq!(session.size, Some((80, 24)));
+        assert_eq!(
+            session.frames,
+            vec![
+                Frame { time: Duration::from_millis(500), data: b"hello ".to_vec() },
+                Frame { time: Duration::from_millis(500), data: b"world\r\n".to_vec() },
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_raw() {
+        let session = Session::parse(b"\x1b[1mone\r\ntwo").unwrap();
+
+        assert_eq!(session.size, None);
+        assert_eq!(
+            session.frames,
+            vec![
+                Frame { time: Duration::ZERO, data: b"\x1b[1mone\r\n".to_vec() },
+                Frame { time: RAW_LINE_INTERVAL, data: b"two".to_vec() },
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_invalid() {
+        let recording = "{\"version\": 2, \"width\": 80, \"height\": 24}\n[0.5, \"o\"]\n";
+        assert!(matches!(Session::parse(recording.as_bytes()), Err(Error::Invalid(2, _))));
+
+        let recording = "{\"version\": 1, \"width\": 80, \"height\": 24}\n";
+        assert!(matches!(Session::parse(recording.as_bytes()), Err(Error::Invalid(1, _))));
+    }
+
+    #[test]
+    fn clock() {
+        let mut clock = Clock::new();
+        clock.paused = true;
+        clock.set(Duration::from_secs(1));
+        assert_eq!(clock.now(), Duration::from_secs(1));
+
+        clock.speed = 2.;
+        assert_eq!(clock.until(Duration::from_secs(3)), Duration::from_secs(1));
+    }
+}
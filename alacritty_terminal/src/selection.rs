@@ -95,15 +95,17 @@ pub enum SelectionType {
     Block,
     Semantic,
     Lines,
+    Output,
 }
 
 /// Describes a region of a 2-dimensional area.
 ///
-/// Used to track a text selection. There are four supported modes, each with its own constructor:
-/// [`simple`], [`block`], [`semantic`], and [`lines`]. The [`simple`] mode precisely tracks which
-/// cells are selected without any expansion. [`block`] will select rectangular regions.
-/// [`semantic`] mode expands the initial selection to the nearest semantic escape char in either
-/// direction. [`lines`] will always select entire lines.
+/// Used to track a text selection. There are five supported modes, each with its own constructor:
+/// [`simple`], [`block`], [`semantic`], [`lines`], and [`output`]. The [`simple`] mode precisely
+/// tracks which cells are selected without any expansion. [`block`] will select rectangular
+/// regions. [`semantic`] mode expands the initial selection to the nearest semantic escape char in
+/// either direction. [`lines`] will always select entire lines. [`output`] selects the entire
+/// output of shell commands, based on their shell integration marks.
 ///
 /// Calls to [`update`] operate different based on the selection kind. The [`simple`] and [`block`]
 /// mode do nothing special, simply track points and sides. [`semantic`] will continue to expand
@@ -114,6 +116,7 @@ pub enum SelectionType {
 /// [`block`]: enum.Selection.html#method.block
 /// [`semantic`]: enum.Selection.html#method.semantic
 /// [`lines`]: enum.Selection.html#method.lines
+/// [`output`]: enum.Selection.html#method.output
 /// [`update`]: enum.Selection.html#method.update
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Selection {
@@ -220,7 +223,7 @@ impl Selection {
                         && start.side == Side::Left
                         && end.side == Side::Right)
             },
-            SelectionType::Semantic | SelectionType::Lines => false,
+            SelectionType::Semantic | SelectionType::Lines | SelectionType::Output => false,
         }
     }
 
@@ -291,6 +294,7 @@ impl Selection {
             SelectionType::Block => self.range_block(start, end),
             SelectionType::Semantic => Some(Self::range_semantic(term, start.point, end.point)),
             SelectionType::Lines => Some(Self::range_lines(term, start.point, end.point)),
+            SelectionType::Output => Some(Self::range_output(term, start.point, end.point)),
         }
     }
 
@@ -322,6 +326,20 @@ impl Selection {
         SelectionRange { start, end, is_block: false }
     }
 
+    fn range_output<T>(term: &Term<T>, start: Point, end: Point) -> SelectionRange {
+        // Fall back to line selection outside of command output.
+        let start = match term.command_output(start) {
+            Some(output) => *output.start(),
+            None => term.line_search_left(start),
+        };
+        let end = match term.command_output(end) {
+            Some(output) => *output.end(),
+            None => term.line_search_right(end),
+        };
+
+        SelectionRange { start, end, is_block: false }
+    }
+
     fn range_simple(
         &self,
         mut start: Anchor,
@@ -395,6 +413,7 @@ impl Selection {
 mod tests {
     use super::*;
 
+    use crate::ansi::SemanticMark;
     use crate::config::Config;
     use crate::index::{Column, Point, Side};
     use crate::term::test::TermSize;
@@ -576,6 +595,34 @@ mod tests {
         });
     }
 
+    #[test]
+    fn output_selection() {
+        let mut term = term(6, 5);
+        term.grid_mut()[Line(0)].set_mark(Column(0), SemanticMark::PromptStart);
+        term.grid_mut()[Line(1)].set_mark(Column(0), SemanticMark::OutputStart);
+        term.grid_mut()[Line(4)].set_mark(Column(0), SemanticMark::CommandEnd(Some(0)));
+        term.grid_mut()[Line(4)].set_mark(Column(0), SemanticMark::PromptStart);
+
+        let selection =
+            Selection::new(SelectionType::Output, Point::new(Line(2), Column(3)), Side::Left);
+
+        assert_eq!(selection.to_range(&term).unwrap(), SelectionRange {
+            start: Point::new(Line(1), Column(0)),
+            end: Point::new(Line(3), Column(4)),
+            is_block: false,
+        });
+
+        // Outside of command output the selection expands to the entire line.
+        let selection =
+            Selection::new(SelectionType::Output, Point::new(Line(4), Column(1)), Side::Left);
+
+        assert_eq!(selection.to_range(&term).unwrap(), SelectionRange {
+            start: Point::new(Line(4), Column(0)),
+            end: Point::new(Line(4), Column(4)),
+            is_block: false,
+        });
+    }
+
     #[test]
     fn simple_is_empty() {
         let mut selection =
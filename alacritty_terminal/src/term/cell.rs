@@ -6,6 +6,7 @@ use serde::{Deserialize, Serialize};
 use vte::ansi::Hyperlink as VteHyperlink;
 
 use crate::ansi::{Color, NamedColor};
+use crate::graphics::GraphicCell;
 use crate::grid::{self, GridCell};
 use crate::index::Column;
 
@@ -124,6 +125,9 @@ pub struct CellExtra {
     underline_color: Option<Color>,
 
     hyperlink: Option<Hyperlink>,
+
+    #[serde(skip)]
+    graphics: Vec<GraphicCell>,
 }
 
 /// Content and attributes of a single cell in the terminal grid.
@@ -177,10 +181,9 @@ impl Cell {
     pub fn set_underline_color(&mut self, color: Option<Color>) {
         // If we reset color and we don't have zerowidth we should drop extra storage.
         if color.is_none()
-            && self
-                .extra
-                .as_ref()
-                .map_or(true, |extra| extra.zerowidth.is_empty() && extra.hyperlink.is_none())
+            && self.extra.as_ref().map_or(true, |extra| {
+                extra.zerowidth.is_empty() && extra.hyperlink.is_none() && extra.graphics.is_empty()
+            })
         {
             self.extra = None;
         } else {
@@ -199,7 +202,9 @@ impl Cell {
     pub fn set_hyperlink(&mut self, hyperlink: Option<Hyperlink>) {
         let should_drop = hyperlink.is_none()
             && self.extra.as_ref().map_or(true, |extra| {
-                extra.zerowidth.is_empty() && extra.underline_color.is_none()
+                extra.zerowidth.is_empty()
+                    && extra.underline_color.is_none()
+                    && extra.graphics.is_empty()
             });
 
         if should_drop {
@@ -215,6 +220,34 @@ impl Cell {
     pub fn hyperlink(&self) -> Option<Hyperlink> {
         self.extra.as_ref()?.hyperlink.clone()
     }
+
+    /// Add a graphic section on top of the ones already in this cell.
+    pub fn push_graphic(&mut self, graphic: GraphicCell) {
+        let extra = self.extra.get_or_insert(Default::default());
+        Arc::make_mut(extra).graphics.push(graphic);
+    }
+
+    /// Remove all graphic sections for which `f` returns `false`.
+    pub fn retain_graphics<F: FnMut(&GraphicCell) -> bool>(&mut self, f: F) {
+        let extra = match &mut self.extra {
+            Some(extra) if !extra.graphics.is_empty() => extra,
+            _ => return,
+        };
+
+        let extra_mut = Arc::make_mut(extra);
+        extra_mut.graphics.retain(f);
+
+        // Drop extra storage once it's no longer used.
+        if *extra_mut == CellExtra::default() {
+            self.extra = None;
+        }
+    }
+
+    /// Graphic sections displayed in this cell.
+    #[inline]
+    pub fn graphics(&self) -> &[GraphicCell] {
+        self.extra.as_ref().map_or(&[], |extra| extra.graphics.as_slice())
+    }
 }
 
 impl GridCell for Cell {
@@ -231,7 +264,10 @@ impl GridCell for Cell {
                     | Flags::WIDE_CHAR_SPACER
                     | Flags::LEADING_WIDE_CHAR_SPACER,
             )
-            && self.extra.as_ref().map(|extra| extra.zerowidth.is_empty()) != Some(false)
+            && self
+                .extra
+                .as_ref()
+                .map_or(true, |extra| extra.zerowidth.is_empty() && extra.graphics.is_empty())
     }
 
     #[inline]
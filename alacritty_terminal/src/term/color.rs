@@ -3,7 +3,7 @@ use std::ops::{Add, Deref, Index, IndexMut, Mul};
 use std::str::FromStr;
 
 use serde::de::{Error as _, Visitor};
-use serde::{Deserialize, Deserializer, Serialize};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use serde_yaml::Value;
 
 use alacritty_config_derive::SerdeReplace;
@@ -167,6 +167,19 @@ impl Default for CellRgb {
     }
 }
 
+impl Serialize for CellRgb {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match self {
+            Self::CellForeground => serializer.serialize_str("CellForeground"),
+            Self::CellBackground => serializer.serialize_str("CellBackground"),
+            Self::Rgb(rgb) => rgb.serialize(serializer),
+        }
+    }
+}
+
 impl<'de> Deserialize<'de> for CellRgb {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
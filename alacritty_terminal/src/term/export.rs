@@ -0,0 +1,527 @@
+//! Export of terminal content including its formatting.
+
+use std::fmt::Write;
+
+use crate::ansi::{Color, NamedColor};
+use crate::grid::Dimensions;
+use crate::index::{Column, Line, Point};
+use crate::selection::{Selection, SelectionRange, SelectionType};
+use crate::term::cell::{Cell, Flags, Hyperlink, LineLength};
+use crate::term::color::{Colors, Rgb};
+use crate::term::Term;
+
+/// Cell flags which affect the exported formatting.
+const STYLE_FLAGS: Flags = Flags::INVERSE
+    .union(Flags::BOLD)
+    .union(Flags::ITALIC)
+    .union(Flags::DIM)
+    .union(Flags::HIDDEN)
+    .union(Flags::STRIKEOUT)
+    .union(Flags::ALL_UNDERLINES);
+
+/// URI schemes which are exported as HTML links.
+const HTML_LINK_SCHEMES: [&str; 4] = ["http", "https", "file", "mailto"];
+
+impl<T> Term<T> {
+    /// Convert range between two points to HTML.
+    ///
+    /// Colors which were not changed through escape sequences are looked up in `palette`.
+    pub fn bounds_to_html(&self, start: Point, end: Point, palette: &Colors) -> String {
+        let mut exporter = HtmlExporter::new(self.colors(), palette);
+        self.export(&mut exporter, start, end, false);
+        exporter.finish()
+    }
+
+    /// Convert range between two points to text with ANSI escape sequences.
+    pub fn bounds_to_ansi(&self, start: Point, end: Point) -> String {
+        let mut exporter = AnsiExporter::default();
+        self.export(&mut exporter, start, end, false);
+        exporter.finish()
+    }
+
+    /// Convert the active selection to HTML.
+    pub fn selection_to_html(&self, palette: &Colors) -> Option<String> {
+        let selection = self.selection.as_ref()?;
+        let SelectionRange { start, end, .. } = selection.to_range(self)?;
+        let is_block = matches!(selection, Selection { ty: SelectionType::Block, .. });
+
+        let mut exporter = HtmlExporter::new(self.colors(), palette);
+        self.export(&mut exporter, start, end, is_block);
+        Some(exporter.finish())
+    }
+
+    /// Pass all cells between two points to an exporter.
+    ///
+    /// With `is_block` set, only the columns between `start` and `end` are exported on every line.
+    fn export<E: Exporter>(&self, exporter: &mut E, start: Point, end: Point, is_block: bool) {
+        let mut reader = self.grid.reader();
+        for line in (start.line.0..=end.line.0).map(Line) {
+            let (start_col, end_col) = match (is_block, line == start.line, line == end.line) {
+                (true, ..) => (start.column, end.column),
+                (false, true, true) => (start.column, end.column),
+                (false, true, false) => (start.column, self.last_column()),
+                (false, false, true) => (Column(0), end.column),
+                (false, false, false) => (Column(0), self.last_column()),
+            };
+
+            let grid_line = reader.row(line);
+            let line_length = grid_line.line_length().min(end_col + 1);
+
+            for column in (start_col.0..line_length.0).map(Column) {
+                let cell = &grid_line[column];
+                if !cell.flags.intersects(Flags::WIDE_CHAR_SPACER | Flags::LEADING_WIDE_CHAR_SPACER)
+                {
+                    exporter.cell(cell);
+                }
+            }
+
+            // Keep soft-wrapped lines joined, unless only parts of the line are exported.
+            let wrapped = line_length == self.columns()
+                && grid_line[self.last_column()].flags.contains(Flags::WRAPLINE);
+            if line != end.line && (is_block || !wrapped) {
+                exporter.newline();
+            }
+        }
+    }
+}
+
+/// Output format of an export.
+trait Exporter {
+    /// Append a single cell.
+    fn cell(&mut self, cell: &Cell);
+
+    /// Start a new line.
+    fn newline(&mut self);
+
+    /// Complete the export.
+    fn finish(self) -> String;
+}
+
+/// Formatting of a cell.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Style {
+    fg: Option<Color>,
+    bg: Option<Color>,
+    underline_color: Option<Color>,
+    hyperlink: Option<Hyperlink>,
+    flags: Flags,
+}
+
+impl Default for Style {
+    fn default() -> Self {
+        Self { fg: None, bg: None, underline_color: None, hyperlink: None, flags: Flags::empty() }
+    }
+}
+
+impl Style {
+    fn new(cell: &Cell) -> Self {
+        // Default colors are omitted, so they can be inherited from the surrounding document.
+        let fg = Some(cell.fg).filter(|fg| *fg != Color::Named(NamedColor::Foreground));
+        let bg = Some(cell.bg).filter(|bg| *bg != Color::Named(NamedColor::Background));
+
+        Self {
+            fg,
+            bg,
+            underline_color: cell.underline_color(),
+            hyperlink: cell.hyperlink(),
+            flags: cell.flags & STYLE_FLAGS,
+        }
+    }
+}
+
+/// Export as HTML, using inline styles to preserve the formatting.
+struct HtmlExporter<'a> {
+    colors: &'a Colors,
+    palette: &'a Colors,
+    style: Style,
+    output: String,
+
+    /// Elements opened for the current style.
+    open_span: bool,
+    open_anchor: bool,
+}
+
+impl<'a> HtmlExporter<'a> {
+    fn new(colors: &'a Colors, palette: &'a Colors) -> Self {
+        Self {
+            colors,
+            palette,
+            style: Style::default(),
+            output: String::new(),
+            open_span: false,
+            open_anchor: false,
+        }
+    }
+
+    /// Resolve a color index to RGB.
+    fn rgb(&self, index: usize) -> Rgb {
+        self.colors[index].or(self.palette[index]).unwrap_or_default()
+    }
+
+    /// Resolve a cell's foreground color.
+    fn fg_rgb(&self, fg: Option<Color>, flags: Flags) -> Rgb {
+        let dim = flags.contains(Flags::DIM);
+        match fg.unwrap_or(Color::Named(NamedColor::Foreground)) {
+            Color::Spec(rgb) => rgb.into(),
+            Color::Named(named) if dim => self.rgb(named.to_dim() as usize),
+            Color::Named(named) => self.rgb(named as usize),
+            Color::Indexed(index @ 0..=7) if dim => {
+                self.rgb(NamedColor::DimBlack as usize + index as usize)
+            },
+            Color::Indexed(index) => self.rgb(index as usize),
+        }
+    }
+
+    /// Resolve a cell's background color.
+    fn bg_rgb(&self, bg: Option<Color>) -> Rgb {
+        match bg.unwrap_or(Color::Named(NamedColor::Background)) {
+            Color::Spec(rgb) => rgb.into(),
+            Color::Named(named) => self.rgb(named as usize),
+            Color::Indexed(index) => self.rgb(index as usize),
+        }
+    }
+
+    /// Switch to a new style, replacing the elements of the previous one.
+    fn set_style(&mut self, style: Style) {
+        if self.open_span {
+            self.output.push_str("</span>");
+            self.open_span = false;
+        }
+        if self.open_anchor {
+            self.output.push_str("</a>");
+            self.open_anchor = false;
+        }
+
+        self.style = style;
+        let style = &self.style;
+
+        // Hyperlinks with other schemes, like `javascript:`, are exported as plain text.
+        if let Some(uri) =
+            style.hyperlink.as_ref().map(Hyperlink::uri).filter(|uri| is_html_link(uri))
+        {
+            let _ = write!(self.output, "<a href=\"{}\">", escape(uri));
+            self.open_anchor = true;
+        }
+
+        let mut css = String::new();
+
+        // Only emit colors which differ from the document defaults.
+        let inverse = style.flags.contains(Flags::INVERSE);
+        let hidden = style.flags.contains(Flags::HIDDEN);
+        let dim = style.flags.contains(Flags::DIM);
+        if style.fg.is_some() || style.bg.is_some() || inverse || hidden || dim {
+            let mut fg = self.fg_rgb(style.fg, style.flags);
+            let mut bg = self.bg_rgb(style.bg);
+            if inverse {
+                std::mem::swap(&mut fg, &mut bg);
+            }
+            if hidden {
+                fg = bg;
+            }
+
+            if style.fg.is_some() || inverse || hidden || dim {
+                let _ = write!(css, "color:{};", fg);
+            }
+            if style.bg.is_some() || inverse {
+                let _ = write!(css, "background-color:{};", bg);
+            }
+        }
+
+        if style.flags.contains(Flags::BOLD) {
+            css.push_str("font-weight:bold;");
+        }
+        if style.flags.contains(Flags::ITALIC) {
+            css.push_str("font-style:italic;");
+        }
+
+        let underline = style.flags.intersects(Flags::ALL_UNDERLINES);
+        let strikeout = style.flags.contains(Flags::STRIKEOUT);
+        match (underline, strikeout) {
+            (true, true) => css.push_str("text-decoration-line:underline line-through;"),
+            (true, false) => css.push_str("text-decoration-line:underline;"),
+            (false, true) => css.push_str("text-decoration-line:line-through;"),
+            (false, false) => (),
+        }
+
+        if underline {
+            if style.flags.contains(Flags::DOUBLE_UNDERLINE) {
+                css.push_str("text-decoration-style:double;");
+            } else if style.flags.contains(Flags::UNDERCURL) {
+                css.push_str("text-decoration-style:wavy;");
+            } else if style.flags.contains(Flags::DOTTED_UNDERLINE) {
+                css.push_str("text-decoration-style:dotted;");
+            } else if style.flags.contains(Flags::DASHED_UNDERLINE) {
+                css.push_str("text-decoration-style:dashed;");
+            }
+
+            if let Some(color) = style.underline_color {
+                let rgb = self.fg_rgb(Some(color), Flags::empty());
+                let _ = write!(css, "text-decoration-color:{};", rgb);
+            }
+        }
+
+        if !css.is_empty() {
+            let _ = write!(self.output, "<span style=\"{}\">", css);
+            self.open_span = true;
+        }
+    }
+
+    fn push(&mut self, c: char) {
+        match c {
+            '<' => self.output.push_str("&lt;"),
+            '>' => self.output.push_str("&gt;"),
+            '&' => self.output.push_str("&amp;"),
+            '\t' => self.output.push(' '),
+            c => self.output.push(c),
+        }
+    }
+}
+
+impl<'a> Exporter for HtmlExporter<'a> {
+    fn cell(&mut self, cell: &Cell) {
+        let style = Style::new(cell);
+        if style != self.style {
+            self.set_style(style);
+        }
+
+        self.push(cell.c);
+        for c in cell.zerowidth().into_iter().flatten() {
+            self.push(*c);
+        }
+    }
+
+    fn newline(&mut self) {
+        // Avoid extending backgrounds and links past the end of the line.
+        self.set_style(Style::default());
+        self.output.push('\n');
+    }
+
+    fn finish(mut self) -> String {
+        self.set_style(Style::default());
+
+        let fg = self.fg_rgb(None, Flags::empty());
+        let bg = self.bg_rgb(None);
+        format!("<pre style=\"color:{};background-color:{};\">{}</pre>", fg, bg, self.output)
+    }
+}
+
+/// Export as text with SGR and OSC 8 escape sequences.
+#[derive(Default)]
+struct AnsiExporter {
+    style: Style,
+    output: String,
+}
+
+impl AnsiExporter {
+    /// Write the escape sequences switching to a new style.
+    fn set_style(&mut self, style: Style) {
+        if style.hyperlink != self.style.hyperlink {
+            let _ = match &style.hyperlink {
+                Some(hyperlink) => {
+                    write!(self.output, "\x1b]8;id={};{}\x1b\\", hyperlink.id(), hyperlink.uri())
+                },
+                None => write!(self.output, "\x1b]8;;\x1b\\"),
+            };
+        }
+
+        let old_style = Style { hyperlink: None, ..self.style.clone() };
+        let new_style = Style { hyperlink: None, ..style.clone() };
+        if old_style != new_style {
+            self.output.push_str("\x1b[0");
+
+            for (flag, sgr) in [
+                (Flags::BOLD, ";1"),
+                (Flags::DIM, ";2"),
+                (Flags::ITALIC, ";3"),
+                (Flags::UNDERLINE, ";4"),
+                (Flags::DOUBLE_UNDERLINE, ";4:2"),
+                (Flags::UNDERCURL, ";4:3"),
+                (Flags::DOTTED_UNDERLINE, ";4:4"),
+                (Flags::DASHED_UNDERLINE, ";4:5"),
+                (Flags::INVERSE, ";7"),
+                (Flags::HIDDEN, ";8"),
+                (Flags::STRIKEOUT, ";9"),
+            ] {
+                if style.flags.contains(flag) {
+                    self.output.push_str(sgr);
+                }
+            }
+
+            if let Some(fg) = style.fg {
+                write_sgr_color(&mut self.output, fg, 30, 90, 38);
+            }
+            if let Some(bg) = style.bg {
+                write_sgr_color(&mut self.output, bg, 40, 100, 48);
+            }
+            if let Some(underline_color) = style.underline_color {
+                write_sgr_color(&mut self.output, underline_color, 0, 0, 58);
+            }
+
+            self.output.push('m');
+        }
+
+        self.style = style;
+    }
+}
+
+impl Exporter for AnsiExporter {
+    fn cell(&mut self, cell: &Cell) {
+        let style = Style::new(cell);
+        if style != self.style {
+            self.set_style(style);
+        }
+
+        self.output.push(cell.c);
+        for c in cell.zerowidth().into_iter().flatten() {
+            self.output.push(*c);
+        }
+    }
+
+    fn newline(&mut self) {
+        self.set_style(Style::default());
+        self.output.push('\n');
+    }
+
+    fn finish(mut self) -> String {
+        self.set_style(Style::default());
+        self.output
+    }
+}
+
+/// Append an SGR color parameter.
+///
+/// The `normal` and `bright` offsets are used for the 16 basic colors, falling back to the
+/// indexed or RGB form introduced by `extended` if the offset is zero.
+fn write_sgr_color(output: &mut String, color: Color, normal: u8, bright: u8, extended: u8) {
+    let index = match color {
+        Color::Spec(rgb) => {
+            let _ = write!(output, ";{};2;{};{};{}", extended, rgb.r, rgb.g, rgb.b);
+            return;
+        },
+        Color::Named(named) if (named as usize) < 16 => named as u8,
+        Color::Named(named) => match named.to_bright() as usize {
+            index if index < 16 => index as u8,
+            _ => return,
+        },
+        Color::Indexed(index) => index,
+    };
+
+    let _ = match index {
+        0..=7 if normal != 0 => write!(output, ";{}", normal + index),
+        8..=15 if bright != 0 => write!(output, ";{}", bright + index - 8),
+        _ => write!(output, ";{};5;{}", extended, index),
+    };
+}
+
+/// Escape text for use in HTML attributes.
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('"', "&quot;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+/// Check if a URI uses a scheme which is safe to export as HTML link.
+fn is_html_link(uri: &str) -> bool {
+    uri.split_once(':').map_or(false, |(scheme, _)| {
+        HTML_LINK_SCHEMES.iter().any(|allowed| scheme.eq_ignore_ascii_case(allowed))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::ansi::{self, Attr, Handler, Hyperlink as VteHyperlink, Rgb as VteRgb};
+    use crate::config::Config;
+    use crate::event::VoidListener;
+    use crate::term::test::TermSize;
+
+    fn term(input: &[u8]) -> Term<VoidListener> {
+        let size = TermSize::new(20, 3);
+        let mut term = Term::new(&Config::default(), &size, VoidListener);
+        let mut parser = ansi::Processor::new();
+        for byte in input {
+            parser.advance(&mut term, *byte);
+        }
+        term
+    }
+
+    fn palette() -> Colors {
+        let mut palette = Colors::default();
+        palette[NamedColor::Foreground] = Some(Rgb::new(0xff, 0xff, 0xff));
+        palette[NamedColor::Background] = Some(Rgb::new(0, 0, 0));
+        palette[NamedColor::Red] = Some(Rgb::new(0xff, 0, 0));
+        palette
+    }
+
+    fn bounds(term: &Term<VoidListener>) -> (Point, Point) {
+        (Point::new(Line(0), Column(0)), Point::new(term.bottommost_line(), term.last_column()))
+    }
+
+    #[test]
+    fn html() {
+        let term = term(b"a\x1b[1;31m<b>\x1b[0m\r\n\x1b[4:3;48;2;0;0;255mc");
+        let (start, end) = bounds(&term);
+
+        assert_eq!(
+            term.bounds_to_html(start, end, &palette()),
+            "<pre style=\"color:#ffffff;background-color:#000000;\">a<span \
+             style=\"color:#ff0000;font-weight:bold;\">&lt;b&gt;</span>\n<span \
+             style=\"background-color:#0000ff;text-decoration-line:underline;\
+             text-decoration-style:wavy;\">c</span>\n</pre>"
+        );
+    }
+
+    #[test]
+    fn html_hyperlink() {
+        let mut term = term(b"");
+        let hyperlink = VteHyperlink { id: None, uri: String::from("https://a.b/?c=\"d\"") };
+        term.set_hyperlink(Some(hyperlink));
+        term.input('x');
+        term.set_hyperlink(None);
+        term.input('y');
+
+        let (start, end) = bounds(&term);
+        let html = term.bounds_to_html(start, end, &palette());
+
+        assert!(html.contains("<a href=\"https://a.b/?c=&quot;d&quot;\">x</a>y"));
+    }
+
+    #[test]
+    fn html_hyperlink_scheme() {
+        let mut term = term(b"");
+        for uri in ["javascript:alert(1)", "MAILTO:a@b.c", "data:text/html,x", "relative"] {
+            term.set_hyperlink(Some(VteHyperlink { id: None, uri: String::from(uri) }));
+            term.input('x');
+        }
+
+        let (start, end) = bounds(&term);
+        let html = term.bounds_to_html(start, end, &palette());
+
+        assert!(html.contains("<a href=\"MAILTO:a@b.c\">x</a>"));
+        assert!(!html.contains("javascript"));
+        assert!(!html.contains("data:"));
+        assert!(!html.contains("relative"));
+    }
+
+    #[test]
+    fn ansi() {
+        let mut term = term(b"a\x1b[1;31mb\x1b[0m\r\n\x1b[38;5;100mc");
+        let rgb = VteRgb { r: 1, g: 2, b: 3 };
+        term.terminal_attribute(Attr::Foreground(Color::Spec(rgb)));
+        term.input('d');
+
+        let (start, end) = bounds(&term);
+
+        assert_eq!(
+            term.bounds_to_ansi(start, end),
+            "a\x1b[0;1;31mb\x1b[0m\n\x1b[0;38;5;100mc\x1b[0;38;2;1;2;3md\x1b[0m\n"
+        );
+    }
+
+    #[test]
+    fn wrapped_lines() {
+        let term = term(b"0123456789012345678901\x1b[31m2");
+        let (start, end) = bounds(&term);
+
+        assert_eq!(term.bounds_to_ansi(start, end), "0123456789012345678901\x1b[0;31m2\x1b[0m\n");
+    }
+}
@@ -0,0 +1,442 @@
+//! Placement and removal of inline graphics.
+
+use std::cmp;
+use std::sync::Arc;
+
+use log::{debug, trace};
+
+use crate::ansi::Handler;
+use crate::event::{Event, EventListener};
+use crate::graphics::kitty::{Action, Error as KittyError, GraphicsCommand, PlacementIndex};
+use crate::graphics::{GraphicCell, GraphicData, GraphicPlacement, KittyPlacement};
+use crate::grid::Dimensions;
+use crate::index::{Column, Line};
+use crate::term::Term;
+
+/// Maximum displayed width and height of a kitty image placement, in pixels.
+const MAX_PLACEMENT_SIZE: u32 = 16384;
+
+/// Cursor position after placing a graphic.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) enum GraphicCursor {
+    /// Line below the graphic, in the column it was placed in.
+    NextLine,
+
+    /// Last line of the graphic, in the column after it.
+    AfterGraphic,
+
+    /// Keep the cursor at its original position.
+    Unchanged,
+}
+
+impl<T: EventListener> Term<T> {
+    /// Attach a graphic to the cells starting at the cursor.
+    pub(crate) fn place_graphic(&mut self, placement: GraphicPlacement, cursor: GraphicCursor) {
+        let (cell_width, cell_height) = match self.cell_size() {
+            Some(cell_size) => cell_size,
+            None => {
+                debug!("Ignoring graphic with unknown cell dimensions");
+                return;
+            },
+        };
+
+        trace!("Placing graphic: {:?}", placement);
+
+        let (columns, lines) = placement.cells(cell_width, cell_height);
+        let start = self.grid.cursor.point;
+        let end = cmp::min(start.column + columns, Column(self.columns()));
+
+        let bg = self.grid.cursor.template.bg;
+
+        let mut line = start.line;
+        let mut placed_lines = 0;
+        for offset in 0..lines {
+            if offset > 0 && cursor == GraphicCursor::Unchanged {
+                // Clip the graphic at the bottom of the screen without scrolling.
+                if line >= self.bottommost_line() {
+                    break;
+                }
+                line += 1;
+            } else if offset > 0 {
+                // Scroll the graphic's remaining lines into view.
+                self.linefeed();
+                line = self.grid.cursor.point.line;
+            }
+
+            self.damage.damage_line(line.0 as usize, start.column.0, end.0 - 1);
+
+            for column in start.column.0..end.0 {
+                let column_offset = column - start.column.0;
+                let graphic = placement.cell(column_offset, offset, cell_width, cell_height);
+
+                // Only kitty placements are stacked, other graphics replace the cell's content.
+                let cell = &mut self.grid[line][Column(column)];
+                if placement.kitty.is_none() {
+                    *cell = bg.into();
+                }
+                cell.push_graphic(graphic);
+            }
+
+            placed_lines += 1;
+        }
+
+        // Lines are placed consecutively, so the graphic ends in the last line.
+        if let Some(kitty) = placement.kitty.filter(|_| placed_lines > 0) {
+            let lines = line - (placed_lines - 1)..line + 1;
+            self.kitty_placements.insert(kitty, lines);
+
+            if self.kitty_placements.needs_reindex() {
+                self.reindex_kitty_placements(|_| false);
+            }
+        }
+
+        match cursor {
+            GraphicCursor::NextLine => {
+                self.linefeed();
+                self.grid.cursor.input_needs_wrap = false;
+            },
+            GraphicCursor::AfterGraphic => {
+                self.grid.cursor.point.column = cmp::min(end, self.last_column());
+                self.grid.cursor.input_needs_wrap = false;
+            },
+            GraphicCursor::Unchanged => (),
+        }
+    }
+
+    /// Handle a kitty graphics protocol command.
+    pub(crate) fn kitty_graphics_command(&mut self, command: GraphicsCommand) {
+        let command = match self.kitty_images.feed(command) {
+            Some(command) => command,
+            None => return,
+        };
+
+        trace!("Kitty graphics command: {:?}", command.action);
+
+        let mut image_id = command.image_id;
+        let result = match command.action {
+            Action::Transmit | Action::TransmitAndDisplay => {
+                self.kitty_transmit(&command, &mut image_id)
+            },
+            Action::Query => command.load().map(|_| ()),
+            Action::Display => self.kitty_display(&command, &mut image_id),
+            Action::Delete => {
+                self.kitty_delete(&command);
+                return;
+            },
+            Action::Animation => Err(KittyError::InvalidArgument("Animation is not supported")),
+        };
+
+        if let Some(response) = command.response(image_id, result) {
+            self.event_proxy.send_event(Event::PtyWrite(response));
+        }
+    }
+
+    /// Store a transmitted image, displaying it if requested.
+    fn kitty_transmit(
+        &mut self,
+        command: &GraphicsCommand,
+        image_id: &mut u32,
+    ) -> Result<(), KittyError> {
+        if *image_id == 0 {
+            *image_id = self.kitty_images.next_image_id();
+        }
+
+        let data = command.load()?;
+
+        // Retransmission replaces the image and all its placements.
+        if self.kitty_images.contains(*image_id) {
+            let id = *image_id;
+            self.remove_kitty_placements(|placement| placement.image_id == id);
+        }
+
+        let data = self.kitty_images.insert(*image_id, command.image_number, data);
+
+        if command.action == Action::TransmitAndDisplay {
+            self.kitty_place(command, *image_id, data)?;
+        }
+
+        Ok(())
+    }
+
+    /// Display a previously transmitted image.
+    fn kitty_display(
+        &mut self,
+        command: &GraphicsCommand,
+        image_id: &mut u32,
+    ) -> Result<(), KittyError> {
+        if command.image_id == 0 && command.image_number != 0 {
+            let id = self.kitty_images.find_number(command.image_number);
+            *image_id = id.ok_or(KittyError::NotFound("Image number not found"))?;
+        }
+
+        let data = self.kitty_images.get(*image_id);
+        let data = data.ok_or(KittyError::NotFound("Image not found"))?;
+        self.kitty_place(command, *image_id, data)
+    }
+
+    /// Create a new placement for an image at the cursor position.
+    fn kitty_place(
+        &mut self,
+        command: &GraphicsCommand,
+        image_id: u32,
+        data: Arc<GraphicData>,
+    ) -> Result<(), KittyError> {
+        let (cell_width, cell_height) =
+            self.cell_size().ok_or(KittyError::InvalidArgument("Unknown cell dimensions"))?;
+
+        if command.unicode_placeholder {
+            return Err(KittyError::InvalidArgument("Unicode placeholders are not supported"));
+        }
+
+        let source = command.source_rect(&data);
+        if source.width == 0 || source.height == 0 {
+            return Err(KittyError::InvalidArgument("Empty source rectangle"));
+        }
+
+        // Scale the image to the requested cells, preserving the aspect ratio if necessary.
+        let (width, height) = match (command.columns, command.rows) {
+            (0, 0) => (source.width as u64, source.height as u64),
+            (columns, 0) => {
+                let width = columns as u64 * cell_width as u64;
+                (width, source.height as u64 * width / source.width as u64)
+            },
+            (0, rows) => {
+                let height = rows as u64 * cell_height as u64;
+                (source.width as u64 * height / source.height as u64, height)
+            },
+            (columns, rows) => {
+                (columns as u64 * cell_width as u64, rows as u64 * cell_height as u64)
+            },
+        };
+
+        let max_size = MAX_PLACEMENT_SIZE as u64;
+        if width == 0 || height == 0 || width > max_size || height > max_size {
+            return Err(KittyError::InvalidArgument("Invalid placement size"));
+        }
+
+        // New placements replace the old placement with the same ID.
+        let placement_id = command.placement_id;
+        if placement_id != 0 {
+            self.remove_kitty_placements(|placement| {
+                placement.image_id == image_id && placement.placement_id == placement_id
+            });
+        }
+
+        let kitty =
+            KittyPlacement { id: self.kitty_images.next_placement_id(), image_id, placement_id };
+
+        let placement = GraphicPlacement {
+            texture: data,
+            source,
+            width: width as u32,
+            height: height as u32,
+            offset_x: cmp::min(command.cell_x_offset, cell_width as u32 - 1),
+            offset_y: cmp::min(command.cell_y_offset, cell_height as u32 - 1),
+            z_index: command.z_index,
+            kitty: Some(kitty),
+        };
+
+        let cursor = match command.cursor_movement {
+            1 => GraphicCursor::Unchanged,
+            _ => GraphicCursor::AfterGraphic,
+        };
+        self.place_graphic(placement, cursor);
+
+        Ok(())
+    }
+
+    /// Delete placements and images.
+    ///
+    /// Lowercase targets only delete placements, while uppercase targets also remove the images
+    /// which are no longer displayed anywhere.
+    fn kitty_delete(&mut self, command: &GraphicsCommand) {
+        let free_images = command.delete.is_ascii_uppercase();
+
+        // Convert one-based cell coordinates of the command.
+        let line = Line(command.y as i32 - 1);
+        let column = Column(command.x.saturating_sub(1) as usize);
+
+        let mut removed = match command.delete.to_ascii_lowercase() {
+            b'a' => self.remove_visible_kitty_placements(|_, _, _| true),
+            b'i' | b'n' => {
+                let image_id = match command.delete.to_ascii_lowercase() {
+                    b'n' => match self.kitty_images.find_number(command.image_number) {
+                        Some(image_id) => image_id,
+                        None => return,
+                    },
+                    _ => command.image_id,
+                };
+
+                let placement_id = command.placement_id;
+                let removed = self.remove_kitty_placements(|placement| {
+                    placement.image_id == image_id
+                        && (placement_id == 0 || placement.placement_id == placement_id)
+                });
+
+                if free_images && placement_id == 0 {
+                    self.kitty_images.remove(image_id);
+                }
+
+                removed
+            },
+            b'r' => {
+                let range = command.x..=command.y;
+                let removed =
+                    self.remove_kitty_placements(|placement| range.contains(&placement.image_id));
+
+                if free_images {
+                    self.kitty_images.retain(|image_id| !range.contains(&image_id));
+                }
+
+                removed
+            },
+            b'c' => {
+                let cursor = self.grid.cursor.point;
+                self.remove_visible_kitty_placements(|point_line, point_column, _| {
+                    point_line == cursor.line && point_column == cursor.column
+                })
+            },
+            b'p' => self.remove_visible_kitty_placements(|point_line, point_column, _| {
+                point_line == line && point_column == column
+            }),
+            b'q' => {
+                let z_index = command.z_index;
+                self.remove_visible_kitty_placements(|point_line, point_column, z| {
+                    point_line == line && point_column == column && z == z_index
+                })
+            },
+            b'x' => {
+                self.remove_visible_kitty_placements(|_, point_column, _| point_column == column)
+            },
+            b'y' => self.remove_visible_kitty_placements(|point_line, _, _| point_line == line),
+            b'z' => {
+                let z_index = command.z_index;
+                self.remove_visible_kitty_placements(|_, _, z| z == z_index)
+            },
+            _ => {
+                debug!("Unsupported kitty graphics delete target: {}", command.delete as char);
+                return;
+            },
+        };
+
+        if free_images {
+            removed.sort_unstable();
+            removed.dedup();
+            self.kitty_images.remove_unused(&removed);
+        }
+    }
+
+    /// Remove placements intersecting the visible cells matching `predicate`.
+    ///
+    /// The predicate receives the line, column, and z-index of every visible placement cell.
+    ///
+    /// Returns the image IDs of all removed placements.
+    fn remove_visible_kitty_placements<F>(&mut self, mut predicate: F) -> Vec<u32>
+    where
+        F: FnMut(Line, Column, i32) -> bool,
+    {
+        let mut placements = Vec::new();
+        for line in (0..self.screen_lines() as i32).map(Line) {
+            for (column, cell) in self.grid[line][..].iter().enumerate() {
+                for graphic in cell.graphics() {
+                    let kitty = match graphic.kitty {
+                        Some(kitty) => kitty,
+                        None => continue,
+                    };
+
+                    if !placements.contains(&kitty.id)
+                        && predicate(line, Column(column), graphic.z_index)
+                    {
+                        placements.push(kitty.id);
+                    }
+                }
+            }
+        }
+
+        if placements.is_empty() {
+            return Vec::new();
+        }
+
+        self.remove_kitty_placements(|placement| placements.contains(&placement.id))
+    }
+
+    /// Remove all placements matching `predicate` from the grid, including its history.
+    ///
+    /// Returns the image IDs of all removed placements.
+    fn remove_kitty_placements<F>(&mut self, predicate: F) -> Vec<u32>
+    where
+        F: FnMut(&KittyPlacement) -> bool,
+    {
+        let placements = self.kitty_placements.remove(predicate);
+        if placements.is_empty() {
+            return Vec::new();
+        }
+
+        let ids: Vec<u64> = placements.iter().map(|(placement, _)| placement.id).collect();
+        let is_removed =
+            |graphic: &GraphicCell| graphic.kitty.map_or(false, |kitty| ids.contains(&kitty.id));
+
+        if placements.iter().any(|(_, lines)| lines.is_none()) {
+            self.reindex_kitty_placements(is_removed);
+        } else {
+            // Compressed history lines never contain any graphics.
+            let topmost_line = self.grid.topmost_line() + self.grid.compressed_lines();
+            let bottommost_line = self.grid.bottommost_line();
+            for lines in placements.iter().filter_map(|(_, lines)| lines.as_ref()) {
+                let start = cmp::max(lines.start, topmost_line);
+                let end = cmp::min(lines.end, bottommost_line + 1);
+                for line in (start.0..end.0).map(Line) {
+                    for cell in &mut self.grid[line][..] {
+                        if cell.graphics().iter().any(is_removed) {
+                            cell.retain_graphics(|graphic| !is_removed(graphic));
+                        }
+                    }
+                }
+            }
+        }
+
+        self.mark_fully_damaged();
+
+        let mut removed: Vec<u32> =
+            placements.iter().map(|(placement, _)| placement.image_id).collect();
+        removed.sort_unstable();
+        removed.dedup();
+        removed
+    }
+
+    /// Rebuild the placement index from the entire grid, removing the graphics matching `predicate`.
+    fn reindex_kitty_placements<F>(&mut self, mut predicate: F)
+    where
+        F: FnMut(&GraphicCell) -> bool,
+    {
+        let mut index = PlacementIndex::default();
+
+        // Compressed history lines never contain any graphics.
+        let topmost_line = self.grid.topmost_line() + self.grid.compressed_lines();
+        let bottommost_line = self.grid.bottommost_line();
+        for line in (topmost_line.0..=bottommost_line.0).map(Line) {
+            for cell in &mut self.grid[line][..] {
+                if cell.graphics().iter().any(&mut predicate) {
+                    cell.retain_graphics(|graphic| !predicate(graphic));
+                }
+
+                for kitty in cell.graphics().iter().filter_map(|graphic| graphic.kitty) {
+                    index.extend(kitty, line);
+                }
+            }
+        }
+
+        index.reindexed();
+        self.kitty_placements = index;
+    }
+
+    /// Cell dimensions in pixels.
+    fn cell_size(&self) -> Option<(usize, usize)> {
+        match self.window_size {
+            Some(size) if size.cell_width > 0 && size.cell_height > 0 => {
+                Some((size.cell_width as usize, size.cell_height as usize))
+            },
+            _ => None,
+        }
+    }
+}
@@ -10,19 +10,27 @@ use unicode_width::UnicodeWidthChar;
 use vte::ansi::{Hyperlink as VteHyperlink, Rgb as VteRgb};
 
 use crate::ansi::{
-    self, Attr, CharsetIndex, Color, CursorShape, CursorStyle, Handler, NamedColor, StandardCharset,
+    self, Attr, CharsetIndex, Color, CursorShape, CursorStyle, ExtendedHandler, Handler,
+    KeyboardModes, KeyboardModesApplyBehavior, NamedColor, SemanticMark, StandardCharset,
+    WorkingDirectory,
 };
 use crate::config::{Config, Osc52, Terminal};
-use crate::event::{Event, EventListener};
+use crate::event::{Event, EventListener, WindowSize};
+use crate::graphics::kitty::{GraphicsCommand, ImageStore, PlacementIndex};
+use crate::graphics::{GraphicData, GraphicPlacement};
 use crate::grid::{Dimensions, Grid, GridIterator, Scroll};
 use crate::index::{self, Boundary, Column, Direction, Line, Point, Side};
 use crate::selection::{Selection, SelectionRange, SelectionType};
 use crate::term::cell::{Cell, Flags, LineLength};
 use crate::term::color::Colors;
-use crate::vi_mode::{ViModeCursor, ViMotion};
+use crate::term::graphics::GraphicCursor;
+use crate::term::search::PromptIndex;
+use crate::vi_mode::{InlineSearch, ViMarks, ViModeCursor, ViMotion};
 
 pub mod cell;
 pub mod color;
+mod export;
+mod graphics;
 pub mod search;
 
 /// Minimum number of columns.
@@ -36,33 +44,46 @@ pub const MIN_SCREEN_LINES: usize = 1;
 /// Max size of the window title stack.
 const TITLE_STACK_MAX_DEPTH: usize = 4096;
 
+/// Max size of the keyboard modes stack.
+const KEYBOARD_MODE_STACK_MAX_DEPTH: usize = TITLE_STACK_MAX_DEPTH;
+
 /// Default tab interval, corresponding to terminfo `it` value.
 const INITIAL_TABSTOPS: usize = 8;
 
 bitflags! {
     #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
     pub struct TermMode: u32 {
-        const NONE                = 0;
-        const SHOW_CURSOR         = 0b0000_0000_0000_0000_0001;
-        const APP_CURSOR          = 0b0000_0000_0000_0000_0010;
-        const APP_KEYPAD          = 0b0000_0000_0000_0000_0100;
-        const MOUSE_REPORT_CLICK  = 0b0000_0000_0000_0000_1000;
-        const BRACKETED_PASTE     = 0b0000_0000_0000_0001_0000;
-        const SGR_MOUSE           = 0b0000_0000_0000_0010_0000;
-        const MOUSE_MOTION        = 0b0000_0000_0000_0100_0000;
-        const LINE_WRAP           = 0b0000_0000_0000_1000_0000;
-        const LINE_FEED_NEW_LINE  = 0b0000_0000_0001_0000_0000;
-        const ORIGIN              = 0b0000_0000_0010_0000_0000;
-        const INSERT              = 0b0000_0000_0100_0000_0000;
-        const FOCUS_IN_OUT        = 0b0000_0000_1000_0000_0000;
-        const ALT_SCREEN          = 0b0000_0001_0000_0000_0000;
-        const MOUSE_DRAG          = 0b0000_0010_0000_0000_0000;
-        const MOUSE_MODE          = 0b0000_0010_0000_0100_1000;
-        const UTF8_MOUSE          = 0b0000_0100_0000_0000_0000;
-        const ALTERNATE_SCROLL    = 0b0000_1000_0000_0000_0000;
-        const VI                  = 0b0001_0000_0000_0000_0000;
-        const URGENCY_HINTS       = 0b0010_0000_0000_0000_0000;
-        const ANY                 = u32::MAX;
+        const NONE                    = 0;
+        const SHOW_CURSOR             = 0b0000_0000_0000_0000_0000_0001;
+        const APP_CURSOR              = 0b0000_0000_0000_0000_0000_0010;
+        const APP_KEYPAD              = 0b0000_0000_0000_0000_0000_0100;
+        const MOUSE_REPORT_CLICK      = 0b0000_0000_0000_0000_0000_1000;
+        const BRACKETED_PASTE         = 0b0000_0000_0000_0000_0001_0000;
+        const SGR_MOUSE               = 0b0000_0000_0000_0000_0010_0000;
+        const MOUSE_MOTION            = 0b0000_0000_0000_0000_0100_0000;
+        const LINE_WRAP               = 0b0000_0000_0000_0000_1000_0000;
+        const LINE_FEED_NEW_LINE      = 0b0000_0000_0000_0001_0000_0000;
+        const ORIGIN                  = 0b0000_0000_0000_0010_0000_0000;
+        const INSERT                  = 0b0000_0000_0000_0100_0000_0000;
+        const FOCUS_IN_OUT            = 0b0000_0000_0000_1000_0000_0000;
+        const ALT_SCREEN              = 0b0000_0000_0001_0000_0000_0000;
+        const MOUSE_DRAG              = 0b0000_0000_0010_0000_0000_0000;
+        const MOUSE_MODE              = 0b0000_0000_0010_0000_0100_1000;
+        const UTF8_MOUSE              = 0b0000_0000_0100_0000_0000_0000;
+        const ALTERNATE_SCROLL        = 0b0000_0000_1000_0000_0000_0000;
+        const VI                      = 0b0000_0001_0000_0000_0000_0000;
+        const URGENCY_HINTS           = 0b0000_0010_0000_0000_0000_0000;
+        const DISAMBIGUATE_ESC_CODES  = 0b0000_0100_0000_0000_0000_0000;
+        const REPORT_EVENT_TYPES      = 0b0000_1000_0000_0000_0000_0000;
+        const REPORT_ALTERNATE_KEYS   = 0b0001_0000_0000_0000_0000_0000;
+        const REPORT_ALL_KEYS_AS_ESC  = 0b0010_0000_0000_0000_0000_0000;
+        const REPORT_ASSOCIATED_TEXT  = 0b0100_0000_0000_0000_0000_0000;
+        const KITTY_KEYBOARD_PROTOCOL = Self::DISAMBIGUATE_ESC_CODES.bits()
+                                      | Self::REPORT_EVENT_TYPES.bits()
+                                      | Self::REPORT_ALTERNATE_KEYS.bits()
+                                      | Self::REPORT_ALL_KEYS_AS_ESC.bits()
+                                      | Self::REPORT_ASSOCIATED_TEXT.bits();
+        const ANY                     = u32::MAX;
     }
 }
 
@@ -75,6 +96,54 @@ impl Default for TermMode {
     }
 }
 
+impl From<KeyboardModes> for TermMode {
+    fn from(value: KeyboardModes) -> Self {
+        let mut mode = Self::empty();
+        mode.set(
+            TermMode::DISAMBIGUATE_ESC_CODES,
+            value.contains(KeyboardModes::DISAMBIGUATE_ESC_CODES),
+        );
+        mode.set(TermMode::REPORT_EVENT_TYPES, value.contains(KeyboardModes::REPORT_EVENT_TYPES));
+        mode.set(
+            TermMode::REPORT_ALTERNATE_KEYS,
+            value.contains(KeyboardModes::REPORT_ALTERNATE_KEYS),
+        );
+        mode.set(
+            TermMode::REPORT_ALL_KEYS_AS_ESC,
+            value.contains(KeyboardModes::REPORT_ALL_KEYS_AS_ESC),
+        );
+        mode.set(
+            TermMode::REPORT_ASSOCIATED_TEXT,
+            value.contains(KeyboardModes::REPORT_ASSOCIATED_TEXT),
+        );
+        mode
+    }
+}
+
+impl From<TermMode> for KeyboardModes {
+    fn from(value: TermMode) -> Self {
+        let mut mode = Self::empty();
+        mode.set(
+            KeyboardModes::DISAMBIGUATE_ESC_CODES,
+            value.contains(TermMode::DISAMBIGUATE_ESC_CODES),
+        );
+        mode.set(KeyboardModes::REPORT_EVENT_TYPES, value.contains(TermMode::REPORT_EVENT_TYPES));
+        mode.set(
+            KeyboardModes::REPORT_ALTERNATE_KEYS,
+            value.contains(TermMode::REPORT_ALTERNATE_KEYS),
+        );
+        mode.set(
+            KeyboardModes::REPORT_ALL_KEYS_AS_ESC,
+            value.contains(TermMode::REPORT_ALL_KEYS_AS_ESC),
+        );
+        mode.set(
+            KeyboardModes::REPORT_ASSOCIATED_TEXT,
+            value.contains(TermMode::REPORT_ASSOCIATED_TEXT),
+        );
+        mode
+    }
+}
+
 /// Convert a terminal point to a viewport relative point.
 #[inline]
 pub fn point_to_viewport(display_offset: usize, point: Point) -> Option<Point<usize>> {
@@ -250,6 +319,24 @@ pub struct Term<T> {
     /// Cursor for keyboard selection.
     pub vi_mode_cursor: ViModeCursor,
 
+    /// Vi mode marks and jump list of the active grid.
+    pub vi_marks: ViMarks,
+
+    /// Vi mode marks and jump list of the currently inactive grid.
+    inactive_vi_marks: ViMarks,
+
+    /// Lines with the start of a prompt in the active grid.
+    prompt_index: PromptIndex,
+
+    /// Lines with the start of a prompt in the currently inactive grid.
+    inactive_prompt_index: PromptIndex,
+
+    /// Counter changed whenever lines move within the active grid.
+    grid_generation: usize,
+
+    /// Number of lines rotated into the scrollback history of the active grid.
+    history_rotation: usize,
+
     pub selection: Option<Selection>,
 
     /// Currently active grid.
@@ -302,6 +389,27 @@ pub struct Term<T> {
     /// term is set.
     title_stack: Vec<Option<String>>,
 
+    /// Stack of active kitty keyboard protocol modes.
+    keyboard_mode_stack: Vec<KeyboardModes>,
+
+    /// Keyboard modes stack of the currently inactive grid.
+    inactive_keyboard_mode_stack: Vec<KeyboardModes>,
+
+    /// Working directory reported by the shell.
+    working_directory: Option<WorkingDirectory>,
+
+    /// Size of the window and its cells in pixels, used for placing graphics.
+    window_size: Option<WindowSize>,
+
+    /// Images transmitted through the kitty graphics protocol.
+    kitty_images: ImageStore,
+
+    /// Kitty graphics placements of the active grid.
+    kitty_placements: PlacementIndex,
+
+    /// Kitty graphics placements of the currently inactive grid.
+    inactive_kitty_placements: PlacementIndex,
+
     /// Information about damaged cells.
     damage: TermDamageState,
 
@@ -352,6 +460,12 @@ impl<T> Term<T> {
             inactive_grid: alt,
             active_charset: Default::default(),
             vi_mode_cursor: Default::default(),
+            vi_marks: Default::default(),
+            inactive_vi_marks: Default::default(),
+            prompt_index: Default::default(),
+            inactive_prompt_index: Default::default(),
+            grid_generation: 0,
+            history_rotation: 0,
             tabs,
             mode: Default::default(),
             scroll_region,
@@ -364,6 +478,13 @@ impl<T> Term<T> {
             is_focused: true,
             title: None,
             title_stack: Vec::new(),
+            keyboard_mode_stack: Vec::new(),
+            inactive_keyboard_mode_stack: Vec::new(),
+            working_directory: None,
+            window_size: None,
+            kitty_images: Default::default(),
+            kitty_placements: Default::default(),
+            inactive_kitty_placements: Default::default(),
             selection: None,
             damage,
             config: config.terminal.clone(),
@@ -461,8 +582,14 @@ impl<T> Term<T> {
 
         if self.mode.contains(TermMode::ALT_SCREEN) {
             self.inactive_grid.update_history(config.scrolling.history() as usize);
+            self.inactive_vi_marks.shift(&self.inactive_grid, 0);
+            self.inactive_prompt_index.truncate(&self.inactive_grid);
+            self.inactive_kitty_placements.truncate(&self.inactive_grid);
         } else {
             self.grid.update_history(config.scrolling.history() as usize);
+            self.vi_marks.shift(&self.grid, 0);
+            self.prompt_index.truncate(&self.grid);
+            self.kitty_placements.truncate(&self.grid);
         }
 
         self.config = config.terminal.clone();
@@ -597,6 +724,46 @@ impl<T> Term<T> {
         &mut self.grid
     }
 
+    /// Grid of the primary screen, even while the alternate screen is active.
+    pub fn primary_grid(&self) -> &Grid<Cell> {
+        if self.mode.contains(TermMode::ALT_SCREEN) {
+            &self.inactive_grid
+        } else {
+            &self.grid
+        }
+    }
+
+    /// Counter which changes whenever lines are moved within the active grid.
+    ///
+    /// This allows long-running operations on the grid content to detect when their stored
+    /// points have been invalidated. Rotating lines into the scrollback history is tracked by
+    /// [`Term::history_rotation`] instead.
+    #[inline]
+    pub fn grid_generation(&self) -> usize {
+        self.grid_generation
+    }
+
+    /// Total number of lines rotated into the scrollback history, wrapping around on overflow.
+    ///
+    /// Points stored by long-running operations can follow the grid content by moving them up by
+    /// the change of this counter, as long as the [`Term::grid_generation`] is unchanged.
+    #[inline]
+    pub fn history_rotation(&self) -> usize {
+        self.history_rotation
+    }
+
+    /// Working directory reported by the shell through OSC 7.
+    pub fn working_directory(&self) -> Option<&WorkingDirectory> {
+        self.working_directory.as_ref()
+    }
+
+    /// Update the window's pixel dimensions.
+    ///
+    /// Graphics are ignored until the cell dimensions are known.
+    pub fn set_window_size(&mut self, window_size: WindowSize) {
+        self.window_size = Some(window_size);
+    }
+
     /// Resize terminal to new dimensions.
     pub fn resize<S: Dimensions>(&mut self, size: S) {
         let old_cols = self.columns();
@@ -623,6 +790,20 @@ impl<T> Term<T> {
         self.grid.resize(!is_alt, num_lines, num_cols);
         self.inactive_grid.resize(is_alt, num_lines, num_cols);
 
+        self.grid_generation = self.grid_generation.wrapping_add(1);
+
+        // Move vi mode marks with the content, like the vi mode cursor.
+        self.vi_marks.shift(&self.grid, delta);
+        self.inactive_vi_marks.shift(&self.inactive_grid, 0);
+
+        // Marks are moved by reflow, so the prompt lines have to be searched for again.
+        self.prompt_index = PromptIndex::new(&self.grid);
+        self.inactive_prompt_index = PromptIndex::new(&self.inactive_grid);
+
+        // Placements can't be followed through reflow, so they're searched for when necessary.
+        self.kitty_placements.invalidate();
+        self.inactive_kitty_placements.invalidate();
+
         // Invalidate selection and tabs only when necessary.
         if old_cols != num_cols {
             self.selection = None;
@@ -650,6 +831,45 @@ impl<T> Term<T> {
         self.damage.resize(num_cols, num_lines);
     }
 
+    /// Replace the primary screen with a previously saved grid.
+    ///
+    /// The saved content is reflowed to the current dimensions and the cursor is placed at the
+    /// start of the line below it, so new output continues underneath the restored history.
+    pub fn restore_grid(&mut self, mut grid: Grid<Cell>) {
+        let num_lines = self.screen_lines();
+        let num_cols = self.columns();
+
+        grid.update_history(self.grid.max_scroll_limit());
+        grid.resize(true, num_lines, num_cols);
+        grid.scroll_display(Scroll::Bottom);
+
+        // Find the first line below all visible content.
+        let next_line = (0..num_lines)
+            .rev()
+            .find(|&line| !grid[Line(line as i32)].is_clear())
+            .map_or(0, |line| line + 1);
+
+        // Scroll the restored content up if there is no empty line left for the cursor.
+        let cursor_line = if next_line >= num_lines {
+            grid.scroll_up(&(Line(0)..Line(num_lines as i32)), 1);
+            num_lines - 1
+        } else {
+            next_line
+        };
+
+        grid.cursor = Default::default();
+        grid.cursor.point.line = Line(cursor_line as i32);
+        grid.saved_cursor = grid.cursor.clone();
+
+        self.grid = grid;
+        self.selection = None;
+        self.vi_marks = Default::default();
+        self.prompt_index = PromptIndex::new(&self.grid);
+        self.kitty_placements = Default::default();
+        self.grid_generation = self.grid_generation.wrapping_add(1);
+        self.mark_fully_damaged();
+    }
+
     /// Active terminal modes.
     #[inline]
     pub fn mode(&self) -> &TermMode {
@@ -667,9 +887,22 @@ impl<T> Term<T> {
 
             // Reset alternate screen contents.
             self.inactive_grid.reset_region(..);
+            self.inactive_vi_marks = Default::default();
+            self.inactive_prompt_index = Default::default();
+            self.inactive_kitty_placements = Default::default();
         }
 
+        // Keyboard modes are tracked separately for each screen buffer.
+        mem::swap(&mut self.keyboard_mode_stack, &mut self.inactive_keyboard_mode_stack);
+        let keyboard_mode = self.keyboard_mode_stack.last().copied().unwrap_or_default();
+        self.mode &= !TermMode::KITTY_KEYBOARD_PROTOCOL;
+        self.mode |= TermMode::from(keyboard_mode);
+
         mem::swap(&mut self.grid, &mut self.inactive_grid);
+        mem::swap(&mut self.vi_marks, &mut self.inactive_vi_marks);
+        mem::swap(&mut self.prompt_index, &mut self.inactive_prompt_index);
+        mem::swap(&mut self.kitty_placements, &mut self.inactive_kitty_placements);
+        self.grid_generation = self.grid_generation.wrapping_add(1);
         self.mode ^= TermMode::ALT_SCREEN;
         self.selection = None;
         self.mark_fully_damaged();
@@ -700,6 +933,10 @@ impl<T> Term<T> {
 
         // Scroll between origin and bottom
         self.grid.scroll_down(&region, lines);
+        self.vi_marks.rotate(&self.grid, &region, -(lines as i32));
+        self.prompt_index.rotate(&self.grid, &region, -(lines as i32));
+        self.kitty_placements.rotate(&self.grid, &region, -(lines as i32));
+        self.grid_generation = self.grid_generation.wrapping_add(1);
         self.mark_fully_damaged();
     }
 
@@ -719,6 +956,17 @@ impl<T> Term<T> {
         self.selection = self.selection.take().and_then(|s| s.rotate(self, &region, lines as i32));
 
         self.grid.scroll_up(&region, lines);
+        self.grid.compress_history();
+        self.vi_marks.rotate(&self.grid, &region, lines as i32);
+        self.prompt_index.rotate(&self.grid, &region, lines as i32);
+        self.kitty_placements.rotate(&self.grid, &region, lines as i32);
+
+        // Only scrolling the entire screen moves all lines up uniformly.
+        if region == (Line(0)..Line(self.screen_lines() as i32)) {
+            self.history_rotation = self.history_rotation.wrapping_add(lines);
+        } else {
+            self.grid_generation = self.grid_generation.wrapping_add(1);
+        }
 
         // Scroll vi mode cursor.
         let viewport_top = Line(-(self.grid.display_offset() as i32));
@@ -791,6 +1039,22 @@ impl<T> Term<T> {
         self.vi_mode_recompute_selection();
     }
 
+    /// Move vi cursor to the next match of an inline search.
+    #[inline]
+    pub fn vi_inline_search(&mut self, search: InlineSearch, repeat: bool)
+    where
+        T: EventListener,
+    {
+        // Require vi mode to be active.
+        if !self.mode.contains(TermMode::VI) {
+            return;
+        }
+
+        // Move cursor.
+        self.vi_mode_cursor = self.vi_mode_cursor.inline_search(self, search, repeat);
+        self.vi_mode_recompute_selection();
+    }
+
     /// Move vi cursor to a point in the grid.
     #[inline]
     pub fn vi_goto_point(&mut self, point: Point)
@@ -1181,7 +1445,7 @@ impl<T: EventListener> Handler for Term<T> {
         match intermediate {
             None => {
                 trace!("Reporting primary device attributes");
-                let text = String::from("\x1b[?6c");
+                let text = String::from("\x1b[?62;4;22c");
                 self.event_proxy.send_event(Event::PtyWrite(text));
             },
             Some('>') => {
@@ -1633,13 +1897,20 @@ impl<T: EventListener> Handler for Term<T> {
                 } else {
                     let old_offset = self.grid.display_offset();
 
-                    self.grid.clear_viewport();
+                    let positions = self.grid.clear_viewport();
 
                     // Compute number of lines scrolled by clearing the viewport.
                     let lines = self.grid.display_offset().saturating_sub(old_offset);
 
                     self.vi_mode_cursor.point.line =
                         (self.vi_mode_cursor.point.line - lines).grid_clamp(self, Boundary::Grid);
+
+                    // Move vi mode marks with the content.
+                    let region = Line(0)..Line(self.screen_lines() as i32);
+                    self.vi_marks.rotate(&self.grid, &region, positions as i32);
+                    self.prompt_index.rotate(&self.grid, &region, positions as i32);
+                    self.kitty_placements.rotate(&self.grid, &region, positions as i32);
+                    self.history_rotation = self.history_rotation.wrapping_add(positions);
                 }
 
                 self.selection = None;
@@ -1649,6 +1920,9 @@ impl<T: EventListener> Handler for Term<T> {
 
                 self.vi_mode_cursor.point.line =
                     self.vi_mode_cursor.point.line.grid_clamp(self, Boundary::Cursor);
+                self.vi_marks.shift(&self.grid, 0);
+                self.prompt_index.truncate(&self.grid);
+                self.kitty_placements.truncate(&self.grid);
 
                 self.selection = self.selection.take().filter(|s| !s.intersects_range(..Line(0)));
             },
@@ -1685,9 +1959,19 @@ impl<T: EventListener> Handler for Term<T> {
         self.scroll_region = Line(0)..Line(self.screen_lines() as i32);
         self.tabs = TabStops::new(self.columns());
         self.title_stack = Vec::new();
+        self.keyboard_mode_stack = Vec::new();
+        self.inactive_keyboard_mode_stack = Vec::new();
+        self.kitty_images = Default::default();
+        self.kitty_placements = Default::default();
+        self.inactive_kitty_placements = Default::default();
         self.title = None;
         self.selection = None;
         self.vi_mode_cursor = Default::default();
+        self.vi_marks = Default::default();
+        self.inactive_vi_marks = Default::default();
+        self.prompt_index = Default::default();
+        self.inactive_prompt_index = Default::default();
+        self.grid_generation = self.grid_generation.wrapping_add(1);
 
         // Preserve vi mode across resets.
         self.mode &= TermMode::VI;
@@ -1989,6 +2273,97 @@ impl<T: EventListener> Handler for Term<T> {
     }
 }
 
+impl<T: EventListener> ExtendedHandler for Term<T> {
+    #[inline]
+    fn push_keyboard_mode(&mut self, mode: KeyboardModes) {
+        trace!("Pushing `{:?}` keyboard mode into the stack", mode);
+
+        if self.keyboard_mode_stack.len() >= KEYBOARD_MODE_STACK_MAX_DEPTH {
+            let removed = self.keyboard_mode_stack.remove(0);
+            trace!(
+                "Removing '{:?}' from bottom of keyboard mode stack that exceeds its maximum depth",
+                removed
+            );
+        }
+
+        self.keyboard_mode_stack.push(mode);
+        self.set_keyboard_mode(mode, KeyboardModesApplyBehavior::Replace);
+    }
+
+    #[inline]
+    fn pop_keyboard_modes(&mut self, to_pop: u16) {
+        trace!("Attempting to pop {} keyboard modes from the stack", to_pop);
+
+        let new_len = self.keyboard_mode_stack.len().saturating_sub(to_pop as usize);
+        self.keyboard_mode_stack.truncate(new_len);
+
+        // Reload the active mode, resetting all flags once the stack is empty.
+        let mode = self.keyboard_mode_stack.last().copied().unwrap_or_default();
+        self.set_keyboard_mode(mode, KeyboardModesApplyBehavior::Replace);
+    }
+
+    #[inline]
+    fn report_keyboard_mode(&mut self) {
+        trace!("Reporting active keyboard mode");
+        let mode = KeyboardModes::from(self.mode);
+        let text = format!("\x1b[?{}u", mode.bits());
+        self.event_proxy.send_event(Event::PtyWrite(text));
+    }
+
+    #[inline]
+    fn set_keyboard_mode(&mut self, mode: KeyboardModes, behavior: KeyboardModesApplyBehavior) {
+        trace!("Setting keyboard mode to {:?} with behavior {:?}", mode, behavior);
+
+        let active_mode = KeyboardModes::from(self.mode);
+        let new_mode = match behavior {
+            KeyboardModesApplyBehavior::Replace => mode,
+            KeyboardModesApplyBehavior::Union => active_mode | mode,
+            KeyboardModesApplyBehavior::Difference => active_mode & !mode,
+        };
+
+        // Keep the top of the stack in sync with the active mode.
+        if let Some(top) = self.keyboard_mode_stack.last_mut() {
+            *top = new_mode;
+        }
+
+        self.mode &= !TermMode::KITTY_KEYBOARD_PROTOCOL;
+        self.mode |= TermMode::from(new_mode);
+    }
+
+    #[inline]
+    fn set_semantic_mark(&mut self, mark: SemanticMark) {
+        trace!("Setting semantic mark {:?}", mark);
+
+        let point = self.grid.cursor.point;
+        self.grid[point.line].set_mark(point.column, mark);
+
+        if mark == SemanticMark::PromptStart {
+            self.prompt_index.insert(point.line);
+        }
+    }
+
+    #[inline]
+    fn set_working_directory(&mut self, working_directory: WorkingDirectory) {
+        trace!("Setting working directory to {:?}", working_directory);
+
+        if self.working_directory.as_ref() != Some(&working_directory) {
+            self.event_proxy.send_event(Event::WorkingDirectory(working_directory.clone()));
+            self.working_directory = Some(working_directory);
+        }
+    }
+
+    #[inline]
+    fn insert_graphic(&mut self, graphic: GraphicData) {
+        let placement = GraphicPlacement::new(Arc::new(graphic));
+        self.place_graphic(placement, GraphicCursor::NextLine);
+    }
+
+    #[inline]
+    fn kitty_graphics(&mut self, command: GraphicsCommand) {
+        self.kitty_graphics_command(command);
+    }
+}
+
 /// Terminal version for escape sequence reports.
 ///
 /// This returns the current terminal version as a unique number based on alacritty_terminal's
@@ -2210,7 +2585,9 @@ pub mod test {
 mod tests {
     use super::*;
 
+    use std::cell::{Cell as StdCell, RefCell};
     use std::mem;
+    use std::path::PathBuf;
 
     use crate::ansi::{self, CharsetIndex, Handler, StandardCharset};
     use crate::config::Config;
@@ -2506,6 +2883,28 @@ mod tests {
         assert_eq!(term.vi_mode_cursor.point, Point::new(Line(-5), Column(3)));
     }
 
+    #[test]
+    fn vi_marks_follow_scrolled_content() {
+        let size = TermSize::new(10, 5);
+        let mut term = Term::new(&Config::default(), &size, VoidListener);
+
+        term.vi_marks.set_mark('a', Point::new(Line(2), Column(3)));
+        term.vi_marks.push_jump(Point::new(Line(4), Column(0)));
+
+        // Scroll the marked lines into the history.
+        for _ in 0..7 {
+            term.newline();
+        }
+
+        assert_eq!(term.vi_marks.mark('a'), Some(Point::new(Line(-1), Column(3))));
+        let current = Point::new(Line(0), Column(0));
+        assert_eq!(term.vi_marks.jump_back(current), Some(Point::new(Line(1), Column(0))));
+
+        // Marks are removed with the history.
+        term.clear_screen(ansi::ClearMode::Saved);
+        assert_eq!(term.vi_marks.mark('a'), None);
+    }
+
     #[test]
     fn clearing_scrollback_resets_display_offset() {
         let size = TermSize::new(10, 20);
@@ -2687,6 +3086,43 @@ mod tests {
         assert_eq!(term.grid.cursor.point, Point::new(Line(4), Column(0)));
     }
 
+    #[test]
+    fn restore_grid() {
+        let size = TermSize::new(10, 5);
+        let mut term = Term::new(&Config::default(), &size, VoidListener);
+
+        // Fill the screen and create 3 lines of scrollback.
+        for c in 'a'..='h' {
+            term.input(c);
+            term.carriage_return();
+            term.linefeed();
+        }
+        let grid: Grid<Cell> = serde_json::from_str(&serde_json::to_string(&term.grid).unwrap())
+            .expect("deserialize grid");
+
+        let mut restored = Term::new(&Config::default(), &size, VoidListener);
+        restored.restore_grid(grid);
+
+        assert_eq!(restored.history_size(), 4);
+        assert_eq!(restored.grid.cursor.point, Point::new(Line(4), Column(0)));
+        assert_eq!(restored.grid[Line(-4)][Column(0)].c, 'a');
+        assert_eq!(restored.grid[Line(3)][Column(0)].c, 'h');
+        assert!(restored.grid[Line(4)].is_clear());
+
+        // Content which does not fill the screen stays at the top.
+        let mut term = Term::new(&Config::default(), &size, VoidListener);
+        term.input('a');
+        let grid = term.grid.clone();
+
+        let mut restored = Term::new(&Config::default(), &TermSize::new(5, 3), VoidListener);
+        restored.restore_grid(grid);
+
+        assert_eq!(restored.history_size(), 0);
+        assert_eq!(restored.columns(), 5);
+        assert_eq!(restored.grid.cursor.point, Point::new(Line(1), Column(0)));
+        assert_eq!(restored.grid[Line(0)][Column(0)].c, 'a');
+    }
+
     #[test]
     fn damage_public_usage() {
         let size = TermSize::new(10, 10);
@@ -3012,6 +3448,395 @@ mod tests {
         assert_eq!(term.title, None);
     }
 
+    #[test]
+    fn keyboard_mode_stack() {
+        let size = TermSize::new(7, 17);
+        let mut term = Term::new(&Config::default(), &size, VoidListener);
+
+        // Pushing a mode activates it.
+        term.push_keyboard_mode(KeyboardModes::DISAMBIGUATE_ESC_CODES);
+        assert!(term.mode.contains(TermMode::DISAMBIGUATE_ESC_CODES));
+
+        term.push_keyboard_mode(KeyboardModes::REPORT_EVENT_TYPES);
+        assert!(!term.mode.contains(TermMode::DISAMBIGUATE_ESC_CODES));
+        assert!(term.mode.contains(TermMode::REPORT_EVENT_TYPES));
+
+        // Modes can be modified in place.
+        term.set_keyboard_mode(
+            KeyboardModes::REPORT_ALTERNATE_KEYS,
+            KeyboardModesApplyBehavior::Union,
+        );
+        assert!(term.mode.contains(TermMode::REPORT_EVENT_TYPES));
+        assert!(term.mode.contains(TermMode::REPORT_ALTERNATE_KEYS));
+        term.set_keyboard_mode(
+            KeyboardModes::REPORT_EVENT_TYPES,
+            KeyboardModesApplyBehavior::Difference,
+        );
+        assert!(!term.mode.contains(TermMode::REPORT_EVENT_TYPES));
+        assert!(term.mode.contains(TermMode::REPORT_ALTERNATE_KEYS));
+
+        // Popping restores the previous mode.
+        term.pop_keyboard_modes(1);
+        assert!(term.mode.contains(TermMode::DISAMBIGUATE_ESC_CODES));
+        assert!(!term.mode.contains(TermMode::REPORT_ALTERNATE_KEYS));
+
+        // Popping too many modes resets everything.
+        term.pop_keyboard_modes(10);
+        assert!(!term.mode.intersects(TermMode::KITTY_KEYBOARD_PROTOCOL));
+
+        // Stack doesn't grow infinitely.
+        for _ in 0..4097 {
+            term.push_keyboard_mode(KeyboardModes::DISAMBIGUATE_ESC_CODES);
+        }
+        assert_eq!(term.keyboard_mode_stack.len(), 4096);
+
+        // Stack is reset when terminal state is reset.
+        term.reset_state();
+        assert!(term.keyboard_mode_stack.is_empty());
+        assert!(!term.mode.intersects(TermMode::KITTY_KEYBOARD_PROTOCOL));
+    }
+
+    #[test]
+    fn keyboard_mode_per_screen() {
+        let size = TermSize::new(7, 17);
+        let mut term = Term::new(&Config::default(), &size, VoidListener);
+
+        term.push_keyboard_mode(KeyboardModes::DISAMBIGUATE_ESC_CODES);
+
+        // Alternate screen starts without any keyboard mode.
+        term.swap_alt();
+        assert!(!term.mode.intersects(TermMode::KITTY_KEYBOARD_PROTOCOL));
+        term.push_keyboard_mode(KeyboardModes::REPORT_ALL_KEYS_AS_ESC);
+
+        // Primary screen mode is restored when leaving the alternate screen.
+        term.swap_alt();
+        assert!(term.mode.contains(TermMode::DISAMBIGUATE_ESC_CODES));
+        assert!(!term.mode.contains(TermMode::REPORT_ALL_KEYS_AS_ESC));
+
+        term.swap_alt();
+        assert!(term.mode.contains(TermMode::REPORT_ALL_KEYS_AS_ESC));
+    }
+
+    #[test]
+    fn parse_semantic_marks() {
+        let size = TermSize::new(10, 6);
+        let mut term = Term::new(&Config::default(), &size, VoidListener);
+        let mut parser = ansi::Processor::new();
+
+        let session = "\x1b]133;A\x07$ \x1b]133;B\x07ls\r\n\x1b]133;C\x07a\r\nb\r\n\
+                       \x1b]133;D;1\x07\x1b]133;A\x1b\\$ \x1b]133;B\x1b\\";
+        for byte in session.bytes() {
+            parser.advance(&mut term, byte);
+        }
+
+        assert_eq!(
+            term.grid[Line(0)].marks(),
+            &[(Column(0), SemanticMark::PromptStart), (Column(2), SemanticMark::CommandStart),]
+        );
+        assert_eq!(term.grid[Line(1)].marks(), &[(Column(0), SemanticMark::OutputStart)]);
+        assert_eq!(
+            term.grid[Line(3)].marks(),
+            &[
+                (Column(0), SemanticMark::CommandEnd(Some(1))),
+                (Column(0), SemanticMark::PromptStart),
+                (Column(2), SemanticMark::CommandStart),
+            ]
+        );
+
+        let output = term.last_command_output().unwrap();
+        assert_eq!(output, Point::new(Line(1), Column(0))..=Point::new(Line(2), Column(9)));
+        assert_eq!(term.bounds_to_string(*output.start(), *output.end()), "a\nb");
+
+        let prompt = term.prompt_search(Point::new(Line(3), Column(0)), Direction::Left);
+        assert_eq!(prompt, Some(Point::new(Line(0), Column(0))));
+        assert_eq!(term.prompt_lines().collect::<Vec<_>>(), [Line(0), Line(3)]);
+
+        // Marks are moved into history with their lines.
+        for _ in 0..6 {
+            term.newline();
+        }
+        let prompt = term.prompt_search(term.grid.cursor.point, Direction::Left);
+        assert_eq!(prompt, Some(Point::new(Line(-1), Column(0))));
+        assert_eq!(term.prompt_lines().collect::<Vec<_>>(), [Line(-4), Line(-1)]);
+    }
+
+    #[test]
+    fn long_command_output() {
+        let mut config = Config::default();
+        config.scrolling.set_history(20_000);
+        let size = TermSize::new(10, 5);
+        let mut term = Term::new(&config, &size, VoidListener);
+        let mut parser = ansi::Processor::new();
+
+        let mut session = String::from("\x1b]133;A\x07$ \x1b]133;B\x07ls\r\n\x1b]133;C\x07");
+        session.push_str(&"a\r\n".repeat(15_000));
+        session.push_str("\x1b]133;D;0\x07\x1b]133;A\x07$ \x1b]133;B\x07");
+        for byte in session.bytes() {
+            parser.advance(&mut term, byte);
+        }
+
+        // Output is found through its prompt, even beyond the mark search limit.
+        let start = Point::new(Line(-14_996), Column(0));
+        let end = Point::new(Line(3), Column(9));
+        assert_eq!(term.last_command_output(), Some(start..=end));
+        assert_eq!(term.command_output(Point::new(Line(-10), Column(0))), Some(start..=end));
+        assert_eq!(term.command_output(Point::new(Line(4), Column(0))), None);
+        assert_eq!(term.command_output(Point::new(Line(-14_997), Column(3))), None);
+    }
+
+    #[test]
+    fn prompt_lines_follow_content() {
+        let size = TermSize::new(10, 4);
+        let mut term = Term::new(&Config::default(), &size, VoidListener);
+        let mut parser = ansi::Processor::new();
+
+        for byte in "\x1b]133;A\x07$ \r\n\r\n\x1b]133;A\x07$ ".bytes() {
+            parser.advance(&mut term, byte);
+        }
+        assert_eq!(term.prompt_lines().collect::<Vec<_>>(), [Line(0), Line(2)]);
+
+        // Cleared prompts are ignored, even after entering the history.
+        term.goto(1, 0);
+        term.clear_screen(ansi::ClearMode::Below);
+        assert_eq!(term.prompt_lines().collect::<Vec<_>>(), [Line(0)]);
+        for _ in 0..5 {
+            term.newline();
+        }
+        assert_eq!(term.prompt_lines().collect::<Vec<_>>(), [Line(-3)]);
+        assert_eq!(term.prompt_index, PromptIndex::new(&term.grid));
+
+        // Scrolling down only moves prompts on the screen.
+        for byte in "\x1b[1;1H\x1b]133;A\x07".bytes() {
+            parser.advance(&mut term, byte);
+        }
+        term.scroll_down_relative(Line(0), 1);
+        assert_eq!(term.prompt_lines().collect::<Vec<_>>(), [Line(-3), Line(1)]);
+        assert_eq!(term.prompt_index, PromptIndex::new(&term.grid));
+
+        // Prompts are searched for again after reflow.
+        term.resize(TermSize::new(5, 4));
+        assert_eq!(term.prompt_index, PromptIndex::new(&term.grid));
+        assert_eq!(term.prompt_lines().count(), 2);
+    }
+
+    #[test]
+    fn parse_working_directory() {
+        /// Event listener counting working directory changes.
+        #[derive(Default)]
+        struct Listener(StdCell<usize>);
+
+        impl EventListener for Listener {
+            fn send_event(&self, event: Event) {
+                if let Event::WorkingDirectory(_) = event {
+                    self.0.set(self.0.get() + 1);
+                }
+            }
+        }
+
+        let size = TermSize::new(10, 6);
+        let mut term = Term::new(&Config::default(), &size, Listener::default());
+        let mut parser = ansi::Processor::new();
+
+        for byte in b"\x1b]7;file://host/tmp/a%20b;c\x07\x1b]7;file://host/tmp/a%20b;c\x1b\\" {
+            parser.advance(&mut term, *byte);
+        }
+
+        let expected = WorkingDirectory {
+            hostname: Some(String::from("host")),
+            path: PathBuf::from("/tmp/a b;c"),
+        };
+        assert_eq!(term.working_directory(), Some(&expected));
+        assert_eq!(term.event_proxy.0.get(), 1);
+
+        for byte in b"\x1b]7;file:///home\x07\x1b]7;invalid\x07" {
+            parser.advance(&mut term, *byte);
+        }
+
+        let expected = WorkingDirectory { hostname: None, path: PathBuf::from("/home") };
+        assert_eq!(term.working_directory(), Some(&expected));
+        assert_eq!(term.event_proxy.0.get(), 2);
+    }
+
+    #[test]
+    fn parse_sixel_graphic() {
+        let size = TermSize::new(10, 6);
+        let mut term = Term::new(&Config::default(), &size, VoidListener);
+        let mut parser = ansi::Processor::new();
+
+        // 15x24 pixel red rectangle.
+        let sixel = b"\x1bP0;1q#1;2;100;0;0!15~-!15~-!15~-!15~\x1b\\";
+
+        // Graphics are ignored without cell dimensions.
+        for byte in sixel {
+            parser.advance(&mut term, *byte);
+        }
+        assert_eq!(term.grid.cursor.point, Point::new(Line(0), Column(0)));
+
+        term.set_window_size(WindowSize {
+            num_lines: 6,
+            num_cols: 10,
+            cell_width: 10,
+            cell_height: 20,
+        });
+
+        parser.advance(&mut term, b'a');
+        for byte in sixel {
+            parser.advance(&mut term, *byte);
+        }
+
+        // Cursor continues below the graphic.
+        assert_eq!(term.grid.cursor.point, Point::new(Line(2), Column(1)));
+
+        assert_eq!(term.grid[Line(0)][Column(0)].c, 'a');
+        assert!(term.grid[Line(0)][Column(0)].graphics().is_empty());
+        assert!(term.grid[Line(0)][Column(3)].graphics().is_empty());
+
+        let graphic = &term.grid[Line(0)][Column(1)].graphics()[0];
+        assert_eq!((graphic.x, graphic.y, graphic.width, graphic.height), (0, 0, 15, 24));
+        assert_eq!(graphic.texture.pixels[..4], [255, 0, 0, 255]);
+
+        let graphic = &term.grid[Line(1)][Column(2)].graphics()[0];
+        assert_eq!((graphic.x, graphic.y), (10, 20));
+
+        // Graphics scroll with the text.
+        for byte in b"\n\n\n\n" {
+            parser.advance(&mut term, *byte);
+        }
+        assert_eq!(term.grid[Line(-1)][Column(1)].graphics().len(), 1);
+        assert_eq!(term.grid[Line(0)][Column(1)].graphics().len(), 1);
+
+        // Graphics are removed by erase operations.
+        for byte in b"\x1b[H\x1b[K" {
+            parser.advance(&mut term, *byte);
+        }
+        assert!(term.grid[Line(0)][Column(1)].graphics().is_empty());
+        assert_eq!(term.grid[Line(-1)][Column(1)].graphics().len(), 1);
+
+        // Graphics replace the content of the cells they're drawn over.
+        for byte in b"\x1b[Hxy\x1b[H".iter().chain(sixel).chain(b"\x1b[H").chain(sixel) {
+            parser.advance(&mut term, *byte);
+        }
+        assert_eq!(term.grid[Line(0)][Column(0)].c, ' ');
+        assert_eq!(term.grid[Line(0)][Column(1)].graphics().len(), 1);
+    }
+
+    #[test]
+    fn parse_kitty_graphics() {
+        /// Event listener collecting PTY writes.
+        #[derive(Default)]
+        struct Listener(RefCell<Vec<String>>);
+
+        impl EventListener for Listener {
+            fn send_event(&self, event: Event) {
+                if let Event::PtyWrite(text) = event {
+                    self.0.borrow_mut().push(text);
+                }
+            }
+        }
+
+        let size = TermSize::new(10, 6);
+        let mut term = Term::new(&Config::default(), &size, Listener::default());
+        term.set_window_size(WindowSize {
+            num_lines: 6,
+            num_cols: 10,
+            cell_width: 10,
+            cell_height: 20,
+        });
+        let mut parser = ansi::Processor::new();
+
+        // Transmit a 1x1 RGBA image in two chunks, then display it scaled to 2x2 cells.
+        let escapes = b"\x1b_Ga=t,i=7,s=1,v=1,m=1;AAAA\x1b\\\x1b_Gm=0;AA==\x1b\\\
+                        \x1b_Ga=p,i=7,p=1,c=2,r=2,z=-1\x1b\\";
+        for byte in escapes {
+            parser.advance(&mut term, *byte);
+        }
+
+        let responses = term.event_proxy.0.take();
+        assert_eq!(responses, ["\x1b_Gi=7;OK\x1b\\", "\x1b_Gi=7,p=1;OK\x1b\\"]);
+
+        // Cursor is moved behind the image.
+        assert_eq!(term.grid.cursor.point, Point::new(Line(1), Column(2)));
+
+        let graphic = &term.grid[Line(1)][Column(1)].graphics()[0];
+        assert_eq!((graphic.x, graphic.y, graphic.width, graphic.height), (10, 20, 20, 40));
+        assert_eq!(graphic.z_index, -1);
+        assert_eq!(graphic.kitty.map(|kitty| kitty.placement_id), Some(1));
+
+        // Placing with the same placement ID replaces the old placement.
+        for byte in b"\x1b[H\x1b_Ga=p,i=7,p=1,C=1,q=1\x1b\\" {
+            parser.advance(&mut term, *byte);
+        }
+        assert_eq!(term.grid.cursor.point, Point::new(Line(0), Column(0)));
+        assert_eq!(term.grid[Line(0)][Column(0)].graphics().len(), 1);
+        assert!(term.grid[Line(1)][Column(1)].graphics().is_empty());
+
+        // Missing images are reported.
+        for byte in b"\x1b_Ga=p,i=8\x1b\\" {
+            parser.advance(&mut term, *byte);
+        }
+        let responses = term.event_proxy.0.take();
+        assert_eq!(responses, ["\x1b_Gi=8;ENOENT:Image not found\x1b\\"]);
+
+        // Deleting with an uppercase target frees the image.
+        for byte in b"\x1b_Ga=d,d=I,i=7\x1b\\\x1b_Ga=p,i=7,q=1\x1b\\" {
+            parser.advance(&mut term, *byte);
+        }
+        assert!(term.grid[Line(0)][Column(0)].graphics().is_empty());
+        let responses = term.event_proxy.0.take();
+        assert_eq!(responses, ["\x1b_Gi=7;ENOENT:Image not found\x1b\\"]);
+
+        // Placements are found after scrolling them into the history and resizing.
+        let escapes = b"\x1b_Ga=T,i=9,s=1,v=1,C=1,q=1;AAAAAA==\x1b\\\n\n\n\n\n\n";
+        for byte in escapes {
+            parser.advance(&mut term, *byte);
+        }
+        assert_eq!(term.grid[Line(-1)][Column(0)].graphics().len(), 1);
+
+        term.resize(TermSize::new(8, 6));
+        for byte in b"\x1b_Ga=d,d=i,i=9\x1b\\" {
+            parser.advance(&mut term, *byte);
+        }
+        assert!(term.grid[Line(-1)][Column(0)].graphics().is_empty());
+        assert!(term.kitty_placements.remove(|_| true).is_empty());
+    }
+
+    #[test]
+    fn parse_keyboard_mode_sequences() {
+        let size = TermSize::new(7, 17);
+        let mut term = Term::new(&Config::default(), &size, VoidListener);
+        let mut parser = ansi::Processor::new();
+
+        for byte in b"\x1b[>3u\x1b[=4;2u" {
+            parser.advance(&mut term, *byte);
+        }
+        let expected = TermMode::DISAMBIGUATE_ESC_CODES
+            | TermMode::REPORT_EVENT_TYPES
+            | TermMode::REPORT_ALTERNATE_KEYS;
+        assert_eq!(term.mode & TermMode::KITTY_KEYBOARD_PROTOCOL, expected);
+
+        // Regular `CSI u` still restores the cursor.
+        term.grid.cursor.point = Point::new(Line(2), Column(2));
+        for byte in b"\x1b[<u\x1b[u" {
+            parser.advance(&mut term, *byte);
+        }
+        assert!(!term.mode.intersects(TermMode::KITTY_KEYBOARD_PROTOCOL));
+        assert_eq!(term.grid.cursor.point, Point::new(Line(0), Column(0)));
+
+        // Sequences are buffered during synchronized updates.
+        for byte in b"\x1bP=1s\x1b\\\x1b[>1u" {
+            parser.advance(&mut term, *byte);
+        }
+        assert!(!term.mode.intersects(TermMode::KITTY_KEYBOARD_PROTOCOL));
+        assert!(parser.sync_bytes_count() > 0);
+
+        for byte in b"\x1bP=2s\x1b\\" {
+            parser.advance(&mut term, *byte);
+        }
+        assert!(term.mode.contains(TermMode::DISAMBIGUATE_ESC_CODES));
+        assert_eq!(parser.sync_bytes_count(), 0);
+        assert!(parser.sync_timeout().sync_timeout().is_none());
+    }
+
     #[test]
     fn parse_cargo_version() {
         assert!(version_number(env!("CARGO_PKG_VERSION")) >= 10_01);
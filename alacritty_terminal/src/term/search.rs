@@ -1,17 +1,20 @@
-use std::cmp::max;
-use std::mem;
-use std::ops::RangeInclusive;
+use std::cmp::{max, min};
+use std::collections::VecDeque;
+use std::ops::{Range, RangeInclusive};
+use std::{iter, mem};
 
 pub use regex_automata::dfa::dense::BuildError;
 use regex_automata::dfa::dense::{Builder, Config, DFA};
 use regex_automata::dfa::Automaton;
 use regex_automata::nfa::thompson::Config as ThompsonConfig;
+use regex_automata::util::primitives::StateID;
 use regex_automata::util::syntax::Config as SyntaxConfig;
 use regex_automata::{Anchored, Input};
 
-use crate::grid::{BidirectionalIterator, Dimensions, GridIterator, Indexed};
-use crate::index::{Boundary, Column, Direction, Point, Side};
-use crate::term::cell::{Cell, Flags};
+use crate::ansi::SemanticMark;
+use crate::grid::{BidirectionalIterator, Dimensions, Grid, GridCursor, Row};
+use crate::index::{Boundary, Column, Direction, Line, Point, Side};
+use crate::term::cell::{Cell, Flags, LineLength};
 use crate::term::Term;
 
 /// Used to match equal brackets, when performing a bracket-pair selection.
@@ -20,6 +23,9 @@ const BRACKET_PAIRS: [(char, char); 4] = [('(', ')'), ('[', ']'), ('{', '}'), ('
 /// Maximum DFA size to prevent pathological regexes taking down the entire system.
 const MAX_DFA_SIZE: usize = 100_000_000;
 
+/// Maximum number of lines searched for shell integration marks.
+const MAX_MARK_SEARCH_LINES: usize = 10_000;
+
 pub type Match = RangeInclusive<Point>;
 
 /// Terminal regex search state.
@@ -27,10 +33,14 @@ pub type Match = RangeInclusive<Point>;
 pub struct RegexSearch {
     dfa: DFA<Vec<u32>>,
     rdfa: DFA<Vec<u32>>,
+    multiline: bool,
 }
 
 impl RegexSearch {
     /// Build the forward and backward search DFAs.
+    ///
+    /// Patterns containing a newline, either literally or as `\n`, can match across hard line
+    /// breaks. All other patterns only match within a single line.
     pub fn new(search: &str) -> Result<RegexSearch, Box<BuildError>> {
         // Setup configs for both DFA directions.
         let has_uppercase = search.chars().any(|c| c.is_uppercase());
@@ -48,8 +58,26 @@ impl RegexSearch {
             .thompson(thompson_config)
             .build(search)?;
 
-        Ok(RegexSearch { dfa, rdfa })
+        Ok(RegexSearch { dfa, rdfa, multiline: contains_newline(search) })
+    }
+
+    /// Check if matches can span multiple lines.
+    pub fn is_multiline(&self) -> bool {
+        self.multiline
+    }
+}
+
+/// Check if a regex pattern contains a newline.
+fn contains_newline(search: &str) -> bool {
+    let mut chars = search.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\n' => return true,
+            '\\' if chars.next() == Some('n') => return true,
+            _ => (),
+        }
     }
+    false
 }
 
 impl<T> Term<T> {
@@ -168,9 +196,11 @@ impl<T> Term<T> {
         end: Point,
     ) -> Option<Match> {
         // Find start and end of match.
-        let match_start = self.regex_search(start, end, Direction::Left, false, &regex.rdfa)?;
+        let multiline = regex.multiline;
+        let match_start =
+            self.regex_search(start, end, Direction::Left, false, multiline, &regex.rdfa)?;
         let match_end =
-            self.regex_search(match_start, start, Direction::Right, true, &regex.dfa)?;
+            self.regex_search(match_start, start, Direction::Right, true, multiline, &regex.dfa)?;
 
         Some(match_start..=match_end)
     }
@@ -185,9 +215,11 @@ impl<T> Term<T> {
         end: Point,
     ) -> Option<Match> {
         // Find start and end of match.
-        let match_end = self.regex_search(start, end, Direction::Right, false, &regex.dfa)?;
+        let multiline = regex.multiline;
+        let match_end =
+            self.regex_search(start, end, Direction::Right, false, multiline, &regex.dfa)?;
         let match_start =
-            self.regex_search(match_end, start, Direction::Left, true, &regex.rdfa)?;
+            self.regex_search(match_end, start, Direction::Left, true, multiline, &regex.rdfa)?;
 
         Some(match_start..=match_end)
     }
@@ -195,22 +227,26 @@ impl<T> Term<T> {
     /// Find the next regex match.
     ///
     /// This will always return the side of the first match which is farthest from the start point.
+    ///
+    /// Multi-line searches pass a newline to the DFA at the end of every unwrapped line, located
+    /// in the first empty cell after its text. The remaining empty cells are skipped.
     fn regex_search(
         &self,
         start: Point,
         end: Point,
         direction: Direction,
         anchored: bool,
+        multiline: bool,
         regex: &impl Automaton,
     ) -> Option<Point> {
         let topmost_line = self.topmost_line();
         let screen_lines = self.screen_lines() as i32;
         let last_column = self.last_column();
 
-        // Advance the iterator.
+        // Advance the cursor.
         let next = match direction {
-            Direction::Right => GridIterator::next,
-            Direction::Left => GridIterator::prev,
+            Direction::Right => GridCursor::next_cell,
+            Direction::Left => GridCursor::prev_cell,
         };
 
         // Get start state for the DFA.
@@ -219,40 +255,29 @@ impl<T> Term<T> {
         let start_state = regex.start_state_forward(&input).unwrap();
         let mut state = start_state;
 
-        let mut iter = self.grid.iter_from(start);
+        let mut cursor = self.grid.cursor_from(start);
         let mut last_wrapped = false;
         let mut regex_match = None;
         let mut done = false;
 
-        let mut cell = iter.cell();
-        self.skip_fullwidth(&mut iter, &mut cell, direction);
-        let mut c = cell.c;
+        let (c, _) = self.skip_fullwidth(&mut cursor, direction);
+        let mut c = Some(c);
 
-        let mut point = iter.point();
+        let mut point = cursor.point();
+        let mut line_end = LineEnd::new(&mut cursor, last_column, point.line);
+        if multiline {
+            c = c.and_then(|c| line_end.char_at(point.column, c));
+        }
+
+        // Last point passed to the DFA.
         let mut last_point = point;
 
         loop {
-            // Convert char to array of bytes.
-            let mut buf = [0; 4];
-            let utf8_len = c.encode_utf8(&mut buf).len();
-
-            // Pass char to DFA as individual bytes.
-            for i in 0..utf8_len {
-                // Inverse byte order when going left.
-                let byte = match direction {
-                    Direction::Right => buf[i],
-                    Direction::Left => buf[utf8_len - i - 1],
-                };
-
-                // Since we get the state from the DFA, it doesn't need to be checked.
-                state = unsafe { regex.next_state_unchecked(state, byte) };
-
-                // Matches require one additional BYTE of lookahead, so we check the match state for
-                // the first byte of every new character to determine if the last character was a
-                // match.
-                if i == 0 && regex.is_match_state(state) {
+            if let Some(c) = c {
+                if next_char_state(regex, &mut state, c, direction) {
                     regex_match = Some(last_point);
                 }
+                last_point = point;
             }
 
             // Abort on dead states.
@@ -266,51 +291,76 @@ impl<T> Term<T> {
                 // is possible and check if the current state is still a match.
                 state = regex.next_eoi_state(state);
                 if regex.is_match_state(state) {
-                    regex_match = Some(point);
+                    regex_match = Some(last_point);
                 }
 
                 break;
             }
 
-            // Advance grid cell iterator.
-            let mut cell = match next(&mut iter) {
-                Some(Indexed { cell, .. }) => cell,
-                None => {
-                    // Wrap around to other end of the scrollback buffer.
-                    let line = topmost_line - point.line + screen_lines - 1;
-                    let start = Point::new(line, last_column - point.column);
-                    iter = self.grid.iter_from(start);
-                    iter.cell()
-                },
-            };
+            // Advance grid cell cursor.
+            let mut wrapped_around = false;
+            if next(&mut cursor).is_none() {
+                // Wrap around to other end of the scrollback buffer.
+                let line = topmost_line - point.line + screen_lines - 1;
+                let start = Point::new(line, last_column - point.column);
+                cursor = self.grid.cursor_from(start);
+                wrapped_around = true;
+            }
 
             // Check for completion before potentially skipping over fullwidth characters.
-            done = iter.point() == end;
+            done = cursor.point() == end;
 
-            self.skip_fullwidth(&mut iter, &mut cell, direction);
+            let (cell_c, flags) = self.skip_fullwidth(&mut cursor, direction);
 
-            let wrapped = cell.flags.contains(Flags::WRAPLINE);
-            c = cell.c;
+            let wrapped = flags.contains(Flags::WRAPLINE);
+            c = Some(cell_c);
 
-            last_point = mem::replace(&mut point, iter.point());
+            let previous_point = mem::replace(&mut point, cursor.point());
 
             // Handle linebreaks.
-            if (last_point.column == last_column && point.column == Column(0) && !last_wrapped)
-                || (last_point.column == Column(0) && point.column == last_column && !wrapped)
+            if (previous_point.column == last_column && point.column == Column(0) && !last_wrapped)
+                || (previous_point.column == Column(0) && point.column == last_column && !wrapped)
             {
-                match regex_match {
-                    Some(_) => break,
-                    None => {
-                        // When reaching the end-of-input, we need to notify the parser that no
-                        // look-ahead is possible and check if the current state is still a match.
-                        state = regex.next_eoi_state(state);
-                        if regex.is_match_state(state) {
+                if multiline && !wrapped_around {
+                    let newline_line = min(previous_point.line, point.line);
+                    if line_end.line != newline_line {
+                        line_end = LineEnd::new(&mut cursor, last_column, newline_line);
+                    }
+
+                    // Lines without empty cells have no cell for their newline.
+                    if line_end.newline.is_none() {
+                        if next_char_state(regex, &mut state, '\n', direction) {
                             regex_match = Some(last_point);
                         }
 
-                        state = start_state;
-                    },
+                        if regex.is_dead_state(state) {
+                            break;
+                        }
+                    }
+                } else {
+                    match regex_match {
+                        Some(_) => break,
+                        None => {
+                            // When reaching the end-of-input, we need to notify the parser that
+                            // no look-ahead is possible and check if the current state is still
+                            // a match.
+                            state = regex.next_eoi_state(state);
+                            if regex.is_match_state(state) {
+                                regex_match = Some(last_point);
+                            }
+
+                            state = start_state;
+                        },
+                    }
+                }
+            }
+
+            // Replace the newline cell and skip the empty cells after it.
+            if multiline {
+                if line_end.line != point.line {
+                    line_end = LineEnd::new(&mut cursor, last_column, point.line);
                 }
+                c = c.and_then(|c| line_end.char_at(point.column, c));
             }
 
             last_wrapped = wrapped;
@@ -319,40 +369,50 @@ impl<T> Term<T> {
         regex_match
     }
 
-    /// Advance a grid iterator over fullwidth characters.
-    fn skip_fullwidth<'a>(
+    /// Advance a grid cursor over fullwidth characters.
+    ///
+    /// Returns the character and flags of the cell at the cursor before it was advanced, or of the
+    /// fullwidth character it was advanced over.
+    fn skip_fullwidth(
         &self,
-        iter: &'a mut GridIterator<'_, Cell>,
-        cell: &mut &'a Cell,
+        cursor: &mut GridCursor<'_, Cell>,
         direction: Direction,
-    ) {
+    ) -> (char, Flags) {
+        let cell = cursor.cell();
+        let (mut c, mut flags) = (cell.c, cell.flags);
+
         match direction {
             // In the alternate screen buffer there might not be a wide char spacer after a wide
-            // char, so we only advance the iterator when the wide char is not in the last column.
+            // char, so we only advance the cursor when the wide char is not in the last column.
             Direction::Right
-                if cell.flags.contains(Flags::WIDE_CHAR)
-                    && iter.point().column < self.last_column() =>
+                if flags.contains(Flags::WIDE_CHAR)
+                    && cursor.point().column < self.last_column() =>
             {
-                iter.next();
+                cursor.next_cell();
             },
-            Direction::Right if cell.flags.contains(Flags::LEADING_WIDE_CHAR_SPACER) => {
-                if let Some(Indexed { cell: new_cell, .. }) = iter.next() {
-                    *cell = new_cell;
+            Direction::Right if flags.contains(Flags::LEADING_WIDE_CHAR_SPACER) => {
+                if let Some(cell) = cursor.next_cell() {
+                    (c, flags) = (cell.c, cell.flags);
                 }
-                iter.next();
+                cursor.next_cell();
             },
-            Direction::Left if cell.flags.contains(Flags::WIDE_CHAR_SPACER) => {
-                if let Some(Indexed { cell: new_cell, .. }) = iter.prev() {
-                    *cell = new_cell;
+            Direction::Left if flags.contains(Flags::WIDE_CHAR_SPACER) => {
+                if let Some(cell) = cursor.prev_cell() {
+                    (c, flags) = (cell.c, cell.flags);
                 }
 
-                let prev = iter.point().sub(self, Boundary::Grid, 1);
-                if self.grid[prev].flags.contains(Flags::LEADING_WIDE_CHAR_SPACER) {
-                    iter.prev();
+                let prev = cursor.point().sub(self, Boundary::Grid, 1);
+                if cursor.row(prev.line)[prev.column]
+                    .flags
+                    .contains(Flags::LEADING_WIDE_CHAR_SPACER)
+                {
+                    cursor.prev_cell();
                 }
             },
             _ => (),
         }
+
+        (c, flags)
     }
 
     /// Find next matching bracket.
@@ -448,8 +508,9 @@ impl<T> Term<T> {
 
     /// Find the beginning of the current line across linewraps.
     pub fn line_search_left(&self, mut point: Point) -> Point {
+        let mut reader = self.grid.reader();
         while point.line > self.topmost_line()
-            && self.grid[point.line - 1i32][self.last_column()].flags.contains(Flags::WRAPLINE)
+            && reader.row(point.line - 1i32)[self.last_column()].flags.contains(Flags::WRAPLINE)
         {
             point.line -= 1;
         }
@@ -461,8 +522,9 @@ impl<T> Term<T> {
 
     /// Find the end of the current line across linewraps.
     pub fn line_search_right(&self, mut point: Point) -> Point {
+        let mut reader = self.grid.reader();
         while point.line + 1 < self.screen_lines()
-            && self.grid[point.line][self.last_column()].flags.contains(Flags::WRAPLINE)
+            && reader.row(point.line)[self.last_column()].flags.contains(Flags::WRAPLINE)
         {
             point.line += 1;
         }
@@ -471,6 +533,289 @@ impl<T> Term<T> {
 
         point
     }
+
+    /// Find the closest shell integration mark in the specified direction.
+    ///
+    /// Marks at the `point` itself are ignored. To avoid reading the entire scrollback history,
+    /// at most [`MAX_MARK_SEARCH_LINES`] lines are searched.
+    pub fn mark_search<P>(
+        &self,
+        point: Point,
+        direction: Direction,
+        mut predicate: P,
+    ) -> Option<(Point, SemanticMark)>
+    where
+        P: FnMut(SemanticMark) -> bool,
+    {
+        let mut reader = self.grid.reader();
+        let max_lines = MAX_MARK_SEARCH_LINES as i32;
+
+        match direction {
+            Direction::Right => {
+                let end = min(point.line.0 + max_lines, self.bottommost_line().0);
+                (point.line.0..=end).map(Line).find_map(|line| {
+                    let marks = reader.row(line).marks().iter();
+                    marks
+                        .map(|&(column, mark)| (Point::new(line, column), mark))
+                        .find(|&(mark_point, mark)| mark_point > point && predicate(mark))
+                })
+            },
+            Direction::Left => {
+                let start = max(point.line.0 - max_lines, self.topmost_line().0);
+                (start..=point.line.0).rev().map(Line).find_map(|line| {
+                    let marks = reader.row(line).marks().iter().rev();
+                    marks
+                        .map(|&(column, mark)| (Point::new(line, column), mark))
+                        .find(|&(mark_point, mark)| mark_point < point && predicate(mark))
+                })
+            },
+        }
+    }
+
+    /// Find the start of the closest prompt in the specified direction.
+    ///
+    /// Only lines known to contain a prompt are read, so this is cheap across the entire
+    /// scrollback history.
+    pub fn prompt_search(&self, point: Point, direction: Direction) -> Option<Point> {
+        let mut reader = self.grid.reader();
+        let mut prompts = |line: Line| -> Vec<Point> {
+            let marks = reader.row(line).marks().iter();
+            marks
+                .filter(|&&(_, mark)| mark == SemanticMark::PromptStart)
+                .map(|&(column, _)| Point::new(line, column))
+                .collect()
+        };
+
+        let lines = &self.prompt_index.lines;
+        match direction {
+            Direction::Right => {
+                let start = lines.partition_point(|&line| line < point.line);
+                let mut lines = lines.range(start..);
+                lines.find_map(|&line| prompts(line).into_iter().find(|&prompt| prompt > point))
+            },
+            Direction::Left => {
+                let end = lines.partition_point(|&line| line <= point.line);
+                let mut lines = lines.range(..end).rev();
+                lines.find_map(|&line| {
+                    prompts(line).into_iter().rev().find(|&prompt| prompt < point)
+                })
+            },
+        }
+    }
+
+    /// Lines containing the start of a prompt, in ascending order.
+    pub fn prompt_lines(&self) -> impl Iterator<Item = Line> + '_ {
+        // Marks on the screen might have been cleared since they were indexed.
+        self.prompt_index
+            .lines
+            .iter()
+            .copied()
+            .filter(move |&line| line < 0 || is_prompt(&self.grid[line]))
+    }
+
+    /// Find the output of the command containing `point`.
+    pub fn command_output(&self, point: Point) -> Option<Match> {
+        // Start at the command's prompt if possible, instead of searching through all its output.
+        let origin = Point::new(point.line, point.column + 1);
+        let output_start = match self.prompt_search(origin, Direction::Left) {
+            Some(prompt) => self.prompt_output_start(prompt)?,
+            None => {
+                // Make sure the closest mark at or before the point starts the output.
+                let (output_start, _) = self
+                    .mark_search(origin, Direction::Left, |_| true)
+                    .filter(|&(_, mark)| mark == SemanticMark::OutputStart)?;
+                output_start
+            },
+        };
+
+        let output = self.output_range(output_start)?;
+        output.contains(&point).then_some(output)
+    }
+
+    /// Find the output of the most recent command.
+    pub fn last_command_output(&self) -> Option<Match> {
+        let output_start = match self.prompt_search(self.grid.cursor.point, Direction::Left) {
+            Some(prompt) => {
+                // The last prompt usually belongs to the command which is being typed.
+                let mut prompts = iter::successors(Some(prompt), |&prompt| {
+                    self.prompt_search(prompt, Direction::Left)
+                });
+                prompts.find_map(|prompt| self.prompt_output_start(prompt))?
+            },
+            None => {
+                let origin = Point::new(self.bottommost_line(), Column(self.columns()));
+                let output_start = self
+                    .mark_search(origin, Direction::Left, |mark| mark == SemanticMark::OutputStart);
+                output_start?.0
+            },
+        };
+
+        self.output_range(output_start)
+    }
+
+    /// Find the start of the output of the command following a prompt.
+    fn prompt_output_start(&self, prompt: Point) -> Option<Point> {
+        let output_start =
+            self.mark_search(prompt, Direction::Right, |mark| mark != SemanticMark::CommandStart);
+        output_start.filter(|&(_, mark)| mark == SemanticMark::OutputStart).map(|(point, _)| point)
+    }
+
+    /// Get the command output starting at the output mark at `output_start`.
+    fn output_range(&self, output_start: Point) -> Option<Match> {
+        // Output ends before the next mark, or at the cursor while the command is still running.
+        let next_prompt = self.prompt_search(output_start, Direction::Right);
+        let next_mark = self
+            .mark_search(output_start, Direction::Right, |_| true)
+            .map(|(next_mark, _)| next_mark)
+            .filter(|&next_mark| next_prompt.map_or(true, |prompt| next_mark <= prompt));
+        let end = match next_mark.or(next_prompt) {
+            Some(next_mark) => next_mark.sub(self, Boundary::Grid, 1),
+            None => self.grid.cursor.point,
+        };
+
+        // Skip to the next line when the mark was placed after the command's text.
+        let mut start = output_start;
+        if start.column > 0
+            && start.column >= self.grid[start.line].line_length()
+            && start.line < end.line
+        {
+            start = Point::new(start.line + 1, Column(0));
+        }
+
+        (start <= end).then_some(start..=end)
+    }
+}
+
+/// Lines containing the start of a prompt, following the grid content.
+///
+/// Marks removed by clearing lines on the screen are only pruned once the lines are rotated into
+/// the scrollback history, so lines on the screen have to be checked before using them.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub(crate) struct PromptIndex {
+    /// Prompt lines in ascending order.
+    lines: VecDeque<Line>,
+}
+
+impl PromptIndex {
+    /// Build the index from all prompt marks in the grid.
+    pub fn new(grid: &Grid<Cell>) -> Self {
+        let mut reader = grid.reader();
+        let lines = (grid.topmost_line().0..=grid.bottommost_line().0)
+            .map(Line)
+            .filter(|&line| is_prompt(reader.row(line)))
+            .collect();
+        Self { lines }
+    }
+
+    /// Add a line containing a prompt mark.
+    pub fn insert(&mut self, line: Line) {
+        let index = self.lines.partition_point(|&prompt| prompt < line);
+        if self.lines.get(index) != Some(&line) {
+            self.lines.insert(index, line);
+        }
+    }
+
+    /// Move lines within `range` by `delta` lines upwards.
+    ///
+    /// This follows the rotation of the grid's scrolling region. Lines rotated out of the region
+    /// or the scrollback history are removed, while lines rotated into the history are pruned.
+    pub fn rotate(&mut self, grid: &Grid<Cell>, range: &Range<Line>, delta: i32) {
+        let topmost_line = grid.topmost_line();
+        // Only rotating lines upwards moves the scrollback history.
+        let in_history = |line: Line| range.start == 0 && line < 0 && delta > 0;
+        let in_region = |line: Line| (line >= range.start || in_history(line)) && line < range.end;
+        self.lines.retain_mut(|line| {
+            if in_region(*line) {
+                let on_screen = *line >= 0;
+                *line -= delta;
+
+                if (*line < range.start && range.start != 0) || *line >= range.end {
+                    return false;
+                }
+
+                // Lines on the screen can be cleared, so they're checked once they become history.
+                if on_screen && *line < 0 && !is_prompt(&grid[*line]) {
+                    return false;
+                }
+            }
+
+            *line >= topmost_line
+        });
+    }
+
+    /// Remove lines which are no longer part of the grid.
+    pub fn truncate(&mut self, grid: &Grid<Cell>) {
+        let topmost_line = grid.topmost_line();
+        self.lines.retain(|&line| line >= topmost_line);
+    }
+}
+
+/// Check if a row contains the start of a prompt.
+fn is_prompt(row: &Row<Cell>) -> bool {
+    row.marks().iter().any(|&(_, mark)| mark == SemanticMark::PromptStart)
+}
+
+/// Pass a character to the DFA.
+///
+/// Returns `true` if the input before the character was a match.
+fn next_char_state(
+    regex: &impl Automaton,
+    state: &mut StateID,
+    c: char,
+    direction: Direction,
+) -> bool {
+    // Convert char to array of bytes.
+    let mut buf = [0; 4];
+    let utf8_len = c.encode_utf8(&mut buf).len();
+
+    let mut is_match = false;
+
+    // Pass char to DFA as individual bytes.
+    for i in 0..utf8_len {
+        // Inverse byte order when going left.
+        let byte = match direction {
+            Direction::Right => buf[i],
+            Direction::Left => buf[utf8_len - i - 1],
+        };
+
+        // Since we get the state from the DFA, it doesn't need to be checked.
+        *state = unsafe { regex.next_state_unchecked(*state, byte) };
+
+        // Matches require one additional BYTE of lookahead, so we check the match state for the
+        // first byte of every new character to determine if the last character was a match.
+        if i == 0 && regex.is_match_state(*state) {
+            is_match = true;
+        }
+    }
+
+    is_match
+}
+
+/// End of the text in a line, locating its newline for multi-line searches.
+struct LineEnd {
+    line: Line,
+
+    /// Column of the newline, `None` for wrapped lines and lines without empty cells.
+    newline: Option<Column>,
+}
+
+impl LineEnd {
+    fn new(cursor: &mut GridCursor<'_, Cell>, last_column: Column, line: Line) -> Self {
+        let row = cursor.row(line);
+        let wrapped = row[last_column].flags.contains(Flags::WRAPLINE);
+        let length = row.line_length();
+        let newline = (!wrapped && length <= last_column).then_some(length);
+        Self { line, newline }
+    }
+
+    /// Character passed to the DFA for the cell in a column.
+    fn char_at(&self, column: Column, c: char) -> Option<char> {
+        match self.newline {
+            Some(newline) if column == newline => Some('\n'),
+            Some(newline) if column > newline => None,
+            _ => Some(c),
+        }
+    }
 }
 
 /// Iterator over regex matches.
@@ -541,11 +886,279 @@ impl<'a, T> Iterator for RegexIter<'a, T> {
     }
 }
 
+/// Incremental scan counting all regex matches in the terminal.
+///
+/// The grid is scanned from the top of the scrollback buffer to the bottom of the screen in
+/// chunks, which allows spreading the search of large histories over multiple frames.
+///
+/// Lines rotated into the scrollback history between chunks move the scanned points with them,
+/// while other changes to the grid restart the scan.
+#[derive(Clone, Debug)]
+pub struct MatchScan {
+    /// Point the scan was started with.
+    pivot: Point,
+
+    /// Point the match indices are relative to, moved with the grid content.
+    moved_pivot: Point,
+
+    /// Start of the next chunk, `None` once the scan is complete.
+    next: Option<Point>,
+
+    /// Grid generation the scanned points belong to.
+    generation: usize,
+
+    /// History rotation the scanned points belong to.
+    rotation: usize,
+
+    /// Number of matches found so far.
+    count: usize,
+
+    /// Number of matches starting before the pivot.
+    before_pivot: usize,
+
+    first: Option<Match>,
+    last: Option<Match>,
+
+    /// Last match starting before the pivot.
+    preceding: Option<Match>,
+
+    /// First match starting at or after the pivot.
+    following: Option<Match>,
+
+    /// Lines containing the start of a match, with the number of matches starting in them.
+    ///
+    /// Lines are stored independent of the history rotation, by adding the rotation to them.
+    lines: VecDeque<(usize, usize)>,
+}
+
+impl MatchScan {
+    /// Start a new scan with match indices relative to `pivot`.
+    pub fn new<T>(term: &Term<T>, pivot: Point) -> Self {
+        Self {
+            pivot,
+            moved_pivot: pivot,
+            next: Some(Point::new(term.topmost_line(), Column(0))),
+            generation: term.grid_generation(),
+            rotation: term.history_rotation(),
+            count: 0,
+            before_pivot: 0,
+            first: None,
+            last: None,
+            preceding: None,
+            following: None,
+            lines: VecDeque::new(),
+        }
+    }
+
+    /// Scan the next `lines` lines for matches.
+    ///
+    /// The scan is automatically updated when the grid content was moved since the last chunk.
+    /// Returns `true` once the entire grid has been searched.
+    pub fn advance<T>(&mut self, term: &Term<T>, regex: &RegexSearch, lines: usize) -> bool {
+        if self.generation != term.grid_generation() {
+            *self = Self::new(term, self.pivot);
+        } else if self.rotation != term.history_rotation() {
+            self.rotate(term, regex);
+        }
+
+        let start = match self.next {
+            Some(start) => start,
+            None => return true,
+        };
+
+        // End chunks on a logical line boundary, so single-line matches are never split, unless
+        // that would extend the chunk by more than `lines` lines.
+        let bottommost_line = term.bottommost_line();
+        let last_column = term.last_column();
+        let last_line = min(start.line + lines.saturating_sub(1), bottommost_line);
+        let max_line = min(last_line + lines, bottommost_line);
+        let mut reader = term.grid().reader();
+        let mut wrapped =
+            |line: Line| reader.row(line)[last_column].flags.contains(Flags::WRAPLINE);
+        let mut chunk_end = Point::new(last_line, last_column);
+        while chunk_end.line < max_line && wrapped(chunk_end.line) {
+            chunk_end.line += 1;
+        }
+
+        // Allow matches starting in this chunk to extend into the next one.
+        let mut search_end = chunk_end;
+        if regex.is_multiline() || (chunk_end.line < bottommost_line && wrapped(chunk_end.line)) {
+            search_end.line = min(chunk_end.line + lines, bottommost_line);
+        }
+
+        let mut scanned = chunk_end;
+        for regex_match in RegexIter::new(start, search_end, Direction::Right, term, regex) {
+            if *regex_match.start() > chunk_end {
+                break;
+            }
+
+            scanned = max(scanned, *regex_match.end());
+            self.record(regex_match);
+        }
+
+        let grid_end = Point::new(bottommost_line, last_column);
+        self.next = (scanned < grid_end).then(|| scanned.add(term, Boundary::None, 1));
+
+        self.next.is_none()
+    }
+
+    /// Check if the entire grid has been searched.
+    pub fn is_complete(&self) -> bool {
+        self.next.is_none()
+    }
+
+    /// Check if the scanned points refer to the current grid content.
+    pub fn is_current<T>(&self, term: &Term<T>) -> bool {
+        self.generation == term.grid_generation() && self.rotation == term.history_rotation()
+    }
+
+    /// Number of matches found so far.
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    /// Point the scan was started with.
+    pub fn pivot(&self) -> Point {
+        self.pivot
+    }
+
+    /// One-based index of the match starting at the pivot.
+    pub fn pivot_index(&self) -> Option<usize> {
+        let following = self.following.as_ref()?;
+        (*following.start() == self.moved_pivot).then_some(self.before_pivot + 1)
+    }
+
+    /// Lines containing the start of a match found so far, in ascending order.
+    ///
+    /// Lines are moved with the content rotated into the scrollback history since the last chunk,
+    /// no lines are returned once the content was moved in any other way.
+    pub fn lines<'a, T>(&'a self, term: &Term<T>) -> impl Iterator<Item = Line> + 'a {
+        let rotation = term.history_rotation();
+        let topmost_line = term.topmost_line();
+        let len = if self.generation == term.grid_generation() { self.lines.len() } else { 0 };
+        self.lines
+            .range(..len)
+            .map(move |&(line, _)| Line(line.wrapping_sub(rotation) as i32))
+            .skip_while(move |&line| line < topmost_line)
+    }
+
+    /// Next match from the pivot in the specified direction, wrapping around the grid.
+    ///
+    /// Returns the match together with its one-based index.
+    pub fn next_match(&self, direction: Direction) -> Option<(Match, usize)> {
+        match direction {
+            Direction::Right => match &self.following {
+                Some(following) => Some((following.clone(), self.before_pivot + 1)),
+                None => self.first.clone().map(|first| (first, 1)),
+            },
+            Direction::Left => match &self.preceding {
+                Some(preceding) => Some((preceding.clone(), self.before_pivot)),
+                None => self.last.clone().map(|last| (last, self.count)),
+            },
+        }
+    }
+
+    /// Move all scanned points up by the lines rotated into the scrollback history.
+    ///
+    /// Matches which were rotated out of the grid are removed from the results.
+    fn rotate<T>(&mut self, term: &Term<T>, regex: &RegexSearch) {
+        let rotation = term.history_rotation();
+        let delta = min(rotation.wrapping_sub(self.rotation), term.total_lines()) as i32;
+        self.rotation = rotation;
+
+        let shift = |regex_match: &mut Option<Match>| {
+            if let Some(rm) = regex_match {
+                let start = Point::new(rm.start().line - delta, rm.start().column);
+                let end = Point::new(rm.end().line - delta, rm.end().column);
+                *rm = start..=end;
+            }
+        };
+        shift(&mut self.first);
+        shift(&mut self.last);
+        shift(&mut self.preceding);
+        shift(&mut self.following);
+        self.moved_pivot.line -= delta;
+
+        // Remove matches in lines which are no longer part of the grid.
+        let topmost_line = term.topmost_line();
+        while let Some(&(line, count)) = self.lines.front() {
+            let line = Line(line.wrapping_sub(rotation) as i32);
+            if line >= topmost_line {
+                break;
+            }
+            self.lines.pop_front();
+
+            // Earlier lines are removed first, so once the pivot's line is reached, all remaining
+            // matches before the pivot are in it.
+            self.count -= count;
+            self.before_pivot =
+                if line < self.moved_pivot.line { self.before_pivot - count } else { 0 };
+        }
+
+        let removed =
+            |rm: &Option<Match>| rm.as_ref().map_or(false, |rm| rm.start().line < topmost_line);
+        if self.count == 0 {
+            self.first = None;
+            self.last = None;
+        } else if removed(&self.first) {
+            let line = Line(self.lines[0].0.wrapping_sub(rotation) as i32);
+            let end = Point::new(term.bottommost_line(), term.last_column());
+            self.first =
+                RegexIter::new(Point::new(line, Column(0)), end, Direction::Right, term, regex)
+                    .next();
+        }
+        if self.before_pivot == 0 {
+            self.preceding = None;
+        }
+        if removed(&self.following) {
+            self.following = self.first.clone();
+        }
+
+        // Continue with the lines added at the bottom, or the oldest remaining ones.
+        self.next = match self.next {
+            Some(next) if next.line - delta < topmost_line => {
+                Some(Point::new(topmost_line, Column(0)))
+            },
+            Some(next) => Some(Point::new(next.line - delta, next.column)),
+            None => {
+                let line = max(term.bottommost_line() - (delta - 1), topmost_line);
+                Some(Point::new(line, Column(0)))
+            },
+        };
+    }
+
+    /// Add a match to the scan results.
+    fn record(&mut self, regex_match: Match) {
+        self.count += 1;
+
+        let line = self.rotation.wrapping_add(regex_match.start().line.0 as usize);
+        match self.lines.back_mut() {
+            Some((last_line, count)) if *last_line == line => *count += 1,
+            _ => self.lines.push_back((line, 1)),
+        }
+
+        if self.first.is_none() {
+            self.first = Some(regex_match.clone());
+        }
+
+        if *regex_match.start() < self.moved_pivot {
+            self.before_pivot += 1;
+            self.preceding = Some(regex_match.clone());
+        } else if self.following.is_none() {
+            self.following = Some(regex_match.clone());
+        }
+
+        self.last = Some(regex_match);
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    use crate::ansi::Handler;
     use crate::config::Config;
+    use crate::event::VoidListener;
     use crate::index::{Column, Line};
     use crate::term::test::{mock_term, TermSize};
 
@@ -589,6 +1202,64 @@ mod tests {
         assert_eq!(term.regex_search_left(&regex, start, end), Some(match_start..=match_end));
     }
 
+    #[test]
+    fn regex_multiline() {
+        #[rustfmt::skip]
+        let term = mock_term("\
+            Error: failed\r\n\
+            at main.rs\r\n\
+            Error: failed\r\n\
+            all tests passed\
+        ");
+
+        // Patterns without newlines never match across hard line breaks.
+        let regex = RegexSearch::new("failed.*main").unwrap();
+        assert!(!regex.is_multiline());
+        let start = Point::new(Line(0), Column(0));
+        let end = Point::new(Line(3), Column(15));
+        assert_eq!(term.regex_search_right(&regex, start, end), None);
+
+        // Check multi-line match in both directions.
+        let regex = RegexSearch::new(r"failed\nat").unwrap();
+        assert!(regex.is_multiline());
+        let match_start = Point::new(Line(0), Column(7));
+        let match_end = Point::new(Line(1), Column(1));
+        assert_eq!(term.regex_search_right(&regex, start, end), Some(match_start..=match_end));
+        assert_eq!(term.regex_search_left(&regex, end, start), Some(match_start..=match_end));
+
+        // The newline is located in the first empty cell after a line's text.
+        let regex = RegexSearch::new(r"failed\n").unwrap();
+        let mut iter = RegexIter::new(start, end, Direction::Right, &term, &regex);
+        assert_eq!(
+            iter.next(),
+            Some(Point::new(Line(0), Column(7))..=Point::new(Line(0), Column(13)))
+        );
+        assert_eq!(
+            iter.next(),
+            Some(Point::new(Line(2), Column(7))..=Point::new(Line(2), Column(13)))
+        );
+        assert_eq!(iter.next(), None);
+    }
+
+    #[test]
+    fn regex_multiline_full_line() {
+        #[rustfmt::skip]
+        let term = mock_term("\
+            first\r\n\
+            second\r\n\
+            third\
+        ");
+
+        // Lines filling every column have no cell for their newline.
+        let regex = RegexSearch::new(r"d\nt").unwrap();
+        let start = Point::new(Line(0), Column(0));
+        let end = Point::new(Line(2), Column(4));
+        let match_start = Point::new(Line(1), Column(5));
+        let match_end = Point::new(Line(2), Column(0));
+        assert_eq!(term.regex_search_right(&regex, start, end), Some(match_start..=match_end));
+        assert_eq!(term.regex_search_left(&regex, end, start), Some(match_start..=match_end));
+    }
+
     #[test]
     fn nested_regex() {
         #[rustfmt::skip]
@@ -896,4 +1567,142 @@ mod tests {
         let match_end = Point::new(Line(1), Column(2));
         assert_eq!(term.regex_search_left(&regex, start, end), Some(match_start..=match_end));
     }
+
+    #[test]
+    fn match_scan() {
+        #[rustfmt::skip]
+        let term = mock_term("\
+            foo bar\r\n\
+            bar\r\n\
+            foo\r\n\
+            baz foo        ");
+
+        let regex = RegexSearch::new("foo").unwrap();
+        let pivot = Point::new(Line(2), Column(0));
+
+        // Results must not depend on the chunk size.
+        for lines in 1..=4 {
+            let mut scan = MatchScan::new(&term, pivot);
+            let mut chunks = 1;
+            while !scan.advance(&term, &regex, lines) {
+                chunks += 1;
+            }
+
+            assert_eq!(chunks, (4 + lines - 1) / lines);
+            assert_eq!(scan.count(), 3);
+            assert_eq!(scan.lines(&term).collect::<Vec<_>>(), [Line(0), Line(2), Line(3)]);
+            assert_eq!(scan.pivot_index(), Some(2));
+
+            let next = Point::new(Line(2), Column(0))..=Point::new(Line(2), Column(2));
+            assert_eq!(scan.next_match(Direction::Right), Some((next, 2)));
+            let previous = Point::new(Line(0), Column(0))..=Point::new(Line(0), Column(2));
+            assert_eq!(scan.next_match(Direction::Left), Some((previous, 1)));
+        }
+
+        // Wrap around the end of the grid.
+        let mut scan = MatchScan::new(&term, Point::new(Line(3), Column(5)));
+        while !scan.advance(&term, &regex, 2) {}
+        assert_eq!(scan.pivot_index(), None);
+        let first = Point::new(Line(0), Column(0))..=Point::new(Line(0), Column(2));
+        assert_eq!(scan.next_match(Direction::Right), Some((first, 1)));
+    }
+
+    #[test]
+    fn match_scan_multiline() {
+        #[rustfmt::skip]
+        let term = mock_term("\
+            foo\r\n\
+            bar\r\n\
+            foo\r\n\
+            bar        ");
+
+        // Matches crossing chunk boundaries are counted once.
+        let regex = RegexSearch::new(r"foo\nbar").unwrap();
+        let mut scan = MatchScan::new(&term, Point::new(Line(0), Column(0)));
+        while !scan.advance(&term, &regex, 1) {}
+        assert_eq!(scan.count(), 2);
+    }
+
+    #[test]
+    fn match_scan_restart() {
+        let size = TermSize::new(5, 3);
+        let mut term = Term::new(&Config::default(), &size, ());
+        term.grid[Line(0)][Column(0)].c = 'x';
+
+        let regex = RegexSearch::new("x").unwrap();
+        let mut scan = MatchScan::new(&term, Point::new(Line(0), Column(0)));
+        assert!(!scan.advance(&term, &regex, 1));
+        assert_eq!(scan.count(), 1);
+
+        // Moving lines invalidates the scan.
+        assert!(scan.is_current(&term));
+        term.resize(TermSize::new(5, 2));
+        assert!(!scan.is_current(&term));
+
+        while !scan.advance(&term, &regex, 1) {}
+        assert_eq!(scan.count(), 1);
+    }
+
+    #[test]
+    fn match_scan_rotation() {
+        let mut config = Config::default();
+        config.scrolling.set_history(2);
+        let mut term = Term::new(&config, &TermSize::new(5, 3), VoidListener);
+        for line in 0..3 {
+            term.grid[Line(line)][Column(0)].c = 'x';
+        }
+
+        let regex = RegexSearch::new("x").unwrap();
+        let mut scan = MatchScan::new(&term, Point::new(Line(1), Column(0)));
+        assert!(!scan.advance(&term, &regex, 1));
+        let lines = scan.lines(&term).collect::<Vec<_>>();
+        assert!(!lines.is_empty());
+
+        // Scrolled lines keep their matches.
+        term.scroll_up(1);
+        term.grid[Line(2)][Column(0)].c = 'x';
+        assert!(!scan.is_current(&term));
+        let scrolled = lines.iter().map(|&line| line - 1).collect::<Vec<_>>();
+        assert_eq!(scan.lines(&term).collect::<Vec<_>>(), scrolled);
+        while !scan.advance(&term, &regex, 1) {}
+        assert_eq!(scan.count(), 4);
+        assert_eq!(scan.lines(&term).collect::<Vec<_>>(), [Line(-1), Line(0), Line(1), Line(2)]);
+        assert_eq!(scan.pivot_index(), Some(2));
+
+        // Lines rotated out of the history are removed.
+        term.scroll_up(3);
+        while !scan.advance(&term, &regex, 1) {}
+        assert!(scan.is_current(&term));
+        assert_eq!(scan.count(), 2);
+        assert_eq!(scan.lines(&term).collect::<Vec<_>>(), [Line(-2), Line(-1)]);
+        assert_eq!(scan.pivot_index(), None);
+        let first = Point::new(Line(-2), Column(0))..=Point::new(Line(-2), Column(0));
+        assert_eq!(scan.next_match(Direction::Right), Some((first, 1)));
+        assert_eq!(scan.next_match(Direction::Left).map(|(_, index)| index), Some(2));
+
+        // Lines can't be followed through other movements.
+        term.scroll_down(1);
+        assert_eq!(scan.lines(&term).count(), 0);
+    }
+
+    #[test]
+    fn match_scan_wrapped_chunk() {
+        let size = TermSize::new(3, 20);
+        let mut term = Term::new(&Config::default(), &size, ());
+        for line in 0..20 {
+            term.grid[Line(line)][Column(0)].c = 'x';
+            if line < 19 {
+                term.grid[Line(line)][Column(2)].flags.insert(Flags::WRAPLINE);
+            }
+        }
+
+        // Chunks are limited even within a single logical line.
+        let regex = RegexSearch::new("x").unwrap();
+        let mut scan = MatchScan::new(&term, Point::new(Line(0), Column(0)));
+        assert!(!scan.advance(&term, &regex, 2));
+        assert_eq!(scan.count(), 4);
+
+        while !scan.advance(&term, &regex, 2) {}
+        assert_eq!(scan.count(), 20);
+    }
 }
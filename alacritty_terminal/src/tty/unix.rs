@@ -40,6 +40,16 @@ fn make_pty(size: winsize) -> Result<(RawFd, RawFd)> {
     Ok((ends.master, ends.slave))
 }
 
+/// Duplicate a fd to pass it to a child process.
+fn dup_stdio(fd: RawFd) -> Result<Stdio> {
+    let fd = unsafe { libc::dup(fd) };
+    if fd < 0 {
+        return Err(Error::last_os_error());
+    }
+
+    Ok(unsafe { Stdio::from_raw_fd(fd) })
+}
+
 /// Really only needed on BSD, but should be fine elsewhere.
 fn set_controlling_terminal(fd: c_int) {
     let res = unsafe {
@@ -207,11 +217,10 @@ pub fn new(config: &PtyConfig, window_size: WindowSize, window_id: u64) -> Resul
     };
 
     // Setup child stdin/stdout/stderr as slave fd of PTY.
-    // Ownership of fd is transferred to the Stdio structs and will be closed by them at the end of
-    // this scope. (It is not an issue that the fd is closed three times since File::drop ignores
-    // error on libc::close.).
-    builder.stdin(unsafe { Stdio::from_raw_fd(slave) });
-    builder.stderr(unsafe { Stdio::from_raw_fd(slave) });
+    // Ownership of the fds is transferred to the Stdio structs and they will be closed by them at
+    // the end of this scope. Each of them gets its own copy, to avoid closing the same fd twice.
+    builder.stdin(dup_stdio(slave)?);
+    builder.stderr(dup_stdio(slave)?);
     builder.stdout(unsafe { Stdio::from_raw_fd(slave) });
 
     // Setup shell environment.
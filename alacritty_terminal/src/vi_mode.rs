@@ -1,9 +1,11 @@
 use std::cmp::min;
+use std::collections::VecDeque;
+use std::ops::{Range, RangeInclusive};
 
 use alacritty_config_derive::ConfigDeserialize;
 
 use crate::event::EventListener;
-use crate::grid::{Dimensions, GridCell};
+use crate::grid::{BidirectionalIterator, Dimensions, GridCell};
 use crate::index::{Boundary, Column, Direction, Line, Point, Side};
 use crate::term::cell::Flags;
 use crate::term::Term;
@@ -50,6 +52,107 @@ pub enum ViMotion {
     WordRightEnd,
     /// Move to opposing bracket.
     Bracket,
+    /// Move to start of previous shell prompt.
+    PromptUp,
+    /// Move to start of next shell prompt.
+    PromptDown,
+    /// Move to the empty line above the current paragraph.
+    ParagraphUp,
+    /// Move to the empty line below the current paragraph.
+    ParagraphDown,
+}
+
+impl ViMotion {
+    /// Whether an operator using this motion affects entire lines.
+    pub fn is_linewise(self) -> bool {
+        matches!(
+            self,
+            ViMotion::Up
+                | ViMotion::Down
+                | ViMotion::High
+                | ViMotion::Middle
+                | ViMotion::Low
+                | ViMotion::PromptUp
+                | ViMotion::PromptDown
+        )
+    }
+
+    /// Whether an operator using this motion includes the cell the motion ends on.
+    pub fn is_inclusive(self) -> bool {
+        matches!(
+            self,
+            ViMotion::Last
+                | ViMotion::SemanticLeftEnd
+                | ViMotion::SemanticRightEnd
+                | ViMotion::WordLeftEnd
+                | ViMotion::WordRightEnd
+                | ViMotion::Bracket
+        )
+    }
+}
+
+/// Search for a character within the current line, like `f`, `F`, `t` and `T` in vi.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct InlineSearch {
+    /// Character to search for.
+    pub character: char,
+
+    /// Direction of the search.
+    pub direction: Direction,
+
+    /// Stop right before the character, instead of on top of it.
+    pub stop_short: bool,
+}
+
+impl InlineSearch {
+    /// Same search in the opposite direction.
+    #[must_use]
+    pub fn reversed(mut self) -> Self {
+        self.direction = self.direction.opposite();
+        self
+    }
+}
+
+/// Region around the vi mode cursor, like `iw` or `a(` in vi.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum TextObject {
+    /// Semantically separated word.
+    Word,
+    /// Text enclosed by a quote character.
+    Quote(char),
+    /// Text enclosed by an opening and closing bracket.
+    Bracket(char, char),
+}
+
+impl TextObject {
+    /// Get the text object identified by the character following `i` or `a` in vi.
+    pub fn from_char(c: char) -> Option<Self> {
+        match c {
+            'w' => Some(Self::Word),
+            '"' | '\'' | '`' => Some(Self::Quote(c)),
+            '(' | ')' | 'b' => Some(Self::Bracket('(', ')')),
+            '[' | ']' => Some(Self::Bracket('[', ']')),
+            '{' | '}' | 'B' => Some(Self::Bracket('{', '}')),
+            '<' | '>' => Some(Self::Bracket('<', '>')),
+            _ => None,
+        }
+    }
+
+    /// Find the text object around `point`.
+    ///
+    /// Inner text objects exclude surrounding whitespace, quotes and brackets.
+    pub fn range<T: EventListener>(
+        self,
+        term: &Term<T>,
+        point: Point,
+        inner: bool,
+    ) -> Option<RangeInclusive<Point>> {
+        match self {
+            Self::Word => Some(word_object(term, point, inner)),
+            Self::Quote(quote) => quote_object(term, point, quote, inner),
+            Self::Bracket(open, close) => bracket_object(term, point, open, close, inner),
+        }
+    }
 }
 
 /// Cursor tracking vi mode position.
@@ -151,6 +254,16 @@ impl ViModeCursor {
                 self.point = word(term, self.point, Direction::Right, Side::Right);
             },
             ViMotion::Bracket => self.point = term.bracket_search(self.point).unwrap_or(self.point),
+            ViMotion::PromptUp => {
+                let point = term.prompt_search(self.point, Direction::Left);
+                self.point = point.unwrap_or(self.point);
+            },
+            ViMotion::PromptDown => {
+                let point = term.prompt_search(self.point, Direction::Right);
+                self.point = point.unwrap_or(self.point);
+            },
+            ViMotion::ParagraphUp => self.point = paragraph(term, self.point, Direction::Left),
+            ViMotion::ParagraphDown => self.point = paragraph(term, self.point, Direction::Right),
         }
 
         term.scroll_to_point(self.point);
@@ -158,6 +271,37 @@ impl ViModeCursor {
         self
     }
 
+    /// Move to the next match of an inline search.
+    ///
+    /// When `repeat` is `true`, a match right next to the cursor is skipped for searches stopping
+    /// short of their character, so repeated searches always make progress.
+    #[must_use = "this returns the result of the operation, without modifying the original"]
+    pub fn inline_search<T>(mut self, term: &Term<T>, search: InlineSearch, repeat: bool) -> Self {
+        let skip = if repeat && search.stop_short { 1 } else { 0 };
+
+        let mut point = self.point;
+        let mut steps = 0;
+        let target = loop {
+            point = match line_step(term, point, search.direction) {
+                Some(point) => point,
+                None => return self,
+            };
+            steps += 1;
+
+            if steps > skip && term.grid()[point].c == search.character {
+                break point;
+            }
+        };
+
+        self.point = if search.stop_short {
+            line_step(term, target, search.direction.opposite()).unwrap_or(target)
+        } else {
+            target
+        };
+
+        self
+    }
+
     /// Get target cursor point for vim-like page movement.
     #[must_use = "this returns the result of the operation, without modifying the original"]
     pub fn scroll<T: EventListener>(mut self, term: &Term<T>, lines: i32) -> Self {
@@ -174,6 +318,141 @@ impl ViModeCursor {
     }
 }
 
+/// Maximum number of positions stored in the jump list.
+const MAX_JUMPS: usize = 100;
+
+/// Vi mode marks and jump list.
+///
+/// Positions are moved together with the grid content, so they keep pointing at the same text
+/// while it is rotated into the scrollback history. Positions which leave the grid are dropped.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ViMarks {
+    /// Marks `a` to `z`.
+    marks: [Option<Point>; 26],
+
+    /// Positions the vi mode cursor jumped away from, oldest first.
+    jumps: VecDeque<Point>,
+
+    /// Current position in the jump list, equal to its length when no jump was undone.
+    jump_index: usize,
+}
+
+impl ViMarks {
+    /// Set a mark, returning `false` if the name is not a valid mark.
+    pub fn set_mark(&mut self, name: char, point: Point) -> bool {
+        match Self::mark_index(name) {
+            Some(index) => {
+                self.marks[index] = Some(point);
+                true
+            },
+            None => false,
+        }
+    }
+
+    /// Position of a mark.
+    pub fn mark(&self, name: char) -> Option<Point> {
+        Self::mark_index(name).and_then(|index| self.marks[index])
+    }
+
+    /// Record a position the vi mode cursor is jumping away from.
+    pub fn push_jump(&mut self, point: Point) {
+        // Keep only the latest jump for each line.
+        self.jumps.retain(|jump| jump.line != point.line);
+
+        if self.jumps.len() >= MAX_JUMPS {
+            self.jumps.pop_front();
+        }
+
+        self.jumps.push_back(point);
+        self.jump_index = self.jumps.len();
+    }
+
+    /// Move backwards in the jump list.
+    ///
+    /// The `current` position is recorded first, so it can be returned to with
+    /// [`ViMarks::jump_forward`].
+    pub fn jump_back(&mut self, current: Point) -> Option<Point> {
+        if self.jump_index >= self.jumps.len() {
+            self.push_jump(current);
+            self.jump_index = self.jumps.len() - 1;
+        }
+
+        self.jump_index = self.jump_index.checked_sub(1)?;
+        Some(self.jumps[self.jump_index])
+    }
+
+    /// Move forwards in the jump list.
+    pub fn jump_forward(&mut self) -> Option<Point> {
+        if self.jump_index + 1 >= self.jumps.len() {
+            return None;
+        }
+
+        self.jump_index += 1;
+        Some(self.jumps[self.jump_index])
+    }
+
+    /// Move positions within `range` by `delta` lines upwards.
+    ///
+    /// This follows the rotation of the grid's scrolling region, positions rotated out of the
+    /// region or the scrollback history are removed.
+    pub fn rotate<D: Dimensions>(&mut self, dimensions: &D, range: &Range<Line>, delta: i32) {
+        let topmost_line = dimensions.topmost_line();
+        self.retain(|mut point| {
+            if (point.line >= range.start || range.start == 0) && point.line < range.end {
+                point.line -= delta;
+
+                if (point.line < range.start && range.start != 0) || point.line >= range.end {
+                    return None;
+                }
+            }
+
+            (point.line >= topmost_line).then_some(point)
+        });
+    }
+
+    /// Move all positions by `delta` lines downwards.
+    ///
+    /// Positions outside of the grid are removed and columns are clamped to the grid's width.
+    pub fn shift<D: Dimensions>(&mut self, dimensions: &D, delta: i32) {
+        let topmost_line = dimensions.topmost_line();
+        let bottommost_line = dimensions.bottommost_line();
+        let last_column = dimensions.last_column();
+        self.retain(|mut point| {
+            point.line += delta;
+            point.column = min(point.column, last_column);
+            (point.line >= topmost_line && point.line <= bottommost_line).then_some(point)
+        });
+    }
+
+    /// Update all positions, removing the ones mapped to `None`.
+    fn retain<F: FnMut(Point) -> Option<Point>>(&mut self, mut f: F) {
+        for mark in &mut self.marks {
+            *mark = mark.and_then(&mut f);
+        }
+
+        let mut i = 0;
+        while i < self.jumps.len() {
+            match f(self.jumps[i]) {
+                Some(point) => {
+                    self.jumps[i] = point;
+                    i += 1;
+                },
+                None => {
+                    self.jumps.remove(i);
+                    if i < self.jump_index {
+                        self.jump_index -= 1;
+                    }
+                },
+            }
+        }
+        self.jump_index = min(self.jump_index, self.jumps.len());
+    }
+
+    fn mark_index(name: char) -> Option<usize> {
+        name.is_ascii_lowercase().then(|| name as usize - 'a' as usize)
+    }
+}
+
 /// Find next end of line to move to.
 fn last<T>(term: &Term<T>, mut point: Point) -> Point {
     // Expand across wide cells.
@@ -332,6 +611,168 @@ fn word<T: EventListener>(
     point
 }
 
+/// Move to the next empty line in the direction of the paragraph, like `{` and `}` in vi.
+fn paragraph<T>(term: &Term<T>, point: Point, direction: Direction) -> Point {
+    let (boundary, step) = match direction {
+        Direction::Left => (term.topmost_line(), -1),
+        Direction::Right => (term.bottommost_line(), 1),
+    };
+
+    // Skip empty lines when starting in between paragraphs.
+    let mut line = point.line;
+    while line != boundary && first_occupied_in_line(term, line).is_none() {
+        line += step;
+    }
+
+    // Move until the end of the paragraph.
+    while line != boundary && first_occupied_in_line(term, line).is_some() {
+        line += step;
+    }
+
+    Point::new(line, Column(0))
+}
+
+/// Find the word or whitespace around `point`, like `iw` and `aw` in vi.
+fn word_object<T: EventListener>(
+    term: &Term<T>,
+    point: Point,
+    inner: bool,
+) -> RangeInclusive<Point> {
+    let point = term.expand_wide(point, Direction::Left);
+    let on_space = is_space(term, point);
+
+    let (mut start, mut end) = if on_space {
+        (space_end(term, point, Direction::Left), space_end(term, point, Direction::Right))
+    } else {
+        (term.semantic_search_left(point), term.semantic_search_right(point))
+    };
+
+    if inner {
+        return start..=end;
+    }
+
+    if on_space {
+        // Include the word following the whitespace.
+        if let Some(next) = line_step(term, end, Direction::Right) {
+            end = term.semantic_search_right(next);
+        }
+        return start..=end;
+    }
+
+    // Include trailing whitespace, unless it only pads the end of the line.
+    let trailing = line_step(term, end, Direction::Right)
+        .filter(|&next| is_space(term, next))
+        .map(|next| space_end(term, next, Direction::Right))
+        .filter(|&space| line_step(term, space, Direction::Right).is_some());
+
+    match trailing {
+        Some(space) => end = space,
+        None => {
+            let leading = line_step(term, start, Direction::Left).filter(|&p| is_space(term, p));
+            if let Some(previous) = leading {
+                start = space_end(term, previous, Direction::Left);
+            }
+        },
+    }
+
+    start..=end
+}
+
+/// Find the quoted text around `point`, like `i"` and `a"` in vi.
+///
+/// Quotes are paired up from the start of the line. If the cursor isn't inside of a quoted
+/// string, the next one in the line is used.
+fn quote_object<T>(
+    term: &Term<T>,
+    point: Point,
+    quote: char,
+    inner: bool,
+) -> Option<RangeInclusive<Point>> {
+    let mut cell = Some(term.line_search_left(point));
+    let mut quotes = Vec::new();
+    while let Some(point) = cell {
+        if term.grid()[point].c == quote {
+            quotes.push(point);
+        }
+        cell = line_step(term, point, Direction::Right);
+    }
+
+    let (open, close) =
+        quotes.chunks_exact(2).map(|pair| (pair[0], pair[1])).find(|&(_, close)| close >= point)?;
+
+    if inner {
+        let start = line_step(term, open, Direction::Right)?;
+        let end = line_step(term, close, Direction::Left)?;
+        (start <= end).then_some(start..=end)
+    } else {
+        Some(open..=close)
+    }
+}
+
+/// Find the brackets enclosing `point`, like `i(` and `a(` in vi.
+fn bracket_object<T>(
+    term: &Term<T>,
+    point: Point,
+    open: char,
+    close: char,
+    inner: bool,
+) -> Option<RangeInclusive<Point>> {
+    let start = if term.grid()[point].c == open {
+        point
+    } else {
+        // Find the opening bracket, skipping over nested pairs.
+        let mut iter = term.grid().iter_from(point);
+        let mut depth = 0;
+        loop {
+            let cell = iter.prev()?;
+            if cell.c == close {
+                depth += 1;
+            } else if cell.c == open && depth == 0 {
+                break cell.point;
+            } else if cell.c == open {
+                depth -= 1;
+            }
+        }
+    };
+    let end = term.bracket_search(start)?;
+
+    if inner {
+        let start = start.add(term, Boundary::Grid, 1);
+        let end = end.sub(term, Boundary::Grid, 1);
+        (start <= end).then_some(start..=end)
+    } else {
+        Some(start..=end)
+    }
+}
+
+/// Find the last whitespace cell of a continuous block of whitespace within the line.
+fn space_end<T>(term: &Term<T>, mut point: Point, direction: Direction) -> Point {
+    while let Some(next) = line_step(term, point, direction).filter(|&p| is_space(term, p)) {
+        point = next;
+    }
+    point
+}
+
+/// Move to the adjacent cell, without leaving the current line.
+fn line_step<T>(term: &Term<T>, point: Point, direction: Direction) -> Option<Point> {
+    match direction {
+        Direction::Right if point.column < term.last_column() => {
+            Some(Point::new(point.line, point.column + 1))
+        },
+        Direction::Right if point.line < term.bottommost_line() && is_wrap(term, point) => {
+            Some(Point::new(point.line + 1, Column(0)))
+        },
+        Direction::Left if point.column > 0 => Some(Point::new(point.line, point.column - 1)),
+        Direction::Left
+            if point.line > term.topmost_line()
+                && is_wrap(term, Point::new(point.line - 1, term.last_column())) =>
+        {
+            Some(Point::new(point.line - 1, term.last_column()))
+        },
+        _ => None,
+    }
+}
+
 /// Find first non-empty cell in line.
 fn first_occupied_in_line<T>(term: &Term<T>, line: Line) -> Option<Point> {
     (0..term.columns())
@@ -379,7 +820,7 @@ fn is_boundary<T>(term: &Term<T>, point: Point, direction: Direction) -> bool {
 mod tests {
     use super::*;
 
-    use crate::ansi::Handler;
+    use crate::ansi::{ExtendedHandler, Handler, SemanticMark};
     use crate::config::Config;
     use crate::event::VoidListener;
     use crate::index::{Column, Line};
@@ -391,6 +832,64 @@ mod tests {
         Term::new(&Config::default(), &size, VoidListener)
     }
 
+    #[test]
+    fn marks() {
+        let mut marks = ViMarks::default();
+        let point = Point::new(Line(3), Column(5));
+
+        assert!(marks.set_mark('a', point));
+        assert!(!marks.set_mark('A', point));
+        assert_eq!(marks.mark('a'), Some(point));
+        assert_eq!(marks.mark('b'), None);
+    }
+
+    #[test]
+    fn jump_list() {
+        let mut marks = ViMarks::default();
+        let first = Point::new(Line(1), Column(0));
+        let second = Point::new(Line(2), Column(0));
+        let current = Point::new(Line(3), Column(0));
+
+        marks.push_jump(first);
+        marks.push_jump(second);
+
+        assert_eq!(marks.jump_back(current), Some(second));
+        assert_eq!(marks.jump_back(second), Some(first));
+        assert_eq!(marks.jump_back(first), None);
+        assert_eq!(marks.jump_forward(), Some(second));
+        assert_eq!(marks.jump_forward(), Some(current));
+        assert_eq!(marks.jump_forward(), None);
+
+        // New jumps are appended after the entire list.
+        marks.jump_back(current);
+        marks.push_jump(Point::new(Line(4), Column(0)));
+        assert_eq!(marks.jump_back(current), Some(Point::new(Line(4), Column(0))));
+    }
+
+    #[test]
+    fn marks_rotate() {
+        let term = term();
+        let mut marks = ViMarks::default();
+        marks.set_mark('a', Point::new(Line(5), Column(3)));
+        marks.set_mark('b', Point::new(Line(0), Column(0)));
+        marks.push_jump(Point::new(Line(0), Column(0)));
+        marks.push_jump(Point::new(Line(10), Column(0)));
+
+        // Without scrollback history, lines rotated above the screen are removed.
+        marks.rotate(&term, &(Line(0)..Line(20)), 2);
+        assert_eq!(marks.mark('a'), Some(Point::new(Line(3), Column(3))));
+        assert_eq!(marks.mark('b'), None);
+        assert_eq!(
+            marks.jump_back(Point::new(Line(0), Column(0))),
+            Some(Point::new(Line(8), Column(0)))
+        );
+        assert_eq!(marks.jump_back(Point::new(Line(8), Column(0))), None);
+
+        // Marks in a scrolling region are removed when leaving it.
+        marks.rotate(&term, &(Line(2)..Line(10)), -7);
+        assert_eq!(marks.mark('a'), None);
+    }
+
     #[test]
     fn motion_simple() {
         let mut term = term();
@@ -819,4 +1318,155 @@ mod tests {
         cursor = cursor.scroll(&term, -20);
         assert_eq!(cursor.point, Point::new(Line(19), Column(0)));
     }
+
+    #[test]
+    fn motion_prompt() {
+        let mut term = term();
+        let marks = [
+            (2, 0, SemanticMark::PromptStart),
+            (2, 2, SemanticMark::CommandStart),
+            (3, 0, SemanticMark::OutputStart),
+            (8, 0, SemanticMark::CommandEnd(Some(0))),
+            (8, 0, SemanticMark::PromptStart),
+        ];
+        for (line, column, mark) in marks {
+            term.goto(line, column);
+            term.set_semantic_mark(mark);
+        }
+
+        let mut cursor = ViModeCursor::new(Point::new(Line(5), Column(3)));
+
+        cursor = cursor.motion(&mut term, ViMotion::PromptUp);
+        assert_eq!(cursor.point, Point::new(Line(2), Column(0)));
+
+        cursor = cursor.motion(&mut term, ViMotion::PromptUp);
+        assert_eq!(cursor.point, Point::new(Line(2), Column(0)));
+
+        cursor = cursor.motion(&mut term, ViMotion::PromptDown);
+        assert_eq!(cursor.point, Point::new(Line(8), Column(0)));
+
+        cursor = cursor.motion(&mut term, ViMotion::PromptDown);
+        assert_eq!(cursor.point, Point::new(Line(8), Column(0)));
+    }
+
+    #[test]
+    fn motion_paragraph() {
+        let mut term = term();
+        term.grid_mut()[Line(2)][Column(0)].c = 'a';
+        term.grid_mut()[Line(3)][Column(0)].c = 'b';
+        term.grid_mut()[Line(6)][Column(0)].c = 'c';
+
+        let mut cursor = ViModeCursor::new(Point::new(Line(2), Column(0)));
+
+        cursor = cursor.motion(&mut term, ViMotion::ParagraphDown);
+        assert_eq!(cursor.point, Point::new(Line(4), Column(0)));
+
+        cursor = cursor.motion(&mut term, ViMotion::ParagraphDown);
+        assert_eq!(cursor.point, Point::new(Line(7), Column(0)));
+
+        cursor = cursor.motion(&mut term, ViMotion::ParagraphUp);
+        assert_eq!(cursor.point, Point::new(Line(5), Column(0)));
+
+        cursor = cursor.motion(&mut term, ViMotion::ParagraphUp);
+        assert_eq!(cursor.point, Point::new(Line(1), Column(0)));
+
+        cursor = cursor.motion(&mut term, ViMotion::ParagraphUp);
+        assert_eq!(cursor.point, Point::new(Line(0), Column(0)));
+    }
+
+    #[test]
+    fn inline_search() {
+        let mut term = term();
+        write_line(&mut term, Line(0), "a,b,c");
+
+        let search =
+            InlineSearch { character: ',', direction: Direction::Right, stop_short: false };
+        let mut cursor = ViModeCursor::new(Point::new(Line(0), Column(0)));
+
+        cursor = cursor.inline_search(&term, search, false);
+        assert_eq!(cursor.point, Point::new(Line(0), Column(1)));
+
+        cursor = cursor.inline_search(&term, search, true);
+        assert_eq!(cursor.point, Point::new(Line(0), Column(3)));
+
+        cursor = cursor.inline_search(&term, search, true);
+        assert_eq!(cursor.point, Point::new(Line(0), Column(3)));
+
+        cursor = cursor.inline_search(&term, search.reversed(), true);
+        assert_eq!(cursor.point, Point::new(Line(0), Column(1)));
+
+        let till = InlineSearch { character: 'c', direction: Direction::Right, stop_short: true };
+        cursor = cursor.inline_search(&term, till, false);
+        assert_eq!(cursor.point, Point::new(Line(0), Column(3)));
+
+        let till = InlineSearch { character: ',', ..till };
+        cursor = cursor.inline_search(&term, till.reversed(), true);
+        assert_eq!(cursor.point, Point::new(Line(0), Column(2)));
+    }
+
+    #[test]
+    fn text_object_word() {
+        let mut term = term();
+        write_line(&mut term, Line(0), "foo bar  baz");
+
+        let point = Point::new(Line(0), Column(5));
+        let range = TextObject::Word.range(&term, point, true);
+        assert_eq!(range, Some(Point::new(Line(0), Column(4))..=Point::new(Line(0), Column(6))));
+
+        let range = TextObject::Word.range(&term, point, false);
+        assert_eq!(range, Some(Point::new(Line(0), Column(4))..=Point::new(Line(0), Column(8))));
+
+        // Trailing whitespace at the end of the line is not included.
+        let point = Point::new(Line(0), Column(10));
+        let range = TextObject::Word.range(&term, point, false);
+        assert_eq!(range, Some(Point::new(Line(0), Column(7))..=Point::new(Line(0), Column(11))));
+
+        let point = Point::new(Line(0), Column(7));
+        let range = TextObject::Word.range(&term, point, true);
+        assert_eq!(range, Some(Point::new(Line(0), Column(7))..=Point::new(Line(0), Column(8))));
+    }
+
+    #[test]
+    fn text_object_quote() {
+        let mut term = term();
+        write_line(&mut term, Line(0), r#"a "bc" d "" "#);
+
+        let quote = TextObject::from_char('"').unwrap();
+
+        let point = Point::new(Line(0), Column(0));
+        let range = quote.range(&term, point, true);
+        assert_eq!(range, Some(Point::new(Line(0), Column(3))..=Point::new(Line(0), Column(4))));
+
+        let range = quote.range(&term, point, false);
+        assert_eq!(range, Some(Point::new(Line(0), Column(2))..=Point::new(Line(0), Column(5))));
+
+        let point = Point::new(Line(0), Column(9));
+        assert_eq!(quote.range(&term, point, true), None);
+        assert_eq!(quote.range(&term, Point::new(Line(0), Column(11)), false), None);
+    }
+
+    #[test]
+    fn text_object_bracket() {
+        let mut term = term();
+        write_line(&mut term, Line(0), "f(a, (b), c)");
+
+        let bracket = TextObject::from_char('b').unwrap();
+
+        let point = Point::new(Line(0), Column(9));
+        let range = bracket.range(&term, point, true);
+        assert_eq!(range, Some(Point::new(Line(0), Column(2))..=Point::new(Line(0), Column(10))));
+
+        let point = Point::new(Line(0), Column(6));
+        let range = bracket.range(&term, point, false);
+        assert_eq!(range, Some(Point::new(Line(0), Column(5))..=Point::new(Line(0), Column(7))));
+
+        let point = Point::new(Line(0), Column(0));
+        assert_eq!(bracket.range(&term, point, false), None);
+    }
+
+    fn write_line(term: &mut Term<VoidListener>, line: Line, text: &str) {
+        for (i, c) in text.chars().enumerate() {
+            term.grid_mut()[line][Column(i)].c = c;
+        }
+    }
 }
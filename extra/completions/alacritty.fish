@@ -3,44 +3,85 @@ complete -c alacritty -n "__fish_use_subcommand" -l config-file -d 'Specify alte
 complete -c alacritty -n "__fish_use_subcommand" -l socket -d 'Path for IPC socket creation' -r -F
 complete -c alacritty -n "__fish_use_subcommand" -s o -l option -d 'Override configuration file options [example: cursor.style=Beam]' -r
 complete -c alacritty -n "__fish_use_subcommand" -l working-directory -d 'Start the shell in the specified working directory' -r -F
+complete -c alacritty -n "__fish_use_subcommand" -l record -d 'Record the session to an asciicast v2 file' -r -F
 complete -c alacritty -n "__fish_use_subcommand" -s e -l command -d 'Command and args to execute (must be last argument)' -r
 complete -c alacritty -n "__fish_use_subcommand" -s T -l title -d 'Defines the window title [default: Alacritty]' -r
 complete -c alacritty -n "__fish_use_subcommand" -l class -d 'Defines window class/app_id on X11/Wayland [default: Alacritty]' -r
 complete -c alacritty -n "__fish_use_subcommand" -l print-events -d 'Print all events to stdout'
 complete -c alacritty -n "__fish_use_subcommand" -l ref-test -d 'Generates ref test'
+complete -c alacritty -n "__fish_use_subcommand" -l restore-session -d 'Reopen the windows saved when Alacritty was last closed'
 complete -c alacritty -n "__fish_use_subcommand" -s q -d 'Reduces the level of verbosity (the min level is -qq)'
 complete -c alacritty -n "__fish_use_subcommand" -s v -d 'Increases the level of verbosity (the max level is -vvv)'
 complete -c alacritty -n "__fish_use_subcommand" -l hold -d 'Remain open after child process exit'
+complete -c alacritty -n "__fish_use_subcommand" -l record-input -d 'Include keyboard input in session recordings'
 complete -c alacritty -n "__fish_use_subcommand" -s h -l help -d 'Print help'
 complete -c alacritty -n "__fish_use_subcommand" -s V -l version -d 'Print version'
 complete -c alacritty -n "__fish_use_subcommand" -f -a "msg" -d 'Send a message to the Alacritty socket'
 complete -c alacritty -n "__fish_use_subcommand" -f -a "migrate" -d 'Migrate the configuration file'
+complete -c alacritty -n "__fish_use_subcommand" -f -a "replay" -d 'Replay a recorded terminal session'
 complete -c alacritty -n "__fish_use_subcommand" -f -a "help" -d 'Print this message or the help of the given subcommand(s)'
-complete -c alacritty -n "__fish_seen_subcommand_from msg; and not __fish_seen_subcommand_from create-window; and not __fish_seen_subcommand_from config; and not __fish_seen_subcommand_from help" -s s -l socket -d 'IPC socket connection path override' -r -F
-complete -c alacritty -n "__fish_seen_subcommand_from msg; and not __fish_seen_subcommand_from create-window; and not __fish_seen_subcommand_from config; and not __fish_seen_subcommand_from help" -s h -l help -d 'Print help'
-complete -c alacritty -n "__fish_seen_subcommand_from msg; and not __fish_seen_subcommand_from create-window; and not __fish_seen_subcommand_from config; and not __fish_seen_subcommand_from help" -f -a "create-window" -d 'Create a new window in the same Alacritty process'
-complete -c alacritty -n "__fish_seen_subcommand_from msg; and not __fish_seen_subcommand_from create-window; and not __fish_seen_subcommand_from config; and not __fish_seen_subcommand_from help" -f -a "config" -d 'Update the Alacritty configuration'
-complete -c alacritty -n "__fish_seen_subcommand_from msg; and not __fish_seen_subcommand_from create-window; and not __fish_seen_subcommand_from config; and not __fish_seen_subcommand_from help" -f -a "help" -d 'Print this message or the help of the given subcommand(s)'
+complete -c alacritty -n "__fish_seen_subcommand_from msg; and not __fish_seen_subcommand_from create-window; and not __fish_seen_subcommand_from config; and not __fish_seen_subcommand_from list-windows; and not __fish_seen_subcommand_from get-text; and not __fish_seen_subcommand_from get-config; and not __fish_seen_subcommand_from subscribe; and not __fish_seen_subcommand_from action; and not __fish_seen_subcommand_from send-text; and not __fish_seen_subcommand_from help" -s s -l socket -d 'IPC socket connection path override' -r -F
+complete -c alacritty -n "__fish_seen_subcommand_from msg; and not __fish_seen_subcommand_from create-window; and not __fish_seen_subcommand_from config; and not __fish_seen_subcommand_from list-windows; and not __fish_seen_subcommand_from get-text; and not __fish_seen_subcommand_from get-config; and not __fish_seen_subcommand_from subscribe; and not __fish_seen_subcommand_from action; and not __fish_seen_subcommand_from send-text; and not __fish_seen_subcommand_from help" -s h -l help -d 'Print help'
+complete -c alacritty -n "__fish_seen_subcommand_from msg; and not __fish_seen_subcommand_from create-window; and not __fish_seen_subcommand_from config; and not __fish_seen_subcommand_from list-windows; and not __fish_seen_subcommand_from get-text; and not __fish_seen_subcommand_from get-config; and not __fish_seen_subcommand_from subscribe; and not __fish_seen_subcommand_from action; and not __fish_seen_subcommand_from send-text; and not __fish_seen_subcommand_from help" -f -a "create-window" -d 'Create a new window in the same Alacritty process'
+complete -c alacritty -n "__fish_seen_subcommand_from msg; and not __fish_seen_subcommand_from create-window; and not __fish_seen_subcommand_from config; and not __fish_seen_subcommand_from list-windows; and not __fish_seen_subcommand_from get-text; and not __fish_seen_subcommand_from get-config; and not __fish_seen_subcommand_from subscribe; and not __fish_seen_subcommand_from action; and not __fish_seen_subcommand_from send-text; and not __fish_seen_subcommand_from help" -f -a "config" -d 'Update the Alacritty configuration'
+complete -c alacritty -n "__fish_seen_subcommand_from msg; and not __fish_seen_subcommand_from create-window; and not __fish_seen_subcommand_from config; and not __fish_seen_subcommand_from list-windows; and not __fish_seen_subcommand_from get-text; and not __fish_seen_subcommand_from get-config; and not __fish_seen_subcommand_from subscribe; and not __fish_seen_subcommand_from action; and not __fish_seen_subcommand_from send-text; and not __fish_seen_subcommand_from help" -f -a "list-windows" -d 'List all open windows'
+complete -c alacritty -n "__fish_seen_subcommand_from msg; and not __fish_seen_subcommand_from create-window; and not __fish_seen_subcommand_from config; and not __fish_seen_subcommand_from list-windows; and not __fish_seen_subcommand_from get-text; and not __fish_seen_subcommand_from get-config; and not __fish_seen_subcommand_from subscribe; and not __fish_seen_subcommand_from action; and not __fish_seen_subcommand_from send-text; and not __fish_seen_subcommand_from help" -f -a "get-text" -d 'Get the text content of a window'
+complete -c alacritty -n "__fish_seen_subcommand_from msg; and not __fish_seen_subcommand_from create-window; and not __fish_seen_subcommand_from config; and not __fish_seen_subcommand_from list-windows; and not __fish_seen_subcommand_from get-text; and not __fish_seen_subcommand_from get-config; and not __fish_seen_subcommand_from subscribe; and not __fish_seen_subcommand_from action; and not __fish_seen_subcommand_from send-text; and not __fish_seen_subcommand_from help" -f -a "get-config" -d 'Get the current configuration'
+complete -c alacritty -n "__fish_seen_subcommand_from msg; and not __fish_seen_subcommand_from create-window; and not __fish_seen_subcommand_from config; and not __fish_seen_subcommand_from list-windows; and not __fish_seen_subcommand_from get-text; and not __fish_seen_subcommand_from get-config; and not __fish_seen_subcommand_from subscribe; and not __fish_seen_subcommand_from action; and not __fish_seen_subcommand_from send-text; and not __fish_seen_subcommand_from help" -f -a "subscribe" -d 'Print events of all windows as newline-delimited JSON'
+complete -c alacritty -n "__fish_seen_subcommand_from msg; and not __fish_seen_subcommand_from create-window; and not __fish_seen_subcommand_from config; and not __fish_seen_subcommand_from list-windows; and not __fish_seen_subcommand_from get-text; and not __fish_seen_subcommand_from get-config; and not __fish_seen_subcommand_from subscribe; and not __fish_seen_subcommand_from action; and not __fish_seen_subcommand_from send-text; and not __fish_seen_subcommand_from help" -f -a "action" -d 'Execute a binding action in a window'
+complete -c alacritty -n "__fish_seen_subcommand_from msg; and not __fish_seen_subcommand_from create-window; and not __fish_seen_subcommand_from config; and not __fish_seen_subcommand_from list-windows; and not __fish_seen_subcommand_from get-text; and not __fish_seen_subcommand_from get-config; and not __fish_seen_subcommand_from subscribe; and not __fish_seen_subcommand_from action; and not __fish_seen_subcommand_from send-text; and not __fish_seen_subcommand_from help" -f -a "send-text" -d 'Write text to the terminal of a window'
+complete -c alacritty -n "__fish_seen_subcommand_from msg; and not __fish_seen_subcommand_from create-window; and not __fish_seen_subcommand_from config; and not __fish_seen_subcommand_from list-windows; and not __fish_seen_subcommand_from get-text; and not __fish_seen_subcommand_from get-config; and not __fish_seen_subcommand_from subscribe; and not __fish_seen_subcommand_from action; and not __fish_seen_subcommand_from send-text; and not __fish_seen_subcommand_from help" -f -a "help" -d 'Print this message or the help of the given subcommand(s)'
 complete -c alacritty -n "__fish_seen_subcommand_from msg; and __fish_seen_subcommand_from create-window" -l working-directory -d 'Start the shell in the specified working directory' -r -F
+complete -c alacritty -n "__fish_seen_subcommand_from msg; and __fish_seen_subcommand_from create-window" -l record -d 'Record the session to an asciicast v2 file' -r -F
 complete -c alacritty -n "__fish_seen_subcommand_from msg; and __fish_seen_subcommand_from create-window" -s e -l command -d 'Command and args to execute (must be last argument)' -r
 complete -c alacritty -n "__fish_seen_subcommand_from msg; and __fish_seen_subcommand_from create-window" -s T -l title -d 'Defines the window title [default: Alacritty]' -r
 complete -c alacritty -n "__fish_seen_subcommand_from msg; and __fish_seen_subcommand_from create-window" -l class -d 'Defines window class/app_id on X11/Wayland [default: Alacritty]' -r
 complete -c alacritty -n "__fish_seen_subcommand_from msg; and __fish_seen_subcommand_from create-window" -l hold -d 'Remain open after child process exit'
+complete -c alacritty -n "__fish_seen_subcommand_from msg; and __fish_seen_subcommand_from create-window" -l record-input -d 'Include keyboard input in session recordings'
 complete -c alacritty -n "__fish_seen_subcommand_from msg; and __fish_seen_subcommand_from create-window" -s h -l help -d 'Print help'
 complete -c alacritty -n "__fish_seen_subcommand_from msg; and __fish_seen_subcommand_from config" -s w -l window-id -d 'Window ID for the new config' -r
 complete -c alacritty -n "__fish_seen_subcommand_from msg; and __fish_seen_subcommand_from config" -s r -l reset -d 'Clear all runtime configuration changes'
 complete -c alacritty -n "__fish_seen_subcommand_from msg; and __fish_seen_subcommand_from config" -s h -l help -d 'Print help (see more with \'--help\')'
-complete -c alacritty -n "__fish_seen_subcommand_from msg; and __fish_seen_subcommand_from help; and not __fish_seen_subcommand_from create-window; and not __fish_seen_subcommand_from config; and not __fish_seen_subcommand_from help" -f -a "create-window" -d 'Create a new window in the same Alacritty process'
-complete -c alacritty -n "__fish_seen_subcommand_from msg; and __fish_seen_subcommand_from help; and not __fish_seen_subcommand_from create-window; and not __fish_seen_subcommand_from config; and not __fish_seen_subcommand_from help" -f -a "config" -d 'Update the Alacritty configuration'
-complete -c alacritty -n "__fish_seen_subcommand_from msg; and __fish_seen_subcommand_from help; and not __fish_seen_subcommand_from create-window; and not __fish_seen_subcommand_from config; and not __fish_seen_subcommand_from help" -f -a "help" -d 'Print this message or the help of the given subcommand(s)'
+complete -c alacritty -n "__fish_seen_subcommand_from msg; and __fish_seen_subcommand_from list-windows" -s h -l help -d 'Print help'
+complete -c alacritty -n "__fish_seen_subcommand_from msg; and __fish_seen_subcommand_from get-text" -s w -l window-id -d 'Window ID of the requested text, defaults to the focused window' -r
+complete -c alacritty -n "__fish_seen_subcommand_from msg; and __fish_seen_subcommand_from get-text" -l scrollback -d 'Include the scrollback history instead of only the visible text'
+complete -c alacritty -n "__fish_seen_subcommand_from msg; and __fish_seen_subcommand_from get-text" -s h -l help -d 'Print help'
+complete -c alacritty -n "__fish_seen_subcommand_from msg; and __fish_seen_subcommand_from get-config" -s w -l window-id -d 'Window ID of the requested configuration, defaults to the global configuration' -r
+complete -c alacritty -n "__fish_seen_subcommand_from msg; and __fish_seen_subcommand_from get-config" -s h -l help -d 'Print help'
+complete -c alacritty -n "__fish_seen_subcommand_from msg; and __fish_seen_subcommand_from subscribe" -s h -l help -d 'Print help'
+complete -c alacritty -n "__fish_seen_subcommand_from msg; and __fish_seen_subcommand_from action" -l chars -d 'Characters to write to the terminal, like the `chars` binding field' -r
+complete -c alacritty -n "__fish_seen_subcommand_from msg; and __fish_seen_subcommand_from action" -l command -d 'Command to spawn, like the `command` binding field' -r
+complete -c alacritty -n "__fish_seen_subcommand_from msg; and __fish_seen_subcommand_from action" -s w -l window-id -d 'Window ID the action is executed in, defaults to the focused window' -r
+complete -c alacritty -n "__fish_seen_subcommand_from msg; and __fish_seen_subcommand_from action" -s h -l help -d 'Print help'
+complete -c alacritty -n "__fish_seen_subcommand_from msg; and __fish_seen_subcommand_from send-text" -s w -l window-id -d 'Window ID the text is sent to, defaults to the focused window' -r
+complete -c alacritty -n "__fish_seen_subcommand_from msg; and __fish_seen_subcommand_from send-text" -s p -l paste -d 'Send the text like a paste, using bracketed paste if the application requested it'
+complete -c alacritty -n "__fish_seen_subcommand_from msg; and __fish_seen_subcommand_from send-text" -s e -l escape -d 'Interpret the backslash escapes `\\n`, `\\r`, `\\t`, `\\e`, `\\\\` and `\\xHH` in the text'
+complete -c alacritty -n "__fish_seen_subcommand_from msg; and __fish_seen_subcommand_from send-text" -s h -l help -d 'Print help'
+complete -c alacritty -n "__fish_seen_subcommand_from msg; and __fish_seen_subcommand_from help; and not __fish_seen_subcommand_from create-window; and not __fish_seen_subcommand_from config; and not __fish_seen_subcommand_from list-windows; and not __fish_seen_subcommand_from get-text; and not __fish_seen_subcommand_from get-config; and not __fish_seen_subcommand_from subscribe; and not __fish_seen_subcommand_from action; and not __fish_seen_subcommand_from send-text; and not __fish_seen_subcommand_from help" -f -a "create-window" -d 'Create a new window in the same Alacritty process'
+complete -c alacritty -n "__fish_seen_subcommand_from msg; and __fish_seen_subcommand_from help; and not __fish_seen_subcommand_from create-window; and not __fish_seen_subcommand_from config; and not __fish_seen_subcommand_from list-windows; and not __fish_seen_subcommand_from get-text; and not __fish_seen_subcommand_from get-config; and not __fish_seen_subcommand_from subscribe; and not __fish_seen_subcommand_from action; and not __fish_seen_subcommand_from send-text; and not __fish_seen_subcommand_from help" -f -a "config" -d 'Update the Alacritty configuration'
+complete -c alacritty -n "__fish_seen_subcommand_from msg; and __fish_seen_subcommand_from help; and not __fish_seen_subcommand_from create-window; and not __fish_seen_subcommand_from config; and not __fish_seen_subcommand_from list-windows; and not __fish_seen_subcommand_from get-text; and not __fish_seen_subcommand_from get-config; and not __fish_seen_subcommand_from subscribe; and not __fish_seen_subcommand_from action; and not __fish_seen_subcommand_from send-text; and not __fish_seen_subcommand_from help" -f -a "list-windows" -d 'List all open windows'
+complete -c alacritty -n "__fish_seen_subcommand_from msg; and __fish_seen_subcommand_from help; and not __fish_seen_subcommand_from create-window; and not __fish_seen_subcommand_from config; and not __fish_seen_subcommand_from list-windows; and not __fish_seen_subcommand_from get-text; and not __fish_seen_subcommand_from get-config; and not __fish_seen_subcommand_from subscribe; and not __fish_seen_subcommand_from action; and not __fish_seen_subcommand_from send-text; and not __fish_seen_subcommand_from help" -f -a "get-text" -d 'Get the text content of a window'
+complete -c alacritty -n "__fish_seen_subcommand_from msg; and __fish_seen_subcommand_from help; and not __fish_seen_subcommand_from create-window; and not __fish_seen_subcommand_from config; and not __fish_seen_subcommand_from list-windows; and not __fish_seen_subcommand_from get-text; and not __fish_seen_subcommand_from get-config; and not __fish_seen_subcommand_from subscribe; and not __fish_seen_subcommand_from action; and not __fish_seen_subcommand_from send-text; and not __fish_seen_subcommand_from help" -f -a "get-config" -d 'Get the current configuration'
+complete -c alacritty -n "__fish_seen_subcommand_from msg; and __fish_seen_subcommand_from help; and not __fish_seen_subcommand_from create-window; and not __fish_seen_subcommand_from config; and not __fish_seen_subcommand_from list-windows; and not __fish_seen_subcommand_from get-text; and not __fish_seen_subcommand_from get-config; and not __fish_seen_subcommand_from subscribe; and not __fish_seen_subcommand_from action; and not __fish_seen_subcommand_from send-text; and not __fish_seen_subcommand_from help" -f -a "subscribe" -d 'Print events of all windows as newline-delimited JSON'
+complete -c alacritty -n "__fish_seen_subcommand_from msg; and __fish_seen_subcommand_from help; and not __fish_seen_subcommand_from create-window; and not __fish_seen_subcommand_from config; and not __fish_seen_subcommand_from list-windows; and not __fish_seen_subcommand_from get-text; and not __fish_seen_subcommand_from get-config; and not __fish_seen_subcommand_from subscribe; and not __fish_seen_subcommand_from action; and not __fish_seen_subcommand_from send-text; and not __fish_seen_subcommand_from help" -f -a "action" -d 'Execute a binding action in a window'
+complete -c alacritty -n "__fish_seen_subcommand_from msg; and __fish_seen_subcommand_from help; and not __fish_seen_subcommand_from create-window; and not __fish_seen_subcommand_from config; and not __fish_seen_subcommand_from list-windows; and not __fish_seen_subcommand_from get-text; and not __fish_seen_subcommand_from get-config; and not __fish_seen_subcommand_from subscribe; and not __fish_seen_subcommand_from action; and not __fish_seen_subcommand_from send-text; and not __fish_seen_subcommand_from help" -f -a "send-text" -d 'Write text to the terminal of a window'
+complete -c alacritty -n "__fish_seen_subcommand_from msg; and __fish_seen_subcommand_from help; and not __fish_seen_subcommand_from create-window; and not __fish_seen_subcommand_from config; and not __fish_seen_subcommand_from list-windows; and not __fish_seen_subcommand_from get-text; and not __fish_seen_subcommand_from get-config; and not __fish_seen_subcommand_from subscribe; and not __fish_seen_subcommand_from action; and not __fish_seen_subcommand_from send-text; and not __fish_seen_subcommand_from help" -f -a "help" -d 'Print this message or the help of the given subcommand(s)'
 complete -c alacritty -n "__fish_seen_subcommand_from migrate" -s c -l config-file -d 'Path to the configuration file' -r -F
 complete -c alacritty -n "__fish_seen_subcommand_from migrate" -s d -l dry-run -d 'Only output TOML config to stdout'
 complete -c alacritty -n "__fish_seen_subcommand_from migrate" -s i -l skip-imports -d 'Do not recurse over imports'
 complete -c alacritty -n "__fish_seen_subcommand_from migrate" -l skip-renames -d 'Do not move renamed fields to their new location'
 complete -c alacritty -n "__fish_seen_subcommand_from migrate" -s s -l silent -d 'Do not output to STDOUT'
 complete -c alacritty -n "__fish_seen_subcommand_from migrate" -s h -l help -d 'Print help'
-complete -c alacritty -n "__fish_seen_subcommand_from help; and not __fish_seen_subcommand_from msg; and not __fish_seen_subcommand_from migrate; and not __fish_seen_subcommand_from help" -f -a "msg" -d 'Send a message to the Alacritty socket'
-complete -c alacritty -n "__fish_seen_subcommand_from help; and not __fish_seen_subcommand_from msg; and not __fish_seen_subcommand_from migrate; and not __fish_seen_subcommand_from help" -f -a "migrate" -d 'Migrate the configuration file'
-complete -c alacritty -n "__fish_seen_subcommand_from help; and not __fish_seen_subcommand_from msg; and not __fish_seen_subcommand_from migrate; and not __fish_seen_subcommand_from help" -f -a "help" -d 'Print this message or the help of the given subcommand(s)'
-complete -c alacritty -n "__fish_seen_subcommand_from help; and __fish_seen_subcommand_from msg; and not __fish_seen_subcommand_from create-window; and not __fish_seen_subcommand_from config" -f -a "create-window" -d 'Create a new window in the same Alacritty process'
-complete -c alacritty -n "__fish_seen_subcommand_from help; and __fish_seen_subcommand_from msg; and not __fish_seen_subcommand_from create-window; and not __fish_seen_subcommand_from config" -f -a "config" -d 'Update the Alacritty configuration'
+complete -c alacritty -n "__fish_seen_subcommand_from replay" -s h -l help -d 'Print help'
+complete -c alacritty -n "__fish_seen_subcommand_from help; and not __fish_seen_subcommand_from msg; and not __fish_seen_subcommand_from migrate; and not __fish_seen_subcommand_from replay; and not __fish_seen_subcommand_from help" -f -a "msg" -d 'Send a message to the Alacritty socket'
+complete -c alacritty -n "__fish_seen_subcommand_from help; and not __fish_seen_subcommand_from msg; and not __fish_seen_subcommand_from migrate; and not __fish_seen_subcommand_from replay; and not __fish_seen_subcommand_from help" -f -a "migrate" -d 'Migrate the configuration file'
+complete -c alacritty -n "__fish_seen_subcommand_from help; and not __fish_seen_subcommand_from msg; and not __fish_seen_subcommand_from migrate; and not __fish_seen_subcommand_from replay; and not __fish_seen_subcommand_from help" -f -a "replay" -d 'Replay a recorded terminal session'
+complete -c alacritty -n "__fish_seen_subcommand_from help; and not __fish_seen_subcommand_from msg; and not __fish_seen_subcommand_from migrate; and not __fish_seen_subcommand_from replay; and not __fish_seen_subcommand_from help" -f -a "help" -d 'Print this message or the help of the given subcommand(s)'
+complete -c alacritty -n "__fish_seen_subcommand_from help; and __fish_seen_subcommand_from msg; and not __fish_seen_subcommand_from create-window; and not __fish_seen_subcommand_from config; and not __fish_seen_subcommand_from list-windows; and not __fish_seen_subcommand_from get-text; and not __fish_seen_subcommand_from get-config; and not __fish_seen_subcommand_from subscribe; and not __fish_seen_subcommand_from action; and not __fish_seen_subcommand_from send-text" -f -a "create-window" -d 'Create a new window in the same Alacritty process'
+complete -c alacritty -n "__fish_seen_subcommand_from help; and __fish_seen_subcommand_from msg; and not __fish_seen_subcommand_from create-window; and not __fish_seen_subcommand_from config; and not __fish_seen_subcommand_from list-windows; and not __fish_seen_subcommand_from get-text; and not __fish_seen_subcommand_from get-config; and not __fish_seen_subcommand_from subscribe; and not __fish_seen_subcommand_from action; and not __fish_seen_subcommand_from send-text" -f -a "config" -d 'Update the Alacritty configuration'
+complete -c alacritty -n "__fish_seen_subcommand_from help; and __fish_seen_subcommand_from msg; and not __fish_seen_subcommand_from create-window; and not __fish_seen_subcommand_from config; and not __fish_seen_subcommand_from list-windows; and not __fish_seen_subcommand_from get-text; and not __fish_seen_subcommand_from get-config; and not __fish_seen_subcommand_from subscribe; and not __fish_seen_subcommand_from action; and not __fish_seen_subcommand_from send-text" -f -a "list-windows" -d 'List all open windows'
+complete -c alacritty -n "__fish_seen_subcommand_from help; and __fish_seen_subcommand_from msg; and not __fish_seen_subcommand_from create-window; and not __fish_seen_subcommand_from config; and not __fish_seen_subcommand_from list-windows; and not __fish_seen_subcommand_from get-text; and not __fish_seen_subcommand_from get-config; and not __fish_seen_subcommand_from subscribe; and not __fish_seen_subcommand_from action; and not __fish_seen_subcommand_from send-text" -f -a "get-text" -d 'Get the text content of a window'
+complete -c alacritty -n "__fish_seen_subcommand_from help; and __fish_seen_subcommand_from msg; and not __fish_seen_subcommand_from create-window; and not __fish_seen_subcommand_from config; and not __fish_seen_subcommand_from list-windows; and not __fish_seen_subcommand_from get-text; and not __fish_seen_subcommand_from get-config; and not __fish_seen_subcommand_from subscribe; and not __fish_seen_subcommand_from action; and not __fish_seen_subcommand_from send-text" -f -a "get-config" -d 'Get the current configuration'
+complete -c alacritty -n "__fish_seen_subcommand_from help; and __fish_seen_subcommand_from msg; and not __fish_seen_subcommand_from create-window; and not __fish_seen_subcommand_from config; and not __fish_seen_subcommand_from list-windows; and not __fish_seen_subcommand_from get-text; and not __fish_seen_subcommand_from get-config; and not __fish_seen_subcommand_from subscribe; and not __fish_seen_subcommand_from action; and not __fish_seen_subcommand_from send-text" -f -a "subscribe" -d 'Print events of all windows as newline-delimited JSON'
+complete -c alacritty -n "__fish_seen_subcommand_from help; and __fish_seen_subcommand_from msg; and not __fish_seen_subcommand_from create-window; and not __fish_seen_subcommand_from config; and not __fish_seen_subcommand_from list-windows; and not __fish_seen_subcommand_from get-text; and not __fish_seen_subcommand_from get-config; and not __fish_seen_subcommand_from subscribe; and not __fish_seen_subcommand_from action; and not __fish_seen_subcommand_from send-text" -f -a "action" -d 'Execute a binding action in a window'
+complete -c alacritty -n "__fish_seen_subcommand_from help; and __fish_seen_subcommand_from msg; and not __fish_seen_subcommand_from create-window; and not __fish_seen_subcommand_from config; and not __fish_seen_subcommand_from list-windows; and not __fish_seen_subcommand_from get-text; and not __fish_seen_subcommand_from get-config; and not __fish_seen_subcommand_from subscribe; and not __fish_seen_subcommand_from action; and not __fish_seen_subcommand_from send-text" -f -a "send-text" -d 'Write text to the terminal of a window'